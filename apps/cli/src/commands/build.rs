@@ -9,8 +9,10 @@ use url::Url;
 use reqwest_middleware::ClientBuilder;
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 
-use blocksense_registry::config::{BlocksenseConfig, FeedsResponse, OraclesResponse};
+use blocksense_registry::config::{BlocksenseConfig, FeedsResponse};
+use blocksense_secrets::SecretRef;
 
+use crate::commands::oracle::fetch_oracles_from_registry;
 use crate::opts::{APP_MANIFEST_FILE_OPT, BUILD_UP_OPT};
 
 use crate::spin_manifest::AppManifest as SpinConfigToml;
@@ -97,6 +99,11 @@ impl BuildConfig {
         }
 
         for capability in config.capabilities.iter_mut() {
+            // References are resolved by the reporter whenever it runs an oracle script
+            if SecretRef::parse(&capability.data).is_some() {
+                tracing::info!("Capability {} refers to {}", capability.id, capability.data);
+                continue;
+            }
             let api_key_path = &capability
                 .data
                 .strip_suffix("\n")
@@ -105,7 +112,7 @@ impl BuildConfig {
                 .await
                 .context(format!("No such file - {}", &api_key_path))?;
             api_key = api_key.strip_suffix(&"\n").unwrap_or(&api_key).to_string();
-            tracing::info!("API key {:?}", &api_key);
+            tracing::info!("Read capability {} from {api_key_path}", capability.id);
             capability.data = api_key;
         }
 
@@ -134,14 +141,7 @@ impl BuildConfig {
     }
 
     async fn fill_oracles_from_registry(config: &mut BlocksenseConfig) -> Result<()> {
-        let base = Url::parse(&config.reporter_info.registry)?;
-        let url = base.join("/get_oracle_scripts")?;
-        tracing::info!("Getting oracles config from {:?}", url);
-
-        let body = reqwest::get(url.clone()).await?.text().await?;
-
-        let response_json: OraclesResponse = serde_json::from_str(&body)?;
-        config.oracles = response_json.oracles;
+        config.oracles = fetch_oracles_from_registry(&config.reporter_info.registry).await?;
 
         Ok(())
     }
@@ -1,9 +1,28 @@
-use tokio::{fs, io::AsyncWriteExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
-use anyhow::Result;
+use alloy::hex;
+use alloy::signers::{local::PrivateKeySigner, SignerSync};
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use rand::RngCore;
+use tokio::{fs, io::AsyncWriteExt};
+use url::Url;
+
+use blocksense_config::{Reporter, ReporterRegistration};
+use blocksense_crypto::{
+    deserialize_priv_key, generate_keys, serialize_priv_key, serialize_public_key, sign_message,
+    SecretKey, MULTIFORMATS_BLS_PUBKYE_PREFIX,
+};
+use blocksense_registry::registry::Registry;
+use blocksense_secrets::{keystore::Keystore, SecretsConfig, DEFAULT_KEYSTORE_PASSPHRASE_ENV};
 
-use crate::commands::{build::BuildConfig, oracle::OracleNodeCommands};
+use crate::commands::build::BuildConfig;
+use crate::commands::oracle::{download_oracle, OracleNodeCommands};
+use crate::node_config::{config_dir, read_node_config, write_node_config, NODE_CONFIG_FILE};
+
+const SECRET_KEY_FILE: &str = "reporter_secret_key";
+const SECOND_CONSENSUS_SECRET_KEY_FILE: &str = "reporter_second_consensus_secret_key";
 
 /// Commands for initializing blocksense projects.
 #[derive(Debug, Subcommand)]
@@ -17,6 +36,8 @@ pub enum NodeCommands {
     Init(Init),
     /// Command for registering the node operator.
     Register(Register),
+    /// Command for adding or rotating a secret in the encrypted keystore of the node operator.
+    SetSecret(SetSecret),
 }
 
 impl NodeCommands {
@@ -26,6 +47,7 @@ impl NodeCommands {
             NodeCommands::Build(cmd) => cmd.run().await,
             NodeCommands::Init(cmd) => cmd.run().await,
             NodeCommands::Register(cmd) => cmd.run().await,
+            NodeCommands::SetSecret(cmd) => cmd.run().await,
         }
     }
 }
@@ -36,27 +58,281 @@ pub struct Init {
     #[arg(short = 'b')]
     // TODO(adikov): Implement
     pub build: bool,
+    /// Specifies the registry URL.
+    #[arg(short = 'r', long = "registry")]
+    pub registry: String,
+    /// Specifies the node configuration file.
+    #[arg(short = 'c', long = "config", default_value = NODE_CONFIG_FILE)]
+    pub config: PathBuf,
 }
 
 impl Init {
     pub async fn run(self) -> Result<()> {
-        let config = blocksense_registry::registry::Registry::get_config().await?;
-        let mut file = fs::File::create("blocksense-config.json").await?;
-        let json = serde_json::to_string_pretty(&config)?;
-        file.write_all(json.as_bytes()).await?;
-        Ok(())
+        let registry = Registry::new(&self.registry)?;
+        let mut config = registry.get_config().await?;
+
+        // The oracle scripts in the configuration refer to their wasm next to it
+        let base_dir = config_dir(&self.config);
+        for oracle in config.oracles.iter_mut() {
+            download_oracle(&registry, oracle, &base_dir).await?;
+        }
+
+        write_node_config(&self.config, &config).await
     }
 }
 
 #[derive(Parser, Debug)]
 pub struct Register {
-    /// Specifies the node operator account
-    #[arg(short = 'a')]
-    pub account: Vec<String>,
+    /// Specifies the sequencer URL. Defaults to the sequencer in the node configuration.
+    #[arg(short = 's', long = "sequencer")]
+    pub sequencer: Option<String>,
+    /// Specifies the reporter id. Defaults to the reporter id in the node configuration.
+    #[arg(long = "reporter-id")]
+    pub reporter_id: Option<u32>,
+    /// Specifies the stake of the reporter.
+    #[arg(long = "stake", default_value_t = 1.0)]
+    pub stake: f64,
+    /// File with the hex encoded BLS secret key to import. A new key is generated if not given.
+    #[arg(long = "secret-key")]
+    pub secret_key: Option<PathBuf>,
+    /// File with the hex encoded secp256k1 secret key for second round consensus to import. A
+    /// new key is generated if not given.
+    #[arg(long = "second-consensus-secret-key")]
+    pub second_consensus_secret_key: Option<PathBuf>,
+    /// Only print the reporter entry, without submitting the registration.
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+    /// Specifies the node configuration file.
+    #[arg(short = 'c', long = "config", default_value = NODE_CONFIG_FILE)]
+    pub config: PathBuf,
 }
 
 impl Register {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        let mut config = read_node_config(&self.config).await?;
+        let base_dir = config_dir(&self.config);
+        let reporter_id = match self.reporter_id {
+            Some(id) => id,
+            None => u32::try_from(config.reporter_info.reporter_id)
+                .context("Reporter id in the node configuration is out of range")?,
+        };
+
+        let (secret_key, secret_key_path) = match &self.secret_key {
+            Some(path) => {
+                let key = read_key_file(path).await?;
+                let secret_key = deserialize_priv_key(&key).map_err(|e| {
+                    anyhow::anyhow!("Invalid BLS secret key in {}: {e}", path.display())
+                })?;
+                (secret_key, path.clone())
+            }
+            None => {
+                let mut ikm = [0u8; 35];
+                rand::thread_rng().fill_bytes(&mut ikm);
+                let (secret_key, _) = generate_keys(&ikm);
+                let path = base_dir.join(SECRET_KEY_FILE);
+                write_key_file(&path, &serialize_priv_key(&secret_key)).await?;
+                (secret_key, path)
+            }
+        };
+        let (signer, signer_path) = match &self.second_consensus_secret_key {
+            Some(path) => {
+                let key = read_key_file(path).await?;
+                let signer = PrivateKeySigner::from_str(&key).with_context(|| {
+                    format!("Invalid secp256k1 secret key in {}", path.display())
+                })?;
+                (signer, path.clone())
+            }
+            None => {
+                let signer = PrivateKeySigner::random();
+                let path = base_dir.join(SECOND_CONSENSUS_SECRET_KEY_FILE);
+                write_key_file(&path, &hex::encode(signer.to_bytes())).await?;
+                (signer, path)
+            }
+        };
+
+        let registration = sign_registration(reporter_id, self.stake, &secret_key, &signer)?;
+
+        config.reporter_info.reporter_id = reporter_id.into();
+        config.reporter_info.secret_key = secret_key_path.display().to_string();
+        config.reporter_info.second_consensus_secret_key = Some(signer_path.display().to_string());
+        write_node_config(&self.config, &config).await?;
+
+        if !self.dry_run {
+            let sequencer = self
+                .sequencer
+                .as_deref()
+                .unwrap_or(&config.reporter_info.sequencer);
+            submit_registration(sequencer, &registration).await?;
+        }
+
+        // The entry the sequencer operators add to `reporters` in their SequencerConfig
+        println!("{}", serde_json::to_string_pretty(&registration.reporter)?);
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+pub struct SetSecret {
+    /// Name of the secret, which capabilities refer to as `keystore:<NAME>`. The value is read
+    /// from stdin and the passphrase from the environment.
+    pub name: String,
+    /// Specifies the keystore file. Defaults to the keystore in the node configuration.
+    #[arg(long = "keystore")]
+    pub keystore: Option<PathBuf>,
+    /// Specifies the node configuration file.
+    #[arg(short = 'c', long = "config", default_value = NODE_CONFIG_FILE)]
+    pub config: PathBuf,
+}
+
+impl SetSecret {
+    pub async fn run(self) -> Result<()> {
+        let secrets = match &self.keystore {
+            Some(_) => SecretsConfig::default(),
+            None => read_node_config(&self.config)
+                .await?
+                .reporter_info
+                .secrets
+                .unwrap_or_default(),
+        };
+        let path = self
+            .keystore
+            .or(secrets.keystore)
+            .context("No keystore is given and none is in the node configuration")?;
+        let passphrase_env = secrets
+            .keystore_passphrase_env
+            .unwrap_or_else(|| DEFAULT_KEYSTORE_PASSPHRASE_ENV.to_string());
+        let passphrase = std::env::var(&passphrase_env).with_context(|| {
+            format!("Environment variable {passphrase_env} with the keystore passphrase is not set")
+        })?;
+
+        let mut value = String::new();
+        std::io::stdin()
+            .read_line(&mut value)
+            .context("Could not read the secret from stdin")?;
+        let value = value.trim_end_matches(['\r', '\n']);
+        if value.is_empty() {
+            anyhow::bail!("The secret {} is empty", self.name);
+        }
+
+        Keystore::new(path.clone()).set(&self.name, value, &passphrase)?;
+        tracing::info!("Stored secret {} in {}", self.name, path.display());
+        Ok(())
+    }
+}
+
+/// Builds the registration of the reporter with the given keys, signed with both of them.
+fn sign_registration(
+    reporter_id: u32,
+    stake: f64,
+    secret_key: &SecretKey,
+    signer: &PrivateKeySigner,
+) -> Result<ReporterRegistration> {
+    let reporter = Reporter {
+        id: reporter_id,
+        pub_key: format!(
+            "{MULTIFORMATS_BLS_PUBKYE_PREFIX}{}",
+            serialize_public_key(&secret_key.sk_to_pk())
+        ),
+        address: signer.address().to_string(),
+        stake,
+    };
+    let message = ReporterRegistration::signing_message(&reporter);
+    Ok(ReporterRegistration {
+        signature: hex::encode(sign_message(secret_key, &message).to_bytes()),
+        address_signature: signer.sign_message_sync(&message)?.to_string(),
+        reporter,
+    })
+}
+
+async fn submit_registration(sequencer: &str, registration: &ReporterRegistration) -> Result<()> {
+    let url = Url::parse(sequencer)
+        .with_context(|| format!("Invalid sequencer URL {sequencer:?}"))?
+        .join("/register_reporter")?;
+    tracing::info!("Submitting registration to {:?}", url);
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(registration)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Registration was rejected with {status}: {body}");
+    }
+    tracing::info!(
+        "Registration of reporter {} submitted, waiting for approval by the sequencer",
+        registration.reporter.id
+    );
+    Ok(())
+}
+
+async fn read_key_file(path: &Path) -> Result<String> {
+    let key = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Could not read key file {}", path.display()))?;
+    Ok(key.trim().to_string())
+}
+
+// Never overwrites an existing key, since it may belong to an already registered reporter
+async fn write_key_file(path: &Path, key: &str) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .await
+        .with_context(|| {
+            format!(
+                "Could not create key file {}, pass an existing key to import it",
+                path.display()
+            )
+        })?;
+    file.write_all(key.as_bytes()).await?;
+    tracing::info!("Generated new key in {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blocksense_crypto::{deserialize_public_key, verify_signature, Signature};
+
+    #[test]
+    fn registration_is_signed_with_both_keys() {
+        let (secret_key, pub_key) = generate_keys(&[3; 35]);
+        let signer = PrivateKeySigner::random();
+
+        let registration = sign_registration(5, 2.0, &secret_key, &signer).unwrap();
+        let reporter = &registration.reporter;
+        assert_eq!(reporter.id, 5);
+        assert_eq!(reporter.stake, 2.0);
+        assert_eq!(reporter.address, signer.address().to_string());
+        let pub_key_hex = reporter
+            .pub_key
+            .strip_prefix(MULTIFORMATS_BLS_PUBKYE_PREFIX)
+            .unwrap();
+        assert_eq!(deserialize_public_key(pub_key_hex).unwrap(), pub_key);
+
+        let message = ReporterRegistration::signing_message(reporter);
+        let signature =
+            Signature::from_bytes(&hex::decode(&registration.signature).unwrap()).unwrap();
+        assert!(verify_signature(&pub_key, &signature, &message));
+        let address_signature =
+            alloy::primitives::Signature::from_str(&registration.address_signature).unwrap();
+        assert_eq!(
+            address_signature
+                .recover_address_from_msg(&message)
+                .unwrap(),
+            signer.address()
+        );
+    }
+
+    #[tokio::test]
+    async fn key_files_are_not_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SECRET_KEY_FILE);
+        write_key_file(&path, "aa").await.unwrap();
+        assert!(write_key_file(&path, "bb").await.is_err());
+        assert_eq!(read_key_file(&path).await.unwrap(), "aa");
     }
 }
@@ -1,10 +1,22 @@
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 use clap::{Parser, Subcommand};
-use tokio::process::Command;
+use prettytable::{format, Cell, Row, Table};
+use tokio::{fs, process::Command};
+use url::Url;
+
+use blocksense_registry::config::{BlocksenseConfig, OracleScript};
+use blocksense_registry::registry::{content_hash, OracleManifest, Registry};
+use blocksense_utils::FeedId;
+
+use crate::node_config::{config_dir, read_node_config, write_node_config, NODE_CONFIG_FILE};
+
+static SPIN_MANIFEST: &str = "spin.toml";
+static WASM: &str = "wasm";
 
 /// Commands for working with the capabilities.
 #[derive(Debug, Subcommand)]
@@ -32,9 +44,9 @@ impl OracleNodeCommands {
 pub enum OracleDevCommands {
     /// Add an oracle script
     Init(Init),
-    /// List oracle scripts.
+    /// Publish a built oracle script to the registry.
     Publish(Publish),
-    /// Remove an oracle script.
+    /// Retire an oracle script in the registry.
     Retire(Retire),
 }
 
@@ -50,14 +62,76 @@ impl OracleDevCommands {
 
 #[derive(Parser, Debug)]
 pub struct Add {
-    /// Specifies entities to add
-    #[arg(short = 'o')]
+    /// Specifies entities to add. Each one is a local path, to an oracle project directory
+    /// (containing spin.toml) or to a .wasm file, or the id of an oracle script in the registry.
+    #[arg(short = 'o', required = true)]
     pub oracles: Vec<String>,
+    /// Hosts the oracle script is allowed to connect to. Added to every oracle script from a
+    /// local path.
+    #[arg(short = 'a', long = "allowed-outbound-host")]
+    pub allowed_outbound_hosts: Vec<String>,
+    /// Capabilities needed by the oracle script. Added to every oracle script from a local path.
+    #[arg(long = "capability")]
+    pub capabilities: Vec<String>,
+    /// Specifies the node configuration file.
+    #[arg(short = 'c', long = "config", default_value = NODE_CONFIG_FILE)]
+    pub config: PathBuf,
 }
 
 impl Add {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        let mut config = read_node_config(&self.config).await?;
+        let base_dir = config_dir(&self.config);
+        let mut registry: Option<(Registry, Vec<OracleScript>)> = None;
+
+        for source in &self.oracles {
+            let oracles = if Path::new(source).exists() {
+                load_local_oracles(
+                    Path::new(source),
+                    &self.allowed_outbound_hosts,
+                    &self.capabilities,
+                )
+                .await?
+            } else {
+                if registry.is_none() {
+                    let url = &config.reporter_info.registry;
+                    let client = Registry::new(url).with_context(|| {
+                        format!("Invalid registry URL {url:?} in reporter_info")
+                    })?;
+                    let oracles = client.get_oracle_scripts().await?;
+                    registry = Some((client, oracles));
+                }
+                let (client, registry_oracles) = registry.as_ref().unwrap();
+                let mut oracle = registry_oracles
+                    .iter()
+                    .find(|oracle| &oracle.id == source)
+                    .cloned()
+                    .with_context(|| {
+                        format!(
+                            "{source} is neither a local path nor an oracle script in the registry"
+                        )
+                    })?;
+                check_not_configured(&config, &oracle.id)?;
+                download_oracle(client, &mut oracle, &base_dir).await?;
+                vec![oracle]
+            };
+
+            for oracle in oracles {
+                check_oracle(&oracle, &base_dir)?;
+                for capability in &oracle.capabilities {
+                    if !config.capabilities.iter().any(|c| &c.id == capability) {
+                        tracing::warn!(
+                            "Capability {capability} needed by oracle script {} is not configured",
+                            oracle.id
+                        );
+                    }
+                }
+                tracing::info!("Adding oracle script {}", oracle.id);
+                add_oracle(&mut config, oracle)?;
+            }
+        }
+
+        write_node_config(&self.config, &config).await
     }
 }
 
@@ -66,27 +140,321 @@ pub struct List {
     /// Specifies entities to list
     #[arg(short = 'o')]
     pub oracles: Option<Vec<String>>,
+    /// Specifies the node configuration file.
+    #[arg(short = 'c', long = "config", default_value = NODE_CONFIG_FILE)]
+    pub config: PathBuf,
 }
 
 impl List {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        let config = read_node_config(&self.config).await?;
+        print!("{}", oracles_table(&config, self.oracles.as_deref()));
+        Ok(())
     }
 }
 
 #[derive(Parser, Debug)]
 pub struct Remove {
-    /// Specifies the path to the entity.
-    #[arg(short = 'd')]
-    pub oracle_dir: PathBuf,
+    /// Specifies entities to remove, either by id or by the local path they were added from.
+    #[arg(short = 'd', visible_short_alias = 'o', required = true)]
+    pub oracles: Vec<String>,
+    /// Specifies the node configuration file.
+    #[arg(short = 'c', long = "config", default_value = NODE_CONFIG_FILE)]
+    pub config: PathBuf,
 }
 
 impl Remove {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        let mut config = read_node_config(&self.config).await?;
+        let base_dir = config_dir(&self.config);
+
+        for selector in &self.oracles {
+            let removed = remove_oracles(&mut config, selector, &base_dir);
+            if removed.is_empty() {
+                anyhow::bail!("No configured oracle script matches {selector}");
+            }
+            for id in removed {
+                tracing::info!("Removed oracle script {id}");
+                let feeds = config
+                    .data_feeds
+                    .iter()
+                    .filter(|feed| feed.oracle_id == id)
+                    .count();
+                if feeds > 0 {
+                    tracing::warn!("{feeds} data feeds still refer to oracle script {id}");
+                }
+            }
+        }
+
+        write_node_config(&self.config, &config).await
     }
 }
 
+pub(crate) async fn fetch_oracles_from_registry(registry: &str) -> Result<Vec<OracleScript>> {
+    Registry::new(registry)
+        .with_context(|| format!("Invalid registry URL {registry:?} in reporter_info"))?
+        .get_oracle_scripts()
+        .await
+}
+
+/// Reads the oracle scripts from an oracle project directory or a single .wasm file.
+async fn load_local_oracles(
+    path: &Path,
+    allowed_outbound_hosts: &[String],
+    capabilities: &[String],
+) -> Result<Vec<OracleScript>> {
+    let path = path.canonicalize()?;
+    let mut oracles = if path.is_dir() {
+        let manifest_path = path.join(SPIN_MANIFEST);
+        let manifest = fs::read_to_string(&manifest_path)
+            .await
+            .with_context(|| format!("No {SPIN_MANIFEST} in {}", path.display()))?;
+        oracles_from_spin_manifest(&manifest, &path)
+            .with_context(|| format!("Invalid manifest {}", manifest_path.display()))?
+    } else if path.extension().is_some_and(|ext| ext == WASM) {
+        let id = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .with_context(|| format!("Invalid oracle script file name {}", path.display()))?
+            .replace('_', "-");
+        vec![OracleScript {
+            id,
+            interval_time_in_seconds: None,
+            name: None,
+            description: None,
+            oracle_script_wasm: path.display().to_string(),
+            allowed_outbound_hosts: vec![],
+            capabilities: Default::default(),
+        }]
+    } else {
+        anyhow::bail!(
+            "{} is neither an oracle project directory nor a .wasm file",
+            path.display()
+        );
+    };
+
+    for oracle in oracles.iter_mut() {
+        for host in allowed_outbound_hosts {
+            if !oracle.allowed_outbound_hosts.contains(host) {
+                oracle.allowed_outbound_hosts.push(host.clone());
+            }
+        }
+        oracle.capabilities.extend(capabilities.iter().cloned());
+    }
+    Ok(oracles)
+}
+
+fn oracles_from_spin_manifest(manifest: &str, project_dir: &Path) -> Result<Vec<OracleScript>> {
+    let manifest: toml::Table = toml::from_str(manifest)?;
+    let description = manifest
+        .get("application")
+        .and_then(|app| app.get("description"))
+        .and_then(|description| description.as_str())
+        .map(str::to_string);
+    let components = manifest
+        .get("component")
+        .and_then(|components| components.as_table())
+        .context("No components")?;
+
+    let mut oracles = vec![];
+    for (id, component) in components {
+        let source = component
+            .get("source")
+            .and_then(|source| source.as_str())
+            .with_context(|| format!("Component {id} has no local source"))?;
+        let allowed_outbound_hosts = match component.get("allowed_outbound_hosts") {
+            Some(hosts) => hosts
+                .as_array()
+                .with_context(|| format!("allowed_outbound_hosts of {id} is not a list"))?
+                .iter()
+                .map(|host| {
+                    host.as_str().map(str::to_string).with_context(|| {
+                        format!("allowed_outbound_hosts of {id} has a non string value")
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            None => vec![],
+        };
+        oracles.push(OracleScript {
+            id: id.clone(),
+            interval_time_in_seconds: None,
+            name: None,
+            description: description.clone(),
+            oracle_script_wasm: project_dir.join(source).display().to_string(),
+            allowed_outbound_hosts,
+            capabilities: Default::default(),
+        });
+    }
+    Ok(oracles)
+}
+
+fn resolve_wasm_path(oracle: &OracleScript, base_dir: &Path) -> PathBuf {
+    let path = Path::new(&oracle.oracle_script_wasm);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        base_dir.join(path)
+    }
+}
+
+fn check_oracle(oracle: &OracleScript, base_dir: &Path) -> Result<()> {
+    if oracle.id.is_empty() {
+        anyhow::bail!("Oracle script without id");
+    }
+    let wasm = resolve_wasm_path(oracle, base_dir);
+    if !wasm.is_file() {
+        anyhow::bail!(
+            "Wasm {} of oracle script {} does not exist",
+            wasm.display(),
+            oracle.id
+        );
+    }
+    if oracle.allowed_outbound_hosts.is_empty() {
+        tracing::warn!("Oracle script {} has no allowed outbound hosts", oracle.id);
+    }
+    for host in &oracle.allowed_outbound_hosts {
+        check_outbound_host(host)
+            .with_context(|| format!("Oracle script {} has invalid outbound host", oracle.id))?;
+    }
+    Ok(())
+}
+
+// Spin accepts `scheme://host[:port]`, where the scheme and host may contain wildcards
+fn check_outbound_host(host: &str) -> Result<()> {
+    let Some((scheme, rest)) = host.split_once("://") else {
+        anyhow::bail!("{host} is not of the form scheme://host[:port]");
+    };
+    if scheme.is_empty() || rest.is_empty() {
+        anyhow::bail!("{host} is not of the form scheme://host[:port]");
+    }
+    if !host.contains('*') {
+        let url = Url::parse(host).with_context(|| format!("{host} is not a valid URL"))?;
+        if url.host_str().is_none() {
+            anyhow::bail!("{host} has no host");
+        }
+    }
+    Ok(())
+}
+
+/// Downloads the wasm of an oracle script from the registry to `base_dir` and points the oracle
+/// script at it. The file is named after the id rather than the `oracle_script_wasm` the registry
+/// sent, so that the registry cannot write outside of `base_dir`.
+pub(crate) async fn download_oracle(
+    registry: &Registry,
+    oracle: &mut OracleScript,
+    base_dir: &Path,
+) -> Result<()> {
+    let file_name = wasm_file_name(&oracle.id)?;
+    let wasm = registry.download_oracle_wasm(&oracle.id).await?;
+    let path = base_dir.join(&file_name);
+    fs::write(&path, wasm)
+        .await
+        .with_context(|| format!("Could not write {}", path.display()))?;
+    oracle.oracle_script_wasm = file_name;
+    tracing::info!("Downloaded oracle script {}", oracle.id);
+    Ok(())
+}
+
+fn wasm_file_name(oracle_id: &str) -> Result<String> {
+    let is_plain_name = !oracle_id.is_empty()
+        && oracle_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_plain_name {
+        anyhow::bail!("Oracle script id {oracle_id:?} cannot be used as a file name");
+    }
+    Ok(format!("{oracle_id}.{WASM}"))
+}
+
+fn check_not_configured(config: &BlocksenseConfig, oracle_id: &str) -> Result<()> {
+    if config.oracles.iter().any(|o| o.id == oracle_id) {
+        anyhow::bail!("Oracle script {oracle_id} is already configured, remove it first");
+    }
+    Ok(())
+}
+
+fn add_oracle(config: &mut BlocksenseConfig, oracle: OracleScript) -> Result<()> {
+    check_not_configured(config, &oracle.id)?;
+    config.oracles.push(oracle);
+    Ok(())
+}
+
+/// Removes the oracle scripts with the given id or whose wasm is at or under the given path.
+/// Returns the ids of the removed oracle scripts.
+fn remove_oracles(config: &mut BlocksenseConfig, selector: &str, base_dir: &Path) -> Vec<String> {
+    let local_path = Path::new(selector).canonicalize().ok();
+    let mut removed = vec![];
+    config.oracles.retain(|oracle| {
+        let matches = oracle.id == selector
+            || local_path.as_ref().is_some_and(|local_path| {
+                resolve_wasm_path(oracle, base_dir)
+                    .canonicalize()
+                    .is_ok_and(|wasm| wasm.starts_with(local_path))
+            });
+        if matches {
+            removed.push(oracle.id.clone());
+        }
+        !matches
+    });
+    removed
+}
+
+fn oracles_table(config: &BlocksenseConfig, filter: Option<&[String]>) -> String {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(
+        [
+            "ID",
+            "INTERVAL",
+            "FEEDS",
+            "CAPABILITIES",
+            "OUTBOUND HOSTS",
+            "WASM",
+        ]
+        .into_iter()
+        .map(|title| Cell::new(title).style_spec("bc"))
+        .collect(),
+    ));
+    for oracle in &config.oracles {
+        if filter.is_some_and(|ids| !ids.contains(&oracle.id)) {
+            continue;
+        }
+        let interval = oracle
+            .interval_time_in_seconds
+            .unwrap_or(config.reporter_info.interval_time_in_seconds);
+        let feeds = config
+            .data_feeds
+            .iter()
+            .filter(|feed| feed.oracle_id == oracle.id)
+            .count();
+        let capabilities = oracle
+            .capabilities
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|capability| {
+                if config.capabilities.iter().any(|c| &c.id == capability) {
+                    capability.clone()
+                } else {
+                    format!("{capability} (missing)")
+                }
+            })
+            .collect::<Vec<_>>();
+        table.add_row(Row::new(vec![
+            Cell::new(&oracle.id),
+            Cell::new(&format!("{interval}s")).style_spec("r"),
+            Cell::new(&feeds.to_string()).style_spec("r"),
+            Cell::new(&capabilities.join(", ")),
+            Cell::new(&oracle.allowed_outbound_hosts.join(", ")),
+            Cell::new(&oracle.oracle_script_wasm),
+        ]));
+    }
+    if table.is_empty() {
+        return "No oracle scripts configured\n".to_string();
+    }
+    table.to_string().replace("\r\n", "\n")
+}
+
 #[derive(Parser, Debug)]
 pub struct Init {
     /// Specifies which template to use
@@ -115,11 +483,53 @@ pub struct Publish {
     /// Specifies the path to the entity.
     #[arg(short = 'd')]
     pub oracle_dir: PathBuf,
+    /// Specifies the registry URL.
+    #[arg(short = 'r', long = "registry")]
+    pub registry: String,
+    /// Capabilities needed by the oracle script, in addition to the ones in spin.toml.
+    #[arg(long = "capability")]
+    pub capabilities: Vec<String>,
 }
 
 impl Publish {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        let registry = Registry::new(&self.registry)?;
+        let project_dir = self
+            .oracle_dir
+            .canonicalize()
+            .with_context(|| format!("No oracle project at {}", self.oracle_dir.display()))?;
+        let manifest_path = project_dir.join(SPIN_MANIFEST);
+        let spin_manifest = fs::read_to_string(&manifest_path)
+            .await
+            .with_context(|| format!("No {SPIN_MANIFEST} in {}", project_dir.display()))?;
+        let oracles = oracle_manifests_from_spin_manifest(&spin_manifest, &project_dir)
+            .with_context(|| format!("Invalid manifest {}", manifest_path.display()))?;
+
+        for (mut manifest, wasm_path) in oracles {
+            for host in &manifest.allowed_outbound_hosts {
+                check_outbound_host(host).with_context(|| {
+                    format!("Oracle script {} has invalid outbound host", manifest.id)
+                })?;
+            }
+            let wasm = fs::read(&wasm_path).await.with_context(|| {
+                format!(
+                    "Wasm {} of oracle script {} does not exist, build the oracle script first",
+                    wasm_path.display(),
+                    manifest.id
+                )
+            })?;
+            manifest
+                .capabilities
+                .extend(self.capabilities.iter().cloned());
+            manifest.content_hash = content_hash(&wasm);
+            registry.publish_oracle(&manifest, &wasm).await?;
+            tracing::info!(
+                "Published oracle script {} with content hash {}",
+                manifest.id,
+                manifest.content_hash
+            );
+        }
+        Ok(())
     }
 }
 
@@ -128,10 +538,262 @@ pub struct Retire {
     /// Specifies the path to the entity.
     #[arg(short = 'o')]
     pub oracle_id: String,
+    /// Specifies the registry URL.
+    #[arg(short = 'r', long = "registry")]
+    pub registry: String,
 }
 
 impl Retire {
     pub async fn run(self) -> Result<()> {
-        unimplemented!()
+        Registry::new(&self.registry)?
+            .retire_oracle(&self.oracle_id)
+            .await?;
+        tracing::info!("Retired oracle script {}", self.oracle_id);
+        Ok(())
+    }
+}
+
+/// Builds the registry manifests of the components of an oracle project, together with the paths
+/// to their wasm. Feeds and capabilities come from the `[[trigger.oracle]]` entries of each
+/// component. The content hash is left empty, since the wasm may not be built yet.
+fn oracle_manifests_from_spin_manifest(
+    manifest: &str,
+    project_dir: &Path,
+) -> Result<Vec<(OracleManifest, PathBuf)>> {
+    let oracles = oracles_from_spin_manifest(manifest, project_dir)?;
+    let manifest: toml::Table = toml::from_str(manifest)?;
+    let interval_time_in_seconds = manifest
+        .get("application")
+        .and_then(|app| app.get("trigger"))
+        .and_then(|trigger| trigger.get("settings"))
+        .and_then(|settings| settings.get("interval_time_in_seconds"))
+        .and_then(|interval| interval.as_integer())
+        .and_then(|interval| u64::try_from(interval).ok());
+    let triggers = match manifest.get("trigger").and_then(|t| t.get("oracle")) {
+        Some(triggers) => triggers
+            .as_array()
+            .context("trigger.oracle is not a list")?
+            .clone(),
+        None => vec![],
+    };
+
+    let mut manifests = vec![];
+    for oracle in oracles {
+        let mut feeds = vec![];
+        let mut capabilities = BTreeSet::new();
+        for trigger in triggers
+            .iter()
+            .filter(|t| t.get("component").and_then(|c| c.as_str()) == Some(oracle.id.as_str()))
+        {
+            for feed in trigger
+                .get("data_feeds")
+                .and_then(|data_feeds| data_feeds.as_array())
+                .into_iter()
+                .flatten()
+            {
+                feeds.push(parse_trigger_feed_id(feed.get("id")).with_context(|| {
+                    format!("Invalid data feed id for oracle script {}", oracle.id)
+                })?);
+            }
+            for capability in trigger
+                .get("capabilities")
+                .and_then(|capabilities| capabilities.as_array())
+                .into_iter()
+                .flatten()
+            {
+                // Either the bare id or a table with the id and a placeholder value
+                let id = capability
+                    .as_str()
+                    .or_else(|| capability.get("id").and_then(|id| id.as_str()))
+                    .with_context(|| {
+                        format!("Invalid capability for oracle script {}", oracle.id)
+                    })?;
+                capabilities.insert(id.to_string());
+            }
+        }
+        manifests.push((
+            OracleManifest {
+                id: oracle.id.clone(),
+                name: oracle.name.clone(),
+                description: oracle.description.clone(),
+                interval_time_in_seconds,
+                feeds,
+                capabilities,
+                allowed_outbound_hosts: oracle.allowed_outbound_hosts.clone(),
+                content_hash: String::new(),
+            },
+            PathBuf::from(oracle.oracle_script_wasm),
+        ));
+    }
+    Ok(manifests)
+}
+
+// Feed ids in spin.toml are strings, since they do not fit in a TOML integer
+fn parse_trigger_feed_id(id: Option<&toml::Value>) -> Result<FeedId> {
+    match id {
+        Some(toml::Value::String(id)) => Ok(id.parse()?),
+        Some(toml::Value::Integer(id)) => Ok(FeedId::try_from(*id)?),
+        _ => anyhow::bail!("Missing data feed id"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn empty_config() -> BlocksenseConfig {
+        serde_json::from_str("{}").unwrap()
+    }
+
+    #[test]
+    fn outbound_hosts_are_checked() {
+        assert!(check_outbound_host("https://pro-api.coinmarketcap.com").is_ok());
+        assert!(check_outbound_host("https://yfapi.net:443").is_ok());
+        assert!(check_outbound_host("https://*.example.com").is_ok());
+        assert!(check_outbound_host("pro-api.coinmarketcap.com").is_err());
+        assert!(check_outbound_host("https://").is_err());
+    }
+
+    #[test]
+    fn wasm_file_name_is_derived_from_plain_ids_only() {
+        assert_eq!(
+            wasm_file_name("cex-price-feeds").unwrap(),
+            "cex-price-feeds.wasm"
+        );
+        assert!(wasm_file_name("").is_err());
+        assert!(wasm_file_name("..").is_err());
+        assert!(wasm_file_name("../../.ssh/authorized_keys").is_err());
+        assert!(wasm_file_name("/etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn local_oracle_project_is_added_and_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("my-oracle");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(
+            project.join(SPIN_MANIFEST),
+            r#"
+spin_manifest_version = 2
+
+[application]
+name = "my-oracle"
+description = "Test oracle"
+
+[component.my-oracle]
+source = "target/my_oracle.wasm"
+allowed_outbound_hosts = ["https://api.example.com"]
+"#,
+        )
+        .unwrap();
+
+        let oracles = load_local_oracles(&project, &[], &["API_KEY".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(oracles.len(), 1);
+        let oracle = oracles[0].clone();
+        assert_eq!(oracle.id, "my-oracle");
+        assert_eq!(
+            oracle.allowed_outbound_hosts,
+            vec!["https://api.example.com"]
+        );
+        assert!(oracle.capabilities.contains("API_KEY"));
+
+        // The project is not built yet
+        assert!(check_oracle(&oracle, dir.path()).is_err());
+        std::fs::create_dir(project.join("target")).unwrap();
+        std::fs::write(project.join("target/my_oracle.wasm"), b"\0asm").unwrap();
+        check_oracle(&oracle, dir.path()).unwrap();
+
+        let mut config = empty_config();
+        add_oracle(&mut config, oracle.clone()).unwrap();
+        assert!(add_oracle(&mut config, oracle).is_err());
+
+        let table = oracles_table(&config, None);
+        assert!(table.contains("OUTBOUND HOSTS"));
+        assert!(table.contains("API_KEY (missing)"));
+
+        let removed = remove_oracles(&mut config, project.to_str().unwrap(), dir.path());
+        assert_eq!(removed, vec!["my-oracle"]);
+        assert!(config.oracles.is_empty());
+    }
+
+    #[test]
+    fn oracles_are_removed_by_id() {
+        let mut config = empty_config();
+        for id in ["cmc", "yahoo"] {
+            add_oracle(
+                &mut config,
+                OracleScript {
+                    id: id.to_string(),
+                    interval_time_in_seconds: None,
+                    name: None,
+                    description: None,
+                    oracle_script_wasm: format!("{id}_oracle.wasm"),
+                    allowed_outbound_hosts: vec![],
+                    capabilities: Default::default(),
+                },
+            )
+            .unwrap();
+        }
+
+        assert!(remove_oracles(&mut config, "exsat", Path::new(".")).is_empty());
+        assert_eq!(
+            remove_oracles(&mut config, "cmc", Path::new(".")),
+            vec!["cmc"]
+        );
+        let table = oracles_table(&config, Some(&["yahoo".to_string()]));
+        assert!(table.contains("yahoo_oracle.wasm"));
+        assert!(!table.contains("cmc"));
+        assert_eq!(
+            oracles_table(&config, Some(&["cmc".to_string()])),
+            "No oracle scripts configured\n"
+        );
+    }
+
+    #[test]
+    fn oracle_manifest_is_read_from_spin_manifest() {
+        let manifests = oracle_manifests_from_spin_manifest(
+            r#"
+spin_manifest_version = 2
+
+[application]
+name = "my-oracle"
+
+[application.trigger.settings]
+interval_time_in_seconds = 10
+
+[[trigger.oracle]]
+component = "my-oracle"
+
+[[trigger.oracle.data_feeds]]
+id = "2000000"
+data = "{}"
+
+[[trigger.oracle.data_feeds]]
+id = 31
+
+[[trigger.oracle.capabilities]]
+data = "x"
+id = "API_KEY"
+
+[component.my-oracle]
+source = "target/my_oracle.wasm"
+allowed_outbound_hosts = ["https://api.example.com"]
+"#,
+            Path::new("/oracles/my-oracle"),
+        )
+        .unwrap();
+
+        assert_eq!(manifests.len(), 1);
+        let (manifest, wasm) = &manifests[0];
+        assert_eq!(manifest.id, "my-oracle");
+        assert_eq!(manifest.interval_time_in_seconds, Some(10));
+        assert_eq!(manifest.feeds, vec![2000000, 31]);
+        assert_eq!(
+            manifest.capabilities,
+            BTreeSet::from(["API_KEY".to_string()])
+        );
+        assert_eq!(wasm, Path::new("/oracles/my-oracle/target/my_oracle.wasm"));
     }
 }
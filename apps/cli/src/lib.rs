@@ -1,3 +1,4 @@
 pub mod commands;
+pub(crate) mod node_config;
 pub(crate) mod opts;
 pub(crate) mod spin_manifest;
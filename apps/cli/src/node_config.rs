@@ -0,0 +1,35 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use tokio::fs;
+
+use blocksense_registry::config::BlocksenseConfig;
+
+/// File the node operator configuration is kept in, relative to the working directory.
+pub const NODE_CONFIG_FILE: &str = "blocksense-config.json";
+
+pub async fn read_node_config(path: &Path) -> Result<BlocksenseConfig> {
+    let contents = fs::read_to_string(path)
+        .await
+        .with_context(|| format!("No blocksense configuration at {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Invalid blocksense configuration in {}", path.display()))
+}
+
+pub async fn write_node_config(path: &Path, config: &BlocksenseConfig) -> Result<()> {
+    let json = serde_json::to_string_pretty(config)?;
+    fs::write(path, json).await.with_context(|| {
+        format!(
+            "Could not write blocksense configuration to {}",
+            path.display()
+        )
+    })
+}
+
+/// Directory relative paths in the configuration are resolved against.
+pub fn config_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
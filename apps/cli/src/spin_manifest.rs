@@ -80,6 +80,28 @@ impl From<BlocksenseConfig> for AppManifest {
             toml::Value::String(config.reporter_info.metrics_url),
         );
 
+        if let Some(clock_skew_tolerance_ms) = config.reporter_info.clock_skew_tolerance_ms {
+            table.insert(
+                "clock_skew_tolerance_ms".into(),
+                toml::Value::Integer(clock_skew_tolerance_ms as i64),
+            );
+        }
+
+        if !config.reporter_info.upstream_hosts.is_empty() {
+            table.insert(
+                "upstream_hosts".into(),
+                toml::Value::try_from(&config.reporter_info.upstream_hosts)
+                    .expect("Upstream host settings are serializable to TOML"),
+            );
+        }
+
+        if let Some(secrets) = &config.reporter_info.secrets {
+            table.insert(
+                "secrets".into(),
+                toml::Value::try_from(secrets).expect("Secrets settings are serializable to TOML"),
+            );
+        }
+
         if let Some(kafka_endpoint) = config.reporter_info.kafka_endpoint {
             table.insert("kafka_endpoint".into(), toml::Value::String(kafka_endpoint));
         }
@@ -92,6 +114,14 @@ impl From<BlocksenseConfig> for AppManifest {
             );
         }
 
+        if let Some(second_consensus_signer) = &config.reporter_info.second_consensus_signer {
+            table.insert(
+                "second_consensus_signer".into(),
+                toml::Value::try_from(second_consensus_signer)
+                    .expect("Signer settings are serializable to TOML"),
+            );
+        }
+
         table.insert(
             "secret_key".to_string(),
             toml::Value::String(config.reporter_info.secret_key),
@@ -120,6 +150,15 @@ impl From<BlocksenseConfig> for AppManifest {
                     "decimals".to_string(),
                     toml::Value::Integer(data_feed.additional_feed_info.decimals as i64),
                 );
+                // Lets the reporter time its votes to the slots of the feed on the sequencer
+                table.insert(
+                    "interval_ms".to_string(),
+                    toml::Value::Integer(data_feed.schedule.interval_ms as i64),
+                );
+                table.insert(
+                    "first_report_start_unix_time_ms".to_string(),
+                    toml::Value::Integer(data_feed.schedule.first_report_start_unix_time_ms as i64),
+                );
                 table.insert(
                     "data".to_string(),
                     toml::Value::String(
@@ -1,10 +1,14 @@
-use blocksense_data_providers_sdk::price_data::types::{PricePair, ProvidersSymbols};
+use blocksense_data_providers_sdk::price_data::{
+    filter::PriceFilterConfig,
+    types::{PricePair, ProvidersSymbols},
+};
 
 #[derive(Debug)]
 pub struct ResourcePairData {
     pub pair: PricePair,
     pub id: String,
     pub symbols_per_exchange: ProvidersSymbols,
+    pub price_filter: PriceFilterConfig,
 }
 
 #[derive(Debug)]
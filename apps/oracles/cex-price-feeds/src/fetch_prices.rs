@@ -3,6 +3,7 @@ use futures::stream::FuturesUnordered;
 use serde::{Deserialize, Serialize};
 
 use blocksense_data_providers_sdk::price_data::{
+    conversion::ConversionGraph,
     fetchers::{
         exchanges::{
             binance::BinancePriceFetcher, binance_us::BinanceUsPriceFetcher,
@@ -56,7 +57,12 @@ impl SymbolsData {
     }
 }
 
-pub async fn get_prices(resources: &ResourceData, timeout_secs: u64) -> Result<PairsToResults> {
+/// Prices of every feed, along with the rates between all the fetched currencies which are used
+/// to convert them to the quote of the feed.
+pub async fn get_prices(
+    resources: &ResourceData,
+    timeout_secs: u64,
+) -> Result<(PairsToResults, ConversionGraph)> {
     let symbols = SymbolsData::from_resources(&resources.all_symbols)?;
 
     let futures_set = FuturesUnordered::from_iter([
@@ -77,6 +83,7 @@ pub async fn get_prices(resources: &ResourceData, timeout_secs: u64) -> Result<P
     ]);
 
     let fetched_provider_prices = fetch_all_prices(futures_set).await;
+    let conversion_graph = ConversionGraph::from_provider_prices(&fetched_provider_prices);
 
     let mut final_results = PairsToResults::new();
     for price_data_for_exchange in fetched_provider_prices {
@@ -86,7 +93,7 @@ pub async fn get_prices(resources: &ResourceData, timeout_secs: u64) -> Result<P
             &mut final_results,
         );
     }
-    Ok(final_results)
+    Ok((final_results, conversion_graph))
 }
 
 fn fill_results(
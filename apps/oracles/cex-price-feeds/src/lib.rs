@@ -7,10 +7,14 @@ use std::collections::{HashMap, HashSet};
 use anyhow::{Context, Result};
 
 use serde::{Deserialize, Serialize};
-use tracing::info;
+use tracing::{info, warn};
 
+use blocksense_data_providers_sdk::price_data::conversion::{split_symbol, ConversionGraph};
+use blocksense_data_providers_sdk::price_data::filter::{
+    filter_price_points, now_ms, PriceFilterConfig,
+};
 use blocksense_data_providers_sdk::price_data::types::{
-    PairsToResults, PricePair, ProviderName, ProvidersSymbols,
+    PairsToResults, PricePair, ProviderName, ProvidersPricePoints, ProvidersSymbols,
 };
 use blocksense_data_providers_sdk::price_data::wap::vwap::compute_vwap;
 
@@ -30,6 +34,8 @@ type ExchangeData = HashMap<ProviderName, HashMap<String, Vec<String>>>;
 #[derive(Serialize, Deserialize, Debug)]
 struct ExchangesData {
     exchanges: Option<ExchangeData>,
+    #[serde(default)]
+    price_filter: PriceFilterConfig,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -47,26 +53,82 @@ async fn oracle_request(settings: Settings) -> Result<Payload> {
     let timeout_secs = settings.interval_time_in_seconds - 1;
     let resources = get_resources_from_settings(&settings)?;
 
-    let results = get_prices(&resources, timeout_secs).await?;
-    let payload = process_results(&results)?;
+    let (results, conversion_graph) = get_prices(&resources, timeout_secs).await?;
+    let payload = process_results(&resources, &results, &conversion_graph)?;
 
     print_results(&resources.pairs, &results, &payload);
 
     Ok(payload)
 }
 
-fn process_results(results: &PairsToResults) -> Result<Payload> {
-    let mut payload = Payload::new();
-    for (feed_id, results) in results.iter() {
-        let price_points = results.providers_data.values();
+/// Price points of a feed with their prices converted to the quote of the feed. Points whose
+/// symbol cannot be split are taken to be in the quote of the feed already.
+fn convert_to_feed_quote(
+    pair: &PricePair,
+    providers_data: &ProvidersPricePoints,
+    conversion_graph: &ConversionGraph,
+) -> Result<ProvidersPricePoints> {
+    let feed_base = pair.base.to_uppercase();
+    let feed_quote = pair.quote.to_uppercase();
+    let mut converted = ProvidersPricePoints::new();
+    let mut first_error = None;
+    for (key, point) in providers_data {
+        // Keys are of the form "<provider> <symbol> price"
+        let mut words = key.split(' ');
+        let split = match (words.next(), words.next()) {
+            (Some(provider), Some(symbol)) => split_symbol(provider, symbol),
+            _ => None,
+        };
+        let Some((_, quote)) = split else {
+            converted.insert(key.clone(), point.clone());
+            continue;
+        };
+        match conversion_graph.convert(point, &quote, &feed_quote, &[&feed_base]) {
+            Ok(point) => {
+                converted.insert(key.clone(), point);
+            }
+            Err(err) => {
+                warn!("Dropped price {} of {key}: {err}", point.price);
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    match first_error {
+        Some(err) if converted.is_empty() => Err(err),
+        _ => Ok(converted),
+    }
+}
 
-        payload.values.push(match compute_vwap(price_points) {
+fn process_results(
+    resources: &ResourceData,
+    results: &PairsToResults,
+    conversion_graph: &ConversionGraph,
+) -> Result<Payload> {
+    let now_ms = now_ms();
+    let mut payload = Payload::new();
+    for resource in &resources.pairs {
+        let Some(results) = results.get(&resource.id) else {
+            continue;
+        };
+        let price =
+            convert_to_feed_quote(&resource.pair, &results.providers_data, conversion_graph)
+                .and_then(|providers_data| {
+                    filter_price_points(
+                        &results.symbol,
+                        &providers_data,
+                        &resource.price_filter,
+                        now_ms,
+                    )
+                })
+                .and_then(|filtered| compute_vwap(filtered.kept.values()));
+
+        payload.values.push(match price {
             Ok(price) => DataFeedResult {
-                id: feed_id.to_string(),
+                id: resource.id.clone(),
                 value: DataFeedResultValue::Numerical(price),
             },
             Err(err) => DataFeedResult {
-                id: feed_id.to_string(),
+                id: resource.id.clone(),
                 value: DataFeedResultValue::Error(err.to_string()),
             },
         });
@@ -107,6 +169,7 @@ fn get_resources_from_settings(settings: &Settings) -> Result<ResourceData> {
             pair: feed_config.pair,
             id: feed_setting.id.clone(),
             symbols_per_exchange,
+            price_filter: feed_config.arguments.price_filter,
         });
     }
 
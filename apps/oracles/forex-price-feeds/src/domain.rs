@@ -1,8 +1,11 @@
 use std::collections::HashMap;
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::info;
 
+use blocksense_data_providers_sdk::market_calendar::MarketCalendar;
 use blocksense_data_providers_sdk::price_data::types::{PricePair, ProvidersSymbols};
 use blocksense_sdk::oracle::Settings;
 
@@ -11,20 +14,36 @@ use blocksense_sdk::oracle::Settings;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProvidersConfig {
     pub providers: Option<Vec<String>>,
+    /// Prices older than this are dropped before they are aggregated
+    #[serde(default)]
+    pub max_price_age_secs: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FeedConfigData {
     pub pair: PricePair,
+    #[serde(default)]
+    pub market_hours: Option<String>,
     pub arguments: ProvidersConfig,
 }
 
+impl FeedConfigData {
+    /// Forex feeds follow the FX calendar unless they name another market.
+    pub fn market_calendar(&self) -> MarketCalendar {
+        self.market_hours
+            .as_deref()
+            .and_then(MarketCalendar::from_market_hours)
+            .unwrap_or(MarketCalendar::Fx)
+    }
+}
+
 /*  Oracle resource data related types */
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResourcePairData {
     pub pair: PricePair,
     pub id: String,
+    pub max_price_age_secs: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -33,7 +52,11 @@ pub struct ResourceData {
     pub symbols: ProvidersSymbols,
 }
 
-pub fn get_resources_from_settings(settings: &Settings) -> Result<ResourceData> {
+/// Resources of the feeds whose market is open at `now`.
+pub fn get_resources_from_settings(
+    settings: &Settings,
+    now: DateTime<Utc>,
+) -> Result<ResourceData> {
     let mut feeds_data = Vec::new();
     let mut providers_symbols: ProvidersSymbols = HashMap::new();
 
@@ -41,6 +64,16 @@ pub fn get_resources_from_settings(settings: &Settings) -> Result<ResourceData>
         let feed_config_data: FeedConfigData =
             serde_json::from_str(&feed_setting.data).context("Couldn't parse data feed")?;
 
+        let calendar = feed_config_data.market_calendar();
+        if !calendar.is_open(now) {
+            info!(
+                "Skipping feed {} as the {} market is closed",
+                feed_setting.id,
+                calendar.name()
+            );
+            continue;
+        }
+
         let base = &feed_config_data.pair.base;
         let quote = &feed_config_data.pair.quote;
         let symbol = format!("{base}:{quote}");
@@ -48,6 +81,7 @@ pub fn get_resources_from_settings(settings: &Settings) -> Result<ResourceData>
         feeds_data.push(ResourcePairData {
             pair: feed_config_data.pair.clone(),
             id: feed_setting.id.clone(),
+            max_price_age_secs: feed_config_data.arguments.max_price_age_secs,
         });
 
         if let Some(providers) = &feed_config_data.arguments.providers {
@@ -3,17 +3,24 @@ mod fetch_prices;
 mod logging;
 
 use anyhow::Result;
+use chrono::Utc;
 
-use blocksense_data_providers_sdk::price_data::{types::PairsToResults, wap::vwap::compute_vwap};
+use blocksense_data_providers_sdk::price_data::{
+    filter::{filter_price_points, now_ms, PriceFilterConfig},
+    types::PairsToResults,
+    wap::vwap::compute_vwap,
+};
 use tracing::info;
 
 use blocksense_sdk::{
-    oracle::{get_capabilities_from_settings, DataFeedResult, DataFeedResultValue, Payload, Settings},
+    oracle::{
+        get_capabilities_from_settings, DataFeedResult, DataFeedResultValue, Payload, Settings,
+    },
     oracle_component,
 };
 
 use crate::{
-    domain::{get_resources_from_settings},
+    domain::{get_resources_from_settings, ResourceData},
     fetch_prices::get_prices,
     logging::print_results,
 };
@@ -24,29 +31,44 @@ async fn oracle_request(settings: Settings) -> Result<Payload> {
 
     info!("Starting oracle component - Forex Price Feeds");
 
-    let resources = get_resources_from_settings(&settings)?;
+    let resources = get_resources_from_settings(&settings, Utc::now())?;
     let capabilities = get_capabilities_from_settings(&settings);
     let timeout_secs = settings.interval_time_in_seconds - 1;
 
     let results = get_prices(&resources, &capabilities, timeout_secs).await?;
-    let payload = process_results(&results)?;
+    let payload = process_results(&resources, &results)?;
 
     print_results(&resources.pairs, &results, &payload);
     Ok(payload)
 }
 
-fn process_results(results: &PairsToResults) -> Result<Payload> {
+/// Drops the stale prices of each feed and aggregates the rest.
+fn process_results(resources: &ResourceData, results: &PairsToResults) -> Result<Payload> {
+    let now_ms = now_ms();
     let mut payload = Payload::new();
-    for (feed_id, results) in results.iter() {
-        let price_points = results.providers_data.values();
+    for resource in &resources.pairs {
+        let Some(results) = results.get(&resource.id) else {
+            continue;
+        };
+        let price_filter = PriceFilterConfig {
+            max_age_secs: resource.max_price_age_secs,
+            ..Default::default()
+        };
+        let price = filter_price_points(
+            &results.symbol,
+            &results.providers_data,
+            &price_filter,
+            now_ms,
+        )
+        .and_then(|filtered| compute_vwap(filtered.kept.values()));
 
-        payload.values.push(match compute_vwap(price_points) {
+        payload.values.push(match price {
             Ok(price) => DataFeedResult {
-                id: feed_id.to_string(),
+                id: resource.id.clone(),
                 value: DataFeedResultValue::Numerical(price),
             },
             Err(err) => DataFeedResult {
-                id: feed_id.to_string(),
+                id: resource.id.clone(),
                 value: DataFeedResultValue::Error(err.to_string()),
             },
         });
@@ -1,13 +1,15 @@
 mod fetch_prices;
 mod logging;
 mod types;
-mod utils;
 
 use std::collections::HashMap;
 
 use anyhow::{Context, Error, Result};
 use tracing::{info, warn};
 
+use blocksense_data_providers_sdk::price_data::filter::{
+    filter_price_points, now_ms, PriceFilterConfig,
+};
 use blocksense_data_providers_sdk::price_data::types::{PairsToResults, ProvidersSymbols};
 use blocksense_data_providers_sdk::price_data::wap::vwap::compute_vwap;
 use blocksense_sdk::{
@@ -16,21 +18,19 @@ use blocksense_sdk::{
     },
     oracle_component,
 };
-use chrono::Utc;
-use chrono_tz::US::Eastern;
+use chrono::{DateTime, Utc};
 
 use types::{FeedConfigData, ResourceData, ResourcePairData};
 
 use crate::fetch_prices::get_prices;
 use crate::logging::print_results;
-use crate::utils::markets_are_closed;
 
 #[oracle_component]
 async fn oracle_request(settings: Settings) -> Result<Payload> {
     tracing_subscriber::fmt::init();
 
-    let now_et = Utc::now().with_timezone(&Eastern);
-    if markets_are_closed(now_et) {
+    let resources = get_resources_from_settings(&settings, Utc::now())?;
+    if resources.pairs.is_empty() && !settings.data_feeds.is_empty() {
         warn!("❌ Markets are closed. Prices can't be fetched.");
         return Err(Error::msg("Markets are closed. Prices can't be fetched."));
     }
@@ -38,29 +38,43 @@ async fn oracle_request(settings: Settings) -> Result<Payload> {
     info!("Starting oracle component - Stock Price Feeds");
 
     let capabilities = get_capabilities_from_settings(&settings);
-    let resources = get_resources_from_settings(&settings)?;
     let timeout_secs = settings.interval_time_in_seconds - 1;
 
     let results = get_prices(&resources, &capabilities, timeout_secs).await?;
-    let payload = process_results(&results)?;
+    let payload = process_results(&resources, &results)?;
 
     print_results(&resources.pairs, &results, &payload);
 
     Ok(payload)
 }
 
-fn process_results(results: &PairsToResults) -> Result<Payload> {
+/// Drops the stale prices of each feed and aggregates the rest.
+fn process_results(resources: &ResourceData, results: &PairsToResults) -> Result<Payload> {
+    let now_ms = now_ms();
     let mut payload = Payload::new();
-    for (feed_id, results) in results.iter() {
-        let price_points = results.providers_data.values();
-
-        payload.values.push(match compute_vwap(price_points) {
+    for resource in &resources.pairs {
+        let Some(results) = results.get(&resource.id) else {
+            continue;
+        };
+        let price_filter = PriceFilterConfig {
+            max_age_secs: resource.max_price_age_secs,
+            ..Default::default()
+        };
+        let price = filter_price_points(
+            &results.symbol,
+            &results.providers_data,
+            &price_filter,
+            now_ms,
+        )
+        .and_then(|filtered| compute_vwap(filtered.kept.values()));
+
+        payload.values.push(match price {
             Ok(price) => DataFeedResult {
-                id: feed_id.to_string(),
+                id: resource.id.clone(),
                 value: DataFeedResultValue::Numerical(price),
             },
             Err(err) => DataFeedResult {
-                id: feed_id.to_string(),
+                id: resource.id.clone(),
                 value: DataFeedResultValue::Error(err.to_string()),
             },
         });
@@ -69,7 +83,8 @@ fn process_results(results: &PairsToResults) -> Result<Payload> {
     Ok(payload)
 }
 
-fn get_resources_from_settings(settings: &Settings) -> Result<ResourceData> {
+/// Resources of the feeds whose market is open at `now`.
+fn get_resources_from_settings(settings: &Settings, now: DateTime<Utc>) -> Result<ResourceData> {
     let mut feeds_data = Vec::new();
     let mut providers_symbols: ProvidersSymbols = HashMap::new();
 
@@ -77,11 +92,22 @@ fn get_resources_from_settings(settings: &Settings) -> Result<ResourceData> {
         let feed_config_data: FeedConfigData =
             serde_json::from_str(&feed_setting.data).context("Couldn't parse data feed")?;
 
+        let calendar = feed_config_data.market_calendar();
+        if !calendar.is_open(now) {
+            info!(
+                "Skipping feed {} as the {} market is closed",
+                feed_setting.id,
+                calendar.name()
+            );
+            continue;
+        }
+
         let base = &feed_config_data.pair.base;
 
         feeds_data.push(ResourcePairData {
             pair: feed_config_data.pair.clone(),
             id: feed_setting.id.clone(),
+            max_price_age_secs: feed_config_data.arguments.max_price_age_secs,
         });
 
         if let Some(providers) = &feed_config_data.arguments.providers {
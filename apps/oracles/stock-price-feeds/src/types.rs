@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use blocksense_data_providers_sdk::market_calendar::MarketCalendar;
 use blocksense_data_providers_sdk::price_data::types::{PricePair, ProvidersSymbols};
 
 /* Feed configuration data related types */
@@ -7,20 +8,36 @@ use blocksense_data_providers_sdk::price_data::types::{PricePair, ProvidersSymbo
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProvidersConfig {
     pub providers: Option<Vec<String>>,
+    /// Prices older than this are dropped before they are aggregated
+    #[serde(default)]
+    pub max_price_age_secs: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FeedConfigData {
     pub pair: PricePair,
+    #[serde(default)]
+    pub market_hours: Option<String>,
     pub arguments: ProvidersConfig,
 }
 
+impl FeedConfigData {
+    /// Stocks trade on the NYSE unless the feed names another market.
+    pub fn market_calendar(&self) -> MarketCalendar {
+        self.market_hours
+            .as_deref()
+            .and_then(MarketCalendar::from_market_hours)
+            .unwrap_or(MarketCalendar::Nyse)
+    }
+}
+
 /*  Oracle resource data related types */
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResourcePairData {
     pub pair: PricePair,
     pub id: String,
+    pub max_price_age_secs: Option<u64>,
 }
 
 #[derive(Debug)]
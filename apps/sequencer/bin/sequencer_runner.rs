@@ -1,3 +1,4 @@
+use actix_web::middleware::from_fn;
 use actix_web::{web, App, HttpServer};
 use blocksense_feed_registry::feed_registration_cmds::FeedsManagementCmds;
 use blocksense_gnosis_safe::data_types::ReporterResponse;
@@ -16,6 +17,7 @@ use blocksense_utils::logging::{
     SharedLoggingHandle,
 };
 use sequencer::http_handlers::admin::add_admin_services;
+use sequencer::http_handlers::admin_auth::{authorize, AdminAuth};
 use sequencer::http_handlers::data_feeds::add_main_services;
 
 use actix_web::web::Data;
@@ -46,14 +48,14 @@ pub async fn prepare_sequencer_state(
     sequencer_config: &SequencerConfig,
     feeds_config: AllFeedsConfig,
     metrics_prefix: Option<&str>,
-) -> (
+) -> eyre::Result<(
     UnboundedReceiver<VotedFeedUpdateWithProof>, // aggregated_votes_to_block_creator_recv
     UnboundedReceiver<FeedsManagementCmds>,      // feeds_management_cmd_to_block_creator_recv
     UnboundedReceiver<FeedsManagementCmds>,      // feeds_slots_manager_cmd_recv
     UnboundedReceiver<(ReporterResponse, SignatureWithAddress)>, // aggregate_batch_sig_recv
     HashMap<String, CountedReceiver<BatchOfUpdatesToProcess>>, // relayers_recv_channels
     Data<SequencerState>,
-) {
+)> {
     let log_handle: SharedLoggingHandle = get_shared_logging_handle();
 
     tokio::task::Builder::new()
@@ -89,22 +91,24 @@ pub async fn prepare_sequencer_state(
         feeds_slots_manager_cmd_send,
         aggregate_batch_sig_send,
         Arc::new(RwLock::new(relayers_send_channels)),
-    ));
+    )?);
+    sequencer_state.replay_reporter_actions().await;
 
-    (
+    Ok((
         aggregated_votes_to_block_creator_recv,
         feeds_management_cmd_to_block_creator_recv,
         feeds_slots_manager_cmd_recv,
         aggregate_batch_sig_recv,
         relayers_recv_channels,
         sequencer_state,
-    )
+    ))
 }
 
 pub async fn prepare_http_servers(
     sequencer_state: Data<SequencerState>,
     sequencer_config_main_port: u16,
     admin_port: u16,
+    admin_auth: Data<AdminAuth>,
 ) -> (
     JoinHandle<std::io::Result<()>>,
     JoinHandle<std::io::Result<()>>,
@@ -134,6 +138,8 @@ pub async fn prepare_http_servers(
             HttpServer::new(move || {
                 App::new()
                     .app_data(admin_sequencer_state.clone())
+                    .app_data(admin_auth.clone())
+                    .wrap(from_fn(authorize))
                     .configure(add_admin_services)
             })
             .workers(1)
@@ -224,7 +230,9 @@ async fn main() -> std::io::Result<()> {
         aggregate_batch_sig_recv,
         relayers_recv_channels,
         sequencer_state,
-    ) = prepare_sequencer_state(&sequencer_config, feeds_config, None).await;
+    ) = prepare_sequencer_state(&sequencer_config, feeds_config, None)
+        .await
+        .map_err(|e| std::io::Error::other(format!("Could not set up the sequencer: {e:?}")))?;
 
     let collected_futures = prepare_app_workers(
         sequencer_state.clone(),
@@ -237,10 +245,15 @@ async fn main() -> std::io::Result<()> {
     )
     .await;
 
+    let admin_auth = Data::new(
+        AdminAuth::from_config(&sequencer_config.admin_auth)
+            .expect("Could not set up admin authentication."),
+    );
     let (main_http_server_fut, admin_http_server_fut) = prepare_http_servers(
         sequencer_state,
         sequencer_config.main_port,
         sequencer_config.admin_port,
+        admin_auth,
     )
     .await;
 
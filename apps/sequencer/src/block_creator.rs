@@ -1,13 +1,14 @@
 use actix_web::web::Data;
 use alloy::hex;
-use blocksense_blockchain_data_model::in_mem_db::InMemDb;
+use blocksense_blockchain_data_model::block_store::BlockStore;
 use blocksense_blockchain_data_model::{
-    MAX_ASSET_FEED_UPDATES_IN_BLOCK, MAX_FEED_ID_TO_DELETE_IN_BLOCK, MAX_NEW_FEEDS_IN_BLOCK,
+    calc_merkle_root, node_to_hash, MAX_ASSET_FEED_UPDATES_IN_BLOCK,
+    MAX_FEED_ID_TO_DELETE_IN_BLOCK, MAX_NEW_FEEDS_IN_BLOCK, MAX_REPORTER_ACTIONS_IN_BLOCK,
 };
 use blocksense_config::BlockConfig;
 use blocksense_data_feeds::feeds_processing::VotedFeedUpdateWithProof;
 use blocksense_feed_registry::feed_registration_cmds::{
-    DeleteAssetFeed, FeedsManagementCmds, RegisterNewAssetFeed,
+    DeleteAssetFeed, FeedsManagementCmds, RegisterNewAssetFeed, ReporterAction,
 };
 use blocksense_feed_registry::registry::SlotTimeTracker;
 use blocksense_feed_registry::types::Repeatability;
@@ -28,6 +29,8 @@ use tokio::time::Duration;
 use tracing::{debug, error, info, info_span, warn};
 
 use crate::feeds::feed_config_conversions::feed_config_to_block;
+use crate::health::Worker;
+use crate::reporters::reporter::reporter_action_to_block;
 use crate::sequencer_state::SequencerState;
 use blocksense_data_feeds::feeds_processing::BatchedAggregatesToSend;
 
@@ -43,6 +46,7 @@ pub async fn block_creator_loop(
         .spawn(async move {
             let span = info_span!("BlockCreator");
             let _guard = span.enter();
+            let _running = sequencer_state.worker_health.start(Worker::BlockCreator);
             let mut max_feed_updates_to_batch = block_config.max_feed_updates_to_batch;
             let block_generation_period = block_config.block_generation_period;
 
@@ -68,12 +72,14 @@ pub async fn block_creator_loop(
 
             let mut new_feeds_to_register = Vec::new();
             let mut feeds_ids_to_delete = Vec::new();
+            let mut reporter_actions = Vec::new();
 
             let backlog_updates = &mut backlog_updates;
             let updates = &mut updates;
 
             let new_feeds_to_register = &mut new_feeds_to_register;
             let feeds_ids_to_delete = &mut feeds_ids_to_delete;
+            let reporter_actions = &mut reporter_actions;
 
             loop {
                 // Loop forever
@@ -82,24 +88,31 @@ pub async fn block_creator_loop(
                     _ = block_generation_time_tracker
                     .await_end_of_current_slot(&Repeatability::Periodic) => {
                          // Only emit a block if data is present
-                        if !updates.is_empty() || !new_feeds_to_register.is_empty() || !feeds_ids_to_delete.is_empty() {
+                        if !updates.is_empty() || !new_feeds_to_register.is_empty() || !feeds_ids_to_delete.is_empty() || !reporter_actions.is_empty() {
                             let mut updates_vec = std::mem::take(updates).into_iter().collect::<Vec<_>>();
 
                             debug!("Emitting block, since there is data present...");
+                            let block_height = next_block_height(
+                                &sequencer_state,
+                                block_generation_time_tracker.get_last_slot(),
+                            ).await;
                             if let Err(e) = generate_block(
                                 &mut updates_vec,
                                 new_feeds_to_register,
                                 feeds_ids_to_delete,
+                                reporter_actions,
                                 &batched_votes_send,
                                 &sequencer_state,
-                                (block_generation_time_tracker.get_last_slot() + 1) as u64,
+                                block_height,
                             ).await {
                                 panic!("Failed to generate block! {e}");
                             };
+                            sequencer_state.worker_health.block_produced();
 
                             updates.clear();
                             new_feeds_to_register.clear();
                             feeds_ids_to_delete.clear();
+                            reporter_actions.clear();
 
                             // Fill the updates that overflowed the capacity of the last block
                             while let Some(v) = backlog_updates.pop_front() {
@@ -117,7 +130,7 @@ pub async fn block_creator_loop(
                     }
 
                     feed_management_cmd = feed_management_cmds_recv.recv() => {
-                        recvd_feed_management_cmd_to_block(feed_management_cmd, new_feeds_to_register, feeds_ids_to_delete);
+                        recvd_feed_management_cmd_to_block(feed_management_cmd, new_feeds_to_register, feeds_ids_to_delete, reporter_actions);
                     }
                 }
             }
@@ -125,6 +138,17 @@ pub async fn block_creator_loop(
         .expect("Failed to spawn block creator!")
 }
 
+// The block height follows the block generation slots. A chain restored from the block store
+// can be ahead of them (e.g. genesis timestamp not configured), so never go below its tip.
+async fn next_block_height(sequencer_state: &Data<SequencerState>, last_slot: u128) -> u64 {
+    let latest_stored_height = sequencer_state
+        .blockchain_db
+        .read()
+        .await
+        .get_latest_block_height();
+    ((last_slot + 1) as u64).max(latest_stored_height + 1)
+}
+
 // When we recv feed updates that have passed aggregation, we prepare them to be placed in the next generated block
 async fn recvd_feed_update_to_block(
     recvd_feed_update: Option<VotedFeedUpdateWithProof>,
@@ -182,6 +206,7 @@ fn recvd_feed_management_cmd_to_block(
     feed_management_cmd: Option<FeedsManagementCmds>,
     new_feeds_to_register: &mut Vec<RegisterNewAssetFeed>,
     feeds_ids_to_delete: &mut Vec<DeleteAssetFeed>,
+    reporter_actions: &mut Vec<ReporterAction>,
 ) {
     match feed_management_cmd {
         Some(cmd) => match cmd {
@@ -207,6 +232,17 @@ fn recvd_feed_management_cmd_to_block(
                     );
                 }
             }
+            FeedsManagementCmds::ReporterAction(action) => {
+                if reporter_actions.len() < MAX_REPORTER_ACTIONS_IN_BLOCK {
+                    reporter_actions.push(action);
+                } else {
+                    error!(
+                        "reporter_actions.len() >= MAX_REPORTER_ACTIONS_IN_BLOCK ({} >= {})",
+                        reporter_actions.len(),
+                        MAX_REPORTER_ACTIONS_IN_BLOCK
+                    );
+                }
+            }
         },
         None => info!("Woke up on empty channel - feed_management_cmds_recv"),
     }
@@ -216,6 +252,7 @@ async fn generate_block(
     updates: &mut Vec<VotedFeedUpdateWithProof>,
     new_feeds_to_register: &mut Vec<RegisterNewAssetFeed>,
     feeds_ids_to_delete: &mut Vec<DeleteAssetFeed>,
+    reporter_actions: &mut Vec<ReporterAction>,
     batched_votes_send: &CountedSender<BatchedAggregatesToSend>,
     sequencer_state: &Data<SequencerState>,
     block_height: u64,
@@ -223,6 +260,7 @@ async fn generate_block(
     let sequencer_id = sequencer_state.sequencer_config.read().await.sequencer_id;
     let new_feeds_to_register = mem::take(new_feeds_to_register);
     let feeds_ids_to_delete = mem::take(feeds_ids_to_delete);
+    let reporter_actions = mem::take(reporter_actions);
     let updates = mem::take(updates);
 
     let mut new_feeds_in_block = Vec::new();
@@ -235,7 +273,26 @@ async fn generate_block(
         feeds_ids_to_delete_in_block.push(delete_feed_id_cmd.id);
     }
 
-    let block_is_empty = new_feeds_to_register.is_empty() && feeds_ids_to_delete.is_empty();
+    // Actions that cannot be encoded are dropped, otherwise the block would not be accepted by
+    // the other sequencers
+    let mut reporter_actions_in_block = Vec::new();
+    let reporter_actions: Vec<ReporterAction> = reporter_actions
+        .into_iter()
+        .filter(|action| match reporter_action_to_block(action) {
+            Ok(block_action) => {
+                reporter_actions_in_block.push(block_action);
+                true
+            }
+            Err(e) => {
+                error!("Dropping reporter action {action:?}: {e}");
+                false
+            }
+        })
+        .collect();
+
+    let block_is_empty = new_feeds_to_register.is_empty()
+        && feeds_ids_to_delete.is_empty()
+        && reporter_actions.is_empty();
     let mut serialized_header = Vec::new();
     let mut serialized_feed_actions = Vec::new();
     // Block holding the db write mutex:
@@ -248,6 +305,7 @@ async fn generate_block(
                 block_height,
                 new_feeds_in_block,
                 feeds_ids_to_delete_in_block,
+                reporter_actions_in_block,
             )
             .map_err(|e| eyre::eyre!(e.to_string()))?;
         serialized_header = match header.clone().serialize() {
@@ -262,12 +320,12 @@ async fn generate_block(
                 panic!("could not serialize block feed_actions: {e}")
             }
         };
-        let header_merkle_root = InMemDb::calc_merkle_root(&mut header.clone())
-            .map_err(|e| eyre::eyre!(e.to_string()))?;
+        let header_merkle_root =
+            calc_merkle_root(&mut header.clone()).map_err(|e| eyre::eyre!(e.to_string()))?;
         info!(
             "Generated new block {:?} with hash {:?}",
             header,
-            InMemDb::node_to_hash(header_merkle_root).map_err(|e| eyre::eyre!(e.to_string()))?
+            node_to_hash(header_merkle_root).map_err(|e| eyre::eyre!(e.to_string()))?
         );
         if let Err(e) = blockchain_db.add_next_block(header, feed_actions) {
             eyre::bail!(e.to_string());
@@ -328,6 +386,17 @@ async fn generate_block(
         };
     }
 
+    // Process changes of the reporter set:
+    for action in reporter_actions {
+        match sequencer_state
+            .feeds_slots_manager_cmd_send
+            .send(FeedsManagementCmds::ReporterAction(action))
+        {
+            Ok(_) => info!("forward reporter action cmd"),
+            Err(e) => error!("Could not forward reporter action cmd: {e}"),
+        };
+    }
+
     if !block_is_empty {
         let block_to_kafka = json!({
             "BlockHeader": hex::encode(serialized_header),
@@ -356,11 +425,13 @@ async fn generate_block(
 #[cfg(test)]
 mod tests {
     use crate::sequencer_state::create_sequencer_state_from_sequencer_config;
+    use blocksense_blockchain_data_model::block_store::BlockStore;
     use blocksense_config::get_test_config_with_no_providers;
     use blocksense_config::AllFeedsConfig;
     use blocksense_config::BlockConfig;
     use blocksense_data_feeds::feeds_processing::VotedFeedUpdate;
     use blocksense_data_feeds::feeds_processing::VotedFeedUpdateWithProof;
+    use blocksense_feed_registry::feed_registration_cmds::{FeedsManagementCmds, ReporterAction};
     use blocksense_feed_registry::types::{FeedType, Timestamp};
     use blocksense_utils::counter_unbounded_channel::counted_unbounded_channel;
     use std::time::Duration;
@@ -374,6 +445,7 @@ mod tests {
             block_generation_period: 100,
             genesis_block_timestamp_ms: None,
             aggregation_consensus_discard_period_blocks: 100,
+            block_store_path: None,
         };
 
         let sequencer_config = get_test_config_with_no_providers();
@@ -480,4 +552,73 @@ mod tests {
             panic!("Batched votes were not received");
         }
     }
+
+    #[actix_web::test]
+    async fn test_block_creator_includes_reporter_actions() {
+        let block_config = BlockConfig {
+            max_feed_updates_to_batch: 3,
+            block_generation_period: 100,
+            genesis_block_timestamp_ms: None,
+            aggregation_consensus_discard_period_blocks: 100,
+            block_store_path: None,
+        };
+
+        let sequencer_config = get_test_config_with_no_providers();
+        let feeds_config = AllFeedsConfig { feeds: vec![] };
+        let metrics_prefix = "test_block_creator_includes_reporter_actions";
+        let (
+            sequencer_state,
+            aggregated_votes_to_block_creator_recv,
+            feeds_management_cmd_to_block_creator_recv,
+            mut feeds_slots_manager_cmd_recv,
+            _aggregate_batch_sig_recv,
+            _,
+        ) = create_sequencer_state_from_sequencer_config(
+            sequencer_config,
+            metrics_prefix,
+            feeds_config,
+        )
+        .await;
+
+        let (batched_votes_send, _batched_votes_recv) = counted_unbounded_channel();
+        let cmd_send = sequencer_state
+            .feeds_management_cmd_to_block_creator_send
+            .clone();
+        super::block_creator_loop(
+            sequencer_state.clone(),
+            aggregated_votes_to_block_creator_recv,
+            feeds_management_cmd_to_block_creator_recv,
+            batched_votes_send,
+            block_config,
+        )
+        .await;
+
+        cmd_send
+            .send(FeedsManagementCmds::ReporterAction(
+                ReporterAction::Suspend { id: 7 },
+            ))
+            .unwrap();
+        time::sleep(Duration::from_millis(500)).await;
+
+        // The action is recorded in the block
+        {
+            let blockchain_db = sequencer_state.blockchain_db.read().await;
+            let height = blockchain_db.get_latest_block_height();
+            assert!(height > 0);
+            let header = blockchain_db.get_block_header_by_height(height).unwrap();
+            let feed_actions = blockchain_db
+                .get_feed_actions(&header.add_remove_feeds_merkle_root)
+                .unwrap();
+            let block_action = feed_actions.reporter_actions[0].as_ref().unwrap();
+            assert_eq!(block_action.reporter_id, 7);
+        }
+
+        // and forwarded to be applied
+        match feeds_slots_manager_cmd_recv.try_recv() {
+            Ok(FeedsManagementCmds::ReporterAction(action)) => {
+                assert_eq!(action, ReporterAction::Suspend { id: 7 })
+            }
+            _ => panic!("Reporter action was not forwarded"),
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use actix_web::web::Data;
 use alloy::hex;
+use blocksense_blockchain_data_model::block_store::BlockStore;
 use blocksense_blockchain_data_model::{BlockHeader, FeedActions};
 use blocksense_feed_registry::feed_registration_cmds::{
     DeleteAssetFeed, FeedsManagementCmds, RegisterNewAssetFeed,
@@ -13,6 +14,8 @@ use tokio_stream::StreamExt;
 use tracing::{debug, error, info, warn};
 
 use crate::feeds::feed_config_conversions::block_feed_to_feed_config;
+use crate::health::Worker;
+use crate::reporters::reporter::block_to_reporter_action;
 use crate::sequencer_state::SequencerState;
 
 pub async fn blocks_reader_loop(
@@ -33,6 +36,8 @@ pub async fn blocks_reader_loop(
                 return Ok(()); // Exit the function early
             };
 
+            let _running = sequencer_state.worker_health.start(Worker::BlocksReader);
+
             // Configure the Kafka consumer
             let consumer: StreamConsumer = ClientConfig::new()
                 .set("bootstrap.servers", kafka_report_endpoint)
@@ -56,11 +61,16 @@ pub async fn blocks_reader_loop(
                 if let Some(message_result) = message_stream.next().await {
                     match message_result {
                         Ok(message) => {
+                            sequencer_state.worker_health.kafka_message_received().await;
                             process_msg_from_stream(sequencer_id, &sequencer_state, message).await;
                         }
                         Err(err) => {
                             // Handle message errors
                             error!("Error while consuming: {:?}", err);
+                            sequencer_state
+                                .worker_health
+                                .kafka_error(err.to_string())
+                                .await;
                         }
                     }
                 }
@@ -114,8 +124,8 @@ async fn process_block(
                     // and all the blocks that it has emitted with block height
                     // higher than what it has in storage. A scenario of a sequencer
                     // having in storage a lower block height compared to the messages
-                    // it has posted to the message queue are on restart, since we don't
-                    // yet have persistent storage.
+                    // it has posted to the message queue are on restart, when the block
+                    // store is not persisted to disk.
                     let process_block = header.issuer_id != sequencer_id
                         || sequencer_state
                             .blockchain_db
@@ -160,6 +170,27 @@ async fn process_block(
                                         }
                                     };
                                 }
+                                for block_action in
+                                    feed_actions.reporter_actions.into_iter().flatten()
+                                {
+                                    let action = match block_to_reporter_action(&block_action) {
+                                        Ok(action) => action,
+                                        Err(e) => {
+                                            error!(
+                                                "Skipping reporter action {block_action:?}: {e}"
+                                            );
+                                            continue;
+                                        }
+                                    };
+                                    info!("reporter action = {:?}", action);
+                                    let cmd = FeedsManagementCmds::ReporterAction(action);
+                                    match sequencer_state.feeds_slots_manager_cmd_send.send(cmd) {
+                                        Ok(_) => info!("forward reporter action cmd"),
+                                        Err(e) => {
+                                            error!("Could not forward reporter action cmd: {e}")
+                                        }
+                                    };
+                                }
                             }
                         }
                     }
@@ -1,5 +1,7 @@
 use blocksense_blockchain_data_model::{BlockFeedConfig, DataChunk, Resources, DATA_CHUNK_SIZE};
-use blocksense_registry::config::{AssetPair, FeedConfig, FeedQuorum, FeedSchedule, PriceFeedInfo};
+use blocksense_registry::config::{
+    AggregationParams, AssetPair, FeedConfig, FeedQuorum, FeedSchedule, PriceFeedInfo,
+};
 
 use anyhow::Result;
 use serde_json::Value;
@@ -33,6 +35,22 @@ pub fn feed_config_to_block(feed_config: &FeedConfig) -> BlockFeedConfig {
         value_type: string_to_data_chunk(&feed_config.value_type),
         aggregate_type: string_to_data_chunk(&feed_config.quorum.aggregation),
         stride: feed_config.stride,
+        trim_percentage: feed_config
+            .quorum
+            .aggregation_params
+            .as_ref()
+            .and_then(|params| params.trim_percentage)
+            .map(f64_to_u8_array),
+        twap_window_ms: feed_config
+            .quorum
+            .aggregation_params
+            .as_ref()
+            .and_then(|params| params.twap_window_ms),
+        anomaly_score_threshold: feed_config
+            .schedule
+            .anomaly_score_threshold
+            .map(f64_to_u8_array),
+        history_depth: feed_config.history_depth.map(|depth| depth as u64),
     }
 }
 
@@ -41,6 +59,10 @@ fn f32_to_u8_array(value: f32) -> [u8; 4] {
     value.to_be_bytes()
 }
 
+fn f64_to_u8_array(value: f64) -> [u8; 8] {
+    value.to_be_bytes()
+}
+
 // Helper function to convert String to DataChunk ([u8; 32])
 fn string_to_data_chunk(input: &str) -> DataChunk {
     let mut chunk = [0u8; 32];
@@ -63,8 +85,9 @@ fn json_to_byte_arrays(json: &Value) -> Result<Resources> {
             );
         }
 
+        // The last chunk is padded with zeros, which never occur in JSON
         let mut array = [0u8; DATA_CHUNK_SIZE];
-        array.copy_from_slice(chunk);
+        array[..chunk.len()].copy_from_slice(chunk);
         result[i] = Some(array);
     }
 
@@ -77,7 +100,8 @@ fn byte_arrays_to_json(byte_arrays: &Resources) -> Result<Value> {
         .filter_map(|opt| *opt)
         .flatten()
         .collect();
-    Ok(serde_json::from_slice(&bytes)?)
+    let len = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    Ok(serde_json::from_slice(&bytes[..len])?)
 }
 
 //TODO(adikov): Properly transform BlockFeedConfig to the new FeedConfig
@@ -94,13 +118,16 @@ pub fn block_feed_to_feed_config(block_feed: &BlockFeedConfig) -> FeedConfig {
         quorum: FeedQuorum {
             percentage: u8_array_to_f32(block_feed.quorum_percentage),
             aggregation: data_chunk_to_string(&block_feed.aggregate_type),
+            aggregation_params: block_aggregation_params(block_feed),
         },
         schedule: FeedSchedule {
             interval_ms: block_feed.report_interval_ms,
             heartbeat_ms: block_feed.always_publish_heartbeat_ms,
             deviation_percentage: u8_array_to_f32(block_feed.skip_publish_if_less_then_percentage),
             first_report_start_unix_time_ms: block_feed.first_report_start_time,
+            anomaly_score_threshold: block_feed.anomaly_score_threshold.map(u8_array_to_f64),
         },
+        history_depth: block_feed.history_depth.map(|depth| depth as usize),
         additional_feed_info: PriceFeedInfo {
             pair: block_feed.pair.as_ref().map(|pair| AssetPair {
                 base: data_chunk_to_string(&pair.base),
@@ -117,6 +144,16 @@ pub fn block_feed_to_feed_config(block_feed: &BlockFeedConfig) -> FeedConfig {
     }
 }
 
+fn block_aggregation_params(block_feed: &BlockFeedConfig) -> Option<AggregationParams> {
+    if block_feed.trim_percentage.is_none() && block_feed.twap_window_ms.is_none() {
+        return None;
+    }
+    Some(AggregationParams {
+        trim_percentage: block_feed.trim_percentage.map(u8_array_to_f64),
+        twap_window_ms: block_feed.twap_window_ms,
+    })
+}
+
 fn data_chunk_to_string(bytes: &DataChunk) -> String {
     let null_terminated = bytes.split(|&b| b == 0).next().unwrap_or(&[]);
     String::from_utf8(null_terminated.to_vec()).unwrap_or_default()
@@ -125,3 +162,38 @@ fn data_chunk_to_string(bytes: &DataChunk) -> String {
 fn u8_array_to_f32(bytes: [u8; 4]) -> f32 {
     f32::from_be_bytes(bytes)
 }
+
+fn u8_array_to_f64(bytes: [u8; 8]) -> f64 {
+    f64::from_be_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blocksense_config::test_feed_config;
+
+    #[test]
+    fn test_feed_config_survives_block_conversion() {
+        let mut feed_config = test_feed_config(7, 1);
+        feed_config.quorum.aggregation = "trimmed_mean".to_owned();
+        feed_config.quorum.aggregation_params = Some(AggregationParams {
+            trim_percentage: Some(25.0),
+            twap_window_ms: None,
+        });
+        feed_config.schedule.anomaly_score_threshold = Some(0.8);
+        feed_config.history_depth = Some(500);
+
+        let converted = block_feed_to_feed_config(&feed_config_to_block(&feed_config));
+        assert_eq!(converted.quorum, feed_config.quorum);
+        assert_eq!(converted.schedule, feed_config.schedule);
+        assert_eq!(converted.history_depth, feed_config.history_depth);
+        assert_eq!(
+            converted.additional_feed_info.arguments,
+            feed_config.additional_feed_info.arguments
+        );
+
+        feed_config.quorum.aggregation_params = None;
+        let converted = block_feed_to_feed_config(&feed_config_to_block(&feed_config));
+        assert_eq!(converted.quorum.aggregation_params, None);
+    }
+}
@@ -1,35 +1,45 @@
+use crate::health::Worker;
 use crate::reporters::reporter::SharedReporters;
 use crate::sequencer_state::SequencerState;
 use actix_web::web::Data;
+use blocksense_anomaly_detection::detector::AnomalyDetector;
 use blocksense_data_feeds::feeds_processing::{
     DoSkipReason, DontSkipReason, SkipDecision, VotedFeedUpdateWithProof,
 };
 use blocksense_feed_registry::feed_registration_cmds::ProcessorResultValue;
-use blocksense_feed_registry::registry::{AllFeedsReports, FeedAggregateHistory, SlotTimeTracker};
+use blocksense_feed_registry::registry::{
+    AllFeedsReports, FeedAggregateHistory, SlotOutcome, SlotTimeTracker,
+};
 use blocksense_feed_registry::types::{
     FeedMetaData, FeedType, FeedsSlotProcessorCmds, Repeatability, Timestamp,
 };
 use blocksense_feed_registry::{aggregate::FeedAggregate, registry::FeedReports};
-use blocksense_feeds_processing::utils::{consume_reports, ConsumedReports};
+use blocksense_feeds_processing::utils::{consume_reports, new_anomaly_detector, ConsumedReports};
 use blocksense_metrics::{inc_metric, metrics::FeedsMetrics};
 use blocksense_utils::time::current_unix_time;
 use blocksense_utils::EncodedFeedId;
 use eyre::{eyre, ContextCompat, Result};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::error;
 use tracing::{debug, info};
 
 pub struct FeedSlotsProcessor {
     name: String,
     key: EncodedFeedId,
+    anomaly_detector: Arc<Mutex<AnomalyDetector>>,
 }
 
 impl FeedSlotsProcessor {
     pub fn new(name: String, key: EncodedFeedId) -> FeedSlotsProcessor {
-        FeedSlotsProcessor { name, key }
+        FeedSlotsProcessor {
+            name,
+            key,
+            anomaly_detector: new_anomaly_detector(),
+        }
     }
 
     async fn read_cmd(
@@ -64,11 +74,32 @@ impl FeedSlotsProcessor {
     async fn get_num_valid_reportes(&self, reporters: &SharedReporters) -> usize {
         let feed_id = self.key;
         debug!("Get a read lock on all reporters [feed {feed_id}]");
-        let res = reporters.read().await.len();
+        let mut res = 0;
+        for reporter in reporters.read().await.values() {
+            // Suspended reporters do not count towards the quorum
+            if !reporter.read().await.suspended {
+                res += 1;
+            }
+        }
         debug!("Release the read lock on all reports [feed {feed_id}]");
         res
     }
 
+    async fn get_reporter_stakes(&self, reporters: &SharedReporters) -> HashMap<u64, f64> {
+        let feed_id = self.key;
+        debug!("Get a read lock on all reporters [feed {feed_id}]");
+        let reporters = reporters.read().await;
+        let mut stakes = HashMap::new();
+        for (id, reporter) in reporters.iter() {
+            let reporter = reporter.read().await;
+            if !reporter.suspended {
+                stakes.insert(*id, reporter.stake);
+            }
+        }
+        debug!("Release the read lock on all reports [feed {feed_id}]");
+        stakes
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn process_end_of_slot(
         &self,
@@ -77,6 +108,8 @@ impl FeedSlotsProcessor {
         quorum_percentage: f32,
         skip_publish_if_less_then_percentage: f64,
         always_publish_heartbeat_ms: Option<u128>,
+        market_hours: Option<String>,
+        anomaly_score_threshold: Option<f64>,
         end_slot_timestamp: Timestamp,
         aggregator: FeedAggregate,
         slot: u64,
@@ -90,9 +123,13 @@ impl FeedSlotsProcessor {
             self.get_num_valid_reportes(&sequencer_state.reporters)
                 .await
         };
+        let reporter_stakes = self.get_reporter_stakes(&sequencer_state.reporters).await;
 
         let mut consumed_reports = ConsumedReports {
             is_quorum_reached: false,
+            votes: 0,
+            non_finite_votes: 0,
+            num_valid_reporters,
             skip_decision: SkipDecision::DoSkip(DoSkipReason::NothingToPost),
             ad_score: None,
             result_post_to_contract: None,
@@ -121,11 +158,15 @@ impl FeedSlotsProcessor {
                     quorum_percentage,
                     skip_publish_if_less_then_percentage,
                     always_publish_heartbeat_ms,
+                    market_hours,
                     end_slot_timestamp,
                     num_valid_reporters,
+                    &reporter_stakes,
                     is_oneshot,
                     aggregator,
                     Some(history.clone()),
+                    Some(self.anomaly_detector.clone()),
+                    anomaly_score_threshold,
                     feed_id,
                     "feed_slot_processor",
                 )
@@ -156,6 +197,16 @@ impl FeedSlotsProcessor {
         let feed_name = &self.name;
         self.increase_quorum_metric(&feed_metrics, consumed_reports.is_quorum_reached)
             .await;
+        if let Some(feed_metrics) = &feed_metrics {
+            if consumed_reports.non_finite_votes > 0 {
+                feed_metrics
+                    .read()
+                    .await
+                    .dropped_non_finite_votes
+                    .with_label_values(&[&feed_id.to_string()])
+                    .inc_by(consumed_reports.non_finite_votes as u64);
+            }
+        }
 
         if !consumed_reports.is_quorum_reached {
             debug!("Quorum not reached for [feed `{feed_name}` feed_id = {feed_id}]");
@@ -173,6 +224,12 @@ impl FeedSlotsProcessor {
                     DoSkipReason::NothingToPost => {
                         inc_metric!(feed_metrics, feed_id, skipped_nothing_to_post)
                     }
+                    DoSkipReason::AggregationFailed => {
+                        inc_metric!(feed_metrics, feed_id, skipped_aggregation_failed)
+                    }
+                    DoSkipReason::AnomalyDetected => {
+                        inc_metric!(feed_metrics, feed_id, skipped_anomaly_detected)
+                    }
                 },
                 SkipDecision::DontSkip(reason) => match reason {
                     DontSkipReason::ThresholdCrossed => {
@@ -243,6 +300,21 @@ impl FeedSlotsProcessor {
             .map_err(|e| eyre!("[post_to_block_creator]: {e}"))
     }
 
+    /// Records the outcome of the slot, after `post_consumed_reports` has pushed a published value
+    /// to the history.
+    async fn record_slot_outcome(
+        &self,
+        history: &Arc<RwLock<FeedAggregateHistory>>,
+        mut outcome: SlotOutcome,
+    ) {
+        let mut history_guard = history.write().await;
+        outcome.update_number = history_guard
+            .last(self.key)
+            .filter(|entry| entry.end_slot_timestamp == outcome.end_slot_timestamp)
+            .map(|entry| entry.update_number);
+        history_guard.push_slot_outcome(self.key, outcome);
+    }
+
     async fn increase_quorum_metric(
         &self,
         feed_metrics: &Option<Arc<RwLock<FeedsMetrics>>>,
@@ -267,6 +339,9 @@ impl FeedSlotsProcessor {
         mut cmd_channel: mpsc::UnboundedReceiver<FeedsSlotProcessorCmds>,
         _cmd_sender: Option<mpsc::UnboundedSender<FeedsSlotProcessorCmds>>,
     ) -> Result<ProcessorResultValue> {
+        let _running = sequencer_state
+            .worker_health
+            .start(Worker::FeedSlotsProcessor);
         let feed_id = self.key;
         let feed_name = &self.name;
         let (
@@ -276,6 +351,8 @@ impl FeedSlotsProcessor {
             quorum_percentage,
             skip_publish_if_less_then_percentage,
             always_publish_heartbeat_ms,
+            market_hours,
+            anomaly_score_threshold,
             aggregator,
             feed_type,
         ) = {
@@ -288,6 +365,8 @@ impl FeedSlotsProcessor {
                 datafeed.get_quorum_percentage(),
                 datafeed.get_skip_publish_if_less_then_percentage() as f64,
                 datafeed.get_always_publish_heartbeat_ms(),
+                datafeed.get_market_hours(),
+                datafeed.get_anomaly_score_threshold(),
                 datafeed.get_feed_aggregator(),
                 datafeed.value_type.clone(),
             )
@@ -352,6 +431,8 @@ impl FeedSlotsProcessor {
                         quorum_percentage,
                         skip_publish_if_less_then_percentage,
                         always_publish_heartbeat_ms,
+                        market_hours.clone(),
+                        anomaly_score_threshold,
                         end_slot_timestamp,
                         aggregator,
                         slot,
@@ -361,9 +442,11 @@ impl FeedSlotsProcessor {
                         ).await {
                             Ok(consumed_reports) => {
                                 debug!("Continued after process_end_of_slot [feed `{feed_name}` feed_id = {feed_id}]");
+                                let outcome = slot_outcome(&consumed_reports);
                                 if let Err(e) = self.post_consumed_reports(consumed_reports, feed_metrics.clone(), skip_publish_if_less_then_percentage, history, sequencer_state).await {
                                     error!("post_consumed_reports failed with {e}")
                                 }
+                                self.record_slot_outcome(history, outcome).await;
                             }
                             Err(e) => {
                                 error!("process_end_of_slot failed with {e}");
@@ -375,6 +458,31 @@ impl FeedSlotsProcessor {
     }
 }
 
+fn slot_outcome(consumed_reports: &ConsumedReports) -> SlotOutcome {
+    let reason = if consumed_reports.votes == 0 {
+        format!("{:?}", DoSkipReason::NothingToPost)
+    } else if !consumed_reports.is_quorum_reached {
+        "QuorumNotReached".to_string()
+    } else {
+        match &consumed_reports.skip_decision {
+            SkipDecision::DoSkip(reason) => format!("{reason:?}"),
+            SkipDecision::DontSkip(reason) => format!("{reason:?}"),
+        }
+    };
+    SlotOutcome {
+        end_slot_timestamp: consumed_reports.end_slot_timestamp,
+        votes: consumed_reports.votes,
+        valid_reporters: consumed_reports.num_valid_reporters,
+        quorum_reached: consumed_reports.is_quorum_reached,
+        value: consumed_reports
+            .result_post_to_contract
+            .as_ref()
+            .map(|result| result.update.value.clone()),
+        update_number: None,
+        reason,
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -677,11 +785,13 @@ pub mod tests {
             id: 42,
             pub_key: "ea30b1533ef5638af7b70a036275642fc453ace97ed2c6b9d220fe1f59a24d61f481a777aa8a579f20e95a74cd4567ed36a3".to_string(),
             address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            stake: 1.0,
         });
         cfg.reporters.push(Reporter {
             id: 14,
             pub_key: "ea30813e2f8cf968e27bad29167b41bce038a3ce9b7b368de05e5cf1af3de919eeba267b8706f55c356d5f71891eff116b98".to_string(),
             address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(),
+            stake: 1.0,
         });
 
         let feeds_config = AllFeedsConfig {
@@ -1082,4 +1192,32 @@ pub mod tests {
         .await;
         check_timeout_expected(received);
     }
+
+    #[test]
+    fn test_slot_outcome_reason() {
+        let mut consumed_reports = ConsumedReports {
+            is_quorum_reached: false,
+            votes: 0,
+            non_finite_votes: 0,
+            num_valid_reporters: 3,
+            skip_decision: SkipDecision::DoSkip(DoSkipReason::NothingToPost),
+            ad_score: None,
+            result_post_to_contract: None,
+            end_slot_timestamp: 1000,
+        };
+        assert_eq!(slot_outcome(&consumed_reports).reason, "NothingToPost");
+
+        consumed_reports.votes = 1;
+        consumed_reports.skip_decision = SkipDecision::DontSkip(DontSkipReason::ThresholdCrossed);
+        assert_eq!(slot_outcome(&consumed_reports).reason, "QuorumNotReached");
+
+        consumed_reports.votes = 3;
+        consumed_reports.is_quorum_reached = true;
+        consumed_reports.skip_decision = SkipDecision::DoSkip(DoSkipReason::AnomalyDetected);
+        let outcome = slot_outcome(&consumed_reports);
+        assert_eq!(outcome.reason, "AnomalyDetected");
+        assert_eq!(outcome.votes, 3);
+        assert_eq!(outcome.valid_reporters, 3);
+        assert_eq!(outcome.update_number, None);
+    }
 }
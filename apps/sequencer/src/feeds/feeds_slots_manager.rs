@@ -1,19 +1,19 @@
 use crate::feeds::feed_slots_processor::FeedSlotsProcessor;
-use crate::sequencer_state::SequencerState;
+use crate::health::Worker;
+use crate::reporters::reporter::apply_reporter_action;
+use crate::sequencer_state::{check_history_memory_budget, SequencerState};
 use actix_web::web;
 use blocksense_feed_registry::feed_registration_cmds::{
     DeleteAssetFeed, FeedsManagementCmds, ProcessorResultValue, RegisterNewAssetFeed,
 };
 use blocksense_feed_registry::types::{FeedMetaData, FeedsSlotProcessorCmds::Terminate};
-use blocksense_registry::config::FeedConfig;
+use blocksense_registry::config::{FeedConfig, DEFAULT_HISTORY_DEPTH};
 use blocksense_utils::EncodedFeedId;
 use eyre::Result;
 use futures::select;
 use futures::stream::{FuturesUnordered, StreamExt};
 use std::io::Error;
 use std::sync::Arc;
-use std::time::Duration;
-use std::time::UNIX_EPOCH;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, error, info, warn};
 
@@ -24,6 +24,9 @@ pub async fn feeds_slots_manager_loop(
     tokio::task::Builder::new()
         .name("feeds_slots_manager")
         .spawn(async move {
+            let _running = sequencer_state
+                .worker_health
+                .start(Worker::FeedsSlotsManager);
             let mut collected_futures = FuturesUnordered::new();
 
             let reg = sequencer_state.registry.read().await;
@@ -33,11 +36,20 @@ pub async fn feeds_slots_manager_loop(
             for key in keys {
                 debug!("key = {} : value = {:?}", key, reg.get(&key));
 
-                sequencer_state
-                    .feed_aggregate_history
-                    .write()
+                // Feeds from the config are registered with their history depth by
+                // `SequencerState::new`, registering again would drop their history.
+                let history_depth = sequencer_state
+                    .active_feeds
+                    .read()
                     .await
-                    .register_feed(key, 10_000); //TODO(snikolov): How to avoid borrow?
+                    .get(&key)
+                    .map_or(DEFAULT_HISTORY_DEPTH, |feed| feed.get_history_depth());
+                {
+                    let mut history = sequencer_state.feed_aggregate_history.write().await;
+                    if !history.is_registered_feed(key) {
+                        history.register_feed(key, history_depth);
+                    }
+                }
 
                 let feed = match reg.get(&key) {
                     Some(x) => x,
@@ -235,6 +247,17 @@ async fn handle_feeds_slots_manager_cmd(
                 }
             };
         }
+        FeedsManagementCmds::ReporterAction(action) => {
+            if let Err(e) = apply_reporter_action(
+                &sequencer_state.reporters,
+                &sequencer_state.reporter_metrics,
+                &action,
+            )
+            .await
+            {
+                warn!("Failed to apply reporter action {action:?}: {e}");
+            }
+        }
     };
     //Register reader task again once the command is processed
     let command_watcher = tokio::task::Builder::new()
@@ -261,31 +284,30 @@ pub async fn register_feed_with_config(
     new_feed_config: &FeedConfig,
 ) -> Result<Arc<RwLock<FeedMetaData>>> {
     let new_feed_id;
-    let new_name;
+    {
+        let total_capacity = sequencer_state
+            .feed_aggregate_history
+            .read()
+            .await
+            .total_capacity();
+        check_history_memory_budget(
+            &*sequencer_state.sequencer_config.read().await,
+            total_capacity + new_feed_config.get_history_depth(),
+        )?;
+    }
     {
         let mut reg = sequencer_state.registry.write().await;
 
         let keys = reg.get_keys();
 
         new_feed_id = EncodedFeedId::new(new_feed_config.id, new_feed_config.stride);
-        new_name = new_feed_config.full_name.clone();
 
         if keys.contains(&new_feed_id) {
             eyre::bail!("Cannot register feed ID, feed with this ID {new_feed_id} already exists.");
         }
 
-        let new_feed_metadata = FeedMetaData::new(
-            new_name,
-            new_feed_config.schedule.interval_ms,
-            new_feed_config.quorum.percentage,
-            new_feed_config.schedule.deviation_percentage,
-            new_feed_config.schedule.heartbeat_ms,
-            UNIX_EPOCH
-                + Duration::from_millis(new_feed_config.schedule.first_report_start_unix_time_ms),
-            new_feed_config.value_type.clone(),
-            new_feed_config.quorum.aggregation.clone(),
-            None,
-        );
+        let new_feed_metadata = FeedMetaData::try_from_config(new_feed_config)
+            .map_err(|e| eyre::eyre!("Cannot register feed ID {new_feed_id}: {e}"))?;
         reg.push(new_feed_id, new_feed_metadata);
     }
     {
@@ -308,7 +330,7 @@ pub async fn register_feed_with_config(
             .feed_aggregate_history
             .write()
             .await
-            .register_feed(new_feed_id, 10_000); //TODO(snikolov): How to avoid borrow?
+            .register_feed(new_feed_id, new_feed_config.get_history_depth());
 
         Ok(registered_feed_metadata)
     }
@@ -402,18 +424,21 @@ mod tests {
             mpsc::unbounded_channel();
         let (aggregate_batch_sig_send, _aggregate_batch_sig_recv) = mpsc::unbounded_channel();
 
-        let sequencer_state = web::Data::new(SequencerState::new(
-            feeds_config,
-            providers,
-            log_handle,
-            &sequencer_config,
-            metrics_prefix,
-            vote_send,
-            feeds_management_cmd_to_block_creator_send,
-            feeds_slots_manager_cmd_send,
-            aggregate_batch_sig_send,
-            Arc::new(RwLock::new(HashMap::new())),
-        ));
+        let sequencer_state = web::Data::new(
+            SequencerState::new(
+                feeds_config,
+                providers,
+                log_handle,
+                &sequencer_config,
+                metrics_prefix,
+                vote_send,
+                feeds_management_cmd_to_block_creator_send,
+                feeds_slots_manager_cmd_send,
+                aggregate_batch_sig_send,
+                Arc::new(RwLock::new(HashMap::new())),
+            )
+            .unwrap(),
+        );
 
         sequencer_state
             .reports
@@ -0,0 +1,400 @@
+//! Liveness and readiness of the sequencer. The long running workers register themselves in
+//! [`WorkerHealth`] for as long as they run, and the probes combine that with checks of the
+//! networks and of Kafka.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::providers::Provider;
+use blocksense_utils::time::current_unix_time;
+use futures::future::join_all;
+use rdkafka::producer::Producer;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio::time::timeout;
+use tracing::warn;
+
+use crate::sequencer_state::SequencerState;
+
+/// Longest wait for a single RPC or Kafka check of the readiness probe, the networks are checked
+/// concurrently
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Ordered from best to worst, so that the status of a report is the maximum of its parts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    /// Still serving, but something needs attention
+    Degraded,
+    /// The sequencer should be taken out
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Worker {
+    BlockCreator,
+    FeedsSlotsManager,
+    FeedSlotsProcessor,
+    BlocksReader,
+}
+
+#[derive(Debug, Default)]
+pub struct WorkerHealth {
+    block_creator: AtomicUsize,
+    feeds_slots_manager: AtomicUsize,
+    feed_slots_processors: AtomicUsize,
+    blocks_reader: AtomicUsize,
+    /// Milliseconds since the UNIX epoch, 0 until the first block
+    last_block_ms: AtomicU64,
+    /// Milliseconds since the UNIX epoch, 0 until the first message
+    last_kafka_message_ms: AtomicU64,
+    last_kafka_error: RwLock<Option<String>>,
+}
+
+/// Marks a worker as running until it is dropped, including when the worker panics.
+pub struct RunningWorker {
+    health: Arc<WorkerHealth>,
+    worker: Worker,
+}
+
+impl Drop for RunningWorker {
+    fn drop(&mut self) {
+        self.health
+            .counter(self.worker)
+            .fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl WorkerHealth {
+    fn counter(&self, worker: Worker) -> &AtomicUsize {
+        match worker {
+            Worker::BlockCreator => &self.block_creator,
+            Worker::FeedsSlotsManager => &self.feeds_slots_manager,
+            Worker::FeedSlotsProcessor => &self.feed_slots_processors,
+            Worker::BlocksReader => &self.blocks_reader,
+        }
+    }
+
+    pub fn start(self: &Arc<Self>, worker: Worker) -> RunningWorker {
+        self.counter(worker).fetch_add(1, Ordering::SeqCst);
+        RunningWorker {
+            health: self.clone(),
+            worker,
+        }
+    }
+
+    pub fn running(&self, worker: Worker) -> usize {
+        self.counter(worker).load(Ordering::SeqCst)
+    }
+
+    pub fn block_produced(&self) {
+        self.last_block_ms
+            .store(current_unix_time() as u64, Ordering::SeqCst);
+    }
+
+    pub async fn kafka_message_received(&self) {
+        self.last_kafka_message_ms
+            .store(current_unix_time() as u64, Ordering::SeqCst);
+        *self.last_kafka_error.write().await = None;
+    }
+
+    pub async fn kafka_error(&self, error: String) {
+        *self.last_kafka_error.write().await = Some(error);
+    }
+
+    fn secs_since(timestamp_ms: &AtomicU64) -> Option<u64> {
+        match timestamp_ms.load(Ordering::SeqCst) {
+            0 => None,
+            timestamp_ms => Some((current_unix_time() as u64).saturating_sub(timestamp_ms) / 1000),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkersReport {
+    pub status: HealthStatus,
+    pub block_creator_running: bool,
+    pub feeds_slots_manager_running: bool,
+    /// Oneshot feeds stop their processor once they are reported, so this can be below the number
+    /// of registered feeds
+    pub feed_slots_processors_running: usize,
+    pub registered_feeds: usize,
+    pub secs_since_last_block: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NetworkReport {
+    pub status: HealthStatus,
+    pub enabled: bool,
+    pub reachable: bool,
+    pub chain_id: Option<u64>,
+    pub expected_chain_id: Option<u64>,
+    pub relayer_queue_depth: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KafkaReport {
+    pub status: HealthStatus,
+    pub configured: bool,
+    pub producer_reachable: bool,
+    pub producer_error: Option<String>,
+    pub consumer_running: bool,
+    pub consumer_error: Option<String>,
+    pub secs_since_last_message: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Liveness {
+    pub status: HealthStatus,
+    pub workers: WorkersReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Readiness {
+    pub status: HealthStatus,
+    pub workers: WorkersReport,
+    pub networks: BTreeMap<String, NetworkReport>,
+    pub kafka: KafkaReport,
+}
+
+async fn workers_report(sequencer_state: &SequencerState) -> WorkersReport {
+    let health = &sequencer_state.worker_health;
+    let block_creator_running = health.running(Worker::BlockCreator) > 0;
+    let feeds_slots_manager_running = health.running(Worker::FeedsSlotsManager) > 0;
+    let feed_slots_processors_running = health.running(Worker::FeedSlotsProcessor);
+    let registered_feeds = sequencer_state.registry.read().await.get_keys().len();
+
+    let status = if !block_creator_running || !feeds_slots_manager_running {
+        HealthStatus::Unhealthy
+    } else if feed_slots_processors_running == 0 && registered_feeds > 0 {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+
+    WorkersReport {
+        status,
+        block_creator_running,
+        feeds_slots_manager_running,
+        feed_slots_processors_running,
+        registered_feeds,
+        secs_since_last_block: WorkerHealth::secs_since(&health.last_block_ms),
+    }
+}
+
+/// A network that cannot be reached only degrades the sequencer, as the others are still served.
+/// Talking to the wrong chain is a misconfiguration that needs the sequencer taken out.
+fn network_report(
+    enabled: bool,
+    chain_id: Result<u64, String>,
+    expected_chain_id: Option<u64>,
+    relayer_queue_depth: usize,
+) -> NetworkReport {
+    let (reachable, chain_id, error) = match chain_id {
+        Ok(chain_id) => (true, Some(chain_id), None),
+        Err(error) => (false, None, Some(error)),
+    };
+    let status = match (chain_id, expected_chain_id) {
+        _ if !enabled => HealthStatus::Ok,
+        (Some(chain_id), Some(expected)) if chain_id != expected => HealthStatus::Unhealthy,
+        (None, _) => HealthStatus::Degraded,
+        _ => HealthStatus::Ok,
+    };
+    let error = match (chain_id, expected_chain_id) {
+        (Some(chain_id), Some(expected)) if chain_id != expected => Some(format!(
+            "Chain id {chain_id} does not match the configured {expected}"
+        )),
+        _ => error,
+    };
+    NetworkReport {
+        status,
+        enabled,
+        reachable,
+        chain_id,
+        expected_chain_id,
+        relayer_queue_depth,
+        error,
+    }
+}
+
+async fn networks_report(sequencer_state: &SequencerState) -> BTreeMap<String, NetworkReport> {
+    let providers = sequencer_state.providers.read().await.clone();
+    let configs = sequencer_state
+        .sequencer_config
+        .read()
+        .await
+        .providers
+        .clone();
+    let relayer_queue_depths: BTreeMap<String, usize> = sequencer_state
+        .relayers_send_channels
+        .read()
+        .await
+        .iter()
+        .map(|(network, sender)| (network.clone(), sender.len()))
+        .collect();
+
+    let checks = providers.into_iter().map(|(network, provider)| {
+        let config = configs.get(&network);
+        let enabled = config.is_some_and(|config| config.is_enabled);
+        let expected_chain_id = config.and_then(|config| config.chain_id);
+        let relayer_queue_depth = relayer_queue_depths.get(&network).copied().unwrap_or(0);
+        async move {
+            // The relayers hold the lock while sending, so do not wait on it for long
+            let chain_id = match timeout(CHECK_TIMEOUT, async {
+                let rpc = provider.lock().await.provider.clone();
+                rpc.get_chain_id().await
+            })
+            .await
+            {
+                Ok(Ok(chain_id)) => Ok(chain_id),
+                // The error can contain the RPC url and its API key, so it is only logged
+                Ok(Err(e)) => {
+                    warn!("Readiness check of network {network} failed: {e}");
+                    Err("Could not get the chain id".to_string())
+                }
+                Err(_) => Err(format!("No answer within {CHECK_TIMEOUT:?}")),
+            };
+            let report = network_report(enabled, chain_id, expected_chain_id, relayer_queue_depth);
+            (network, report)
+        }
+    });
+    join_all(checks).await.into_iter().collect()
+}
+
+async fn kafka_report(sequencer_state: &SequencerState) -> KafkaReport {
+    let health = &sequencer_state.worker_health;
+    let consumer_running = health.running(Worker::BlocksReader) > 0;
+    let consumer_error = health.last_kafka_error.read().await.clone();
+    let secs_since_last_message = WorkerHealth::secs_since(&health.last_kafka_message_ms);
+
+    let Some(producer) = sequencer_state.kafka_endpoint.clone() else {
+        return KafkaReport {
+            status: HealthStatus::Ok,
+            configured: false,
+            producer_reachable: false,
+            producer_error: None,
+            consumer_running,
+            consumer_error,
+            secs_since_last_message,
+        };
+    };
+
+    // Fetching the metadata blocks, so it runs off the async workers
+    let metadata = tokio::task::spawn_blocking(move || {
+        producer
+            .client()
+            .fetch_metadata(Some("blockchain"), CHECK_TIMEOUT)
+            .map(|_| ())
+    })
+    .await;
+    let producer_error = match metadata {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+
+    let status = if producer_error.is_some() {
+        HealthStatus::Unhealthy
+    } else if !consumer_running || consumer_error.is_some() {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Ok
+    };
+
+    KafkaReport {
+        status,
+        configured: true,
+        producer_reachable: producer_error.is_none(),
+        producer_error,
+        consumer_running,
+        consumer_error,
+        secs_since_last_message,
+    }
+}
+
+/// Whether the sequencer process should be restarted. Only looks at its own workers.
+pub async fn liveness(sequencer_state: &SequencerState) -> Liveness {
+    let workers = workers_report(sequencer_state).await;
+    let status = match workers.status {
+        HealthStatus::Unhealthy => HealthStatus::Unhealthy,
+        _ => HealthStatus::Ok,
+    };
+    Liveness { status, workers }
+}
+
+/// Whether the sequencer should receive traffic, including the state of its dependencies.
+pub async fn readiness(sequencer_state: &SequencerState) -> Readiness {
+    let workers = workers_report(sequencer_state).await;
+    let networks = networks_report(sequencer_state).await;
+    let kafka = kafka_report(sequencer_state).await;
+
+    let status = networks
+        .values()
+        .map(|network| network.status)
+        .chain([workers.status, kafka.status])
+        .max()
+        .unwrap_or(HealthStatus::Ok);
+
+    Readiness {
+        status,
+        workers,
+        networks,
+        kafka,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workers_are_running_until_dropped() {
+        let health = Arc::new(WorkerHealth::default());
+        let first = health.start(Worker::FeedSlotsProcessor);
+        let second = health.start(Worker::FeedSlotsProcessor);
+        assert_eq!(health.running(Worker::FeedSlotsProcessor), 2);
+        assert_eq!(health.running(Worker::BlockCreator), 0);
+
+        drop(first);
+        assert_eq!(health.running(Worker::FeedSlotsProcessor), 1);
+
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _running = health.start(Worker::BlockCreator);
+            panic!("worker failed");
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(health.running(Worker::BlockCreator), 0);
+        drop(second);
+        assert_eq!(health.running(Worker::FeedSlotsProcessor), 0);
+    }
+
+    #[test]
+    fn network_status_follows_reachability_and_chain_id() {
+        assert_eq!(
+            network_report(true, Ok(1), Some(1), 0).status,
+            HealthStatus::Ok
+        );
+        assert_eq!(
+            network_report(true, Ok(1), None, 3).status,
+            HealthStatus::Ok
+        );
+
+        let wrong_chain = network_report(true, Ok(5), Some(1), 0);
+        assert_eq!(wrong_chain.status, HealthStatus::Unhealthy);
+        assert_eq!(
+            wrong_chain.error.as_deref(),
+            Some("Chain id 5 does not match the configured 1")
+        );
+
+        let unreachable = network_report(true, Err("connection refused".to_string()), Some(1), 0);
+        assert_eq!(unreachable.status, HealthStatus::Degraded);
+        assert!(!unreachable.reachable);
+
+        let disabled = network_report(false, Err("connection refused".to_string()), None, 0);
+        assert_eq!(disabled.status, HealthStatus::Ok);
+    }
+}
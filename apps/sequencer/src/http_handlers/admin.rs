@@ -1,6 +1,8 @@
+use crate::health::{self, HealthStatus};
 use crate::http_handlers::MAX_SIZE;
+use crate::oracle_scripts::{catalog_view, check_manifest};
 use crate::providers::provider::ProviderStatus;
-use crate::sequencer_state::SequencerState;
+use crate::sequencer_state::{check_history_memory_budget, SequencerState};
 use actix_web::http::header::ContentType;
 use actix_web::web::ServiceConfig;
 use actix_web::{error, Error};
@@ -11,20 +13,25 @@ use alloy::{
     rpc::types::eth::TransactionRequest,
 };
 
-use blocksense_config::{AllFeedsConfig, SequencerConfig, ADFS_CONTRACT_NAME};
+use blocksense_config::{AllFeedsConfig, Reporter, SequencerConfig, Validated, ADFS_CONTRACT_NAME};
+use blocksense_crypto::serialize_public_key;
+use blocksense_crypto::MULTIFORMATS_BLS_PUBKYE_PREFIX;
 use blocksense_feed_registry::feed_registration_cmds::{
-    DeleteAssetFeed, FeedsManagementCmds, RegisterNewAssetFeed,
+    DeleteAssetFeed, FeedsManagementCmds, RegisterNewAssetFeed, ReporterAction,
 };
-use blocksense_registry::config::{FeedConfig, OracleScript, OraclesResponse};
+use blocksense_registry::config::{FeedConfig, OraclesResponse};
+use blocksense_registry::registry::OracleManifest;
 use blocksense_utils::logging::tokio_console_active;
 use blocksense_utils::EncodedFeedId;
 use eyre::eyre;
 use eyre::Result;
 use futures::StreamExt;
-use std::collections::{BTreeMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::providers::eth_send_utils::deploy_contract;
 use crate::providers::provider::SharedRpcProviders;
+use crate::reporters::reporter::{parse_address, parse_pub_key};
 use blocksense_feed_registry::types::FeedType;
 use blocksense_metrics::metrics_collector::gather_and_dump_metrics;
 use tokio::time::Duration;
@@ -318,6 +325,10 @@ pub async fn register_asset_feed(
     debug!("revcd body = {:?}!", body);
 
     let new_feed_config: FeedConfig = serde_json::from_str(std::str::from_utf8(&body)?)?;
+    // An invalid feed would be rejected by every sequencer that reads the block
+    if let Err(e) = new_feed_config.validate("register_asset_feed") {
+        return Err(error::ErrorBadRequest(e.to_string()));
+    }
 
     {
         let reg = sequencer_state.registry.read().await;
@@ -478,6 +489,49 @@ pub async fn get_history(sequencer_state: web::Data<SequencerState>) -> HttpResp
     }
 }
 
+/// Changes the number of entries kept in the history of a feed. Existing entries are kept, except
+/// for the oldest ones when the history shrinks.
+///
+/// Only this sequencer is affected: the new depth is not written to a block, so other sequencers
+/// keep their depth and this one goes back to the configured depth after a restart. Change
+/// `history_depth` in the feed config to make it permanent.
+#[post("/set_feed_history_depth/{encoded_feed_id}/{history_depth}")]
+pub async fn set_feed_history_depth(
+    path: web::Path<(String, usize)>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let (feed_id, history_depth) = path.into_inner();
+    let encoded_feed_id: EncodedFeedId = feed_id
+        .parse()
+        .map_err(|e: std::num::ParseIntError| error::ErrorBadRequest(e.to_string()))?;
+    if history_depth == 0 {
+        return Err(error::ErrorBadRequest("History depth cannot be 0"));
+    }
+
+    let mut history = sequencer_state.feed_aggregate_history.write().await;
+    let Some(old_depth) = history.capacity(encoded_feed_id) else {
+        return Err(error::ErrorNotFound(format!(
+            "Feed {encoded_feed_id} is not registered"
+        )));
+    };
+    check_history_memory_budget(
+        &*sequencer_state.sequencer_config.read().await,
+        history.total_capacity() - old_depth + history_depth,
+    )
+    .map_err(|e| error::ErrorBadRequest(e.to_string()))?;
+    history.resize_feed(encoded_feed_id, history_depth);
+    if let Some(feed) = sequencer_state
+        .active_feeds
+        .write()
+        .await
+        .get_mut(&encoded_feed_id)
+    {
+        feed.history_depth = Some(history_depth);
+    }
+    info!("History depth of feed {encoded_feed_id} changed from {old_depth} to {history_depth}");
+    Ok(HttpResponse::Ok().into())
+}
+
 #[post("/delete_asset_feed/{encoded_feed_id}")]
 pub async fn delete_asset_feed(
     req: HttpRequest,
@@ -529,91 +583,262 @@ pub async fn delete_asset_feed(
         .body(format!("{}", feed.read().await.get_report_interval_ms())))
 }
 
+async fn read_payload(mut payload: web::Payload) -> Result<web::BytesMut, Error> {
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        // limit max size of in-memory payload
+        if (body.len() + chunk.len()) > MAX_SIZE {
+            return Err(error::ErrorBadRequest("overflow"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ReporterInfo {
+    pub id: u64,
+    pub pub_key: String,
+    pub address: String,
+    pub stake: f64,
+    pub suspended: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateReporterKeys {
+    pub pub_key: String,
+    pub address: String,
+}
+
+#[get("/list_reporters")]
+pub async fn list_reporters(sequencer_state: web::Data<SequencerState>) -> HttpResponse {
+    let mut reporters = Vec::new();
+    for (id, reporter) in sequencer_state.reporters.read().await.iter() {
+        let reporter = reporter.read().await;
+        reporters.push(ReporterInfo {
+            id: *id,
+            pub_key: format!(
+                "{MULTIFORMATS_BLS_PUBKYE_PREFIX}{}",
+                serialize_public_key(&reporter.pub_key)
+            ),
+            address: reporter.address.to_string(),
+            stake: reporter.stake,
+            suspended: reporter.suspended,
+        });
+    }
+    reporters.sort_by_key(|r| r.id);
+    match serde_json::to_string_pretty(&reporters) {
+        Ok(serialized_list) => HttpResponse::Ok()
+            .content_type(ContentType::json())
+            .body(serialized_list),
+        Err(err) => HttpResponse::InternalServerError()
+            .content_type(ContentType::plaintext())
+            .body(err.to_string()),
+    }
+}
+
+// Changes of the reporter set go through the block creator, so that they are recorded in the
+// blockchain and applied by all sequencers in the same order.
+fn schedule_reporter_action(
+    sequencer_state: &web::Data<SequencerState>,
+    action: ReporterAction,
+) -> Result<HttpResponse, Error> {
+    match sequencer_state
+        .feeds_management_cmd_to_block_creator_send
+        .send(FeedsManagementCmds::ReporterAction(action.clone()))
+    {
+        Ok(_) => {
+            info!("Scheduled reporter action: {action:?}");
+            Ok(HttpResponse::Ok().into())
+        }
+        Err(e) => {
+            error!("Sequencer internal error, could not forward reporter action cmd {e}");
+            Err(error::ErrorInternalServerError(
+                "Could not schedule reporter action",
+            ))
+        }
+    }
+}
+
+async fn get_reporter_suspended(
+    sequencer_state: &web::Data<SequencerState>,
+    reporter_id: u64,
+) -> Result<bool, Error> {
+    let reporter = sequencer_state
+        .reporters
+        .read()
+        .await
+        .get(&reporter_id)
+        .cloned();
+    match reporter {
+        Some(reporter) => Ok(reporter.read().await.suspended),
+        None => Err(error::ErrorNotFound(format!(
+            "Reporter with ID {reporter_id} not found"
+        ))),
+    }
+}
+
+#[post("/add_reporter")]
+pub async fn add_reporter(
+    payload: web::Payload,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let _span = info_span!("add_reporter");
+    let body = read_payload(payload).await?;
+    let reporter: Reporter = serde_json::from_str(std::str::from_utf8(&body)?)?;
+
+    reporter
+        .validate("add_reporter")
+        .map_err(|e| error::ErrorBadRequest(e.to_string()))?;
+    parse_pub_key(&reporter.pub_key).map_err(|e| error::ErrorBadRequest(e.to_string()))?;
+    parse_address(&reporter.address).map_err(|e| error::ErrorBadRequest(e.to_string()))?;
+
+    let reporter_id = u64::from(reporter.id);
+    if sequencer_state
+        .reporters
+        .read()
+        .await
+        .contains_key(&reporter_id)
+    {
+        let err_msg =
+            format!("Can not add reporter. Reporter with ID {reporter_id} already exists.");
+        error!(err_msg);
+        return Err(error::ErrorBadRequest(err_msg));
+    }
+
+    schedule_reporter_action(&sequencer_state, ReporterAction::Add(reporter))
+}
+
+#[post("/remove_reporter/{reporter_id}")]
+pub async fn remove_reporter(
+    path: web::Path<u64>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    get_reporter_suspended(&sequencer_state, id).await?;
+    schedule_reporter_action(&sequencer_state, ReporterAction::Remove { id })
+}
+
+/// Stop accepting votes from the reporter, without removing it.
+#[post("/suspend_reporter/{reporter_id}")]
+pub async fn suspend_reporter(
+    path: web::Path<u64>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    if get_reporter_suspended(&sequencer_state, id).await? {
+        return Err(error::ErrorBadRequest(format!(
+            "Reporter {id} already suspended"
+        )));
+    }
+    schedule_reporter_action(&sequencer_state, ReporterAction::Suspend { id })
+}
+
+#[post("/resume_reporter/{reporter_id}")]
+pub async fn resume_reporter(
+    path: web::Path<u64>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    if !get_reporter_suspended(&sequencer_state, id).await? {
+        return Err(error::ErrorBadRequest(format!(
+            "Reporter {id} is not suspended"
+        )));
+    }
+    schedule_reporter_action(&sequencer_state, ReporterAction::Resume { id })
+}
+
+/// Replace the BLS public key and the address of the reporter.
+#[post("/rotate_reporter_keys/{reporter_id}")]
+pub async fn rotate_reporter_keys(
+    path: web::Path<u64>,
+    payload: web::Payload,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    let body = read_payload(payload).await?;
+    let keys: RotateReporterKeys = serde_json::from_str(std::str::from_utf8(&body)?)?;
+    parse_pub_key(&keys.pub_key).map_err(|e| error::ErrorBadRequest(e.to_string()))?;
+    parse_address(&keys.address).map_err(|e| error::ErrorBadRequest(e.to_string()))?;
+    get_reporter_suspended(&sequencer_state, id).await?;
+    schedule_reporter_action(
+        &sequencer_state,
+        ReporterAction::Rotate {
+            id,
+            pub_key: keys.pub_key,
+            address: keys.address,
+        },
+    )
+}
+
+#[get("/list_reporter_registrations")]
+pub async fn list_reporter_registrations(
+    sequencer_state: web::Data<SequencerState>,
+) -> HttpResponse {
+    let mut registrations: Vec<Reporter> = sequencer_state
+        .pending_reporter_registrations
+        .read()
+        .await
+        .values()
+        .cloned()
+        .collect();
+    registrations.sort_by_key(|r| r.id);
+    HttpResponse::Ok().json(registrations)
+}
+
+/// Add the reporter of a pending registration to the reporter set.
+#[post("/approve_reporter_registration/{reporter_id}")]
+pub async fn approve_reporter_registration(
+    path: web::Path<u64>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    let mut pending = sequencer_state.pending_reporter_registrations.write().await;
+    let Some(reporter) = pending.get(&id).cloned() else {
+        return Err(error::ErrorNotFound(format!(
+            "No pending registration for reporter ID {id}"
+        )));
+    };
+    if sequencer_state.reporters.read().await.contains_key(&id) {
+        pending.remove(&id);
+        return Err(error::ErrorConflict(format!(
+            "Reporter with ID {id} was registered in the meantime"
+        )));
+    }
+    let response = schedule_reporter_action(&sequencer_state, ReporterAction::Add(reporter))?;
+    pending.remove(&id);
+    Ok(response)
+}
+
+#[post("/reject_reporter_registration/{reporter_id}")]
+pub async fn reject_reporter_registration(
+    path: web::Path<u64>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    match sequencer_state
+        .pending_reporter_registrations
+        .write()
+        .await
+        .remove(&id)
+    {
+        Some(reporter) => {
+            info!("Rejected registration of reporter {reporter:?}");
+            Ok(HttpResponse::Ok().into())
+        }
+        None => Err(error::ErrorNotFound(format!(
+            "No pending registration for reporter ID {id}"
+        ))),
+    }
+}
+
 #[get("/get_oracle_scripts")]
 pub async fn get_oracle_scripts(
-    _sequencer_state: web::Data<SequencerState>,
+    sequencer_state: web::Data<SequencerState>,
 ) -> Result<HttpResponse, Error> {
-    //TODO(adikov): Remove hardcoded data when persistent storage is added
     let oracle_scripts = OraclesResponse {
-        oracles: vec![
-            OracleScript {
-                id: "cmc".to_string(),
-                interval_time_in_seconds: None,
-                name: None,
-                description: None,
-                oracle_script_wasm: "cmc_oracle.wasm".to_string(),
-                allowed_outbound_hosts: vec!["https://pro-api.coinmarketcap.com".to_string()],
-                capabilities: HashSet::from_iter(["CMC_API_KEY".to_string()]),
-            },
-            OracleScript {
-                id: "yahoo".to_string(),
-                interval_time_in_seconds: None,
-                name: None,
-                description: None,
-                oracle_script_wasm: "yahoo_oracle.wasm".to_string(),
-                allowed_outbound_hosts: vec!["https://yfapi.net:443".to_string()],
-                capabilities: HashSet::from_iter(["YAHOO_API_KEY".to_string()]),
-            },
-            OracleScript {
-                id: "exsat".to_string(),
-                interval_time_in_seconds: None,
-                name: None,
-                description: None,
-                oracle_script_wasm: "exsat_holdings_oracle.wasm".to_string(),
-                allowed_outbound_hosts: vec![
-                    "https://raw.githubusercontent.com".to_string(),
-                    "https://rpc-us.exsat.network".to_string(),
-                    "https://blockchain.info".to_string(),
-                ],
-                capabilities: HashSet::new(),
-            },
-            OracleScript {
-                id: "gecko-terminal".to_string(),
-                interval_time_in_seconds: None,
-                name: None,
-                description: None,
-                oracle_script_wasm: "gecko_terminal_oracle.wasm".to_string(),
-                allowed_outbound_hosts: vec!["https://api.geckoterminal.com".to_string()],
-                capabilities: HashSet::new(),
-            },
-            OracleScript {
-                id: "eth-rpc".to_string(),
-                interval_time_in_seconds: None,
-                name: None,
-                description: None,
-                oracle_script_wasm: "eth_rpc.wasm".to_string(),
-                allowed_outbound_hosts: vec![
-                    "https://eth.llamarpc.com".to_string(),
-                    "https://rpc.eth.gateway.fm".to_string(),
-                ],
-                capabilities: HashSet::new(),
-            },
-            OracleScript {
-                id: "cex-price-feeds".to_string(),
-                interval_time_in_seconds: None,
-                name: None,
-                description: None,
-                oracle_script_wasm: "cex-price-feeds.wasm".to_string(),
-                allowed_outbound_hosts: vec![
-                    "https://api.kraken.com".to_string(),
-                    "https://api.bybit.com".to_string(),
-                    "https://api.coinbase.com".to_string(),
-                    "https://api.exchange.coinbase.com".to_string(),
-                    "https://api1.binance.com".to_string(),
-                    "https://api.kucoin.com".to_string(),
-                    "https://api.mexc.com".to_string(),
-                    "https://api.crypto.com".to_string(),
-                    "https://api.binance.us".to_string(),
-                    "https://api.gemini.com".to_string(),
-                    "https://api-pub.bitfinex.com".to_string(),
-                    "https://api.upbit.com".to_string(),
-                    "https://api.bitget.com".to_string(),
-                    "https://api.gateio.ws".to_string(),
-                    "https://www.okx.com".to_string(),
-                ],
-                capabilities: HashSet::new(),
-            },
-        ],
+        oracles: sequencer_state.oracle_scripts.read().await.oracle_scripts(),
     };
 
     let oracles_config_pretty = serde_json::to_string_pretty(&oracle_scripts)?;
@@ -623,15 +848,145 @@ pub async fn get_oracle_scripts(
         .body(oracles_config_pretty.to_string()))
 }
 
+/// All oracle scripts, including the retired ones, with the active feeds each of them serves.
+#[get("/get_oracle_script_catalog")]
+pub async fn get_oracle_script_catalog(
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let catalog = sequencer_state.oracle_scripts.read().await;
+    let active_feeds = sequencer_state.active_feeds.read().await;
+    Ok(HttpResponse::Ok().json(catalog_view(&catalog, &active_feeds)))
+}
+
+async fn read_oracle_manifest(
+    payload: web::Payload,
+    sequencer_state: &SequencerState,
+) -> Result<OracleManifest, Error> {
+    let body = read_payload(payload).await?;
+    let manifest: OracleManifest = serde_json::from_slice(&body)
+        .map_err(|e| error::ErrorBadRequest(format!("Invalid oracle script: {e}")))?;
+    check_manifest(&manifest, &*sequencer_state.active_feeds.read().await)
+        .map_err(error::ErrorBadRequest)?;
+    Ok(manifest)
+}
+
+#[post("/register_oracle_script")]
+pub async fn register_oracle_script(
+    payload: web::Payload,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let manifest = read_oracle_manifest(payload, &sequencer_state).await?;
+    let mut catalog = sequencer_state.oracle_scripts.write().await;
+    // A retired script can be registered again
+    if catalog
+        .get(&manifest.id)
+        .is_some_and(|entry| !entry.retired)
+    {
+        return Err(error::ErrorConflict(format!(
+            "Oracle script {} is already registered",
+            manifest.id
+        )));
+    }
+    let id = manifest.id.clone();
+    catalog
+        .upsert(manifest)
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    info!("Registered oracle script {id}");
+    Ok(HttpResponse::Ok().into())
+}
+
+#[post("/update_oracle_script/{id}")]
+pub async fn update_oracle_script(
+    path: web::Path<String>,
+    payload: web::Payload,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    let manifest = read_oracle_manifest(payload, &sequencer_state).await?;
+    if manifest.id != id {
+        return Err(error::ErrorBadRequest(format!(
+            "Oracle script id {} does not match {id}",
+            manifest.id
+        )));
+    }
+    let mut catalog = sequencer_state.oracle_scripts.write().await;
+    match catalog.get(&id) {
+        Some(entry) if !entry.retired => {}
+        Some(_) => {
+            return Err(error::ErrorConflict(format!(
+                "Oracle script {id} is retired, register it again instead"
+            )))
+        }
+        None => return Err(error::ErrorNotFound(format!("Unknown oracle script {id}"))),
+    }
+    catalog
+        .upsert(manifest)
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    info!("Updated oracle script {id}");
+    Ok(HttpResponse::Ok().into())
+}
+
+/// Retire an oracle script. The feeds it serves have to be deleted first.
+#[post("/retire_oracle_script/{id}")]
+pub async fn retire_oracle_script(
+    path: web::Path<String>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let id = path.into_inner();
+    let mut catalog = sequencer_state.oracle_scripts.write().await;
+    if !catalog.get(&id).is_some_and(|entry| !entry.retired) {
+        return Err(error::ErrorNotFound(format!(
+            "No registered oracle script {id}"
+        )));
+    }
+    let mut served_feeds: Vec<String> = sequencer_state
+        .active_feeds
+        .read()
+        .await
+        .values()
+        .filter(|feed| feed.oracle_id == id)
+        .map(|feed| feed.id.to_string())
+        .collect();
+    if !served_feeds.is_empty() {
+        served_feeds.sort();
+        return Err(error::ErrorConflict(format!(
+            "Oracle script {id} still serves feeds {}",
+            served_feeds.join(", ")
+        )));
+    }
+    catalog
+        .retire(&id)
+        .map_err(|e| error::ErrorInternalServerError(e.to_string()))?;
+    info!("Retired oracle script {id}");
+    Ok(HttpResponse::Ok().into())
+}
+
+fn health_response<T: Serialize>(status: HealthStatus, report: &T) -> HttpResponse {
+    let mut response = match status {
+        HealthStatus::Unhealthy => HttpResponse::ServiceUnavailable(),
+        HealthStatus::Ok | HealthStatus::Degraded => HttpResponse::Ok(),
+    };
+    response.json(report)
+}
+
+#[get("/health/live")]
+pub async fn liveness_probe(sequencer_state: web::Data<SequencerState>) -> HttpResponse {
+    let liveness = health::liveness(&sequencer_state).await;
+    health_response(liveness.status, &liveness)
+}
+
+#[get("/health/ready")]
+pub async fn readiness_probe(sequencer_state: web::Data<SequencerState>) -> HttpResponse {
+    let readiness = health::readiness(&sequencer_state).await;
+    health_response(readiness.status, &readiness)
+}
+
+/// Kept for the existing deployments probing `/health`. Answers as long as the admin API is
+/// served, the state of the workers is only reported in the body.
 #[get("/health")]
-pub async fn health(_sequencer_state: web::Data<SequencerState>) -> Result<HttpResponse, Error> {
-    //TODO(adikov): Check if we have connection to:
-    // * All blockchain networks
-    // * Kafka
-    // * ...
-    Ok(HttpResponse::Ok()
-        .content_type(ContentType::plaintext())
-        .body("".to_string()))
+pub async fn health(sequencer_state: web::Data<SequencerState>) -> HttpResponse {
+    let liveness = health::liveness(&sequencer_state).await;
+    HttpResponse::Ok().json(&liveness)
 }
 
 pub fn add_admin_services(cfg: &mut ServiceConfig) {
@@ -648,13 +1003,30 @@ pub fn add_admin_services(cfg: &mut ServiceConfig) {
         .service(enable_provider)
         .service(list_provider_status)
         .service(get_history)
+        .service(set_feed_history_depth)
+        .service(list_reporters)
+        .service(add_reporter)
+        .service(remove_reporter)
+        .service(suspend_reporter)
+        .service(resume_reporter)
+        .service(rotate_reporter_keys)
+        .service(list_reporter_registrations)
+        .service(approve_reporter_registration)
+        .service(reject_reporter_registration)
         .service(get_oracle_scripts)
+        .service(get_oracle_script_catalog)
+        .service(register_oracle_script)
+        .service(update_oracle_script)
+        .service(retire_oracle_script)
+        .service(liveness_probe)
+        .service(readiness_probe)
         .service(health);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::oracle_scripts::OracleScriptCatalog;
     use crate::providers::provider::init_shared_rpc_providers;
     use actix_test::to_bytes;
     use actix_web::{test, App};
@@ -665,6 +1037,7 @@ mod tests {
     };
     use blocksense_config::{AllFeedsConfig, SequencerConfig};
     use regex::Regex;
+    use ringbuf::traits::Consumer;
 
     use blocksense_utils::logging::init_shared_logging_handle;
     use blocksense_utils::test_env::get_test_private_key_path;
@@ -699,18 +1072,21 @@ mod tests {
             mpsc::unbounded_channel();
         let (aggregate_batch_sig_send, _aggregate_batch_sig_recv) = mpsc::unbounded_channel();
 
-        let sequencer_state = web::Data::new(SequencerState::new(
-            feeds_config,
-            providers,
-            log_handle,
-            &sequencer_config,
-            metrics_prefix,
-            vote_send,
-            feeds_management_cmd_to_block_creator_send,
-            feeds_slots_manager_cmd_send,
-            aggregate_batch_sig_send,
-            Arc::new(RwLock::new(HashMap::new())),
-        ));
+        let sequencer_state = web::Data::new(
+            SequencerState::new(
+                feeds_config,
+                providers,
+                log_handle,
+                &sequencer_config,
+                metrics_prefix,
+                vote_send,
+                feeds_management_cmd_to_block_creator_send,
+                feeds_slots_manager_cmd_send,
+                aggregate_batch_sig_send,
+                Arc::new(RwLock::new(HashMap::new())),
+            )
+            .unwrap(),
+        );
 
         let app = test::init_service(
             App::new()
@@ -962,7 +1338,11 @@ mod tests {
                 ];
                 *provider = blocksense_config::Provider {
                     private_key_path: key_path.to_str().unwrap().to_owned(),
+                    signer: None,
                     url,
+                    fallback_urls: vec![],
+                    rpc_failover_after_failures: 3,
+                    rpc_read_quorum: 1,
                     transaction_retries_count_limit: 42,
                     transaction_retry_timeout_secs: 20,
                     retry_fee_increment_fraction: 0.1,
@@ -974,6 +1354,7 @@ mod tests {
                     impersonated_anvil_account: None,
                     publishing_criteria: vec![],
                     should_load_rb_indices: true,
+                    chain_id: None,
                     contracts,
                 }
             });
@@ -1140,4 +1521,401 @@ mod tests {
         );
         drop(provider_status);
     }
+
+    #[actix_web::test]
+    async fn reporter_endpoints_schedule_reporter_actions() {
+        let mut sequencer_config = get_test_config_with_no_providers();
+        sequencer_config.reporters = vec![Reporter {
+            id: 42,
+            pub_key: "ea30b1533ef5638af7b70a036275642fc453ace97ed2c6b9d220fe1f59a24d61f481a777aa8a579f20e95a74cd4567ed36a3".to_string(),
+            address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            stake: 1.0,
+        }];
+        let (
+            sequencer_state,
+            _vote_recv,
+            mut feeds_management_cmd_to_block_creator_recv,
+            _feeds_slots_manager_cmd_recv,
+            _aggregate_batch_sig_recv,
+            _,
+        ) = create_sequencer_state_from_sequencer_config(
+            sequencer_config,
+            "reporter_endpoints_schedule_reporter_actions",
+            AllFeedsConfig { feeds: vec![] },
+        )
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_admin_services),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/list_reporters").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        let reporters: Vec<ReporterInfo> =
+            serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(reporters.len(), 1);
+        assert_eq!(reporters[0].id, 42);
+        assert!(!reporters[0].suspended);
+
+        let new_reporter = Reporter {
+            id: 14,
+            pub_key: "ea30813e2f8cf968e27bad29167b41bce038a3ce9b7b368de05e5cf1af3de919eeba267b8706f55c356d5f71891eff116b98".to_string(),
+            address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(),
+            stake: 2.0,
+        };
+        let req = test::TestRequest::post()
+            .uri("/add_reporter")
+            .set_json(&new_reporter)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        match feeds_management_cmd_to_block_creator_recv.try_recv() {
+            Ok(FeedsManagementCmds::ReporterAction(action)) => {
+                assert_eq!(action, ReporterAction::Add(new_reporter))
+            }
+            _ => panic!("Add reporter action was not scheduled"),
+        }
+
+        // Reporter 42 is already registered
+        let req = test::TestRequest::post()
+            .uri("/add_reporter")
+            .set_json(&sequencer_state.sequencer_config.read().await.reporters[0])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status());
+
+        let req = test::TestRequest::post()
+            .uri("/suspend_reporter/42")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        match feeds_management_cmd_to_block_creator_recv.try_recv() {
+            Ok(FeedsManagementCmds::ReporterAction(action)) => {
+                assert_eq!(action, ReporterAction::Suspend { id: 42 })
+            }
+            _ => panic!("Suspend reporter action was not scheduled"),
+        }
+
+        // Reporter is not suspended until the action is applied
+        let req = test::TestRequest::post()
+            .uri("/resume_reporter/42")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status());
+
+        let req = test::TestRequest::post()
+            .uri("/remove_reporter/7")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(404, resp.status());
+        assert!(feeds_management_cmd_to_block_creator_recv
+            .try_recv()
+            .is_err());
+    }
+
+    #[actix_web::test]
+    async fn approved_registration_schedules_add_reporter() {
+        let (
+            sequencer_state,
+            _vote_recv,
+            mut feeds_management_cmd_to_block_creator_recv,
+            _feeds_slots_manager_cmd_recv,
+            _aggregate_batch_sig_recv,
+            _,
+        ) = create_sequencer_state_from_sequencer_config(
+            get_test_config_with_no_providers(),
+            "approved_registration_schedules_add_reporter",
+            AllFeedsConfig { feeds: vec![] },
+        )
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_admin_services),
+        )
+        .await;
+
+        let reporter = Reporter {
+            id: 14,
+            pub_key: "ea30813e2f8cf968e27bad29167b41bce038a3ce9b7b368de05e5cf1af3de919eeba267b8706f55c356d5f71891eff116b98".to_string(),
+            address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(),
+            stake: 1.0,
+        };
+        {
+            let mut pending = sequencer_state.pending_reporter_registrations.write().await;
+            pending.insert(14, reporter.clone());
+            pending.insert(
+                15,
+                Reporter {
+                    id: 15,
+                    ..reporter.clone()
+                },
+            );
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/list_reporter_registrations")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        let registrations: Vec<Reporter> =
+            serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(
+            registrations.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![14, 15]
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/approve_reporter_registration/14")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        match feeds_management_cmd_to_block_creator_recv.try_recv() {
+            Ok(FeedsManagementCmds::ReporterAction(action)) => {
+                assert_eq!(action, ReporterAction::Add(reporter))
+            }
+            _ => panic!("Add reporter action was not scheduled"),
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/reject_reporter_registration/15")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        assert!(sequencer_state
+            .pending_reporter_registrations
+            .read()
+            .await
+            .is_empty());
+
+        let req = test::TestRequest::post()
+            .uri("/approve_reporter_registration/15")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(404, resp.status());
+        assert!(feeds_management_cmd_to_block_creator_recv
+            .try_recv()
+            .is_err());
+    }
+
+    #[actix_web::test]
+    async fn set_feed_history_depth_keeps_history() {
+        let mut sequencer_config = get_test_config_with_no_providers();
+        sequencer_config.history_memory_budget_mb = Some(1);
+        let mut feed_config = test_feed_config(1, 0);
+        feed_config.history_depth = Some(2);
+        let (
+            sequencer_state,
+            _vote_recv,
+            _feeds_management_cmd_to_block_creator_recv,
+            _feeds_slots_manager_cmd_recv,
+            _aggregate_batch_sig_recv,
+            _,
+        ) = create_sequencer_state_from_sequencer_config(
+            sequencer_config,
+            "set_feed_history_depth_keeps_history",
+            AllFeedsConfig {
+                feeds: vec![feed_config],
+            },
+        )
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_admin_services),
+        )
+        .await;
+
+        let feed_id = EncodedFeedId::new(1, 0);
+        {
+            let mut history = sequencer_state.feed_aggregate_history.write().await;
+            assert_eq!(history.capacity(feed_id), Some(2));
+            history.push_next(feed_id, FeedType::Numerical(1.0), 1000);
+            history.push_next(feed_id, FeedType::Numerical(2.0), 2000);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/set_feed_history_depth/1/500")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        {
+            let history = sequencer_state.feed_aggregate_history.read().await;
+            assert_eq!(history.capacity(feed_id), Some(500));
+            assert_eq!(history.get(feed_id).unwrap().iter().count(), 2);
+            assert_eq!(history.last_value(feed_id), Some(&FeedType::Numerical(2.0)));
+        }
+        assert_eq!(
+            sequencer_state.active_feeds.read().await[&feed_id].history_depth,
+            Some(500)
+        );
+
+        // Over the memory budget
+        let req = test::TestRequest::post()
+            .uri("/set_feed_history_depth/1/100000000")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status());
+        let req = test::TestRequest::post()
+            .uri("/set_feed_history_depth/1/0")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(400, resp.status());
+        let req = test::TestRequest::post()
+            .uri("/set_feed_history_depth/2/100")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(404, resp.status());
+        assert_eq!(
+            sequencer_state
+                .feed_aggregate_history
+                .read()
+                .await
+                .capacity(feed_id),
+            Some(500)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_liveness_follows_workers() {
+        let sequencer_config = get_test_config_with_no_providers();
+        let feeds_config = AllFeedsConfig {
+            feeds: vec![test_feed_config(1, 0)],
+        };
+        let metrics_prefix = "test_liveness_follows_workers";
+        let (
+            sequencer_state,
+            _vote_recv,
+            _feeds_management_cmd_to_block_creator_recv,
+            _feeds_slots_manager_cmd_recv,
+            _aggregate_batch_sig_recv,
+            _,
+        ) = create_sequencer_state_from_sequencer_config(
+            sequencer_config,
+            metrics_prefix,
+            feeds_config,
+        )
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_admin_services),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(503, resp.status());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "unhealthy");
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "unhealthy");
+
+        let health = &sequencer_state.worker_health;
+        let _block_creator = health.start(crate::health::Worker::BlockCreator);
+        let _feeds_slots_manager = health.start(crate::health::Worker::FeedsSlotsManager);
+        let _processor = health.start(crate::health::Worker::FeedSlotsProcessor);
+
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(200, resp.status());
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["workers"]["feed_slots_processors_running"], 1);
+        assert_eq!(body["workers"]["registered_feeds"], 1);
+    }
+
+    #[actix_web::test]
+    async fn test_oracle_script_catalog() {
+        let mut sequencer_config = get_test_config_with_no_providers();
+        let catalog_dir = tempfile::tempdir().unwrap();
+        let catalog_path = catalog_dir.path().join("oracle_scripts.json");
+        std::fs::write(&catalog_path, r#"{"oracle_scripts": []}"#).unwrap();
+        sequencer_config.oracle_scripts_path = Some(catalog_path.to_str().unwrap().to_string());
+        let feeds_config = AllFeedsConfig {
+            feeds: vec![test_feed_config(1, 0)],
+        };
+        let metrics_prefix = "test_oracle_script_catalog";
+        let (
+            sequencer_state,
+            _vote_recv,
+            _feeds_management_cmd_to_block_creator_recv,
+            _feeds_slots_manager_cmd_recv,
+            _aggregate_batch_sig_recv,
+            _,
+        ) = create_sequencer_state_from_sequencer_config(
+            sequencer_config,
+            metrics_prefix,
+            feeds_config,
+        )
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_admin_services),
+        )
+        .await;
+
+        let manifest = serde_json::json!({
+            "id": "cex-price-feeds",
+            "feeds": [1],
+            "allowed_outbound_hosts": ["https://api.kraken.com"],
+            "content_hash": format!("0x{}", "ab".repeat(32)),
+        });
+        let req = test::TestRequest::post()
+            .uri("/register_oracle_script")
+            .set_json(&manifest)
+            .to_request();
+        assert_eq!(200, test::call_service(&app, req).await.status());
+        let req = test::TestRequest::post()
+            .uri("/register_oracle_script")
+            .set_json(&manifest)
+            .to_request();
+        assert_eq!(409, test::call_service(&app, req).await.status());
+
+        let req = test::TestRequest::get()
+            .uri("/get_oracle_scripts")
+            .to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let oracles: OraclesResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(oracles.oracles.len(), 1);
+        assert_eq!(
+            oracles.oracles[0].oracle_script_wasm,
+            "cex-price-feeds.wasm"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/get_oracle_script_catalog")
+            .to_request();
+        let catalog: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(catalog["oracle_scripts"][0]["served_feeds"][0], 1);
+        assert_eq!(catalog["unassigned_feeds"], serde_json::json!([]));
+
+        // Feed 1 is still served by the script
+        let req = test::TestRequest::post()
+            .uri("/retire_oracle_script/cex-price-feeds")
+            .to_request();
+        assert_eq!(409, test::call_service(&app, req).await.status());
+        sequencer_state.active_feeds.write().await.clear();
+        let req = test::TestRequest::post()
+            .uri("/retire_oracle_script/cex-price-feeds")
+            .to_request();
+        assert_eq!(200, test::call_service(&app, req).await.status());
+        let req = test::TestRequest::post()
+            .uri("/update_oracle_script/cex-price-feeds")
+            .set_json(&manifest)
+            .to_request();
+        assert_eq!(409, test::call_service(&app, req).await.status());
+
+        let reopened = OracleScriptCatalog::open(Some(catalog_path.to_str().unwrap())).unwrap();
+        assert!(reopened.get("cex-price-feeds").unwrap().retired);
+        assert!(reopened.oracle_scripts().is_empty());
+    }
 }
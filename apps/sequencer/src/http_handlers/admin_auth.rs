@@ -0,0 +1,478 @@
+//! Bearer token authentication of the admin API. Endpoints other than the probes and the reporter
+//! facing ones require an `AdminRole`, and the calls to endpoints that change the state of the
+//! sequencer are written to an audit log.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{error, web, Error};
+use alloy_primitives::keccak256;
+use blocksense_config::{AdminAuthConfig, AdminRole};
+use blocksense_utils::time::current_unix_time;
+use eyre::{Context, Result};
+use futures::StreamExt;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::http_handlers::MAX_SIZE;
+
+/// Role needed to call each admin endpoint, `None` if anyone may call it.
+const ENDPOINT_ROLES: &[(&str, Option<AdminRole>)] = &[
+    // Probed by the orchestration and scraped by Prometheus without credentials
+    ("/health", None),
+    ("/health/live", None),
+    ("/health/ready", None),
+    ("/metrics", None),
+    // Fetched by the reporters when they build their oracle components
+    ("/get_feeds_config", None),
+    ("/get_oracle_scripts", None),
+    ("/get_key/{network}/{key}", Some(AdminRole::ReadOnly)),
+    (
+        "/get_feed_report_interval/{encoded_feed_id}",
+        Some(AdminRole::ReadOnly),
+    ),
+    (
+        "/get_feed_config/{encoded_feed_id}",
+        Some(AdminRole::ReadOnly),
+    ),
+    ("/get_sequencer_config", Some(AdminRole::ReadOnly)),
+    ("/list_provider_status", Some(AdminRole::ReadOnly)),
+    ("/get_history", Some(AdminRole::ReadOnly)),
+    ("/list_reporters", Some(AdminRole::ReadOnly)),
+    ("/list_reporter_registrations", Some(AdminRole::ReadOnly)),
+    ("/get_oracle_script_catalog", Some(AdminRole::ReadOnly)),
+    ("/main_log_level/{log_level}", Some(AdminRole::Operator)),
+    ("/register_asset_feed", Some(AdminRole::Operator)),
+    (
+        "/delete_asset_feed/{encoded_feed_id}",
+        Some(AdminRole::Operator),
+    ),
+    (
+        "/disable_provider/{network_name}",
+        Some(AdminRole::Operator),
+    ),
+    ("/enable_provider/{network_name}", Some(AdminRole::Operator)),
+    (
+        "/set_feed_history_depth/{encoded_feed_id}/{history_depth}",
+        Some(AdminRole::Operator),
+    ),
+    ("/add_reporter", Some(AdminRole::Operator)),
+    ("/remove_reporter/{reporter_id}", Some(AdminRole::Operator)),
+    ("/suspend_reporter/{reporter_id}", Some(AdminRole::Operator)),
+    ("/resume_reporter/{reporter_id}", Some(AdminRole::Operator)),
+    (
+        "/rotate_reporter_keys/{reporter_id}",
+        Some(AdminRole::Operator),
+    ),
+    (
+        "/approve_reporter_registration/{reporter_id}",
+        Some(AdminRole::Operator),
+    ),
+    (
+        "/reject_reporter_registration/{reporter_id}",
+        Some(AdminRole::Operator),
+    ),
+    ("/register_oracle_script", Some(AdminRole::Operator)),
+    ("/update_oracle_script/{id}", Some(AdminRole::Operator)),
+    ("/retire_oracle_script/{id}", Some(AdminRole::Operator)),
+    (
+        "/deploy/{network}/{contract_name}",
+        Some(AdminRole::Deployer),
+    ),
+];
+
+/// Role needed to call the endpoint matched by `pattern`, `None` if anyone may call it.
+/// Endpoints missing from `ENDPOINT_ROLES` need the highest role, so a new endpoint is closed
+/// until it is given a role.
+pub fn required_role(pattern: &str) -> Option<AdminRole> {
+    match ENDPOINT_ROLES
+        .iter()
+        .find(|(endpoint, _)| *endpoint == pattern)
+    {
+        Some((_, role)) => *role,
+        None => {
+            warn!("Admin endpoint {pattern} has no role, only deployers may call it");
+            Some(AdminRole::Deployer)
+        }
+    }
+}
+
+struct Principal {
+    name: String,
+    token: String,
+    role: AdminRole,
+}
+
+pub struct AdminAuth {
+    principals: Vec<Principal>,
+    audit_log: Option<Mutex<File>>,
+}
+
+#[derive(Serialize)]
+struct AuditRecord<'a> {
+    timestamp_ms: u128,
+    principal: Option<&'a str>,
+    role: Option<AdminRole>,
+    remote_addr: Option<&'a str>,
+    method: &'a str,
+    path: &'a str,
+    /// Size and hash of the request body, which may contain secrets and is not recorded itself
+    payload_size: usize,
+    payload_keccak256: String,
+    status: u16,
+}
+
+impl AdminAuth {
+    pub fn from_config(config: &AdminAuthConfig) -> Result<AdminAuth> {
+        let mut principals = Vec::new();
+        for token in &config.tokens {
+            let secret = std::fs::read_to_string(&token.token_path).wrap_err_with(|| {
+                format!(
+                    "Could not read token of admin {} from {}",
+                    token.name, token.token_path
+                )
+            })?;
+            principals.push(Principal {
+                name: token.name.clone(),
+                token: secret.trim().to_string(),
+                role: token.role,
+            });
+        }
+        if principals.is_empty() {
+            if !config.allow_unauthenticated {
+                eyre::bail!("No admin tokens configured and allow_unauthenticated is not set");
+            }
+            warn!("No admin tokens configured, the admin API is open to anyone reaching it");
+        }
+
+        let audit_log = match &config.audit_log_path {
+            Some(path) => Some(Mutex::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .wrap_err_with(|| format!("Could not open admin audit log {path}"))?,
+            )),
+            None => None,
+        };
+
+        Ok(AdminAuth {
+            principals,
+            audit_log,
+        })
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.principals.is_empty()
+    }
+
+    fn principal(&self, req: &ServiceRequest) -> Option<&Principal> {
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)?
+            .to_str()
+            .ok()?
+            .strip_prefix("Bearer ")?
+            .trim();
+        self.principals
+            .iter()
+            .find(|principal| constant_time_eq(principal.token.as_bytes(), token.as_bytes()))
+    }
+
+    fn audit(&self, record: &AuditRecord) {
+        let Ok(line) = serde_json::to_string(record) else {
+            return;
+        };
+        info!(target: "admin_audit", "{line}");
+        if let Some(audit_log) = &self.audit_log {
+            let mut file = audit_log.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(e) = writeln!(file, "{line}") {
+                warn!("Could not write to the admin audit log: {e}");
+            }
+        }
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Reads the whole payload so that its size and hash can be audited, and puts it back for the
+/// handler.
+async fn take_payload(req: &mut ServiceRequest) -> Result<web::Bytes, Error> {
+    let mut payload = std::mem::replace(req.parts_mut().1, Payload::None);
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        if (body.len() + chunk.len()) > MAX_SIZE {
+            return Err(error::ErrorBadRequest("overflow"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let body = body.freeze();
+    req.set_payload(Payload::from(body.clone()));
+    Ok(body)
+}
+
+/// Middleware of the admin API, expects `web::Data<AdminAuth>` in the app data.
+pub async fn authorize<B: MessageBody>(
+    mut req: ServiceRequest,
+    next: Next<B>,
+) -> Result<ServiceResponse<EitherBody<B>>, Error> {
+    let auth = req
+        .app_data::<web::Data<AdminAuth>>()
+        .cloned()
+        .ok_or_else(|| error::ErrorInternalServerError("Admin authentication not configured"))?;
+    let pattern = req
+        .match_pattern()
+        .unwrap_or_else(|| req.path().to_string());
+    let Some(role) = required_role(&pattern) else {
+        return next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body);
+    };
+
+    let principal = auth.principal(&req).map(|p| (p.name.clone(), p.role));
+    let denied = if !auth.is_enabled() {
+        None
+    } else {
+        match &principal {
+            None => Some(error::ErrorUnauthorized("Missing or unknown bearer token")),
+            Some((name, granted)) if *granted < role => Some(error::ErrorForbidden(format!(
+                "Admin {name} is not allowed to call {pattern}"
+            ))),
+            Some(_) => None,
+        }
+    };
+    if role == AdminRole::ReadOnly {
+        return match denied {
+            Some(err) => Ok(req.error_response(err).map_into_right_body()),
+            None => next
+                .call(req)
+                .await
+                .map(ServiceResponse::map_into_left_body),
+        };
+    }
+
+    let payload = take_payload(&mut req).await?;
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let remote_addr = req
+        .connection_info()
+        .realip_remote_addr()
+        .map(str::to_string);
+    let result = match denied {
+        Some(err) => Ok(req.error_response(err).map_into_right_body()),
+        None => next
+            .call(req)
+            .await
+            .map(ServiceResponse::map_into_left_body),
+    };
+    let status = match &result {
+        Ok(res) => res.status(),
+        Err(err) => err.as_response_error().status_code(),
+    };
+    auth.audit(&AuditRecord {
+        timestamp_ms: current_unix_time(),
+        principal: principal.as_ref().map(|(name, _)| name.as_str()),
+        role: principal.as_ref().map(|(_, role)| *role),
+        remote_addr: remote_addr.as_deref(),
+        method: &method,
+        path: &path,
+        payload_size: payload.len(),
+        payload_keccak256: keccak256(&payload).to_string(),
+        status: status.as_u16(),
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::Method;
+    use actix_web::middleware::from_fn;
+    use actix_web::{get, post, test, App, HttpResponse};
+    use blocksense_config::AdminToken;
+
+    #[get("/get_sequencer_config")]
+    async fn get_sequencer_config() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[post("/disable_provider/{network_name}")]
+    async fn disable_provider(body: String) -> HttpResponse {
+        HttpResponse::Ok().body(body)
+    }
+
+    #[get("/deploy/{network}/{contract_name}")]
+    async fn deploy() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[get("/health")]
+    async fn health() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    #[actix_web::test]
+    async fn roles_follow_the_endpoint() {
+        assert_eq!(required_role("/health/ready"), None);
+        assert_eq!(required_role("/metrics"), None);
+        assert_eq!(required_role("/get_feeds_config"), None);
+        assert_eq!(
+            required_role("/get_sequencer_config"),
+            Some(AdminRole::ReadOnly)
+        );
+        assert_eq!(
+            required_role("/register_asset_feed"),
+            Some(AdminRole::Operator)
+        );
+        assert_eq!(
+            required_role("/deploy/{network}/{contract_name}"),
+            Some(AdminRole::Deployer)
+        );
+        assert_eq!(required_role("/not_listed"), Some(AdminRole::Deployer));
+    }
+
+    #[test]
+    fn every_admin_endpoint_has_a_role() {
+        let route = regex::Regex::new(r#"#\[(?:get|post)\("([^"]+)"\)\]"#).unwrap();
+        let routes: Vec<_> = route
+            .captures_iter(include_str!("admin.rs"))
+            .map(|captures| captures[1].to_string())
+            .collect();
+        assert!(routes.len() > 20);
+        for route in routes {
+            assert!(
+                ENDPOINT_ROLES
+                    .iter()
+                    .any(|(endpoint, _)| *endpoint == route),
+                "{route} is missing from ENDPOINT_ROLES"
+            );
+        }
+    }
+
+    #[test]
+    fn admin_auth_fails_closed_without_tokens() {
+        assert!(AdminAuth::from_config(&AdminAuthConfig::default()).is_err());
+        assert!(AdminAuth::from_config(&AdminAuthConfig {
+            allow_unauthenticated: true,
+            ..Default::default()
+        })
+        .is_ok());
+    }
+
+    #[actix_web::test]
+    async fn calls_are_authorized_and_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tokens = Vec::new();
+        for (name, role) in [
+            ("viewer", AdminRole::ReadOnly),
+            ("ops", AdminRole::Operator),
+        ] {
+            let token_path = dir.path().join(name);
+            std::fs::write(&token_path, format!("{name}-secret\n")).unwrap();
+            tokens.push(AdminToken {
+                name: name.to_string(),
+                token_path: token_path.to_str().unwrap().to_string(),
+                role,
+            });
+        }
+        let audit_log_path = dir.path().join("audit.log");
+        let auth = AdminAuth::from_config(&AdminAuthConfig {
+            tokens,
+            audit_log_path: Some(audit_log_path.to_str().unwrap().to_string()),
+            allow_unauthenticated: false,
+        })
+        .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(auth))
+                .wrap(from_fn(authorize))
+                .service(get_sequencer_config)
+                .service(disable_provider)
+                .service(deploy)
+                .service(health),
+        )
+        .await;
+        let call = |method: Method, uri: &str, token: Option<&str>| {
+            let mut req = test::TestRequest::default()
+                .method(method)
+                .uri(uri)
+                .set_payload("{\"reason\":\"rpc down\"}");
+            if let Some(token) = token {
+                req = req.insert_header((header::AUTHORIZATION, format!("Bearer {token}")));
+            }
+            req.to_request()
+        };
+
+        let app = &app;
+        let status = |req| async move { test::call_service(app, req).await.status().as_u16() };
+        assert_eq!(status(call(Method::GET, "/health", None)).await, 200);
+        assert_eq!(
+            status(call(Method::GET, "/get_sequencer_config", None)).await,
+            401
+        );
+        assert_eq!(
+            status(call(Method::GET, "/get_sequencer_config", Some("wrong"))).await,
+            401
+        );
+        assert_eq!(
+            status(call(
+                Method::GET,
+                "/get_sequencer_config",
+                Some("viewer-secret")
+            ))
+            .await,
+            200
+        );
+        assert_eq!(
+            status(call(
+                Method::POST,
+                "/disable_provider/ETH1",
+                Some("viewer-secret")
+            ))
+            .await,
+            403
+        );
+        let resp = test::call_service(
+            app,
+            call(Method::POST, "/disable_provider/ETH1", Some("ops-secret")),
+        )
+        .await;
+        assert_eq!(resp.status(), 200);
+        // The handler still gets the payload after it was audited
+        assert_eq!(test::read_body(resp).await, "{\"reason\":\"rpc down\"}");
+        assert_eq!(
+            status(call(Method::GET, "/deploy/ETH1/ADFS", Some("ops-secret"))).await,
+            403
+        );
+
+        let audit_log = std::fs::read_to_string(audit_log_path).unwrap();
+        let records: Vec<serde_json::Value> = audit_log
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0]["principal"], "viewer");
+        assert_eq!(records[0]["status"], 403);
+        assert_eq!(records[1]["principal"], "ops");
+        assert_eq!(records[1]["path"], "/disable_provider/ETH1");
+        assert_eq!(records[1]["role"], "operator");
+        assert_eq!(records[1]["method"], "POST");
+        assert_eq!(records[1]["payload_size"], 21);
+        assert_eq!(
+            records[1]["payload_keccak256"],
+            keccak256(b"{\"reason\":\"rpc down\"}").to_string()
+        );
+        assert!(!audit_log.contains("rpc down"));
+        assert_eq!(records[1]["status"], 200);
+        assert_eq!(records[2]["path"], "/deploy/ETH1/ADFS");
+    }
+}
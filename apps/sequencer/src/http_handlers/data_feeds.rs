@@ -11,17 +11,19 @@ use actix_web::web::{self, ServiceConfig};
 use actix_web::Error;
 use actix_web::{get, post, HttpResponse};
 use blocksense_feed_registry::types::{
-    GetLastPublishedRequestData, LastPublishedValue, ReportRelevance,
+    GetLastPublishedRequestData, LastPublishedValue, ReportRelevance, Timestamp,
 };
 use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 
 use tracing::{debug, info, info_span, warn};
 
 use crate::http_handlers::MAX_SIZE;
+use crate::reporters::reporter::verify_registration;
 use crate::sequencer_state::SequencerState;
-use blocksense_config::SequencerConfig;
-use blocksense_feed_registry::registry::VoteStatus;
-use blocksense_feed_registry::types::DataFeedPayload;
+use blocksense_config::{ReporterRegistration, SequencerConfig};
+use blocksense_feed_registry::registry::{HistoryQuery, SlotOutcome, VoteStatus};
+use blocksense_feed_registry::types::{DataFeedPayload, FeedType};
 use blocksense_feeds_processing::utils::check_signature;
 use blocksense_gnosis_safe::data_types::ReporterResponse;
 use blocksense_metrics::{inc_metric, inc_vec_metric};
@@ -61,6 +63,14 @@ async fn process_report(
                 };
                 {
                     let rlocked_reporter = reporter.read().await;
+                    if rlocked_reporter.suspended {
+                        drop(rlocked_reporter);
+                        debug!(
+                            "Recvd vote from suspended reporter_id: {} for encoded_feed_id: {}",
+                            reporter_id, encoded_feed_id
+                        );
+                        return HttpResponse::Forbidden().into();
+                    }
                     if !check_signature(
                         &signature.sig,
                         &rlocked_reporter.pub_key,
@@ -283,6 +293,97 @@ pub async fn get_last_published_value_and_time(
     Ok(HttpResponse::Ok().json(results))
 }
 
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Query parameters of `/get_feed_history`. `from` and `to` are slot end timestamps in ms.
+#[derive(Debug, Deserialize)]
+pub struct FeedHistoryParams {
+    pub last: Option<usize>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    #[serde(default)]
+    pub published_only: bool,
+    #[serde(default)]
+    pub format: HistoryFormat,
+}
+
+// Flat version of SlotOutcome, since CSV has no nested values
+#[derive(Serialize)]
+struct SlotOutcomeRow<'a> {
+    end_slot_timestamp: u128,
+    value: Option<String>,
+    update_number: Option<u128>,
+    votes: usize,
+    valid_reporters: usize,
+    quorum_reached: bool,
+    reason: &'a str,
+}
+
+fn slot_outcomes_to_csv(outcomes: &[SlotOutcome]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for outcome in outcomes {
+        writer.serialize(SlotOutcomeRow {
+            end_slot_timestamp: outcome.end_slot_timestamp,
+            value: outcome.value.as_ref().map(|value| match value {
+                FeedType::Numerical(v) => v.to_string(),
+                FeedType::Text(s) => s.clone(),
+                FeedType::Bytes(b) => alloy::hex::encode_prefixed(b),
+                FeedType::Decimal { .. } => value.parse_to_string(),
+            }),
+            update_number: outcome.update_number,
+            votes: outcome.votes,
+            valid_reporters: outcome.valid_reporters,
+            quorum_reached: outcome.quorum_reached,
+            reason: &outcome.reason,
+        })?;
+    }
+    let bytes = writer.into_inner().map_err(|e| eyre::eyre!("{e}"))?;
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// Outcomes of the most recent voting slots of a feed, with the aggregated value, the number of
+/// votes and the reason it was published or skipped.
+#[get("/get_feed_history/{encoded_feed_id}")]
+pub async fn get_feed_history(
+    path: web::Path<String>,
+    params: web::Query<FeedHistoryParams>,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let encoded_feed_id: EncodedFeedId = path
+        .parse()
+        .map_err(|e| ErrorBadRequest(format!("Invalid feed id {path}: {e}")))?;
+    let params = params.into_inner();
+    let query = HistoryQuery {
+        last: params.last,
+        from: params.from.map(Timestamp::from),
+        to: params.to.map(Timestamp::from),
+        published_only: params.published_only,
+    };
+    let outcomes = sequencer_state
+        .feed_aggregate_history
+        .read()
+        .await
+        .query_slot_outcomes(encoded_feed_id, &query);
+    let Some(outcomes) = outcomes else {
+        return Ok(
+            HttpResponse::NotFound().body(format!("Feed {encoded_feed_id} is not registered"))
+        );
+    };
+    match params.format {
+        HistoryFormat::Json => Ok(HttpResponse::Ok().json(outcomes)),
+        HistoryFormat::Csv => match slot_outcomes_to_csv(&outcomes) {
+            Ok(csv) => Ok(HttpResponse::Ok().content_type("text/csv").body(csv)),
+            Err(e) => Ok(HttpResponse::InternalServerError().body(e.to_string())),
+        },
+    }
+}
+
 use serde::de::DeserializeOwned;
 
 async fn deserialize_payload_to_vec<T>(
@@ -383,7 +484,14 @@ pub async fn post_aggregated_consensus_vote(
             }
         };
 
-        let signer_address = reporter.read().await.address;
+        let (signer_address, suspended) = {
+            let reporter = reporter.read().await;
+            (reporter.address, reporter.suspended)
+        };
+        if suspended {
+            warn!("Suspended Reporter sending aggregation batch signature {body:?}!");
+            return Ok(HttpResponse::Forbidden().body("Suspended Reporter".to_string()));
+        }
         let call_data_with_signatures = sequencer_state
             .batches_awaiting_consensus
             .read()
@@ -439,11 +547,67 @@ pub async fn post_aggregated_consensus_vote(
     }
 }
 
+/// Upper bound of the registrations waiting for an admin, since anyone can register.
+pub const MAX_PENDING_REPORTER_REGISTRATIONS: usize = 100;
+
+/// Node operators ask to join the reporter set here. A registration with valid signatures is
+/// kept until an admin approves or rejects it. Pending registrations are only kept in memory, so
+/// they are lost when the sequencer restarts and have to be submitted again.
+#[post("/register_reporter")]
+pub async fn register_reporter(
+    mut payload: web::Payload,
+    sequencer_state: web::Data<SequencerState>,
+) -> Result<HttpResponse, Error> {
+    let max_size = get_max_buffer_size(&*sequencer_state.sequencer_config.read().await);
+    let mut body = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk?;
+        // limit max size of in-memory payload
+        if (body.len() + chunk.len()) > max_size {
+            return Err(ErrorBadRequest("overflow"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let registration: ReporterRegistration = serde_json::from_str(std::str::from_utf8(&body)?)?;
+
+    if let Err(e) = verify_registration(&registration) {
+        warn!("Rejected reporter registration {registration:?}: {e}");
+        return Ok(HttpResponse::BadRequest().body(e.to_string()));
+    }
+    let reporter = registration.reporter;
+    let reporter_id = u64::from(reporter.id);
+    if sequencer_state
+        .reporters
+        .read()
+        .await
+        .contains_key(&reporter_id)
+    {
+        return Ok(HttpResponse::Conflict().body(format!("Reporter id {reporter_id} is taken")));
+    }
+    let mut pending = sequencer_state.pending_reporter_registrations.write().await;
+    if pending.contains_key(&reporter_id) {
+        return Ok(HttpResponse::Conflict().body(format!(
+            "Reporter id {reporter_id} already has a pending registration"
+        )));
+    }
+    if pending.len() >= MAX_PENDING_REPORTER_REGISTRATIONS {
+        warn!("Rejected registration of reporter {reporter_id}, too many pending registrations");
+        return Ok(HttpResponse::TooManyRequests().body(format!(
+            "{MAX_PENDING_REPORTER_REGISTRATIONS} registrations are already waiting for approval"
+        )));
+    }
+    info!("Received registration of reporter {reporter:?}");
+    pending.insert(reporter_id, reporter);
+    Ok(HttpResponse::Accepted().into())
+}
+
 pub fn add_main_services(cfg: &mut ServiceConfig) {
     cfg.service(post_report)
         .service(post_reports_batch)
         .service(get_last_published_value_and_time)
-        .service(post_aggregated_consensus_vote);
+        .service(get_feed_history)
+        .service(post_aggregated_consensus_vote)
+        .service(register_reporter);
 }
 
 #[cfg(test)]
@@ -487,18 +651,21 @@ pub mod tests {
             mpsc::unbounded_channel();
         let (aggregate_batch_sig_send, _aggregate_batch_sig_recv) = mpsc::unbounded_channel();
 
-        let sequencer_state = web::Data::new(SequencerState::new(
-            feeds_config,
-            providers,
-            log_handle,
-            &sequencer_config,
-            metrics_prefix,
-            vote_send,
-            feeds_management_cmd_to_block_creator_send,
-            feeds_slots_manager_cmd_send,
-            aggregate_batch_sig_send,
-            Arc::new(RwLock::new(HashMap::new())),
-        ));
+        let sequencer_state = web::Data::new(
+            SequencerState::new(
+                feeds_config,
+                providers,
+                log_handle,
+                &sequencer_config,
+                metrics_prefix,
+                vote_send,
+                feeds_management_cmd_to_block_creator_send,
+                feeds_slots_manager_cmd_send,
+                aggregate_batch_sig_send,
+                Arc::new(RwLock::new(HashMap::new())),
+            )
+            .unwrap(),
+        );
 
         let app = test::init_service(
             App::new()
@@ -894,4 +1061,152 @@ pub mod tests {
         );
         assert!(last_values[0].error.is_none())
     }
+
+    #[actix_web::test]
+    async fn test_get_feed_history() {
+        let (sequencer_state, _, _, _, _, _) = create_sequencer_state_from_sequencer_config(
+            get_test_config_with_no_providers(),
+            "test_get_feed_history",
+            AllFeedsConfig {
+                feeds: vec![test_feed_config(1, 0)],
+            },
+        )
+        .await;
+        {
+            let mut history = sequencer_state.feed_aggregate_history.write().await;
+            for (i, reason) in ["ThresholdCrossed", "TooSimilarTooSoon", "QuorumNotReached"]
+                .into_iter()
+                .enumerate()
+            {
+                history.push_slot_outcome(
+                    EncodedFeedId::new(1, 0),
+                    SlotOutcome {
+                        end_slot_timestamp: 1000 * (i as u128 + 1),
+                        votes: 3 - i,
+                        valid_reporters: 3,
+                        quorum_reached: i < 2,
+                        value: (i < 2).then_some(FeedType::Numerical(100.5)),
+                        update_number: (i == 0).then_some(0),
+                        reason: reason.to_string(),
+                    },
+                );
+            }
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_main_services),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/get_feed_history/0:1?last=2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let outcomes: Vec<SlotOutcome> =
+            serde_json::from_slice(&test::read_body(resp).await).unwrap();
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|o| o.reason.as_str())
+                .collect::<Vec<_>>(),
+            vec!["TooSimilarTooSoon", "QuorumNotReached"]
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/get_feed_history/1?from=1000&to=2000&format=csv")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert_eq!(
+            body,
+            "end_slot_timestamp,value,update_number,votes,valid_reporters,quorum_reached,reason\n\
+             1000,100.5,0,3,3,true,ThresholdCrossed\n\
+             2000,100.5,,2,3,true,TooSimilarTooSoon\n"
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/get_feed_history/0:2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn register_reporter_is_capped() {
+        use alloy::hex;
+        use alloy::signers::{local::PrivateKeySigner, SignerSync};
+        use blocksense_config::Reporter;
+        use blocksense_crypto::{
+            generate_keys, serialize_public_key, sign_message, MULTIFORMATS_BLS_PUBKYE_PREFIX,
+        };
+
+        let (sequencer_state, _, _, _, _, _) = create_sequencer_state_from_sequencer_config(
+            get_test_config_with_no_providers(),
+            "register_reporter_is_capped",
+            AllFeedsConfig { feeds: vec![] },
+        )
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(sequencer_state.clone())
+                .configure(add_main_services),
+        )
+        .await;
+
+        let (secret_key, pub_key) = generate_keys(&[5; 35]);
+        let signer = PrivateKeySigner::random();
+        let reporter = Reporter {
+            id: 1000,
+            pub_key: format!(
+                "{MULTIFORMATS_BLS_PUBKYE_PREFIX}{}",
+                serialize_public_key(&pub_key)
+            ),
+            address: signer.address().to_string(),
+            stake: 1.0,
+        };
+        let message = ReporterRegistration::signing_message(&reporter);
+        let registration = ReporterRegistration {
+            reporter: reporter.clone(),
+            signature: hex::encode(sign_message(&secret_key, &message).to_bytes()),
+            address_signature: signer.sign_message_sync(&message).unwrap().to_string(),
+        };
+
+        {
+            let mut pending = sequencer_state.pending_reporter_registrations.write().await;
+            for id in 0..MAX_PENDING_REPORTER_REGISTRATIONS as u32 {
+                pending.insert(
+                    id.into(),
+                    Reporter {
+                        id,
+                        ..reporter.clone()
+                    },
+                );
+            }
+        }
+        let req = test::TestRequest::post()
+            .uri("/register_reporter")
+            .set_json(&registration)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 429);
+
+        sequencer_state
+            .pending_reporter_registrations
+            .write()
+            .await
+            .remove(&0);
+        let req = test::TestRequest::post()
+            .uri("/register_reporter")
+            .set_json(&registration)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 202);
+        assert_eq!(
+            sequencer_state.pending_reporter_registrations.read().await[&1000],
+            reporter
+        );
+    }
 }
@@ -2,8 +2,10 @@ pub mod aggregate_batch_consensus_processor;
 pub mod block_creator;
 pub mod blocks_reader;
 pub mod feeds;
+pub mod health;
 pub mod http_handlers;
 pub mod metrics_collector;
+pub mod oracle_scripts;
 pub mod providers;
 pub mod pyroscope_profiler;
 pub mod reporters;
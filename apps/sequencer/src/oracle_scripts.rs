@@ -0,0 +1,442 @@
+//! Catalog of the oracle scripts known to the sequencer. Feeds are linked to the oracle script
+//! that serves them through `FeedConfig.oracle_id`, so the catalog only stores the scripts and
+//! the links are resolved against the active feeds when the catalog is viewed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use blocksense_registry::config::{FeedConfig, OracleScript};
+use blocksense_registry::registry::OracleManifest;
+use blocksense_utils::{EncodedFeedId, FeedId};
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct CatalogEntry {
+    pub manifest: OracleManifest,
+    /// Retired scripts are kept so that their history is not lost and they can be registered
+    /// again, but they are no longer handed out to the reporters.
+    #[serde(default)]
+    pub retired: bool,
+    /// Name of the wasm file if it is not `<id>.wasm`, as for the built-in scripts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle_script_wasm: Option<String>,
+}
+
+/// The oracle scripts the sequencer served before the catalog existed. A catalog without a file
+/// starts with them, so upgraded deployments keep handing them out to the reporters. Their
+/// content hashes are unknown until they are updated through the admin API.
+fn builtin_scripts() -> BTreeMap<String, CatalogEntry> {
+    let script = |id: &str, wasm: &str, hosts: &[&str], capabilities: &[&str]| CatalogEntry {
+        manifest: OracleManifest {
+            id: id.to_string(),
+            name: None,
+            description: None,
+            interval_time_in_seconds: None,
+            feeds: vec![],
+            capabilities: capabilities.iter().map(|c| c.to_string()).collect(),
+            allowed_outbound_hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            content_hash: String::new(),
+        },
+        retired: false,
+        oracle_script_wasm: Some(wasm.to_string()),
+    };
+    [
+        script(
+            "cmc",
+            "cmc_oracle.wasm",
+            &["https://pro-api.coinmarketcap.com"],
+            &["CMC_API_KEY"],
+        ),
+        script(
+            "yahoo",
+            "yahoo_oracle.wasm",
+            &["https://yfapi.net:443"],
+            &["YAHOO_API_KEY"],
+        ),
+        script(
+            "exsat",
+            "exsat_holdings_oracle.wasm",
+            &[
+                "https://raw.githubusercontent.com",
+                "https://rpc-us.exsat.network",
+                "https://blockchain.info",
+            ],
+            &[],
+        ),
+        script(
+            "gecko-terminal",
+            "gecko_terminal_oracle.wasm",
+            &["https://api.geckoterminal.com"],
+            &[],
+        ),
+        script(
+            "eth-rpc",
+            "eth_rpc.wasm",
+            &["https://eth.llamarpc.com", "https://rpc.eth.gateway.fm"],
+            &[],
+        ),
+        script(
+            "cex-price-feeds",
+            "cex-price-feeds.wasm",
+            &[
+                "https://api.kraken.com",
+                "https://api.bybit.com",
+                "https://api.coinbase.com",
+                "https://api.exchange.coinbase.com",
+                "https://api1.binance.com",
+                "https://api.kucoin.com",
+                "https://api.mexc.com",
+                "https://api.crypto.com",
+                "https://api.binance.us",
+                "https://api.gemini.com",
+                "https://api-pub.bitfinex.com",
+                "https://api.upbit.com",
+                "https://api.bitget.com",
+                "https://api.gateio.ws",
+                "https://www.okx.com",
+            ],
+            &[],
+        ),
+    ]
+    .into_iter()
+    .map(|entry| (entry.manifest.id.clone(), entry))
+    .collect()
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct CatalogFile {
+    #[serde(default)]
+    oracle_scripts: Vec<CatalogEntry>,
+}
+
+/// Oracle scripts by id, written to a JSON file on every change if a path is configured.
+#[derive(Debug, Default)]
+pub struct OracleScriptCatalog {
+    path: Option<PathBuf>,
+    scripts: BTreeMap<String, CatalogEntry>,
+}
+
+impl OracleScriptCatalog {
+    /// Loads the catalog from `path`. If the file does not exist yet, it is created with the
+    /// built-in scripts. Without a path the catalog is kept only in memory and starts with the
+    /// built-in scripts on every restart.
+    pub fn open(path: Option<&str>) -> Result<OracleScriptCatalog> {
+        let Some(path) = path.map(PathBuf::from) else {
+            return Ok(OracleScriptCatalog {
+                path: None,
+                scripts: builtin_scripts(),
+            });
+        };
+        if !path.exists() {
+            info!(
+                "Creating oracle script catalog {} with the built-in scripts",
+                path.display()
+            );
+            let mut catalog = OracleScriptCatalog {
+                path: Some(path),
+                scripts: BTreeMap::new(),
+            };
+            catalog.save(builtin_scripts())?;
+            return Ok(catalog);
+        }
+        let data = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Could not read {}", path.display()))?;
+        let file: CatalogFile = serde_json::from_str(&data)
+            .wrap_err_with(|| format!("Invalid oracle script catalog {}", path.display()))?;
+        let scripts = file
+            .oracle_scripts
+            .into_iter()
+            .map(|entry| (entry.manifest.id.clone(), entry))
+            .collect::<BTreeMap<_, _>>();
+        info!(
+            "Loaded {} oracle scripts from {}",
+            scripts.len(),
+            path.display()
+        );
+        Ok(OracleScriptCatalog {
+            path: Some(path),
+            scripts,
+        })
+    }
+
+    pub fn get(&self, id: &str) -> Option<&CatalogEntry> {
+        self.scripts.get(id)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &CatalogEntry> {
+        self.scripts.values()
+    }
+
+    /// The scripts that are not retired, as the reporters configure them.
+    pub fn oracle_scripts(&self) -> Vec<OracleScript> {
+        self.entries()
+            .filter(|entry| !entry.retired)
+            .map(|entry| {
+                let mut script = entry.manifest.to_oracle_script();
+                if let Some(wasm) = &entry.oracle_script_wasm {
+                    script.oracle_script_wasm = wasm.clone();
+                }
+                script
+            })
+            .collect()
+    }
+
+    /// Adds the script or replaces the one with the same id. Nothing changes if it cannot be
+    /// saved.
+    pub fn upsert(&mut self, manifest: OracleManifest) -> Result<()> {
+        let mut scripts = self.scripts.clone();
+        scripts.insert(
+            manifest.id.clone(),
+            CatalogEntry {
+                manifest,
+                retired: false,
+                oracle_script_wasm: None,
+            },
+        );
+        self.save(scripts)
+    }
+
+    pub fn retire(&mut self, id: &str) -> Result<()> {
+        let mut scripts = self.scripts.clone();
+        if let Some(entry) = scripts.get_mut(id) {
+            entry.retired = true;
+        }
+        self.save(scripts)
+    }
+
+    fn save(&mut self, scripts: BTreeMap<String, CatalogEntry>) -> Result<()> {
+        if let Some(path) = &self.path {
+            let file = CatalogFile {
+                oracle_scripts: scripts.values().cloned().collect(),
+            };
+            // Write next to the catalog and rename, so a crash never leaves a truncated file
+            let tmp_path = path.with_extension("json.tmp");
+            fs::write(&tmp_path, serde_json::to_string_pretty(&file)?)
+                .wrap_err_with(|| format!("Could not write {}", tmp_path.display()))?;
+            fs::rename(&tmp_path, path)
+                .wrap_err_with(|| format!("Could not replace {}", path.display()))?;
+        }
+        self.scripts = scripts;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OracleScriptStatus {
+    pub entry: CatalogEntry,
+    /// Active feeds with this script as their `oracle_id`
+    pub served_feeds: Vec<FeedId>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CatalogView {
+    pub oracle_scripts: Vec<OracleScriptStatus>,
+    /// Active feeds whose `oracle_id` is not a script in the catalog, or a retired one
+    pub unassigned_feeds: Vec<FeedId>,
+}
+
+pub fn catalog_view(
+    catalog: &OracleScriptCatalog,
+    active_feeds: &HashMap<EncodedFeedId, FeedConfig>,
+) -> CatalogView {
+    let mut feeds_by_oracle: BTreeMap<&str, Vec<FeedId>> = BTreeMap::new();
+    for feed in active_feeds.values() {
+        feeds_by_oracle
+            .entry(feed.oracle_id.as_str())
+            .or_default()
+            .push(feed.id);
+    }
+    for feeds in feeds_by_oracle.values_mut() {
+        feeds.sort();
+    }
+
+    let oracle_scripts = catalog
+        .entries()
+        .map(|entry| OracleScriptStatus {
+            served_feeds: feeds_by_oracle
+                .get(entry.manifest.id.as_str())
+                .cloned()
+                .unwrap_or_default(),
+            entry: entry.clone(),
+        })
+        .collect();
+    let mut unassigned_feeds: Vec<FeedId> = feeds_by_oracle
+        .into_iter()
+        .filter(|(oracle_id, _)| catalog.get(oracle_id).is_none_or(|entry| entry.retired))
+        .flat_map(|(_, feeds)| feeds)
+        .collect();
+    unassigned_feeds.sort();
+
+    CatalogView {
+        oracle_scripts,
+        unassigned_feeds,
+    }
+}
+
+/// Checks a script before it is registered or updated. The feeds it declares may be registered
+/// later, but those that are active already must have it as their `oracle_id`.
+pub fn check_manifest(
+    manifest: &OracleManifest,
+    active_feeds: &HashMap<EncodedFeedId, FeedConfig>,
+) -> Result<(), String> {
+    if manifest.id.trim().is_empty() {
+        return Err("Oracle script id can not be empty".to_string());
+    }
+    let hash = manifest.content_hash.strip_prefix("0x").unwrap_or_default();
+    if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!(
+            "Invalid content hash {} of oracle script {}, expected a 0x prefixed keccak-256 hash",
+            manifest.content_hash, manifest.id
+        ));
+    }
+    for feed_id in &manifest.feeds {
+        for feed in active_feeds.values().filter(|feed| feed.id == *feed_id) {
+            if feed.oracle_id != manifest.id {
+                return Err(format!(
+                    "Feed {feed_id} is served by oracle script {}, not {}",
+                    feed.oracle_id, manifest.id
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blocksense_config::test_feed_config;
+    use std::collections::BTreeSet;
+
+    fn manifest(id: &str, feeds: Vec<FeedId>) -> OracleManifest {
+        OracleManifest {
+            id: id.to_string(),
+            name: None,
+            description: None,
+            interval_time_in_seconds: None,
+            feeds,
+            capabilities: Default::default(),
+            allowed_outbound_hosts: vec!["https://api.kraken.com".to_string()],
+            content_hash: format!("0x{}", "ab".repeat(32)),
+        }
+    }
+
+    fn active_feeds(feeds: &[(FeedId, &str)]) -> HashMap<EncodedFeedId, FeedConfig> {
+        feeds
+            .iter()
+            .map(|(id, oracle_id)| {
+                let mut feed = test_feed_config(*id, 0);
+                feed.oracle_id = oracle_id.to_string();
+                (EncodedFeedId::new(feed.id, feed.stride), feed)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn catalog_is_persisted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oracle_scripts.json");
+        fs::write(&path, r#"{"oracle_scripts": []}"#).unwrap();
+        let path = path.to_str().unwrap();
+
+        let mut catalog = OracleScriptCatalog::open(Some(path)).unwrap();
+        assert_eq!(catalog.entries().count(), 0);
+        catalog
+            .upsert(manifest("cex-price-feeds", vec![1]))
+            .unwrap();
+        catalog.upsert(manifest("gecko-terminal", vec![])).unwrap();
+        catalog.retire("gecko-terminal").unwrap();
+
+        let reopened = OracleScriptCatalog::open(Some(path)).unwrap();
+        assert_eq!(
+            reopened.entries().collect::<Vec<_>>(),
+            catalog.entries().collect::<Vec<_>>()
+        );
+        assert!(reopened.get("gecko-terminal").unwrap().retired);
+        let live: Vec<String> = reopened
+            .oracle_scripts()
+            .into_iter()
+            .map(|script| script.id)
+            .collect();
+        assert_eq!(live, vec!["cex-price-feeds"]);
+    }
+
+    #[test]
+    fn missing_catalog_starts_with_the_builtin_scripts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("oracle_scripts.json");
+        let path = path.to_str().unwrap();
+
+        // A deployment from before the catalog has no file yet
+        let mut catalog = OracleScriptCatalog::open(Some(path)).unwrap();
+        let scripts = catalog.oracle_scripts();
+        let ids: BTreeSet<&str> = scripts.iter().map(|script| script.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            BTreeSet::from([
+                "cex-price-feeds",
+                "cmc",
+                "eth-rpc",
+                "exsat",
+                "gecko-terminal",
+                "yahoo"
+            ])
+        );
+        let cmc = scripts.iter().find(|script| script.id == "cmc").unwrap();
+        assert_eq!(cmc.oracle_script_wasm, "cmc_oracle.wasm");
+        assert!(cmc.capabilities.contains("CMC_API_KEY"));
+
+        // The seeded catalog is saved, so changes to it survive a restart
+        catalog.retire("cmc").unwrap();
+        let reopened = OracleScriptCatalog::open(Some(path)).unwrap();
+        assert!(reopened.get("cmc").unwrap().retired);
+        assert_eq!(reopened.oracle_scripts().len(), 5);
+
+        // Updating a built-in script names its wasm after the id
+        catalog.upsert(manifest("exsat", vec![])).unwrap();
+        let exsat = catalog
+            .oracle_scripts()
+            .into_iter()
+            .find(|script| script.id == "exsat")
+            .unwrap();
+        assert_eq!(exsat.oracle_script_wasm, "exsat.wasm");
+
+        assert_eq!(
+            OracleScriptCatalog::open(None).unwrap().entries().count(),
+            6
+        );
+    }
+
+    #[test]
+    fn feeds_are_linked_through_their_oracle_id() {
+        let mut catalog = OracleScriptCatalog::default();
+        catalog
+            .upsert(manifest("cex-price-feeds", vec![1]))
+            .unwrap();
+        catalog.upsert(manifest("yahoo", vec![])).unwrap();
+        catalog.retire("yahoo").unwrap();
+        let feeds = active_feeds(&[
+            (1, "cex-price-feeds"),
+            (2, "cex-price-feeds"),
+            (3, "yahoo"),
+            (4, "cmc"),
+        ]);
+
+        let view = catalog_view(&catalog, &feeds);
+        assert_eq!(view.oracle_scripts[0].served_feeds, vec![1, 2]);
+        assert_eq!(view.oracle_scripts[1].served_feeds, vec![3]);
+        assert_eq!(view.unassigned_feeds, vec![3, 4]);
+
+        assert!(check_manifest(&manifest("cex-price-feeds", vec![1, 5]), &feeds).is_ok());
+        assert_eq!(
+            check_manifest(&manifest("cex-price-feeds", vec![4]), &feeds),
+            Err("Feed 4 is served by oracle script cmc, not cex-price-feeds".to_string())
+        );
+        let mut bad_hash = manifest("cmc", vec![4]);
+        bad_hash.content_hash = "cmc_oracle.wasm".to_string();
+        assert!(check_manifest(&bad_hash, &feeds).is_err());
+    }
+}
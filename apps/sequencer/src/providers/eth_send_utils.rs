@@ -393,8 +393,7 @@ pub async fn eth_batch_send_to_contract(
         contract_address, net
     );
 
-    let provider_metrics = &provider.provider_metrics;
-    let rpc_handle = &provider.provider;
+    let provider_metrics = &provider.provider_metrics.clone();
 
     let input = Bytes::from(serialized_updates);
 
@@ -430,13 +429,12 @@ pub async fn eth_batch_send_to_contract(
             return Ok(("timeout".to_string(), feeds_to_update_ids));
         }
 
-        let nonce = match get_nonce(
+        let nonce = match read_nonce(
+            &mut provider,
             &net,
-            rpc_handle,
             &sender_address,
             block_height,
             transaction_retry_timeout_secs,
-            false,
         )
         .await
         {
@@ -465,13 +463,12 @@ pub async fn eth_batch_send_to_contract(
             return Ok(("timeout".to_string(), feeds_to_update_ids));
         }
 
-        match get_nonce(
+        match read_nonce(
+            &mut provider,
             &net,
-            rpc_handle,
             &sender_address,
             block_height,
             transaction_retry_timeout_secs,
-            false,
         )
         .await
         {
@@ -479,7 +476,7 @@ pub async fn eth_batch_send_to_contract(
                 if latest_nonce > nonce {
                     // Check the tx hashes of all posted/retried txs for block inclusion
                     if let Some(included_tx_hash) = check_tx_hashes_for_inclusion(
-                        rpc_handle,
+                        &provider.provider,
                         generated_transaction_hashes.as_slice(),
                         receipt_polling_back_off_period_ms,
                     )
@@ -505,14 +502,17 @@ pub async fn eth_batch_send_to_contract(
             }
         };
 
-        let gas_price = match get_gas_price(
+        let gas_price = get_gas_price(
             net.as_str(),
             &provider,
             &updates,
             transaction_retry_timeout_secs,
         )
-        .await
-        {
+        .await;
+        provider
+            .record_rpc_call("get_gas_price", gas_price.is_ok())
+            .await;
+        let gas_price = match gas_price {
             Ok(v) => {
                 debug!("Successfully got value in network `{net}` block height {block_height} for gas_price");
                 v
@@ -534,7 +534,7 @@ pub async fn eth_batch_send_to_contract(
 
         let chain_id = match get_chain_id(
             net.as_str(),
-            &provider,
+            &mut provider,
             &updates,
             transaction_retry_timeout_secs,
         )
@@ -557,17 +557,22 @@ pub async fn eth_batch_send_to_contract(
             }
         };
 
-        let gas_fees = match get_tx_retry_params(
+        // The calls above may have failed over to another endpoint
+        let rpc_handle = provider.provider.clone();
+        let gas_fees = get_tx_retry_params(
             net.as_str(),
-            rpc_handle,
+            &rpc_handle,
             provider_metrics,
             &sender_address,
             transaction_retry_timeout_secs,
             transaction_retries_count,
             retry_fee_increment_fraction,
         )
-        .await
-        {
+        .await;
+        provider
+            .record_rpc_call("get_tx_retry_params", gas_fees.is_ok())
+            .await;
+        let gas_fees = match gas_fees {
             Ok(res) => res,
             Err(e) => {
                 let block_height = updates.block_height;
@@ -594,7 +599,7 @@ pub async fn eth_batch_send_to_contract(
 
         let gas_limit = get_gas_limit(
             net.as_str(),
-            rpc_handle,
+            &rpc_handle,
             &tx,
             transaction_retry_timeout_secs,
         )
@@ -637,12 +642,16 @@ pub async fn eth_batch_send_to_contract(
             {
                 Ok(r) => match r {
                     Ok(tx_builder) => {
+                        provider.record_rpc_call("send_transaction", true).await;
                         debug!("Successfully submitted for {transaction_retries_count}-th time transaction in network `{net}` block height {block_height} tx_hash = {}", tx_builder.tx_hash());
                         tx_builder
                     }
                     Err(err) => {
                         warn!("Error while submitting transaction in network `{net}` block height {block_height} and address {sender_address} due to {err}");
-                        if err.to_string().contains("execution revert") {
+                        let reverted = err.to_string().contains("execution revert");
+                        // A revert is an answer of a working endpoint
+                        provider.record_rpc_call("send_transaction", reverted).await;
+                        if reverted {
                             return Ok(("false".to_string(), feeds_to_update_ids));
                         } else {
                             inc_retries_with_backoff(
@@ -658,6 +667,7 @@ pub async fn eth_batch_send_to_contract(
                 },
                 Err(err) => {
                     warn!("Error timeout while submitting transaction in network `{net}` block height {block_height} and address {sender_address} due to {err}");
+                    provider.record_rpc_call("send_transaction", false).await;
                     inc_retries_with_backoff(
                         net.as_str(),
                         &mut transaction_retries_count,
@@ -678,7 +688,7 @@ pub async fn eth_batch_send_to_contract(
             let tx_get_receipt_start_time = Instant::now();
             let tx_receipt = match await_receipt(
                 net.as_str(),
-                rpc_handle,
+                &rpc_handle,
                 transaction_retry_timeout_secs,
                 &tx_hash,
                 block_height,
@@ -690,11 +700,13 @@ pub async fn eth_batch_send_to_contract(
             {
                 Ok(receipt) => {
                     inc_metric!(provider_metrics, net, success_get_receipt);
+                    provider.record_rpc_call("await_receipt", true).await;
                     receipt
                 }
                 Err(e) => {
                     warn!("await_receipt: {e}");
                     inc_metric!(provider_metrics, net, failed_get_receipt);
+                    provider.record_rpc_call("await_receipt", false).await;
                     inc_retries_with_backoff(
                         net.as_str(),
                         &mut transaction_retries_count,
@@ -913,41 +925,68 @@ pub async fn get_gas_price(
     Ok(base_fee)
 }
 
+/// Reads the nonce of `sender_address`, from a quorum of the RPC endpoints if one is configured.
+async fn read_nonce(
+    provider: &mut RpcProvider,
+    net: &str,
+    sender_address: &Address,
+    block_height: u64,
+    transaction_retry_timeout_secs: u64,
+) -> Result<u64> {
+    provider
+        .quorum_read("get_nonce", |rpc_handle| async move {
+            get_nonce(
+                net,
+                &rpc_handle,
+                sender_address,
+                block_height,
+                transaction_retry_timeout_secs,
+                false,
+            )
+            .await
+        })
+        .await
+}
+
 pub async fn get_chain_id(
     net: &str,
-    provider: &RpcProvider,
+    provider: &mut RpcProvider,
     updates: &BatchedAggregatesToSend,
     transaction_retry_timeout_secs: u64,
 ) -> Result<u64> {
-    let provider_metrics = &provider.provider_metrics;
-    let rpc_handle = &provider.provider;
-
-    let chain_id_result = match actix_web::rt::time::timeout(
-        Duration::from_secs(transaction_retry_timeout_secs),
-        rpc_handle.get_chain_id(),
-    )
-    .await
-    {
-        Ok(v) => v,
-        Err(err) => {
-            let block_height = updates.block_height;
-            bail!("Timed out on get chain_id for network {net} Blocksense block height: {block_height} due to {err}");
-        }
-    };
+    let provider_metrics = provider.provider_metrics.clone();
+    let block_height = updates.block_height;
 
     debug!("Getting chain_id for network {net}...");
-    let chain_id = match process_provider_getter!(
-        chain_id_result,
-        net,
-        provider_metrics,
-        get_chain_id
-    ) {
-        Ok(v) => v,
-        Err(err) => {
-            let block_height = updates.block_height;
-            bail!("Error while trying to get chain_id for network {net} Blocksense block height: {block_height} due to {err}");
-        }
-    };
+    let chain_id = provider
+        .quorum_read("get_chain_id", |rpc_handle| {
+            let provider_metrics = provider_metrics.clone();
+            async move {
+                let chain_id_result = match actix_web::rt::time::timeout(
+                    Duration::from_secs(transaction_retry_timeout_secs),
+                    rpc_handle.get_chain_id(),
+                )
+                .await
+                {
+                    Ok(v) => v,
+                    Err(err) => {
+                        bail!("Timed out on get chain_id for network {net} Blocksense block height: {block_height} due to {err}");
+                    }
+                };
+                match process_provider_getter!(
+                    chain_id_result,
+                    net,
+                    provider_metrics,
+                    get_chain_id
+                ) {
+                    Ok(v) => Ok(v),
+                    Err(err) => {
+                        bail!("Error while trying to get chain_id for network {net} Blocksense block height: {block_height} due to {err}");
+                    }
+                }
+            }
+        })
+        .await?;
 
     debug!("Got chain_id={chain_id} for network {net}");
     Ok(chain_id)
@@ -1777,6 +1816,7 @@ mod tests {
                     always_publish_heartbeat_ms: None,
                     peg_to_value: Some(1f64),
                     peg_tolerance_percentage: 1.0f64,
+                    market_hours: None,
                 };
                 p.publishing_criteria.push(c);
             });
@@ -1832,6 +1872,7 @@ mod tests {
                     always_publish_heartbeat_ms: None,
                     peg_to_value: None,
                     peg_tolerance_percentage: 100.0f64,
+                    market_hours: None,
                 };
                 p.publishing_criteria.push(c);
             });
@@ -1,2 +1,3 @@
 pub mod eth_send_utils;
 pub mod provider;
+pub mod rpc_endpoints;
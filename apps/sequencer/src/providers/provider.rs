@@ -9,7 +9,6 @@ use alloy::{
         fillers::{FillProvider, JoinFill, WalletFiller},
         Identity, ProviderBuilder, RootProvider,
     },
-    signers::local::PrivateKeySigner,
 };
 use alloy_primitives::U256;
 use alloy_u256_literal::u256;
@@ -30,20 +29,24 @@ use blocksense_data_feeds::feeds_processing::{
 };
 use blocksense_feed_registry::registry::FeedAggregateHistory;
 use blocksense_feed_registry::types::FeedType;
+use blocksense_gnosis_safe::signer::AnySigner;
 use blocksense_metrics::{metrics::ProviderMetrics, process_provider_getter};
 use eyre::{eyre, Result};
 use paste::paste;
 use ringbuf::traits::{Consumer, Observer};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::mem;
 use std::sync::Arc;
-use std::{fs, mem};
 use tokio::sync::{Mutex, RwLock};
 use tokio::time::error::Elapsed;
 use tokio::time::Duration;
 use tracing::{debug, error, info, warn};
 
 use crate::providers::eth_send_utils::{get_gas_limit, get_tx_retry_params, GasFees};
+use crate::providers::rpc_endpoints::{quorum_value, RpcEndpoints};
+use std::fmt::Debug;
+use std::future::Future;
 use std::time::Instant;
 
 pub type ProviderType =
@@ -89,7 +92,7 @@ impl Contract {
 pub struct RpcProvider {
     pub network: String,
     pub provider: ProviderType,
-    pub signer: PrivateKeySigner,
+    pub signer: AnySigner,
     pub provider_metrics: Arc<RwLock<ProviderMetrics>>,
     pub transaction_retries_count_limit: u32,
     pub transaction_retry_timeout_secs: u32,
@@ -100,7 +103,9 @@ pub struct RpcProvider {
     pub publishing_criteria: HashMap<EncodedFeedId, PublishCriteria>,
     pub feeds_variants: HashMap<EncodedFeedId, FeedVariant>,
     pub contracts: Vec<Contract>,
+    /// Url of the active endpoint, which `provider` is connected to
     pub rpc_url: Url,
+    pub rpc_endpoints: RpcEndpoints,
     pub rb_indices: RoundBufferIndices,
     num_tx_in_progress: u32,
 }
@@ -142,22 +147,22 @@ async fn get_rpc_providers(
     ));
 
     for (net, p) in &conf.providers {
-        let rpc_url: Url = p
-            .url
-            .parse()
-            .unwrap_or_else(|_| panic!("Not a valid url provided for {net}!"));
-        let priv_key_path = &p.private_key_path;
-        let priv_key = fs::read_to_string(priv_key_path.clone()).unwrap_or_else(|_| {
-            panic!("Failed to read private key for {net} from {priv_key_path}")
+        let rpc_urls: Vec<Url> = p
+            .urls()
+            .into_iter()
+            .map(|url| {
+                url.parse()
+                    .unwrap_or_else(|_| panic!("Not a valid url provided for {net}!"))
+            })
+            .collect();
+        let signer_config = p.signer_config();
+        let signer = AnySigner::from_config(&signer_config).unwrap_or_else(|e| {
+            panic!("Failed to load signer for {net} from {signer_config}: {e:?}")
         });
-        let signer: PrivateKeySigner = priv_key
-            .trim()
-            .parse()
-            .unwrap_or_else(|_| panic!("Incorrect private key specified {priv_key}."));
 
         let rpc_provider = RpcProvider::new(
             net.as_str(),
-            rpc_url,
+            rpc_urls,
             &signer,
             p,
             &provider_metrics,
@@ -280,16 +285,20 @@ pub struct FeedVariant {
 impl RpcProvider {
     pub async fn new(
         network: &str,
-        rpc_url: Url,
-        signer: &PrivateKeySigner,
+        rpc_urls: Vec<Url>,
+        signer: &AnySigner,
         p: &blocksense_config::Provider,
         provider_metrics: &Arc<tokio::sync::RwLock<ProviderMetrics>>,
         feeds_config: &AllFeedsConfig,
     ) -> RpcProvider {
-        let provider = ProviderBuilder::new()
-            .disable_recommended_fillers()
-            .wallet(EthereumWallet::from(signer.clone()))
-            .connect_http(rpc_url.clone());
+        let rpc_endpoints = RpcEndpoints::new(
+            rpc_urls,
+            signer,
+            p.rpc_failover_after_failures,
+            p.rpc_read_quorum,
+        );
+        let provider = rpc_endpoints.active().provider.clone();
+        let rpc_url = rpc_endpoints.active().url.clone();
 
         let impersonated_anvil_account = p
             .impersonated_anvil_account
@@ -316,7 +325,7 @@ impl RpcProvider {
                 }
             }
         }
-        RpcProvider {
+        let rpc_provider = RpcProvider {
             network: network.to_string(),
             provider,
             signer: signer.clone(),
@@ -331,9 +340,12 @@ impl RpcProvider {
             feeds_variants,
             contracts,
             rpc_url,
+            rpc_endpoints,
             rb_indices: RoundBufferIndices::new(),
             num_tx_in_progress: 0,
-        }
+        };
+        rpc_provider.set_active_rpc_endpoint_metric().await;
+        rpc_provider
     }
 
     pub async fn load_rb_indices_from_chain(
@@ -764,6 +776,99 @@ impl RpcProvider {
         self.rpc_url.clone()
     }
 
+    /// Records the outcome of a call to the active RPC endpoint.
+    pub async fn record_rpc_call(&mut self, call: &str, success: bool) {
+        let index = self.rpc_endpoints.active_index();
+        self.record_endpoint_call(index, call, success).await;
+    }
+
+    async fn record_endpoint_call(&mut self, index: usize, call: &str, success: bool) {
+        let endpoint = self.rpc_endpoints.label(index);
+        {
+            let provider_metrics = self.provider_metrics.read().await;
+            let calls = if success {
+                &provider_metrics.success_rpc_endpoint_calls
+            } else {
+                &provider_metrics.failed_rpc_endpoint_calls
+            };
+            calls
+                .with_label_values(&[self.network.as_str(), endpoint.as_str(), call])
+                .inc();
+        }
+
+        let previous = self.rpc_endpoints.label(self.rpc_endpoints.active_index());
+        if self
+            .rpc_endpoints
+            .record(index, success, Instant::now())
+            .is_some()
+        {
+            let active = self.rpc_endpoints.active();
+            self.provider = active.provider.clone();
+            self.rpc_url = active.url.clone();
+            warn!(
+                "Switched RPC endpoint of network {} from {previous} to {} after {call} failed on {endpoint}",
+                self.network,
+                self.rpc_endpoints.label(self.rpc_endpoints.active_index())
+            );
+            self.set_active_rpc_endpoint_metric().await;
+        }
+    }
+
+    async fn set_active_rpc_endpoint_metric(&self) {
+        let provider_metrics = self.provider_metrics.read().await;
+        for index in 0..self.rpc_endpoints.endpoints().len() {
+            let is_active = index == self.rpc_endpoints.active_index();
+            provider_metrics
+                .active_rpc_endpoint
+                .with_label_values(&[
+                    self.network.as_str(),
+                    self.rpc_endpoints.label(index).as_str(),
+                ])
+                .set(is_active as i64);
+        }
+    }
+
+    /// Reads a value that has to be right, like the nonce or the chain id. With an
+    /// `rpc_read_quorum` above 1 every endpoint is asked, and the value is only accepted if
+    /// enough of them agree on it.
+    pub async fn quorum_read<T, F, Fut>(&mut self, call: &str, read: F) -> Result<T>
+    where
+        T: PartialEq + Clone + Debug,
+        F: Fn(ProviderType) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let quorum = self.rpc_endpoints.read_quorum();
+        if quorum <= 1 {
+            let result = read(self.provider.clone()).await;
+            self.record_rpc_call(call, result.is_ok()).await;
+            return result;
+        }
+
+        let results = join_all(
+            self.rpc_endpoints
+                .endpoints()
+                .iter()
+                .map(|endpoint| read(endpoint.provider.clone())),
+        )
+        .await;
+        let mut answers = Vec::new();
+        let mut errors = Vec::new();
+        for (index, result) in results.into_iter().enumerate() {
+            self.record_endpoint_call(index, call, result.is_ok()).await;
+            match result {
+                Ok(answer) => answers.push(answer),
+                Err(e) => errors.push(format!("{}: {e}", self.rpc_endpoints.label(index))),
+            }
+        }
+        quorum_value(&answers, quorum).ok_or_else(|| {
+            eyre!(
+                "Fewer than {quorum} RPC endpoints of network {} agree on {call}, answers: {answers:?}, errors: [{}]",
+                self.network,
+                errors.join("; ")
+            )
+        })
+    }
+
     pub async fn log_if_contract_exists(&self, contract_name: &str) {
         let address = self.get_contract_address(contract_name).ok();
         let network = self.network.as_str();
@@ -1428,6 +1533,7 @@ mod tests {
                 always_publish_heartbeat_ms: Some(864000),
                 peg_to_value: None,
                 peg_tolerance_percentage: 0.5,
+                market_hours: None,
             });
         });
 
@@ -0,0 +1,211 @@
+//! RPC endpoints of a network. Calls go to the active endpoint, which is replaced by the first
+//! healthy one in the configured order after too many consecutive failures. A failed endpoint
+//! gets calls again once `RECOVERY_PERIOD` has passed, so the sequencer returns to the preferred
+//! endpoints when they recover.
+
+use std::time::{Duration, Instant};
+
+use alloy::network::EthereumWallet;
+use alloy::providers::ProviderBuilder;
+use blocksense_gnosis_safe::signer::AnySigner;
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::providers::provider::ProviderType;
+
+/// How long an endpoint that failed over gets no calls before it is tried again
+pub const RECOVERY_PERIOD: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EndpointHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    #[serde(skip)]
+    unhealthy_since: Option<Instant>,
+}
+
+impl EndpointHealth {
+    fn is_healthy(&self, now: Instant) -> bool {
+        self.unhealthy_since
+            .is_none_or(|since| now.duration_since(since) >= RECOVERY_PERIOD)
+    }
+}
+
+pub struct RpcEndpoint {
+    pub url: Url,
+    pub provider: ProviderType,
+    pub health: EndpointHealth,
+}
+
+pub struct RpcEndpoints {
+    endpoints: Vec<RpcEndpoint>,
+    active: usize,
+    failover_after_failures: u32,
+    read_quorum: usize,
+}
+
+impl RpcEndpoints {
+    /// `urls` are in the order of preference and must not be empty.
+    pub fn new(
+        urls: Vec<Url>,
+        signer: &AnySigner,
+        failover_after_failures: u32,
+        read_quorum: usize,
+    ) -> RpcEndpoints {
+        assert!(!urls.is_empty(), "At least one RPC endpoint is needed");
+        let endpoints = urls
+            .into_iter()
+            .map(|url| RpcEndpoint {
+                provider: ProviderBuilder::new()
+                    .disable_recommended_fillers()
+                    .wallet(EthereumWallet::from(signer.clone()))
+                    .connect_http(url.clone()),
+                url,
+                health: EndpointHealth::default(),
+            })
+            .collect();
+        RpcEndpoints {
+            endpoints,
+            active: 0,
+            failover_after_failures: failover_after_failures.max(1),
+            read_quorum: read_quorum.max(1),
+        }
+    }
+
+    pub fn endpoints(&self) -> &[RpcEndpoint] {
+        &self.endpoints
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active(&self) -> &RpcEndpoint {
+        &self.endpoints[self.active]
+    }
+
+    pub fn read_quorum(&self) -> usize {
+        self.read_quorum
+    }
+
+    /// Label of the endpoint in the metrics and logs. Only the host is used, as the rest of an
+    /// RPC url often contains an API key.
+    pub fn label(&self, index: usize) -> String {
+        let url = &self.endpoints[index].url;
+        format!("{index}:{}", url.host_str().unwrap_or_default())
+    }
+
+    /// Records the outcome of a call to the endpoint at `index` and returns the new active
+    /// endpoint if it changed.
+    pub fn record(&mut self, index: usize, success: bool, now: Instant) -> Option<usize> {
+        let health = &mut self.endpoints[index].health;
+        if success {
+            health.successes += 1;
+            health.consecutive_failures = 0;
+            health.unhealthy_since = None;
+        } else {
+            health.failures += 1;
+            health.consecutive_failures += 1;
+            if health.consecutive_failures >= self.failover_after_failures {
+                health.unhealthy_since = Some(now);
+            }
+        }
+
+        let selected = self.select(now);
+        if selected == self.active {
+            return None;
+        }
+        self.active = selected;
+        Some(selected)
+    }
+
+    /// The first healthy endpoint, or the one that failed the fewest times in a row if none is.
+    fn select(&self, now: Instant) -> usize {
+        self.endpoints
+            .iter()
+            .position(|endpoint| endpoint.health.is_healthy(now))
+            .unwrap_or_else(|| {
+                self.endpoints
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, endpoint)| endpoint.health.consecutive_failures)
+                    .map(|(index, _)| index)
+                    .unwrap_or(self.active)
+            })
+    }
+}
+
+/// The answer given by the most endpoints, if at least `quorum` of them gave it. Ties go to the
+/// answer that came first.
+pub fn quorum_value<T: PartialEq + Clone>(answers: &[T], quorum: usize) -> Option<T> {
+    let mut best: Option<(&T, usize)> = None;
+    for answer in answers {
+        let votes = answers.iter().filter(|other| *other == answer).count();
+        if best.is_none_or(|(_, best_votes)| votes > best_votes) {
+            best = Some((answer, votes));
+        }
+    }
+    best.filter(|(_, votes)| *votes >= quorum)
+        .map(|(answer, _)| answer.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn endpoints(failover_after_failures: u32) -> RpcEndpoints {
+        // First Anvil account
+        let signer: PrivateKeySigner =
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+                .parse()
+                .unwrap();
+        let urls = [
+            "http://primary:8545",
+            "http://secondary:8545",
+            "http://third:8545",
+        ]
+        .iter()
+        .map(|url| url.parse().unwrap())
+        .collect();
+        RpcEndpoints::new(urls, &signer.into(), failover_after_failures, 2)
+    }
+
+    #[test]
+    fn fails_over_and_back_after_recovery() {
+        let mut endpoints = endpoints(2);
+        let now = Instant::now();
+        assert_eq!(endpoints.label(0), "0:primary");
+
+        assert_eq!(endpoints.record(0, false, now), None);
+        assert_eq!(endpoints.record(0, false, now), Some(1));
+        assert_eq!(endpoints.active().url.as_str(), "http://secondary:8545/");
+        assert_eq!(endpoints.record(1, true, now), None);
+
+        // Once the primary had time to recover it is tried again
+        let later = now + RECOVERY_PERIOD;
+        assert_eq!(endpoints.record(1, true, later), Some(0));
+        assert_eq!(endpoints.record(0, false, later), Some(1));
+
+        // With every endpoint failing the one with the fewest failures in a row is used
+        for _ in 0..2 {
+            endpoints.record(1, false, later);
+            endpoints.record(2, false, later);
+        }
+        endpoints.record(2, false, later);
+        assert_eq!(endpoints.active_index(), 1);
+        assert_eq!(endpoints.endpoints()[1].health.consecutive_failures, 2);
+        assert_eq!(endpoints.endpoints()[0].health.failures, 3);
+        assert_eq!(endpoints.endpoints()[1].health.successes, 2);
+    }
+
+    #[test]
+    fn quorum_needs_enough_matching_answers() {
+        assert_eq!(quorum_value(&[7_u64, 7, 6], 2), Some(7));
+        assert_eq!(quorum_value(&[6_u64, 7, 7], 2), Some(7));
+        assert_eq!(quorum_value(&[6_u64, 7], 2), None);
+        assert_eq!(quorum_value(&[6_u64, 7], 1), Some(6));
+        assert_eq!(quorum_value::<u64>(&[], 1), None);
+    }
+}
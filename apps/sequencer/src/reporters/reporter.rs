@@ -1,49 +1,446 @@
-use alloy::primitives::Address;
-use blocksense_config::SequencerConfig;
-use blocksense_crypto::{deserialize_public_key, PublicKey, MULTIFORMATS_BLS_PUBKYE_PREFIX};
+use alloy::hex;
+use alloy::primitives::{Address, Signature};
+use blocksense_blockchain_data_model::block_store::BlockStore;
+use blocksense_blockchain_data_model::{
+    BlockReporterAction, DataChunk, ReporterActionKind, BLS_PUBLIC_KEY_SIZE, DATA_CHUNK_SIZE,
+};
+use blocksense_config::{ReporterRegistration, SequencerConfig, Validated};
+use blocksense_crypto::{
+    deserialize_public_key, verify_signature, PublicKey, MULTIFORMATS_BLS_PUBKYE_PREFIX,
+};
+use blocksense_feed_registry::feed_registration_cmds::ReporterAction;
 use blocksense_metrics::metrics::ReporterMetrics;
+use eyre::{eyre, Result};
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use tracing::{info, warn};
 
 #[derive(Debug)]
 pub struct Reporter {
     pub pub_key: PublicKey,
     pub address: Address,
+    pub stake: f64,
+    pub suspended: bool,
     pub reporter_metrics: Arc<RwLock<ReporterMetrics>>,
 }
 
+impl Reporter {
+    pub fn from_config(
+        reporter: &blocksense_config::Reporter,
+        reporter_metrics: Arc<RwLock<ReporterMetrics>>,
+    ) -> Result<Reporter> {
+        Ok(Reporter {
+            pub_key: parse_pub_key(&reporter.pub_key)?,
+            address: parse_address(&reporter.address)?,
+            stake: reporter.stake,
+            suspended: false,
+            reporter_metrics,
+        })
+    }
+}
+
 pub type SharedReporter = Arc<RwLock<Reporter>>;
 
 pub type Reporters = HashMap<u64, SharedReporter>;
 
 pub type SharedReporters = Arc<RwLock<Reporters>>;
 
-pub fn init_shared_reporters(conf: &SequencerConfig, prefix: Option<&str>) -> SharedReporters {
-    let prefix = prefix.unwrap_or("");
-    Arc::new(RwLock::new(init_reporters(conf, prefix)))
+pub fn init_shared_reporters(
+    conf: &SequencerConfig,
+    reporter_metrics: &Arc<RwLock<ReporterMetrics>>,
+) -> SharedReporters {
+    Arc::new(RwLock::new(init_reporters(conf, reporter_metrics)))
 }
 
-fn init_reporters(conf: &SequencerConfig, prefix: &str) -> HashMap<u64, Arc<RwLock<Reporter>>> {
+fn init_reporters(
+    conf: &SequencerConfig,
+    reporter_metrics: &Arc<RwLock<ReporterMetrics>>,
+) -> HashMap<u64, Arc<RwLock<Reporter>>> {
     let mut reporters = HashMap::new();
-    let reporter_metrics = Arc::new(RwLock::new(
-        ReporterMetrics::new(prefix).expect("Failed to allocate ReporterMetrics."),
-    ));
     for r in &conf.reporters {
         reporters.insert(
             r.id.into(),
-            Arc::new(RwLock::new(Reporter {
-                pub_key: deserialize_public_key(
-                    r.pub_key
-                        .strip_prefix(MULTIFORMATS_BLS_PUBKYE_PREFIX)
-                        .expect("Multiformats key prefix error. Only BLS is currently supported."),
-                )
-                .expect("Pub key format error: "),
-                address: Address::from_str(&r.address).expect("Could not deserialize address!"),
-                reporter_metrics: reporter_metrics.clone(),
-            })),
+            Arc::new(RwLock::new(
+                Reporter::from_config(r, reporter_metrics.clone())
+                    .unwrap_or_else(|e| panic!("Invalid reporter id {}: {e}", r.id)),
+            )),
         );
     }
     reporters
 }
+
+pub fn parse_pub_key(pub_key: &str) -> Result<PublicKey> {
+    let pub_key = pub_key
+        .strip_prefix(MULTIFORMATS_BLS_PUBKYE_PREFIX)
+        .ok_or_else(|| eyre!("Multiformats key prefix error. Only BLS is currently supported."))?;
+    deserialize_public_key(pub_key).map_err(|e| eyre!("Pub key format error: {e}"))
+}
+
+pub fn parse_address(address: &str) -> Result<Address> {
+    Address::from_str(address).map_err(|e| eyre!("Could not deserialize address {address}: {e}"))
+}
+
+/// Checks that a registration request is signed with both keys of the reporter it describes.
+pub fn verify_registration(registration: &ReporterRegistration) -> Result<()> {
+    let reporter = &registration.reporter;
+    reporter
+        .validate("reporter registration")
+        .map_err(|e| eyre!("{e}"))?;
+    let pub_key = parse_pub_key(&reporter.pub_key)?;
+    let address = parse_address(&reporter.address)?;
+    let message = ReporterRegistration::signing_message(reporter);
+
+    let signature = hex::decode(&registration.signature)
+        .ok()
+        .and_then(|bytes| blocksense_crypto::Signature::from_bytes(&bytes).ok())
+        .ok_or_else(|| eyre!("Could not deserialize BLS signature"))?;
+    if !verify_signature(&pub_key, &signature, &message) {
+        eyre::bail!(
+            "BLS signature does not match pub key of reporter id {}",
+            reporter.id
+        );
+    }
+
+    let address_signature = Signature::from_str(&registration.address_signature)
+        .map_err(|e| eyre!("Could not deserialize address signature: {e}"))?;
+    let recovered_address = address_signature.recover_address_from_msg(&message)?;
+    if recovered_address != address {
+        eyre::bail!(
+            "Address signature of reporter id {} is from {recovered_address}, expected {address}",
+            reporter.id
+        );
+    }
+    Ok(())
+}
+
+/// Applies a change of the reporter set. All sequencers apply the actions in block order, so an
+/// action that is not valid for the current set is rejected the same way everywhere.
+pub async fn apply_reporter_action(
+    reporters: &SharedReporters,
+    reporter_metrics: &Arc<RwLock<ReporterMetrics>>,
+    action: &ReporterAction,
+) -> Result<()> {
+    let id = action.reporter_id();
+    let mut reporters = reporters.write().await;
+    match action {
+        ReporterAction::Add(reporter) => {
+            if reporters.contains_key(&id) {
+                eyre::bail!("Reporter id {id} is already registered");
+            }
+            let reporter = Reporter::from_config(reporter, reporter_metrics.clone())?;
+            reporters.insert(id, Arc::new(RwLock::new(reporter)));
+        }
+        ReporterAction::Remove { .. } => {
+            // The metrics are labeled by reporter id and stay registered, so a reporter that is
+            // added again continues its counters.
+            if reporters.remove(&id).is_none() {
+                eyre::bail!("Cannot remove unknown reporter id {id}");
+            }
+        }
+        ReporterAction::Suspend { .. } | ReporterAction::Resume { .. } => {
+            let reporter = reporters
+                .get(&id)
+                .ok_or_else(|| eyre!("Cannot change status of unknown reporter id {id}"))?;
+            reporter.write().await.suspended = matches!(action, ReporterAction::Suspend { .. });
+        }
+        ReporterAction::Rotate {
+            pub_key, address, ..
+        } => {
+            let reporter = reporters
+                .get(&id)
+                .ok_or_else(|| eyre!("Cannot rotate keys of unknown reporter id {id}"))?;
+            let pub_key = parse_pub_key(pub_key)?;
+            let address = parse_address(address)?;
+            let mut reporter = reporter.write().await;
+            reporter.pub_key = pub_key;
+            reporter.address = address;
+        }
+    }
+    info!("Applied reporter action {action:?}");
+    Ok(())
+}
+
+/// Applies the reporter actions stored in `blockchain_db` in block order on top of the reporters
+/// from the config, so changes to the reporter set survive a restart. Actions that fail are
+/// logged and skipped, as they are when a block is created.
+pub async fn replay_reporter_actions(
+    blockchain_db: &dyn BlockStore,
+    reporters: &SharedReporters,
+    reporter_metrics: &Arc<RwLock<ReporterMetrics>>,
+) {
+    let block_actions: Vec<BlockReporterAction> = (1..=blockchain_db.get_latest_block_height())
+        .filter_map(|height| blockchain_db.get_block_header_by_height(height))
+        .filter_map(|header| blockchain_db.get_feed_actions(&header.add_remove_feeds_merkle_root))
+        .flat_map(|feed_actions| feed_actions.reporter_actions.iter().flatten().cloned())
+        .collect();
+    for block_action in &block_actions {
+        let action = match block_to_reporter_action(block_action) {
+            Ok(action) => action,
+            Err(e) => {
+                warn!("Failed to decode stored reporter action {block_action:?}: {e}");
+                continue;
+            }
+        };
+        if let Err(e) = apply_reporter_action(reporters, reporter_metrics, &action).await {
+            warn!("Failed to replay reporter action {action:?}: {e}");
+        }
+    }
+    info!(
+        "Replayed {} reporter actions from the block store",
+        block_actions.len()
+    );
+}
+
+// Functions for conversion of ReporterAction to BlockReporterAction and back:
+
+pub fn reporter_action_to_block(action: &ReporterAction) -> Result<BlockReporterAction> {
+    let mut block_action = BlockReporterAction {
+        reporter_id: action.reporter_id(),
+        ..Default::default()
+    };
+    let kind = match action {
+        ReporterAction::Add(reporter) => {
+            block_action.pub_key = pub_key_to_chunks(&reporter.pub_key)?;
+            block_action.address = parse_address(&reporter.address)?.0 .0;
+            block_action.stake = reporter.stake.to_be_bytes();
+            ReporterActionKind::Add
+        }
+        ReporterAction::Remove { .. } => ReporterActionKind::Remove,
+        ReporterAction::Suspend { .. } => ReporterActionKind::Suspend,
+        ReporterAction::Resume { .. } => ReporterActionKind::Resume,
+        ReporterAction::Rotate {
+            pub_key, address, ..
+        } => {
+            block_action.pub_key = pub_key_to_chunks(pub_key)?;
+            block_action.address = parse_address(address)?.0 .0;
+            ReporterActionKind::Rotate
+        }
+    };
+    block_action.action = kind as u8;
+    Ok(block_action)
+}
+
+pub fn block_to_reporter_action(block_action: &BlockReporterAction) -> Result<ReporterAction> {
+    let id = block_action.reporter_id;
+    let kind =
+        ReporterActionKind::try_from(block_action.action).map_err(|e| eyre!(e.to_string()))?;
+    Ok(match kind {
+        ReporterActionKind::Add => ReporterAction::Add(blocksense_config::Reporter {
+            id: u32::try_from(id).map_err(|_| eyre!("Reporter id {id} out of range"))?,
+            pub_key: chunks_to_pub_key(&block_action.pub_key),
+            address: Address::from(block_action.address).to_string(),
+            stake: f64::from_be_bytes(block_action.stake),
+        }),
+        ReporterActionKind::Remove => ReporterAction::Remove { id },
+        ReporterActionKind::Suspend => ReporterAction::Suspend { id },
+        ReporterActionKind::Resume => ReporterAction::Resume { id },
+        ReporterActionKind::Rotate => ReporterAction::Rotate {
+            id,
+            pub_key: chunks_to_pub_key(&block_action.pub_key),
+            address: Address::from(block_action.address).to_string(),
+        },
+    })
+}
+
+// The BLS public key does not fit in a single DataChunk, so it is split over two
+fn pub_key_to_chunks(pub_key: &str) -> Result<[DataChunk; 2]> {
+    let bytes = parse_pub_key(pub_key)?.to_bytes();
+    let mut chunks: [DataChunk; 2] = Default::default();
+    let (first, second) = bytes.split_at(DATA_CHUNK_SIZE);
+    chunks[0].copy_from_slice(first);
+    chunks[1][..second.len()].copy_from_slice(second);
+    Ok(chunks)
+}
+
+fn chunks_to_pub_key(chunks: &[DataChunk; 2]) -> String {
+    let bytes: Vec<u8> = chunks.iter().flatten().copied().collect();
+    format!(
+        "{MULTIFORMATS_BLS_PUBKYE_PREFIX}{}",
+        hex::encode(&bytes[..BLS_PUBLIC_KEY_SIZE])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUB_KEY: &str = "ea30b1533ef5638af7b70a036275642fc453ace97ed2c6b9d220fe1f59a24d61f481a777aa8a579f20e95a74cd4567ed36a3";
+    const OTHER_PUB_KEY: &str = "ea30813e2f8cf968e27bad29167b41bce038a3ce9b7b368de05e5cf1af3de919eeba267b8706f55c356d5f71891eff116b98";
+
+    fn test_reporter(id: u32) -> blocksense_config::Reporter {
+        blocksense_config::Reporter {
+            id,
+            pub_key: PUB_KEY.to_string(),
+            address: "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".to_string(),
+            stake: 2.5,
+        }
+    }
+
+    fn test_reporter_metrics(prefix: &str) -> Arc<RwLock<ReporterMetrics>> {
+        Arc::new(RwLock::new(ReporterMetrics::new(prefix).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_apply_reporter_actions() {
+        let reporter_metrics = test_reporter_metrics("test_apply_reporter_actions_");
+        let reporters: SharedReporters = Default::default();
+
+        let add = ReporterAction::Add(test_reporter(1));
+        apply_reporter_action(&reporters, &reporter_metrics, &add)
+            .await
+            .unwrap();
+        assert!(apply_reporter_action(&reporters, &reporter_metrics, &add)
+            .await
+            .is_err());
+
+        apply_reporter_action(
+            &reporters,
+            &reporter_metrics,
+            &ReporterAction::Suspend { id: 1 },
+        )
+        .await
+        .unwrap();
+        let reporter = reporters.read().await.get(&1).cloned().unwrap();
+        assert!(reporter.read().await.suspended);
+
+        apply_reporter_action(
+            &reporters,
+            &reporter_metrics,
+            &ReporterAction::Resume { id: 1 },
+        )
+        .await
+        .unwrap();
+        assert!(!reporter.read().await.suspended);
+
+        let rotate = ReporterAction::Rotate {
+            id: 1,
+            pub_key: OTHER_PUB_KEY.to_string(),
+            address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(),
+        };
+        apply_reporter_action(&reporters, &reporter_metrics, &rotate)
+            .await
+            .unwrap();
+        {
+            let reporter = reporter.read().await;
+            assert_eq!(reporter.pub_key, parse_pub_key(OTHER_PUB_KEY).unwrap());
+            assert_eq!(reporter.stake, 2.5);
+            assert!(Arc::ptr_eq(&reporter.reporter_metrics, &reporter_metrics));
+        }
+
+        apply_reporter_action(
+            &reporters,
+            &reporter_metrics,
+            &ReporterAction::Remove { id: 1 },
+        )
+        .await
+        .unwrap();
+        assert!(reporters.read().await.is_empty());
+        assert!(apply_reporter_action(
+            &reporters,
+            &reporter_metrics,
+            &ReporterAction::Suspend { id: 1 }
+        )
+        .await
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_replay_reporter_actions_from_block_store() {
+        use blocksense_blockchain_data_model::in_mem_db::InMemDb;
+
+        let reporter_metrics = test_reporter_metrics("test_replay_reporter_actions_");
+        let mut blockchain_db = InMemDb::new();
+        let blocks = [
+            vec![
+                ReporterAction::Add(test_reporter(1)),
+                ReporterAction::Add(test_reporter(2)),
+            ],
+            vec![
+                ReporterAction::Suspend { id: 1 },
+                ReporterAction::Remove { id: 2 },
+                // Fails and is skipped
+                ReporterAction::Resume { id: 3 },
+            ],
+        ];
+        for (i, actions) in blocks.iter().enumerate() {
+            let block_actions = actions
+                .iter()
+                .map(|action| reporter_action_to_block(action).unwrap())
+                .collect();
+            let (header, feed_actions) = blockchain_db
+                .create_new_block(1, i as u64 + 1, vec![], vec![], block_actions)
+                .unwrap();
+            blockchain_db.add_next_block(header, feed_actions).unwrap();
+        }
+
+        let reporters: SharedReporters = Default::default();
+        replay_reporter_actions(&blockchain_db, &reporters, &reporter_metrics).await;
+
+        let reporters = reporters.read().await;
+        assert_eq!(reporters.len(), 1);
+        assert!(reporters.get(&1).unwrap().read().await.suspended);
+    }
+
+    #[test]
+    fn test_reporter_action_block_round_trip() {
+        let actions = [
+            ReporterAction::Add(test_reporter(3)),
+            ReporterAction::Remove { id: 3 },
+            ReporterAction::Suspend { id: 4 },
+            ReporterAction::Resume { id: 4 },
+            ReporterAction::Rotate {
+                id: 5,
+                pub_key: OTHER_PUB_KEY.to_string(),
+                address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(),
+            },
+        ];
+        for action in actions {
+            let block_action = reporter_action_to_block(&action).unwrap();
+            assert_eq!(block_to_reporter_action(&block_action).unwrap(), action);
+        }
+    }
+
+    #[test]
+    fn test_verify_registration() {
+        use alloy::signers::{local::PrivateKeySigner, SignerSync};
+        use blocksense_crypto::{generate_keys, serialize_public_key, sign_message};
+
+        let (secret_key, pub_key) = generate_keys(&[7; 35]);
+        let signer = PrivateKeySigner::random();
+        let reporter = blocksense_config::Reporter {
+            id: 9,
+            pub_key: format!(
+                "{MULTIFORMATS_BLS_PUBKYE_PREFIX}{}",
+                serialize_public_key(&pub_key)
+            ),
+            address: signer.address().to_string(),
+            stake: 1.0,
+        };
+        let message = ReporterRegistration::signing_message(&reporter);
+        let mut registration = ReporterRegistration {
+            reporter,
+            signature: hex::encode(sign_message(&secret_key, &message).to_bytes()),
+            address_signature: signer.sign_message_sync(&message).unwrap().to_string(),
+        };
+        verify_registration(&registration).unwrap();
+
+        // The signatures do not cover a different stake
+        registration.reporter.stake = 100.0;
+        assert!(verify_registration(&registration).is_err());
+
+        registration.reporter.stake = 1.0;
+        registration.reporter.address = PrivateKeySigner::random().address().to_string();
+        assert!(verify_registration(&registration).is_err());
+    }
+
+    #[test]
+    fn test_block_reporter_action_with_unknown_kind_is_rejected() {
+        let block_action = BlockReporterAction {
+            action: 42,
+            ..Default::default()
+        };
+        assert!(block_to_reporter_action(&block_action).is_err());
+    }
+}
@@ -1,30 +1,36 @@
 use crate::feeds::consensus_second_round_manager::AggregationBatchConsensus;
+use crate::health::WorkerHealth;
+use crate::oracle_scripts::OracleScriptCatalog;
 use crate::providers::eth_send_utils::create_and_collect_relayers_futures;
 use crate::providers::eth_send_utils::BatchOfUpdatesToProcess;
 use crate::providers::provider::ProviderStatus;
 use crate::providers::provider::SharedRpcProviders;
 use crate::providers::provider::{init_shared_rpc_providers, RpcProvider};
 use crate::reporters::reporter::init_shared_reporters;
+use crate::reporters::reporter::replay_reporter_actions;
 use crate::reporters::reporter::SharedReporters;
 use actix_web::web::Data;
+use blocksense_blockchain_data_model::block_store::BlockStore;
+use blocksense_blockchain_data_model::disk_db::DiskDb;
 use blocksense_blockchain_data_model::in_mem_db::InMemDb;
-use blocksense_config::{AllFeedsConfig, SequencerConfig};
+use blocksense_config::{AllFeedsConfig, BlockConfig, Reporter, SequencerConfig};
 use blocksense_data_feeds::feeds_processing::VotedFeedUpdateWithProof;
 use blocksense_feed_registry::feed_registration_cmds::FeedsManagementCmds;
+use blocksense_feed_registry::registry::history_memory_bytes;
 use blocksense_feed_registry::registry::new_feeds_meta_data_reg_from_config;
 use blocksense_feed_registry::registry::{
     AllFeedsReports, FeedAggregateHistory, FeedMetaDataRegistry,
 };
 use blocksense_gnosis_safe::data_types::ReporterResponse;
 use blocksense_gnosis_safe::utils::SignatureWithAddress;
-use blocksense_metrics::metrics::{FeedsMetrics, SequencerMetrics};
+use blocksense_metrics::metrics::{FeedsMetrics, ReporterMetrics, SequencerMetrics};
 use blocksense_registry::config::FeedConfig;
 use blocksense_utils::counter_unbounded_channel::{
     counted_unbounded_channel, CountedReceiver, CountedSender,
 };
 use blocksense_utils::logging::{init_shared_logging_handle, SharedLoggingHandle};
 use blocksense_utils::EncodedFeedId;
-use eyre::eyre;
+use eyre::{eyre, Context};
 use futures::stream::FuturesUnordered;
 use rdkafka::producer::FutureProducer;
 use rdkafka::ClientConfig;
@@ -35,6 +41,7 @@ use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
+use tracing::info;
 
 pub struct SequencerState {
     pub registry: Arc<RwLock<FeedMetaDataRegistry>>,
@@ -42,6 +49,11 @@ pub struct SequencerState {
     pub providers: SharedRpcProviders,
     pub log_handle: SharedLoggingHandle,
     pub reporters: SharedReporters,
+    // Shared by all reporters, including the ones added at runtime
+    pub reporter_metrics: Arc<RwLock<ReporterMetrics>>,
+    // Verified registration requests of node operators, waiting for approval by an admin. Not
+    // persisted, so they are lost on restart.
+    pub pending_reporter_registrations: Arc<RwLock<HashMap<u64, Reporter>>>,
     pub aggregated_votes_to_block_creator_send: UnboundedSender<VotedFeedUpdateWithProof>,
     pub feeds_metrics: Arc<RwLock<FeedsMetrics>>,
     pub active_feeds: Arc<RwLock<HashMap<EncodedFeedId, FeedConfig>>>,
@@ -49,7 +61,7 @@ pub struct SequencerState {
     pub feed_aggregate_history: Arc<RwLock<FeedAggregateHistory>>,
     pub feeds_management_cmd_to_block_creator_send: UnboundedSender<FeedsManagementCmds>,
     pub feeds_slots_manager_cmd_send: UnboundedSender<FeedsManagementCmds>,
-    pub blockchain_db: Arc<RwLock<InMemDb>>,
+    pub blockchain_db: Arc<RwLock<Box<dyn BlockStore>>>,
     pub kafka_endpoint: Option<FutureProducer>,
     pub provider_status: Arc<RwLock<HashMap<String, ProviderStatus>>>,
     pub batches_awaiting_consensus: Arc<RwLock<AggregationBatchConsensus>>,
@@ -57,6 +69,8 @@ pub struct SequencerState {
     pub relayers_send_channels:
         Arc<RwLock<HashMap<String, CountedSender<BatchOfUpdatesToProcess>>>>,
     pub sequencer_metrics: Arc<RwLock<SequencerMetrics>>,
+    pub worker_health: Arc<WorkerHealth>,
+    pub oracle_scripts: Arc<RwLock<OracleScriptCatalog>>,
 }
 
 impl SequencerState {
@@ -74,7 +88,7 @@ impl SequencerState {
         relayers_send_channels: Arc<
             RwLock<HashMap<String, CountedSender<BatchOfUpdatesToProcess>>>,
         >,
-    ) -> SequencerState {
+    ) -> eyre::Result<SequencerState> {
         let provider_status: HashMap<String, ProviderStatus> = sequencer_config
             .providers
             .iter()
@@ -88,18 +102,39 @@ impl SequencerState {
             })
             .collect();
         let provider_status = Arc::new(RwLock::new(provider_status));
+        let reporter_metrics = Arc::new(RwLock::new(
+            ReporterMetrics::new(metrics_prefix.unwrap_or(""))
+                .expect("Failed to allocate ReporterMetrics."),
+        ));
         let mut history = FeedAggregateHistory::new();
         for feed in &feeds_config.feeds {
-            history.register_feed(EncodedFeedId::new(feed.id, feed.stride), 100);
+            history.register_feed(
+                EncodedFeedId::new(feed.id, feed.stride),
+                feed.get_history_depth(),
+            );
         }
-        SequencerState {
-            registry: Arc::new(RwLock::new(new_feeds_meta_data_reg_from_config(
-                &feeds_config,
-            ))),
+        let history_bytes =
+            check_history_memory_budget(sequencer_config, history.total_capacity())?;
+        info!(
+            "Feed history for {} feeds takes about {} KB",
+            feeds_config.feeds.len(),
+            history_bytes / 1024
+        );
+        let registry = new_feeds_meta_data_reg_from_config(&feeds_config)
+            .map_err(|e| eyre!("Could not create feed registry from config: {e:#}"))?;
+        let blockchain_db = open_block_store(&sequencer_config.block_config)
+            .wrap_err("Could not open block store")?;
+        let oracle_scripts =
+            OracleScriptCatalog::open(sequencer_config.oracle_scripts_catalog_path().as_deref())
+                .wrap_err("Could not open oracle script catalog")?;
+        Ok(SequencerState {
+            registry: Arc::new(RwLock::new(registry)),
             reports: Arc::new(RwLock::new(AllFeedsReports::new())),
             providers,
             log_handle,
-            reporters: init_shared_reporters(sequencer_config, metrics_prefix),
+            reporters: init_shared_reporters(sequencer_config, &reporter_metrics),
+            reporter_metrics,
+            pending_reporter_registrations: Default::default(),
             aggregated_votes_to_block_creator_send,
             feeds_metrics: Arc::new(RwLock::new(
                 FeedsMetrics::new(metrics_prefix.unwrap_or(""))
@@ -116,7 +151,7 @@ impl SequencerState {
             feed_aggregate_history: Arc::new(RwLock::new(history)),
             feeds_management_cmd_to_block_creator_send,
             feeds_slots_manager_cmd_send,
-            blockchain_db: Arc::new(RwLock::new(InMemDb::new())),
+            blockchain_db: Arc::new(RwLock::new(blockchain_db)),
             kafka_endpoint: sequencer_config
                 .kafka_report_endpoint
                 .url
@@ -133,13 +168,27 @@ impl SequencerState {
                 SequencerMetrics::new(metrics_prefix.unwrap_or(""))
                     .expect("Failed to allocate sequencer_metrics"),
             )),
-        }
+            worker_health: Arc::new(WorkerHealth::default()),
+            oracle_scripts: Arc::new(RwLock::new(oracle_scripts)),
+        })
     }
 
     pub async fn get_provider(&self, network: &str) -> Option<Arc<Mutex<RpcProvider>>> {
         self.providers.read().await.get(network).cloned()
     }
 
+    /// Brings the reporters from the config up to date with the reporter actions in the block
+    /// store. Called once after the state is created.
+    pub async fn replay_reporter_actions(&self) {
+        let blockchain_db = self.blockchain_db.read().await;
+        replay_reporter_actions(
+            blockchain_db.as_ref(),
+            &self.reporters,
+            &self.reporter_metrics,
+        )
+        .await;
+    }
+
     pub async fn deploy_contract(
         &self,
         network: &str,
@@ -190,7 +239,9 @@ pub async fn create_sequencer_state_from_sequencer_config(
         feeds_slots_manager_cmd_send,
         aggregate_batch_sig_send,
         Arc::new(RwLock::new(relayers_send_channels)),
-    );
+    )
+    .expect("Could not create sequencer state.");
+    sequencer_state.replay_reporter_actions().await;
 
     (
         actix_web::web::Data::new(sequencer_state),
@@ -234,6 +285,35 @@ pub async fn create_sequencer_state_and_collected_futures(
     (sequencer_state, collected_futures)
 }
 
+/// Returns the approximate memory of a feed history with `total_capacity` entries, or an error if
+/// it exceeds `history_memory_budget_mb`.
+pub fn check_history_memory_budget(
+    sequencer_config: &SequencerConfig,
+    total_capacity: usize,
+) -> eyre::Result<usize> {
+    let history_bytes = history_memory_bytes(total_capacity);
+    if let Some(budget_mb) = sequencer_config.history_memory_budget_mb {
+        let budget_bytes = budget_mb.saturating_mul(1024 * 1024);
+        if history_bytes as u64 > budget_bytes {
+            eyre::bail!(
+                "Feed history of {total_capacity} entries needs about {} MB, budget is {budget_mb} MB",
+                history_bytes.div_ceil(1024 * 1024)
+            );
+        }
+    }
+    Ok(history_bytes)
+}
+
+fn open_block_store(block_config: &BlockConfig) -> eyre::Result<Box<dyn BlockStore>> {
+    match &block_config.block_store_path {
+        Some(path) => {
+            let db = DiskDb::open(path).map_err(|e| eyre!(e.to_string()))?;
+            Ok(Box::new(db))
+        }
+        None => Ok(Box::new(InMemDb::new())),
+    }
+}
+
 fn create_kafka_producer(
     bootstrap_server: &str,
 ) -> Result<FutureProducer, Box<dyn std::error::Error>> {
@@ -0,0 +1,257 @@
+//! Record and replay of the requests oracle scripts send through the local proxy, so that an
+//! oracle script can be run with `--test` offline and give the same payload every time.
+//!
+//! Set `ORACLE_RECORD_CASSETTE` to a file to save every request and its response there, or
+//! `ORACLE_REPLAY_CASSETTE` to serve the responses from that file instead of the upstream APIs.
+//! Only the method, URL and body of a request are saved, API keys sent in headers stay out of
+//! the cassette, but ones in the URL do not. Response bodies are saved base64-encoded, as not
+//! every API answers with UTF-8.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::oracle;
+
+pub const RECORD_CASSETTE_ENV: &str = "ORACLE_RECORD_CASSETTE";
+pub const REPLAY_CASSETTE_ENV: &str = "ORACLE_REPLAY_CASSETTE";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct Interaction {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    /// Status code of the upstream API
+    pub status: u16,
+    #[serde(with = "base64_body")]
+    pub response_body: Vec<u8>,
+}
+
+mod base64_body {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(body))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Interaction {
+    fn matches(&self, method: &str, url: &str, request_body: Option<&str>) -> bool {
+        self.method == method && self.url == url && self.request_body.as_deref() == request_body
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Cassette> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read cassette {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Could not parse cassette {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Could not write cassette {}", path.display()))
+    }
+}
+
+pub enum ProxyMode {
+    Live,
+    Record {
+        path: PathBuf,
+        cassette: Mutex<Cassette>,
+    },
+    /// Interactions are taken out once served, so repeated requests get the responses in the
+    /// order they were recorded
+    Replay {
+        interactions: Mutex<Vec<Interaction>>,
+    },
+}
+
+impl ProxyMode {
+    pub fn from_env() -> Result<ProxyMode> {
+        let record = std::env::var_os(RECORD_CASSETTE_ENV);
+        let replay = std::env::var_os(REPLAY_CASSETTE_ENV);
+        match (record, replay) {
+            (Some(_), Some(_)) => anyhow::bail!(
+                "Only one of {RECORD_CASSETTE_ENV} and {REPLAY_CASSETTE_ENV} can be set"
+            ),
+            (Some(path), None) => Ok(ProxyMode::record(PathBuf::from(path))),
+            (None, Some(path)) => ProxyMode::replay(Path::new(&path)),
+            (None, None) => Ok(ProxyMode::Live),
+        }
+    }
+
+    pub fn record(path: PathBuf) -> ProxyMode {
+        ProxyMode::Record {
+            path,
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+
+    pub fn replay(path: &Path) -> Result<ProxyMode> {
+        Ok(ProxyMode::Replay {
+            interactions: Mutex::new(Cassette::load(path)?.interactions),
+        })
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, ProxyMode::Replay { .. })
+    }
+
+    /// The recorded response to the request when replaying.
+    pub fn replayed_response(
+        &self,
+        method: &str,
+        url: &str,
+        request_body: Option<&str>,
+    ) -> Option<Interaction> {
+        let ProxyMode::Replay { interactions } = self else {
+            return None;
+        };
+        let mut interactions = interactions.lock().unwrap();
+        let index = interactions
+            .iter()
+            .position(|interaction| interaction.matches(method, url, request_body))?;
+        Some(interactions.remove(index))
+    }
+
+    /// Saves the interaction to the cassette when recording. The cassette is written after
+    /// every interaction, as the trigger may be stopped at any time.
+    pub fn record_interaction(&self, interaction: Interaction) -> Result<()> {
+        let ProxyMode::Record { path, cassette } = self else {
+            return Ok(());
+        };
+        let mut cassette = cassette.lock().unwrap();
+        cassette.interactions.push(interaction);
+        cassette.save(path)
+    }
+}
+
+static PROXY_MODE: OnceLock<ProxyMode> = OnceLock::new();
+
+/// Reads the mode of the proxy from the environment, before any request is proxied.
+pub fn init_proxy_mode() -> Result<()> {
+    let mode = ProxyMode::from_env()?;
+    if PROXY_MODE.set(mode).is_err() {
+        anyhow::bail!("Proxy mode is already set");
+    }
+    Ok(())
+}
+
+/// Requests go to the upstream APIs unless `init_proxy_mode` says otherwise.
+pub fn proxy_mode() -> &'static ProxyMode {
+    PROXY_MODE.get_or_init(|| ProxyMode::Live)
+}
+
+/// Payload as JSON with the values sorted by feed id, for diffing against a golden file.
+pub fn payload_to_json(payload: &oracle::Payload) -> Value {
+    let values: BTreeMap<&str, Value> = payload
+        .values
+        .iter()
+        .map(|result| {
+            let value = match &result.value {
+                oracle::DataFeedResultValue::None => Value::Null,
+                oracle::DataFeedResultValue::Error(error) => json!({ "error": error }),
+                oracle::DataFeedResultValue::Numerical(value) => json!(value),
+                oracle::DataFeedResultValue::Bytes(bytes) => {
+                    json!({ "bytes": format!("0x{}", hex::encode(bytes)) })
+                }
+                oracle::DataFeedResultValue::Text(text) => json!({ "text": text }),
+                oracle::DataFeedResultValue::Decimal(decimal) => json!({ "decimal": decimal }),
+            };
+            (result.id.as_str(), value)
+        })
+        .collect();
+    json!(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interaction(url: &str, response_body: &[u8]) -> Interaction {
+        Interaction {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            request_body: None,
+            status: 200,
+            response_body: response_body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn recorded_interactions_are_replayed_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassette.json");
+
+        let recorder = ProxyMode::record(path.clone());
+        for (url, body) in [
+            ("https://api.example.com/price", b"1".as_slice()),
+            ("https://api.example.com/other", b"2"),
+            ("https://api.example.com/price", &[0xff, 0xfe, 0x00]),
+        ] {
+            recorder.record_interaction(interaction(url, body)).unwrap();
+        }
+
+        let player = ProxyMode::replay(&path).unwrap();
+        assert!(player.is_replay());
+        let body = |url| {
+            player
+                .replayed_response("GET", url, None)
+                .map(|interaction| interaction.response_body)
+        };
+        assert_eq!(
+            body("https://api.example.com/price").as_deref(),
+            Some(b"1".as_slice())
+        );
+        assert_eq!(
+            body("https://api.example.com/price").as_deref(),
+            Some([0xff, 0xfe, 0x00].as_slice())
+        );
+        assert_eq!(body("https://api.example.com/price"), None);
+        assert_eq!(
+            body("https://api.example.com/other").as_deref(),
+            Some(b"2".as_slice())
+        );
+        assert!(player
+            .replayed_response("POST", "https://api.example.com/other", Some("{}"))
+            .is_none());
+    }
+
+    #[test]
+    fn payload_json_is_sorted_by_feed_id() {
+        let payload = oracle::Payload {
+            values: vec![
+                oracle::DataFeedResult {
+                    id: "2".to_string(),
+                    value: oracle::DataFeedResultValue::Decimal("-1.5".to_string()),
+                },
+                oracle::DataFeedResult {
+                    id: "1".to_string(),
+                    value: oracle::DataFeedResultValue::Numerical(42.5),
+                },
+            ],
+        };
+        assert_eq!(
+            serde_json::to_string(&payload_to_json(&payload)).unwrap(),
+            r#"{"1":42.5,"2":{"decimal":"-1.5"}}"#
+        );
+    }
+}
@@ -1,13 +1,19 @@
+pub mod cassette;
 mod custom_serde;
+mod oracle_errors;
+mod schedule;
+pub mod upstream;
 
+use anyhow::Context;
 use clap::Args;
 use custom_serde::serialize_string_as_json;
 use serde::{Deserialize, Serialize};
 
 use std::{
     borrow::Borrow,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     hash::{Hash, Hasher},
+    path::PathBuf,
     sync::Arc,
     time::Instant,
 };
@@ -20,7 +26,7 @@ use tokio::{
         RwLock,
     },
     task::{spawn, Builder, JoinHandle},
-    time::{sleep, timeout, Duration},
+    time::{sleep, timeout_at, Duration},
 };
 use tracing::Instrument;
 use url::Url;
@@ -46,23 +52,27 @@ use blocksense_crypto::JsonSerializableSignature;
 use blocksense_data_feeds::{
     feeds_processing::VotedFeedUpdate, generate_signature::generate_signature,
 };
-use blocksense_feed_registry::{
-    registry::SlotTimeTracker,
-    types::{DataFeedPayload, FeedError, FeedType, PayloadMetaData, Repeatability},
-};
+use blocksense_feed_registry::types::{DataFeedPayload, FeedError, FeedType, PayloadMetaData};
 use blocksense_feeds_processing::utils::validate;
 use blocksense_metrics::{
     actix_server::handle_prometheus_metrics,
     metrics::{
         REPORTER_BATCH_COUNTER, REPORTER_FAILED_SEQ_REQUESTS, REPORTER_FAILED_WASM_EXECS,
-        REPORTER_FEED_COUNTER, REPORTER_WASM_EXECUTION_TIME_GAUGE,
+        REPORTER_FEED_COUNTER, REPORTER_WASM_ERRORS, REPORTER_WASM_EXECUTION_TIME_GAUGE,
     },
     TextEncoder,
 };
+use blocksense_registry::config::UpstreamHostConfig;
+use blocksense_secrets::{signer::SignerConfig, SecretRef, SecretsConfig, SecretsProvider};
 use blocksense_utils::{time::current_unix_time, EncodedFeedId, FeedId, Stride};
 
+use oracle_errors::{error_handling, error_kind, ErrorHandling, MAX_RETRIES};
+use schedule::{group_by_schedule, ReportSchedule, DEFAULT_CLOCK_SKEW_TOLERANCE_MS};
+use upstream::configure_upstream;
+
 use blocksense_gnosis_safe::{
     data_types::{ConsensusSecondRoundBatch, ReporterResponse},
+    signer::AnySigner,
     utils::{bytes_to_hex_string, create_private_key_signer, hex_str_to_bytes32, sign_hash},
 };
 
@@ -77,6 +87,8 @@ use blocksense::oracle::oracle_types as oracle;
 pub(crate) type RuntimeData = HttpRuntimeData;
 pub(crate) type _Store = spin_core::Store<RuntimeData>;
 type DataFeedResults = Arc<RwLock<HashMap<EncodedFeedId, VotedFeedUpdate>>>;
+/// Feeds to report in the current slot of their schedule
+type SlotSignal = (ReportSchedule, HashSet<DataFeedSetting>);
 
 #[derive(Debug, Deserialize)]
 pub struct Params {
@@ -99,6 +111,9 @@ pub struct CliArgs {
     /// If true, run each component once and exit
     #[clap(long)]
     pub test: bool,
+    /// In test mode, write the payloads of the components as JSON to this file
+    #[clap(long, requires = "test")]
+    pub payload_output: Option<PathBuf>,
     ///// IP address of the sequencer
     // #[clap(long = "sequencer-address", default_value = "127.0.0.1:3000", value_parser = parse_listen_addr)]
     // pub sequencer_address: SocketAddr,
@@ -111,8 +126,10 @@ pub struct OracleTrigger {
     metrics_url: Option<String>,
     kafka_endpoint: Option<String>,
     secret_key: String,
-    second_consensus_secret_key: String,
+    second_consensus_signer: Option<AnySigner>,
     reporter_id: u64,
+    clock_skew_tolerance_ms: u64,
+    secrets: Arc<SecretsProvider>,
     queue_components: HashMap<String, Component>,
 }
 
@@ -132,7 +149,14 @@ struct TriggerMetadata {
     kafka_endpoint: Option<String>,
     secret_key: Option<String>,
     second_consensus_secret_key: Option<String>,
+    /// Used instead of `second_consensus_secret_key` if set
+    second_consensus_signer: Option<SignerConfig>,
     reporter_id: Option<u64>,
+    clock_skew_tolerance_ms: Option<u64>,
+    #[serde(default)]
+    upstream_hosts: HashMap<String, UpstreamHostConfig>,
+    #[serde(default)]
+    secrets: SecretsConfig,
 }
 
 #[derive(Clone, Eq, Debug, Default, Deserialize, Serialize)]
@@ -142,6 +166,11 @@ pub struct DataFeedSetting {
     pub decimals: u8,
     #[serde(serialize_with = "serialize_string_as_json")]
     pub data: String,
+    /// Slot length of the feed on the sequencer, the interval of the component if not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_report_start_unix_time_ms: Option<u64>,
 }
 
 impl PartialEq for DataFeedSetting {
@@ -162,12 +191,28 @@ impl Borrow<String> for DataFeedSetting {
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct CapabilitySetting {
     pub id: String,
+    /// The value, or a reference to a secret which is resolved on every execution, see
+    /// `blocksense_secrets::SecretRef`
     pub data: String,
 }
 
+impl std::fmt::Debug for CapabilitySetting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // References are safe to log, values are not
+        let data = match SecretRef::parse(&self.data) {
+            Some(reference) => reference.to_string(),
+            None => "[REDACTED]".to_string(),
+        };
+        f.debug_struct("CapabilitySetting")
+            .field("id", &self.id)
+            .field("data", &data)
+            .finish()
+    }
+}
+
 // Per-component settings (raw serialization format)
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -227,17 +272,32 @@ impl TriggerExecutor for OracleTrigger {
         let metrics_url = metadata.metrics_url;
         let secret_key = metadata.secret_key.expect("Secret key is not provided");
 
-        let second_consensus_secret_key = if metadata.kafka_endpoint.is_some() {
-            metadata
-                .second_consensus_secret_key
-                .expect("Second consensus secret key is not provided")
+        // Only the second consensus round, which is read from Kafka, needs a signer
+        let second_consensus_signer = if metadata.kafka_endpoint.is_some() {
+            let signer = match (
+                &metadata.second_consensus_signer,
+                &metadata.second_consensus_secret_key,
+            ) {
+                (Some(config), _) => AnySigner::from_config(config)
+                    .with_context(|| format!("Could not load second consensus {config}"))?,
+                (None, Some(key)) => create_private_key_signer(key)
+                    .context("Invalid second consensus secret key")?
+                    .into(),
+                (None, None) => anyhow::bail!("Second consensus signer is not provided"),
+            };
+            Some(signer)
         } else {
-            "".into()
+            None
         };
 
         let kafka_endpoint = metadata.kafka_endpoint;
 
         let reporter_id = metadata.reporter_id.expect("Reporter ID is not provided");
+        let clock_skew_tolerance_ms = metadata
+            .clock_skew_tolerance_ms
+            .unwrap_or(DEFAULT_CLOCK_SKEW_TOLERANCE_MS);
+        configure_upstream(metadata.upstream_hosts);
+        let secrets = Arc::new(SecretsProvider::new(&metadata.secrets)?);
         // TODO(adikov) There is a specific case in which one reporter receives task to report multiple
         // data feeds which are gathered from one wasm component. For example -
         // USD/BTC and USD/ETH. In that case we need to optimize calling the component once and
@@ -273,8 +333,10 @@ impl TriggerExecutor for OracleTrigger {
             metrics_url,
             kafka_endpoint,
             secret_key,
-            second_consensus_secret_key,
+            second_consensus_signer,
             reporter_id,
+            clock_skew_tolerance_ms,
+            secrets,
             queue_components,
         })
     }
@@ -285,10 +347,26 @@ impl TriggerExecutor for OracleTrigger {
         let engine = Arc::new(self.engine);
         if config.test {
             tracing::trace!("Running in test mode");
+            let mut payloads = BTreeMap::new();
             for component in self.queue_components.values() {
                 let settings: Vec<DataFeedSetting> =
                     component.oracle_settings.clone().into_iter().collect();
-                Self::execute_wasm(engine.clone(), component, settings).await?;
+                let payload = Self::execute_wasm(
+                    engine.clone(),
+                    component,
+                    settings,
+                    component.interval_time_in_seconds,
+                    &self.secrets,
+                )
+                .await?
+                .map_err(|error| {
+                    anyhow::anyhow!("Component {} returned error {error:?}", component.id)
+                })?;
+                payloads.insert(component.id.clone(), cassette::payload_to_json(&payload));
+            }
+            if let Some(path) = config.payload_output {
+                std::fs::write(&path, serde_json::to_string_pretty(&payloads)?)
+                    .with_context(|| format!("Could not write payloads to {}", path.display()))?;
             }
             return Ok(());
         }
@@ -334,6 +412,7 @@ impl TriggerExecutor for OracleTrigger {
                 .collect(),
         );
         let mut data_feed_senders = HashMap::new();
+        let secrets = self.secrets.clone();
         tracing::trace!("Starting oracle scripts");
         let mut loops: Vec<_> = self
             .queue_components
@@ -346,19 +425,27 @@ impl TriggerExecutor for OracleTrigger {
                     receiver,
                     data_feed_sender.clone(),
                     &component,
+                    secrets.clone(),
                 )
             })
             .collect();
 
         tracing::trace!("Starting orchestrator");
-        let mut orchestrators =
-            Self::start_orchestrators(components, data_feed_senders, self.metrics_url);
+        let mut orchestrators = Self::start_orchestrators(
+            components,
+            data_feed_senders,
+            self.metrics_url,
+            self.clock_skew_tolerance_ms,
+        );
         loops.append(&mut orchestrators);
 
         let url = Url::parse(&self.sequencer.clone())?;
         let sequencer_aggregated_consensus_url = url.join("/post_aggregated_consensus_vote")?;
 
         if let Some(endpoint) = self.kafka_endpoint {
+            let second_consensus_signer = self
+                .second_consensus_signer
+                .context("Second consensus signer is not provided")?;
             let (aggregated_consensus_sender, aggregated_consensus_receiver) = unbounded_channel();
             tracing::trace!("Starting secondary signature");
             loops.push(Self::start_secondary_signature_listener(
@@ -372,7 +459,7 @@ impl TriggerExecutor for OracleTrigger {
                 feeds_config,
                 data_feed_results.clone(),
                 sequencer_aggregated_consensus_url,
-                self.second_consensus_secret_key,
+                second_consensus_signer,
                 self.reporter_id,
             )));
         }
@@ -445,11 +532,18 @@ impl OracleTrigger {
 
     fn start_oracle_loop(
         engine: Arc<TriggerAppEngine<Self>>,
-        signal_receiver: UnboundedReceiver<HashSet<DataFeedSetting>>,
+        signal_receiver: UnboundedReceiver<SlotSignal>,
         payload_sender: UnboundedSender<(String, Payload)>,
         component: &Component,
+        secrets: Arc<SecretsProvider>,
     ) -> JoinHandle<TerminationReason> {
-        let future = Self::execute(engine, signal_receiver, payload_sender, component.clone());
+        let future = Self::execute(
+            engine,
+            signal_receiver,
+            payload_sender,
+            component.clone(),
+            secrets,
+        );
         let task_name = format!("processor for {}", component.id);
         Builder::new()
             .name(&task_name)
@@ -459,15 +553,17 @@ impl OracleTrigger {
 
     async fn execute(
         engine: Arc<TriggerAppEngine<Self>>,
-        mut signal_receiver: UnboundedReceiver<HashSet<DataFeedSetting>>,
+        mut signal_receiver: UnboundedReceiver<SlotSignal>,
         payload_sender: UnboundedSender<(String, Payload)>,
         component: Component,
+        secrets: Arc<SecretsProvider>,
     ) -> TerminationReason {
         let component_id = component.id.clone();
         tracing::trace!("Starting processing loop `{component_id}`");
+        let mut backoff_until: Option<Instant> = None;
         loop {
-            let feeds = match signal_receiver.recv().await {
-                Some(feeds) => feeds,
+            let (schedule, feeds) = match signal_receiver.recv().await {
+                Some(signal) => signal,
                 None => {
                     tracing::info!("Woke up on empty channel for {component_id}");
                     return TerminationReason::Other(format!(
@@ -491,45 +587,80 @@ impl OracleTrigger {
                 tracing::trace!("Empty intersection between component {component_id}");
                 continue;
             }
+            if let Some(until) = backoff_until {
+                if Instant::now() < until {
+                    tracing::debug!("Component `{component_id}` is backing off, skipping the slot");
+                    continue;
+                }
+                backoff_until = None;
+            }
             tracing::trace!(
                 "Intersection for {} has size {}",
                 &component.id,
                 intersection.len()
             );
 
-            let payload = match timeout(
-                Duration::from_secs(component.interval_time_in_seconds),
-                Self::execute_wasm(engine.clone(), &component, intersection),
-            )
-            .await
-            {
-                Ok(result) => {
-                    match result {
-                        Ok(payload) => {
-                            tracing::trace!("Component `{component_id}` executed successfully");
-                            payload
-                        }
-                        Err(error) => {
-                            tracing::error!(
-                                "Component - ({component_id}) execution ended with error {}",
-                                error
-                            );
-                            //TODO(adikov): We need to come up with proper way of handling errors in wasm
-                            //components.
-                            continue;
-                        }
+            // Retries share the deadline of the slot that was signalled
+            let interval = Duration::from_millis(schedule.interval_ms);
+            let deadline = tokio::time::Instant::now() + interval;
+            let mut attempt = 0;
+            let payload = loop {
+                attempt += 1;
+                let error = match timeout_at(
+                    deadline,
+                    Self::execute_wasm(
+                        engine.clone(),
+                        &component,
+                        intersection.clone(),
+                        interval.as_secs().max(1),
+                        &secrets,
+                    ),
+                )
+                .await
+                {
+                    Ok(Ok(Ok(payload))) => {
+                        tracing::trace!("Component `{component_id}` executed successfully");
+                        break Some(payload);
+                    }
+                    Ok(Ok(Err(error))) => error,
+                    Ok(Err(error)) => {
+                        tracing::error!(
+                            "Component - ({component_id}) execution ended with error {error}"
+                        );
+                        break None;
+                    }
+                    Err(_) => {
+                        tracing::error!(
+                            "Component - ({component_id}) execution did not finish within {interval:?}"
+                        );
+                        REPORTER_WASM_ERRORS
+                            .with_label_values(&[&component_id, "timeout"])
+                            .inc();
+                        break None;
+                    }
+                };
+                match error_handling(&error, interval) {
+                    ErrorHandling::Retry if attempt <= MAX_RETRIES => {
+                        tracing::warn!(
+                            "Component - ({component_id}) returned {error:?}, retrying [attempt={attempt}]"
+                        );
+                    }
+                    ErrorHandling::BackOff(duration) => {
+                        tracing::warn!(
+                            "Component - ({component_id}) returned {error:?}, backing off for {duration:?}"
+                        );
+                        backoff_until = Some(Instant::now() + duration);
+                        break None;
+                    }
+                    ErrorHandling::Retry | ErrorHandling::Report => {
+                        tracing::error!("Component - ({component_id}) returned {error:?}");
+                        break None;
                     }
-                }
-                Err(error) => {
-                    tracing::error!(
-                        "Component - ({component_id}) execution ended with timout - {}",
-                        error
-                    );
-                    //TODO(adikov): We need to come up with proper way of handling errors in wasm
-                    //components.
-                    continue;
                 }
             };
+            let Some(payload) = payload else {
+                continue;
+            };
             tracing::trace!("Sending update to sequencer for `{component_id}`...");
             match payload_sender.send((component.id.clone(), payload)) {
                 Ok(_) => {
@@ -550,29 +681,49 @@ impl OracleTrigger {
 
     fn start_orchestrators(
         components: HashMap<String, Component>,
-        signal_senders: HashMap<String, UnboundedSender<HashSet<DataFeedSetting>>>,
+        signal_senders: HashMap<String, UnboundedSender<SlotSignal>>,
         metrics_url: Option<String>,
+        clock_skew_tolerance_ms: u64,
     ) -> Vec<tokio::task::JoinHandle<TerminationReason>> {
         let mut join_handles = vec![];
         for (key, component) in components {
-            let time_interval =
-                tokio::time::Duration::from_secs(component.interval_time_in_seconds);
-            let future = Self::signal_data_feeds(
-                key.clone(),
-                time_interval,
-                component.oracle_settings.clone(),
-                signal_senders
-                    .get(&key)
-                    .expect("Logical error, no data_feed channel for {key}")
-                    .clone(),
-                metrics_url.clone(),
-            );
-            join_handles.push(
-                tokio::task::Builder::new()
-                    .name(format!("orchestrator-{key}").as_str())
-                    .spawn(future)
-                    .expect("orchestrator failed to start"),
+            // Feeds of one component can have different slots on the sequencer, each group of
+            // feeds with the same slots gets its own orchestrator
+            let groups = group_by_schedule(
+                &component.oracle_settings,
+                component.interval_time_in_seconds * 1000,
             );
+            for (schedule, oracle_settings) in groups {
+                if schedule.interval_ms < 2 * clock_skew_tolerance_ms {
+                    tracing::warn!(
+                        "Orchestrator-{key}: slots of {} ms are too short for a clock skew tolerance of {clock_skew_tolerance_ms} ms",
+                        schedule.interval_ms
+                    );
+                }
+                let future = Self::signal_data_feeds(
+                    key.clone(),
+                    schedule,
+                    oracle_settings,
+                    signal_senders
+                        .get(&key)
+                        .expect("Logical error, no data_feed channel for {key}")
+                        .clone(),
+                    metrics_url.clone(),
+                    clock_skew_tolerance_ms,
+                );
+                join_handles.push(
+                    tokio::task::Builder::new()
+                        .name(
+                            format!(
+                                "orchestrator-{key}-{}ms-{}",
+                                schedule.interval_ms, schedule.first_report_start_unix_time_ms
+                            )
+                            .as_str(),
+                        )
+                        .spawn(future)
+                        .expect("orchestrator failed to start"),
+                );
+            }
         }
 
         join_handles
@@ -580,14 +731,17 @@ impl OracleTrigger {
 
     async fn signal_data_feeds(
         oracle_id: String,
-        time_interval: tokio::time::Duration,
+        schedule: ReportSchedule,
         oracle_settings: HashSet<DataFeedSetting>,
-        signal_sender: UnboundedSender<HashSet<DataFeedSetting>>,
+        signal_sender: UnboundedSender<SlotSignal>,
         metrics_url: Option<String>,
+        clock_skew_tolerance_ms: u64,
     ) -> TerminationReason {
-        tracing::trace!("Task orchestrator-{} started", oracle_id);
-        //TODO(adikov): Implement proper logic and remove dummy values
-        let time_tracker = SlotTimeTracker::new("feed_update".into(), time_interval, 0);
+        tracing::trace!(
+            "Task orchestrator-{} started for {} data feeds with {schedule:?}",
+            oracle_id,
+            oracle_settings.len()
+        );
 
         loop {
             REPORTER_BATCH_COUNTER.inc();
@@ -597,21 +751,28 @@ impl OracleTrigger {
                 oracle_id
             );
 
-            time_tracker
-                .await_end_of_current_slot(&Repeatability::Periodic)
-                .await;
+            let report_time_ms = schedule.next_report_time_ms(current_unix_time());
+            let sleep_ms = report_time_ms.saturating_sub(current_unix_time());
+            sleep(Duration::from_millis(sleep_ms as u64)).await;
 
             tracing::trace!(
                 "Orchestrator-{} woke up [batch_count={batch_count}]",
                 oracle_id
             );
 
+            if schedule.is_too_late(report_time_ms, current_unix_time(), clock_skew_tolerance_ms) {
+                tracing::warn!(
+                    "Orchestrator-{oracle_id} woke up too close to the end of the slot, skipping it [batch_count={batch_count}]"
+                );
+                continue;
+            }
+
             tracing::trace!(
                 "Orchestrator-{} Signal {} data feeds [batch_count={batch_count}]",
                 oracle_id,
                 oracle_settings.len()
             );
-            if let Err(e) = signal_sender.send(oracle_settings.clone()) {
+            if let Err(e) = signal_sender.send((schedule, oracle_settings.clone())) {
                 log::error!("Orchestrator-{oracle_id} ERROR from signal_sender.send = {e:?}");
                 continue;
             }
@@ -781,6 +942,14 @@ impl OracleTrigger {
                     oracle::DataFeedResultValue::Numerical(value) => Ok(FeedType::Numerical(value)),
                     oracle::DataFeedResultValue::Text(value) => Ok(FeedType::Text(value)),
                     oracle::DataFeedResultValue::Bytes(value) => Ok(FeedType::Bytes(value)),
+                    oracle::DataFeedResultValue::Decimal(value) => {
+                        match FeedType::decimal_from_str(&value) {
+                            Ok(value) => Ok(value),
+                            Err(e) => Err(FeedError::APIError(format!(
+                                "Invalid decimal value {value:?}: {e}"
+                            ))),
+                        }
+                    }
                     oracle::DataFeedResultValue::Error(error_string) => {
                         Err(FeedError::APIError(error_string))
                     }
@@ -850,12 +1019,10 @@ impl OracleTrigger {
         feeds_config: HashMap<EncodedFeedId, FeedStrideAndDecimals>,
         latest_votes: DataFeedResults,
         sequencer: Url,
-        second_consensus_secret_key: String,
+        second_consensus_signer: AnySigner,
         reporter_id: u64,
     ) -> TerminationReason {
         while let Some(aggregated_consensus) = ss_rx.recv().await {
-            let signer = create_private_key_signer(second_consensus_secret_key.as_str());
-
             let tx = match hex_str_to_bytes32(aggregated_consensus.tx_hash.as_str()) {
                 Ok(t) => t,
                 Err(e) => {
@@ -889,7 +1056,7 @@ impl OracleTrigger {
                 }
             };
 
-            let signed = match sign_hash(&signer, &tx).await {
+            let signed = match sign_hash(&second_consensus_signer, &tx).await {
                 Ok(s) => s,
                 Err(e) => {
                     tracing::error!("Failed to sign hash on second consensus: {}", &e);
@@ -930,8 +1097,33 @@ impl OracleTrigger {
         engine: Arc<TriggerAppEngine<Self>>,
         component: &Component,
         feeds: Vec<DataFeedSetting>,
-    ) -> anyhow::Result<Payload> {
+        interval_time_in_seconds: u64,
+        secrets: &SecretsProvider,
+    ) -> anyhow::Result<Result<Payload, oracle::Error>> {
         let component_id = component.id.clone();
+
+        // Secrets are resolved on every execution, so rotated ones are used right away
+        let mut capabilities = Vec::with_capacity(component.capabilities.len());
+        for capability in &component.capabilities {
+            match secrets.resolve_capability(&capability.data).await {
+                Ok(secret) => capabilities.push(oracle::Capability {
+                    id: capability.id.clone(),
+                    data: secret.expose().to_string(),
+                }),
+                Err(e) => {
+                    tracing::error!(
+                        "Could not resolve capability {} of component {component_id}: {e:#}",
+                        capability.id
+                    );
+                    let error = oracle::Error::MissingCapability(capability.id.clone());
+                    REPORTER_WASM_ERRORS
+                        .with_label_values(&[&component_id, error_kind(&error)])
+                        .inc();
+                    return Ok(Err(error));
+                }
+            }
+        }
+
         tracing::debug!("Loading guest for `{component_id }`");
 
         // Load the guest...
@@ -968,16 +1160,8 @@ impl OracleTrigger {
                     data: feed.data,
                 })
                 .collect(),
-            capabilities: component
-                .capabilities
-                .iter()
-                .cloned()
-                .map(|capability| oracle::Capability {
-                    id: capability.id,
-                    data: capability.data,
-                })
-                .collect(),
-            interval_time_in_seconds: component.interval_time_in_seconds,
+            capabilities,
+            interval_time_in_seconds,
         };
 
         let start_time = Instant::now();
@@ -996,14 +1180,17 @@ impl OracleTrigger {
                 tracing::info!("Component {component_id} completed okay");
                 // TODO(stanm): increment metric for successful executions
 
-                Ok(payload)
+                Ok(Ok(payload))
             }
             Ok(Err(e)) => {
                 tracing::warn!("Component {component_id} returned error {:?}", e);
                 REPORTER_FAILED_WASM_EXECS
                     .with_label_values(&[&component_id.clone()])
                     .inc();
-                Err(anyhow::anyhow!("Component {component_id} returned error")) // TODO: more details when WIT provides them
+                REPORTER_WASM_ERRORS
+                    .with_label_values(&[&component_id, error_kind(&e)])
+                    .inc();
+                Ok(Err(e))
             }
             Err(e) => {
                 tracing::error!("error running component {component_id}: {:?}", e);
@@ -1065,6 +1252,13 @@ impl OutboundWasiHttpHandler for HttpRuntimeData {
                     }
                 };
             }
+        } else if cassette::proxy_mode().is_replay() {
+            // Only requests through the proxy are replayed, nothing may reach the network
+            tracing::error!(
+                "Destination not replayed: {}, send requests with a timeout to go through the proxy",
+                request.uri()
+            );
+            return Err(ErrorCode::HttpRequestDenied.into());
         } else {
             let uri_string = uri_to_check.to_string();
             let unallowed = !this.allowed_hosts.allows(
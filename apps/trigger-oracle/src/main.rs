@@ -3,6 +3,8 @@ use clap::Parser;
 use http::Uri;
 use spin_trigger::cli::TriggerExecutorCommand;
 use std::io::IsTerminal;
+use trigger_oracle::cassette::{self, Interaction};
+use trigger_oracle::upstream::{self, RequestKey, UpstreamResponse};
 use trigger_oracle::{OracleTrigger, Params};
 
 type Command = TriggerExecutorCommand<OracleTrigger>;
@@ -104,74 +106,58 @@ async fn timing_out_request(req: HttpRequest, payload: web::Payload) -> impl Res
 
     let endpoint_url = &payload.endpoint_url;
 
-    let client = reqwest::Client::new();
-    let response = match payload.request_method.as_str() {
-        "POST" => {
-            let mut send_future = client.post(endpoint_url).headers(reqwest_headers);
-            if let Some(body) = payload.request_body {
-                send_future = send_future.body(body);
-            }
-            match actix_web::rt::time::timeout(
-                Duration::from_secs(payload.seconds),
-                send_future.send(),
-            )
-            .await
-            {
-                Ok(resp_result) => match resp_result {
-                    Ok(r) => r,
-                    Err(e) => {
-                        let err_msg = format!(
-                            "failed to get response for POST request to {endpoint_url}: {e}"
-                        );
-                        tracing::error!(err_msg);
-                        return HttpResponse::BadRequest().body(err_msg);
-                    }
-                },
-                Err(e) => {
-                    let err_msg =
-                        format!("failed to get response for POST request to {endpoint_url}: {e}");
-                    tracing::error!(err_msg);
-                    return HttpResponse::BadRequest().body(err_msg);
-                }
-            }
-        }
-        _ =>
-        // Make a GET request
-        {
-            match actix_web::rt::time::timeout(
-                Duration::from_secs(payload.seconds),
-                client.get(endpoint_url).headers(reqwest_headers).send(),
-            )
-            .await
-            {
-                Ok(resp_result) => match resp_result {
-                    Ok(r) => r,
-                    Err(e) => {
-                        let err_msg = format!(
-                            "failed to get response for GET request to {endpoint_url}: {e}"
-                        );
-                        tracing::error!(err_msg);
-                        return HttpResponse::BadRequest().body(err_msg);
-                    }
-                },
-                Err(e) => {
-                    let err_msg =
-                        format!("failed to get response for GET request to {endpoint_url}: {e}");
-                    tracing::error!(err_msg);
-                    return HttpResponse::BadRequest().body(err_msg);
-                }
+    let proxy_mode = cassette::proxy_mode();
+    if proxy_mode.is_replay() {
+        return match proxy_mode.replayed_response(
+            &payload.request_method,
+            endpoint_url,
+            payload.request_body.as_deref(),
+        ) {
+            Some(interaction) => HttpResponse::Ok().body(interaction.response_body),
+            None => {
+                let err_msg = format!(
+                    "No recorded response for {} request to {endpoint_url}",
+                    payload.request_method
+                );
+                tracing::error!(err_msg);
+                HttpResponse::BadRequest().body(err_msg)
             }
-        }
-    };
+        };
+    }
 
-    let body = match response.bytes().await {
-        Ok(val) => val,
+    let response = match upstream::upstream()
+        .send(
+            RequestKey {
+                method: payload.request_method.clone(),
+                url: endpoint_url.clone(),
+                body: payload.request_body.clone(),
+            },
+            reqwest_headers,
+            Duration::from_secs(payload.seconds),
+        )
+        .await
+    {
+        Ok(response) => response,
         Err(e) => {
-            let err_msg = format!("Failed to convert response to bytes from {endpoint_url}: {e}");
+            let err_msg = format!(
+                "failed to get response for {} request to {endpoint_url}: {e}",
+                payload.request_method
+            );
             tracing::error!(err_msg);
             return HttpResponse::BadRequest().body(err_msg);
         }
     };
+    let UpstreamResponse { status, body } = response;
+
+    if let Err(e) = proxy_mode.record_interaction(Interaction {
+        method: payload.request_method.clone(),
+        url: endpoint_url.clone(),
+        request_body: payload.request_body.clone(),
+        status,
+        response_body: body.to_vec(),
+    }) {
+        tracing::error!("Failed to record response from {endpoint_url}: {e:#}");
+    }
 
     // Return empty 200 OK
     HttpResponse::Ok().body(body)
@@ -179,6 +165,8 @@ async fn timing_out_request(req: HttpRequest, payload: web::Payload) -> impl Res
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
+    cassette::init_proxy_mode()?;
+
     // LocalSet is required for spawn_local
     let local = LocalSet::new();
 
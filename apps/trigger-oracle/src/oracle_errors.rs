@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use crate::oracle;
+
+/// How many times an oracle script is run again within one slot after a transient error.
+pub const MAX_RETRIES: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorHandling {
+    /// Run the oracle script again while the slot lasts
+    Retry,
+    /// Skip the slots until the duration passes
+    BackOff(Duration),
+    /// Nothing to gain from running the oracle script again, the error needs attention
+    Report,
+}
+
+/// Label of the error in the `WASM_ERRORS` metric.
+pub fn error_kind(error: &oracle::Error) -> &'static str {
+    match error {
+        oracle::Error::Other(_) => "other",
+        oracle::Error::Timeout => "timeout",
+        oracle::Error::UpstreamHttp(_) => "upstream_http",
+        oracle::Error::RateLimited(_) => "rate_limited",
+        oracle::Error::MissingCapability(_) => "missing_capability",
+        oracle::Error::InvalidSettings(_) => "invalid_settings",
+    }
+}
+
+/// Rate limited oracle scripts without a known retry-after back off for `interval`.
+pub fn error_handling(error: &oracle::Error, interval: Duration) -> ErrorHandling {
+    match error {
+        oracle::Error::Timeout => ErrorHandling::Retry,
+        oracle::Error::UpstreamHttp(status) if *status >= 500 => ErrorHandling::Retry,
+        oracle::Error::RateLimited(Some(retry_after)) => {
+            ErrorHandling::BackOff(Duration::from_secs(*retry_after))
+        }
+        oracle::Error::RateLimited(None) => ErrorHandling::BackOff(interval),
+        oracle::Error::UpstreamHttp(_)
+        | oracle::Error::MissingCapability(_)
+        | oracle::Error::InvalidSettings(_)
+        | oracle::Error::Other(_) => ErrorHandling::Report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_handled_by_kind() {
+        let interval = Duration::from_secs(10);
+        let handling = |error| error_handling(&error, interval);
+
+        assert_eq!(handling(oracle::Error::Timeout), ErrorHandling::Retry);
+        assert_eq!(
+            handling(oracle::Error::UpstreamHttp(503)),
+            ErrorHandling::Retry
+        );
+        assert_eq!(
+            handling(oracle::Error::UpstreamHttp(404)),
+            ErrorHandling::Report
+        );
+        assert_eq!(
+            handling(oracle::Error::RateLimited(Some(60))),
+            ErrorHandling::BackOff(Duration::from_secs(60))
+        );
+        assert_eq!(
+            handling(oracle::Error::RateLimited(None)),
+            ErrorHandling::BackOff(interval)
+        );
+        assert_eq!(
+            handling(oracle::Error::MissingCapability("API_KEY".to_string())),
+            ErrorHandling::Report
+        );
+        assert_eq!(
+            handling(oracle::Error::InvalidSettings("no pairs".to_string())),
+            ErrorHandling::Report
+        );
+        assert_eq!(
+            handling(oracle::Error::Other("failed".to_string())),
+            ErrorHandling::Report
+        );
+    }
+
+    #[test]
+    fn error_kinds_are_metric_labels() {
+        assert_eq!(
+            error_kind(&oracle::Error::UpstreamHttp(500)),
+            "upstream_http"
+        );
+        assert_eq!(
+            error_kind(&oracle::Error::RateLimited(None)),
+            "rate_limited"
+        );
+        assert_eq!(
+            error_kind(&oracle::Error::MissingCapability("API_KEY".to_string())),
+            "missing_capability"
+        );
+    }
+}
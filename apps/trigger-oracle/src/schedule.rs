@@ -0,0 +1,134 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::DataFeedSetting;
+
+pub const DEFAULT_CLOCK_SKEW_TOLERANCE_MS: u64 = 500;
+
+/// Slots of a feed as the sequencer sees them, see `FeedSchedule` in the feeds config.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ReportSchedule {
+    pub interval_ms: u64,
+    pub first_report_start_unix_time_ms: u64,
+}
+
+impl ReportSchedule {
+    /// Schedule of a feed, falling back to `default_interval_ms` for feeds without one.
+    pub fn of_feed(feed: &DataFeedSetting, default_interval_ms: u64) -> ReportSchedule {
+        ReportSchedule {
+            interval_ms: feed
+                .interval_ms
+                .filter(|interval_ms| *interval_ms > 0)
+                .unwrap_or(default_interval_ms)
+                .max(1),
+            first_report_start_unix_time_ms: feed.first_report_start_unix_time_ms.unwrap_or(0),
+        }
+    }
+
+    /// Middle of the first slot that has not reached its middle at `now_ms`. Reporting there
+    /// leaves the most room for clock skew between the reporter and the sequencer.
+    pub fn next_report_time_ms(&self, now_ms: u128) -> u128 {
+        let start = self.first_report_start_unix_time_ms as u128;
+        let interval = self.interval_ms as u128;
+        let half = interval / 2;
+        if now_ms < start + half {
+            return start + half;
+        }
+        let slot = (now_ms - start - half) / interval + 1;
+        start + slot * interval + half
+    }
+
+    /// Whether a vote signed at `now_ms` for the slot with its middle at `report_time_ms` is too
+    /// close to the end of the slot to be accepted by a sequencer whose clock is up to
+    /// `clock_skew_tolerance_ms` ahead.
+    pub fn is_too_late(
+        &self,
+        report_time_ms: u128,
+        now_ms: u128,
+        clock_skew_tolerance_ms: u64,
+    ) -> bool {
+        let interval = self.interval_ms as u128;
+        let slot_end = report_time_ms - interval / 2 + interval;
+        now_ms + clock_skew_tolerance_ms as u128 >= slot_end
+    }
+}
+
+/// Splits the feeds of an oracle script into groups that are reported together.
+pub fn group_by_schedule(
+    feeds: &HashSet<DataFeedSetting>,
+    default_interval_ms: u64,
+) -> BTreeMap<ReportSchedule, HashSet<DataFeedSetting>> {
+    let mut groups: BTreeMap<ReportSchedule, HashSet<DataFeedSetting>> = BTreeMap::new();
+    for feed in feeds {
+        groups
+            .entry(ReportSchedule::of_feed(feed, default_interval_ms))
+            .or_default()
+            .insert(feed.clone());
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(
+        id: &str,
+        interval_ms: Option<u64>,
+        first_report_start: Option<u64>,
+    ) -> DataFeedSetting {
+        DataFeedSetting {
+            id: id.to_string(),
+            interval_ms,
+            first_report_start_unix_time_ms: first_report_start,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn feeds_are_grouped_by_schedule() {
+        let feeds = HashSet::from([
+            feed("1", Some(60_000), Some(1_000)),
+            feed("2", Some(60_000), Some(1_000)),
+            feed("3", Some(60_000), Some(2_000)),
+            feed("4", None, None),
+            feed("5", Some(10_000), None),
+        ]);
+        let groups = group_by_schedule(&feeds, 10_000);
+
+        let ids = |interval_ms, first_report_start_unix_time_ms| -> Vec<String> {
+            let mut ids: Vec<String> = groups[&ReportSchedule {
+                interval_ms,
+                first_report_start_unix_time_ms,
+            }]
+                .iter()
+                .map(|feed| feed.id.clone())
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(groups.len(), 3);
+        assert_eq!(ids(60_000, 1_000), vec!["1", "2"]);
+        assert_eq!(ids(60_000, 2_000), vec!["3"]);
+        assert_eq!(ids(10_000, 0), vec!["4", "5"]);
+    }
+
+    #[test]
+    fn reports_are_in_the_middle_of_the_slot() {
+        let schedule = ReportSchedule {
+            interval_ms: 10_000,
+            first_report_start_unix_time_ms: 3_000,
+        };
+        // Before the first slot
+        assert_eq!(schedule.next_report_time_ms(0), 8_000);
+        assert_eq!(schedule.next_report_time_ms(7_999), 8_000);
+        // Every report time is strictly in the future
+        assert_eq!(schedule.next_report_time_ms(8_000), 18_000);
+        assert_eq!(schedule.next_report_time_ms(12_999), 18_000);
+        assert_eq!(schedule.next_report_time_ms(13_000), 18_000);
+        assert_eq!(schedule.next_report_time_ms(1_000_008_000), 1_000_018_000);
+
+        assert!(!schedule.is_too_late(18_000, 18_100, 500));
+        assert!(!schedule.is_too_late(18_000, 22_499, 500));
+        assert!(schedule.is_too_late(18_000, 22_500, 500));
+    }
+}
@@ -0,0 +1,327 @@
+//! Requests of the local proxy to the upstream APIs. All components share one client, identical
+//! requests in flight are sent only once, and every host can have its own limits and cache.
+//!
+//! Requests are identified by method, URL and body. Headers are not part of that, so components
+//! sending the same request with different API keys share the response.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use blocksense_registry::config::UpstreamHostConfig;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use reqwest::header::HeaderMap;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep_until, Instant};
+use url::Url;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RequestKey {
+    pub method: String,
+    pub url: String,
+    pub body: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UpstreamResponse {
+    pub status: u16,
+    pub body: Bytes,
+}
+
+type PendingResponse = Shared<BoxFuture<'static, Result<UpstreamResponse, String>>>;
+
+struct HostLimits {
+    concurrency: Option<Arc<Semaphore>>,
+    min_request_interval: Option<Duration>,
+    next_request: Mutex<Instant>,
+}
+
+pub struct Upstream {
+    client: reqwest::Client,
+    settings: HashMap<String, UpstreamHostConfig>,
+    limits: Mutex<HashMap<String, Arc<HostLimits>>>,
+    cache: Mutex<HashMap<RequestKey, (Instant, UpstreamResponse)>>,
+    in_flight: Mutex<HashMap<RequestKey, PendingResponse>>,
+}
+
+/// Settings can be keyed by an allowed outbound host such as `https://api.example.com` or just by
+/// the host name.
+fn host_of(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
+impl Upstream {
+    pub fn new(settings: HashMap<String, UpstreamHostConfig>) -> Upstream {
+        Upstream {
+            client: reqwest::Client::new(),
+            settings: settings
+                .into_iter()
+                .map(|(host, settings)| (host_of(&host), settings))
+                .collect(),
+            limits: Default::default(),
+            cache: Default::default(),
+            in_flight: Default::default(),
+        }
+    }
+
+    /// Response of the host, from the cache or from a request which is already in flight if
+    /// possible. `timeout` covers the waiting for the limits of the host as well.
+    pub async fn send(
+        self: &Arc<Self>,
+        key: RequestKey,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<UpstreamResponse, String> {
+        if let Some(response) = self.cached(&key) {
+            tracing::debug!("Serving {} {} from the cache", key.method, key.url);
+            return Ok(response);
+        }
+        let pending = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(&key) {
+                Some(pending) => {
+                    tracing::debug!("Joining request in flight {} {}", key.method, key.url);
+                    pending.clone()
+                }
+                None => {
+                    let pending = self
+                        .clone()
+                        .fetch(key.clone(), headers, timeout)
+                        .boxed()
+                        .shared();
+                    in_flight.insert(key, pending.clone());
+                    pending
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, pending).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("no response within {timeout:?}")),
+        }
+    }
+
+    fn cached(&self, key: &RequestKey) -> Option<UpstreamResponse> {
+        let mut cache = self.cache.lock().unwrap();
+        match cache.get(key) {
+            Some((expires_at, response)) if *expires_at > Instant::now() => Some(response.clone()),
+            Some(_) => {
+                cache.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn host_limits(&self, host: &str) -> Arc<HostLimits> {
+        let mut limits = self.limits.lock().unwrap();
+        limits
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                let settings = self.settings.get(host).cloned().unwrap_or_default();
+                Arc::new(HostLimits {
+                    concurrency: settings
+                        .max_concurrent_requests
+                        .map(|max| Arc::new(Semaphore::new(max.max(1)))),
+                    min_request_interval: settings
+                        .min_request_interval_ms
+                        .map(Duration::from_millis),
+                    next_request: Mutex::new(Instant::now()),
+                })
+            })
+            .clone()
+    }
+
+    async fn fetch(
+        self: Arc<Self>,
+        key: RequestKey,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<UpstreamResponse, String> {
+        let host = host_of(&key.url);
+        let result = self.fetch_uncached(&key, &host, headers, timeout).await;
+        let cache_ttl_ms = self
+            .settings
+            .get(&host)
+            .and_then(|settings| settings.cache_ttl_ms);
+        if let (Ok(response), Some(cache_ttl_ms)) = (&result, cache_ttl_ms) {
+            if (200..300).contains(&response.status) {
+                let now = Instant::now();
+                let mut cache = self.cache.lock().unwrap();
+                cache.retain(|_, (expires_at, _)| *expires_at > now);
+                cache.insert(
+                    key.clone(),
+                    (now + Duration::from_millis(cache_ttl_ms), response.clone()),
+                );
+            }
+        }
+        self.in_flight.lock().unwrap().remove(&key);
+        result
+    }
+
+    async fn fetch_uncached(
+        &self,
+        key: &RequestKey,
+        host: &str,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<UpstreamResponse, String> {
+        let limits = self.host_limits(host);
+        let _permit = match &limits.concurrency {
+            Some(semaphore) => Some(
+                semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| e.to_string())?,
+            ),
+            None => None,
+        };
+        if let Some(min_request_interval) = limits.min_request_interval {
+            let send_at = {
+                let mut next_request = limits.next_request.lock().unwrap();
+                let send_at = (*next_request).max(Instant::now());
+                *next_request = send_at + min_request_interval;
+                send_at
+            };
+            sleep_until(send_at).await;
+        }
+
+        let request = match key.method.as_str() {
+            "POST" => {
+                let mut request = self.client.post(&key.url);
+                if let Some(body) = &key.body {
+                    request = request.body(body.clone());
+                }
+                request
+            }
+            _ => self.client.get(&key.url),
+        };
+        let response = request
+            .headers(headers)
+            .timeout(timeout)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status().as_u16();
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("failed to read the response body: {e}"))?;
+        Ok(UpstreamResponse { status, body })
+    }
+}
+
+static UPSTREAM: OnceLock<Arc<Upstream>> = OnceLock::new();
+
+/// Sets the limits of the upstream hosts, before any request is proxied.
+pub fn configure_upstream(settings: HashMap<String, UpstreamHostConfig>) {
+    if UPSTREAM.set(Arc::new(Upstream::new(settings))).is_err() {
+        tracing::warn!("Upstream hosts are already configured, ignoring the new settings");
+    }
+}
+
+/// Client shared by all proxied requests, without limits unless `configure_upstream` was called.
+pub fn upstream() -> Arc<Upstream> {
+    UPSTREAM
+        .get_or_init(|| Arc::new(Upstream::new(HashMap::new())))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use actix_web::{get, web, App, HttpResponse, HttpServer, Responder};
+
+    use super::*;
+
+    #[get("/{path}")]
+    async fn counting(path: web::Path<String>, hits: web::Data<AtomicUsize>) -> impl Responder {
+        hits.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        HttpResponse::Ok().body(path.into_inner())
+    }
+
+    async fn start_counting_server() -> (String, web::Data<AtomicUsize>) {
+        let hits = web::Data::new(AtomicUsize::new(0));
+        let app_hits = hits.clone();
+        let server =
+            HttpServer::new(move || App::new().app_data(app_hits.clone()).service(counting))
+                .workers(2)
+                .bind("127.0.0.1:0")
+                .unwrap();
+        let addr = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        (format!("http://{addr}"), hits)
+    }
+
+    fn get(url: String) -> RequestKey {
+        RequestKey {
+            method: "GET".to_string(),
+            url,
+            body: None,
+        }
+    }
+
+    #[actix_web::test]
+    async fn identical_requests_are_coalesced_and_cached() {
+        let (base_url, hits) = start_counting_server().await;
+        let upstream = Arc::new(Upstream::new(HashMap::from([(
+            "127.0.0.1".to_string(),
+            UpstreamHostConfig {
+                cache_ttl_ms: Some(60_000),
+                ..Default::default()
+            },
+        )])));
+        let timeout = Duration::from_secs(5);
+
+        let (first, second) = futures::join!(
+            upstream.send(get(format!("{base_url}/a")), HeaderMap::new(), timeout),
+            upstream.send(get(format!("{base_url}/a")), HeaderMap::new(), timeout),
+        );
+        assert_eq!(first.unwrap().body, Bytes::from("a"));
+        assert_eq!(second.unwrap().body, Bytes::from("a"));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        let cached = upstream
+            .send(get(format!("{base_url}/a")), HeaderMap::new(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(cached.status, 200);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        upstream
+            .send(get(format!("{base_url}/b")), HeaderMap::new(), timeout)
+            .await
+            .unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[actix_web::test]
+    async fn requests_to_a_host_are_spaced() {
+        let (base_url, hits) = start_counting_server().await;
+        let upstream = Arc::new(Upstream::new(HashMap::from([(
+            format!("{base_url}/"),
+            UpstreamHostConfig {
+                max_concurrent_requests: Some(1),
+                min_request_interval_ms: Some(300),
+                ..Default::default()
+            },
+        )])));
+        let timeout = Duration::from_secs(5);
+
+        let start = Instant::now();
+        let (first, second) = futures::join!(
+            upstream.send(get(format!("{base_url}/a")), HeaderMap::new(), timeout),
+            upstream.send(get(format!("{base_url}/b")), HeaderMap::new(), timeout),
+        );
+        first.unwrap();
+        second.unwrap();
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+}
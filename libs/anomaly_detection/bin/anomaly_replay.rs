@@ -0,0 +1,43 @@
+use blocksense_anomaly_detection::{
+    detector::DetectorConfig, ingest::read_csv_to_vec, replay::replay,
+};
+
+const USAGE: &str = "Usage: anomaly_replay <csv_file> <column> [--threshold <score>] [--labels <column>] [--window <values>]";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() < 2 {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    }
+    let file_path = &args[0];
+    let column = &args[1];
+
+    let mut threshold = 0.5;
+    let mut labels_column = None;
+    let mut config = DetectorConfig::default();
+    let mut options = args[2..].iter();
+    while let Some(option) = options.next() {
+        let Some(value) = options.next() else {
+            eprintln!("Missing value for {option}\n{USAGE}");
+            std::process::exit(1);
+        };
+        match option.as_str() {
+            "--threshold" => threshold = value.parse().expect("Invalid threshold"),
+            "--labels" => labels_column = Some(value.clone()),
+            "--window" => config.training_window = value.parse().expect("Invalid window"),
+            _ => {
+                eprintln!("Unknown option {option}\n{USAGE}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let values = read_csv_to_vec(file_path, column).unwrap();
+    let labels =
+        labels_column.map(|labels_column| read_csv_to_vec(file_path, &labels_column).unwrap());
+
+    let report = replay(&values, labels.as_deref(), config, threshold).unwrap();
+
+    println!("{report}");
+}
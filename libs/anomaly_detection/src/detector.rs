@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+
+use anyhow::{Context, Result};
+use tracing::{debug, warn};
+
+use crate::{
+    hdbscan_detector::make_f64_hdbscan,
+    isolation_forest::{IsolationForest, DEFAULT_N_TREES, DEFAULT_SAMPLE_SIZE},
+};
+
+pub const DEFAULT_TRAINING_WINDOW: usize = 500;
+pub const DEFAULT_MIN_TRAINING_VALUES: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectorConfig {
+    /// Number of most recent values the model is trained on
+    pub training_window: usize,
+    /// Number of values needed before the detector starts scoring
+    pub min_training_values: usize,
+}
+
+impl Default for DetectorConfig {
+    fn default() -> Self {
+        Self {
+            training_window: DEFAULT_TRAINING_WINDOW,
+            min_training_values: DEFAULT_MIN_TRAINING_VALUES,
+        }
+    }
+}
+
+/// Anomaly detector for the values of a single feed.
+///
+/// Prices drift over time, so the models are trained on the relative change between consecutive
+/// values rather than on the values themselves. A candidate is scored by its change from the last
+/// ingested value.
+///
+/// The model is trained anew for every score from the given seed, so the same values and seed
+/// always give the same score.
+pub struct AnomalyDetector {
+    config: DetectorConfig,
+    values: VecDeque<f64>,
+    last_sequence_number: Option<u128>,
+}
+
+fn relative_change(previous: f64, value: f64) -> f64 {
+    if previous == 0.0 {
+        value - previous
+    } else {
+        (value - previous) / previous.abs()
+    }
+}
+
+impl AnomalyDetector {
+    pub fn new(config: DetectorConfig) -> Self {
+        Self {
+            config,
+            values: VecDeque::with_capacity(config.training_window + 1),
+            last_sequence_number: None,
+        }
+    }
+
+    pub fn config(&self) -> &DetectorConfig {
+        &self.config
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn reset(&mut self) {
+        self.values.clear();
+        self.last_sequence_number = None;
+    }
+
+    pub fn ingest(&mut self, value: f64) {
+        if !value.is_finite() {
+            warn!("[AD] ignoring non finite value {value}");
+            return;
+        }
+        self.values.push_back(value);
+        // One value more than the window, so that the window is filled with changes
+        while self.values.len() > self.config.training_window + 1 {
+            self.values.pop_front();
+        }
+    }
+
+    /// Ingests `(sequence_number, value)` pairs, oldest first, skipping the ones that were
+    /// already ingested. Lets the caller pass the whole feed history on every call while the
+    /// model only sees the new values.
+    pub fn ingest_history(&mut self, entries: impl IntoIterator<Item = (u128, f64)>) {
+        let entries: Vec<(u128, f64)> = entries.into_iter().collect();
+        if let (Some(last_seen), Some((newest, _))) = (self.last_sequence_number, entries.last()) {
+            if *newest < last_seen {
+                debug!("[AD] history was reset, retraining from scratch");
+                self.reset();
+            }
+        }
+        for (sequence_number, value) in entries {
+            if self
+                .last_sequence_number
+                .is_some_and(|last_seen| sequence_number <= last_seen)
+            {
+                continue;
+            }
+            self.ingest(value);
+            self.last_sequence_number = Some(sequence_number);
+        }
+    }
+
+    fn changes(&self) -> Vec<f64> {
+        self.values
+            .iter()
+            .zip(self.values.iter().skip(1))
+            .map(|(previous, value)| relative_change(*previous, *value))
+            .collect()
+    }
+
+    /// Scores how anomalous `candidate` is compared to the ingested values. The score is in
+    /// [0, 1], where 0 is a perfectly normal value. The candidate itself is not ingested.
+    pub fn score(&self, candidate: f64, seed: u64) -> Result<f64> {
+        if !candidate.is_finite() {
+            anyhow::bail!("Cannot score non finite value {candidate}");
+        }
+        let changes = self.changes();
+        if changes.len() < self.config.min_training_values {
+            anyhow::bail!(
+                "Skipping anomaly detection; {} values, need at least {}",
+                changes.len(),
+                self.config.min_training_values
+            );
+        }
+        let last_value = *self
+            .values
+            .back()
+            .context("Not enough values for anomaly detection")?;
+        let change = relative_change(last_value, candidate);
+
+        debug!("[AD] training forest on {} values", changes.len());
+        let forest = IsolationForest::new(&changes, DEFAULT_N_TREES, DEFAULT_SAMPLE_SIZE, seed)?;
+        // Isolation forest scores are around 0.5 for normal points and approach 1 for anomalies
+        let isolation_forest_result = ((forest.score(change) - 0.5) * 2.0).clamp(0.0, 1.0);
+        debug!("[AD] change={change} isolation_forest_result={isolation_forest_result}");
+
+        let data = changes
+            .iter()
+            .chain(std::iter::once(&change))
+            .map(|&x| vec![x])
+            .collect();
+        let score = match make_f64_hdbscan(data) {
+            Ok(labels) => {
+                // HDBSCAN marks points that do not belong to any cluster with -1
+                let hdbscan_result = f64::from(labels.last() == Some(&-1));
+                debug!("[AD] hdbscan_result={hdbscan_result}");
+                (isolation_forest_result + hdbscan_result) / 2.
+            }
+            Err(e) => {
+                warn!("[AD] hdbscan failed, using only the isolation forest: {e}");
+                isolation_forest_result
+            }
+        };
+        debug!("[AD] score={score}");
+        Ok(score)
+    }
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        Self::new(DetectorConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smooth_values(count: usize) -> Vec<f64> {
+        (0..count).map(|i| 100. + (i as f64 / 10.).sin()).collect()
+    }
+
+    #[test]
+    fn detector_needs_enough_values() {
+        let mut detector = AnomalyDetector::default();
+        smooth_values(50)
+            .into_iter()
+            .for_each(|v| detector.ingest(v));
+
+        assert!(detector.score(100., 0).is_err());
+    }
+
+    #[test]
+    fn detector_scores_spike_higher_than_normal_value() {
+        let mut detector = AnomalyDetector::default();
+        let values = smooth_values(300);
+        values.iter().for_each(|v| detector.ingest(*v));
+
+        let next = 100. + (300. / 10_f64).sin();
+        let normal_score = detector.score(next, 7).unwrap();
+        let spike_score = detector.score(next * 1.5, 7).unwrap();
+
+        assert!((0.0..=1.0).contains(&normal_score));
+        assert!((0.0..=1.0).contains(&spike_score));
+        assert!(spike_score > 0.5);
+        assert!(spike_score > normal_score);
+    }
+
+    #[test]
+    fn detector_scores_are_reproducible() {
+        let mut detector = AnomalyDetector::default();
+        let mut other = AnomalyDetector::default();
+        for v in smooth_values(300) {
+            detector.ingest(v);
+            other.ingest(v);
+        }
+
+        for candidate in [100.5, 101., 150.] {
+            assert_eq!(
+                detector.score(candidate, 11).unwrap(),
+                other.score(candidate, 11).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn detector_ingests_only_new_history() {
+        let mut detector = AnomalyDetector::default();
+        let history: Vec<(u128, f64)> = smooth_values(10)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (i as u128, v))
+            .collect();
+
+        detector.ingest_history(history.clone());
+        detector.ingest_history(history.clone());
+        assert_eq!(detector.len(), 10);
+
+        // Sequence numbers starting over means the history was cleared
+        detector.ingest_history(history[..3].to_vec());
+        assert_eq!(detector.len(), 3);
+    }
+
+    #[test]
+    fn detector_keeps_only_training_window() {
+        let mut detector = AnomalyDetector::new(DetectorConfig {
+            training_window: 20,
+            ..Default::default()
+        });
+        smooth_values(100)
+            .into_iter()
+            .for_each(|v| detector.ingest(v));
+
+        assert_eq!(detector.len(), 21);
+    }
+}
@@ -8,7 +8,8 @@ Reference implementation - https://github.com/sahandha/eif
 pub fn make_f64_forest<const N: usize>(values: Vec<[f64; N]>) -> Result<Forest<f64, N>> {
     let options = ForestOptions {
         n_trees: 150,
-        sample_size: 200,
+        // Training fails if there are fewer values than samples
+        sample_size: values.len().min(200),
         max_tree_depth: None,
         extension_level: 0, //TODO(snikolov): Figure out why it's required `extension_level` < `Dimensions`
     };
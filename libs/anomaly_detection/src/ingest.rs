@@ -4,7 +4,7 @@ use anyhow::Context;
 use csv::ReaderBuilder;
 use tracing::debug;
 
-// use crate::{extended_isolation_forest::make_f64_forest, hdbscan_detector::make_f64_hdbscan};
+use crate::detector::{AnomalyDetector, DetectorConfig};
 
 pub fn read_csv_to_vec(file_path: &str, column: &str) -> Result<Vec<f64>, Box<dyn Error>> {
     let file = File::open(Path::new(file_path))?;
@@ -28,50 +28,20 @@ pub fn read_csv_to_vec(file_path: &str, column: &str) -> Result<Vec<f64>, Box<dy
 
 /// Takes in Vec<f64> passes it through several Anomaly Detection algos and returns a score
 ///
-/// The last value of `values` will be checked for anomalies
-/// Sending an empty `values` vector will result in a panic!
-pub fn anomaly_detector_aggregate(values: Vec<f64>) -> Result<f64, anyhow::Error> {
+/// The last value of `values` will be checked for anomalies against all the previous ones.
+/// The score is in [0, 1], see `AnomalyDetector::score`.
+pub fn anomaly_detector_aggregate(values: Vec<f64>, seed: u64) -> Result<f64, anyhow::Error> {
     debug!("[AD] input: values={values:?}");
 
-    const DIM: usize = 1; // Dimensionality of data
-
-    debug!(
-        "[AD] packing values into single-element arrays; values.len()={}",
-        values.len()
-    );
-    let values_array: Vec<[f64; DIM]> = values.iter().map(|&x| [x]).collect();
-
-    debug!("[AD] getting last value");
-    let last_value = *values_array
-        .last()
+    let (candidate, history) = values
+        .split_last()
         .context("Not enough values for anomaly detection")?;
 
-    debug!("[AD] bypassing and returning last_value={last_value:?}");
-    Ok(last_value[0])
-    /*
-    debug!("[AD] about to make forest...; last_value={last_value:?}");
-    let forest = make_f64_forest::<DIM>(values_array)?;
-    debug!("[AD] done making forest");
-
-    debug!("[AD] computing score...");
-    let isolation_forest_result = forest.score(&last_value);
-    debug!("[AD] done computing score; isolation_forest_result={isolation_forest_result}");
-
-    debug!("[AD] again packing values into single-element arrays");
-    let values_array = values.iter().map(|&x| vec![x]).collect();
-
-    debug!("[AD] before make_f64_hdbscan");
-    let detector_result =
-        make_f64_hdbscan(values_array).context("hdbscan error during anomaly detection")?;
-    debug!("[AD] after make_f64_hdbscan");
-
-    debug!("[AD] converting result");
-    let hdbscan_result = f64::from(*detector_result.last().unwrap() == -1_i32);
-
-    let aggregate_result = (isolation_forest_result + hdbscan_result) / 2.;
-
-    debug!("[AD] aggregate_result = {aggregate_result}");
+    let mut detector = AnomalyDetector::new(DetectorConfig {
+        training_window: history.len().max(1),
+        ..Default::default()
+    });
+    history.iter().for_each(|value| detector.ingest(*value));
 
-    Ok(aggregate_result)
-    */
+    detector.score(*candidate, seed)
 }
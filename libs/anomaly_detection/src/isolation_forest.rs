@@ -0,0 +1,158 @@
+use anyhow::Result;
+use rand::{rngs::StdRng, seq::index, Rng, SeedableRng};
+
+/*
+"Isolation Forest" paper - https://ieeexplore.ieee.org/document/4781136
+
+Unlike the forest in `extended_isolation_forest`, which is grown from `thread_rng`, the trees are
+grown from a seeded RNG. The same values and seed always give the same scores, which lets every
+sequencer reach the same decision for the same slot.
+*/
+
+pub const DEFAULT_N_TREES: usize = 150;
+pub const DEFAULT_SAMPLE_SIZE: usize = 200;
+
+const EULER_GAMMA: f64 = 0.577_215_664_901_532_9;
+
+enum Node {
+    Leaf {
+        size: usize,
+    },
+    Split {
+        threshold: f64,
+        left: Box<Node>,
+        right: Box<Node>,
+    },
+}
+
+pub struct IsolationForest {
+    trees: Vec<Node>,
+    sample_size: usize,
+}
+
+// Average path length of an unsuccessful search in a binary search tree with `n` values
+fn average_path_length(n: usize) -> f64 {
+    match n {
+        0 | 1 => 0.0,
+        2 => 1.0,
+        n => {
+            let n = n as f64;
+            2.0 * ((n - 1.0).ln() + EULER_GAMMA) - 2.0 * (n - 1.0) / n
+        }
+    }
+}
+
+fn grow(values: &mut [f64], depth: usize, max_depth: usize, rng: &mut StdRng) -> Node {
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &x| {
+            (min.min(x), max.max(x))
+        });
+    if depth >= max_depth || values.len() <= 1 || min >= max {
+        return Node::Leaf { size: values.len() };
+    }
+
+    let threshold = rng.gen_range(min..max);
+    let mut split = 0;
+    for i in 0..values.len() {
+        if values[i] < threshold {
+            values.swap(i, split);
+            split += 1;
+        }
+    }
+    let (left, right) = values.split_at_mut(split);
+    Node::Split {
+        threshold,
+        left: Box::new(grow(left, depth + 1, max_depth, rng)),
+        right: Box::new(grow(right, depth + 1, max_depth, rng)),
+    }
+}
+
+fn path_length(mut node: &Node, value: f64) -> f64 {
+    let mut depth = 0.0;
+    loop {
+        match node {
+            Node::Leaf { size } => return depth + average_path_length(*size),
+            Node::Split {
+                threshold,
+                left,
+                right,
+            } => {
+                node = if value < *threshold { left } else { right };
+                depth += 1.0;
+            }
+        }
+    }
+}
+
+impl IsolationForest {
+    pub fn new(values: &[f64], n_trees: usize, sample_size: usize, seed: u64) -> Result<Self> {
+        if values.len() < 2 {
+            anyhow::bail!(
+                "Need at least 2 values to build a forest, got {}",
+                values.len()
+            );
+        }
+        if let Some(value) = values.iter().find(|x| !x.is_finite()) {
+            anyhow::bail!("Cannot build a forest from non finite value {value}");
+        }
+
+        let sample_size = sample_size.clamp(2, values.len());
+        let max_depth = (sample_size as f64).log2().ceil() as usize;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let trees = (0..n_trees)
+            .map(|_| {
+                let mut sample: Vec<f64> = index::sample(&mut rng, values.len(), sample_size)
+                    .into_iter()
+                    .map(|i| values[i])
+                    .collect();
+                grow(&mut sample, 0, max_depth, &mut rng)
+            })
+            .collect();
+        Ok(Self { trees, sample_size })
+    }
+
+    /// Scores around 0.5 are normal, scores approaching 1 are anomalies.
+    pub fn score(&self, value: f64) -> f64 {
+        if self.trees.is_empty() {
+            return 0.5;
+        }
+        let mean_path_length = self
+            .trees
+            .iter()
+            .map(|tree| path_length(tree, value))
+            .sum::<f64>()
+            / self.trees.len() as f64;
+        2f64.powf(-mean_path_length / average_path_length(self.sample_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forest_is_reproducible_and_isolates_outliers() {
+        // Evenly spread over [-0.5, 0.5)
+        let values: Vec<f64> = (0..500)
+            .map(|i| (i as f64 * 0.618_033_988_7).fract() - 0.5)
+            .collect();
+
+        let forest = IsolationForest::new(&values, DEFAULT_N_TREES, DEFAULT_SAMPLE_SIZE, 42)
+            .expect("Error building forest");
+        let same_seed = IsolationForest::new(&values, DEFAULT_N_TREES, DEFAULT_SAMPLE_SIZE, 42)
+            .expect("Error building forest");
+
+        assert_eq!(forest.score(0.1), same_seed.score(0.1));
+        assert_eq!(forest.score(25.), same_seed.score(25.));
+        assert!(forest.score(0.1) < 0.55);
+        assert!(forest.score(25.) > 0.6);
+        assert!(forest.score(-25.) > 0.6);
+    }
+
+    #[test]
+    fn forest_rejects_too_few_or_non_finite_values() {
+        assert!(IsolationForest::new(&[1.0], 10, 10, 0).is_err());
+        assert!(IsolationForest::new(&[1.0, f64::NAN], 10, 10, 0).is_err());
+    }
+}
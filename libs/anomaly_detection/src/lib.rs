@@ -1,3 +1,6 @@
+pub mod detector;
 pub mod extended_isolation_forest;
 pub mod hdbscan_detector;
 pub mod ingest;
+pub mod isolation_forest;
+pub mod replay;
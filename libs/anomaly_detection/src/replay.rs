@@ -0,0 +1,129 @@
+use std::fmt::Display;
+
+use anyhow::Result;
+
+use crate::detector::{AnomalyDetector, DetectorConfig};
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ReplayReport {
+    pub total: usize,
+    /// Values for which the detector had enough history to produce a score
+    pub scored: usize,
+    pub flagged: usize,
+    pub labeled_anomalies: usize,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub max_score: f64,
+}
+
+impl ReplayReport {
+    /// Share of the scored normal values that were flagged as anomalies.
+    pub fn false_positive_rate(&self) -> f64 {
+        let normal = self.scored - self.labeled_anomalies;
+        if normal == 0 {
+            0.0
+        } else {
+            self.false_positives as f64 / normal as f64
+        }
+    }
+
+    /// Share of the scored labeled anomalies that were flagged, if there are any.
+    pub fn detection_rate(&self) -> Option<f64> {
+        if self.labeled_anomalies == 0 {
+            None
+        } else {
+            Some(self.true_positives as f64 / self.labeled_anomalies as f64)
+        }
+    }
+}
+
+impl Display for ReplayReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "values:              {}", self.total)?;
+        writeln!(f, "scored:              {}", self.scored)?;
+        writeln!(f, "flagged:             {}", self.flagged)?;
+        writeln!(f, "labeled anomalies:   {}", self.labeled_anomalies)?;
+        writeln!(f, "false positives:     {}", self.false_positives)?;
+        writeln!(f, "false positive rate: {:.4}", self.false_positive_rate())?;
+        if let Some(detection_rate) = self.detection_rate() {
+            writeln!(f, "detection rate:      {detection_rate:.4}")?;
+        }
+        write!(f, "max score:           {:.4}", self.max_score)
+    }
+}
+
+/// Replays `values` through a detector, as if every value was published in order, and counts
+/// how many of them score above `threshold`. The position of a value seeds its score, like the
+/// slot does in the sequencer.
+///
+/// `labels` marks the known anomalies (any non-zero value). Without labels all values are
+/// considered normal, so every flagged value is a false positive.
+pub fn replay(
+    values: &[f64],
+    labels: Option<&[f64]>,
+    config: DetectorConfig,
+    threshold: f64,
+) -> Result<ReplayReport> {
+    if let Some(labels) = labels {
+        if labels.len() != values.len() {
+            anyhow::bail!("Got {} labels for {} values", labels.len(), values.len());
+        }
+    }
+
+    let mut detector = AnomalyDetector::new(config);
+    let mut report = ReplayReport {
+        total: values.len(),
+        ..Default::default()
+    };
+    for (i, value) in values.iter().enumerate() {
+        if let Ok(score) = detector.score(*value, i as u64) {
+            let is_anomaly = labels.is_some_and(|labels| labels[i] != 0.0);
+            let is_flagged = score > threshold;
+            report.scored += 1;
+            report.max_score = report.max_score.max(score);
+            if is_anomaly {
+                report.labeled_anomalies += 1;
+            }
+            if is_flagged {
+                report.flagged += 1;
+                if is_anomaly {
+                    report.true_positives += 1;
+                } else {
+                    report.false_positives += 1;
+                }
+            }
+        }
+        detector.ingest(*value);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_counts_false_positives_and_detections() {
+        let mut values: Vec<f64> = (0..400).map(|i| 100. + (i as f64 / 10.).sin()).collect();
+        let mut labels = vec![0.0; values.len()];
+        values[300] *= 1.5;
+        labels[300] = 1.0;
+        // Going back to normal after the spike is as big a jump as the spike itself
+        labels[301] = 1.0;
+
+        let report = replay(&values, Some(&labels), DetectorConfig::default(), 0.5).unwrap();
+
+        assert_eq!(report.total, 400);
+        assert_eq!(report.labeled_anomalies, 2);
+        assert!(report.true_positives >= 1);
+        assert!(report.false_positive_rate() < 0.1);
+    }
+
+    #[test]
+    fn replay_rejects_mismatched_labels() {
+        let values = vec![1.0, 2.0, 3.0];
+        let labels = vec![0.0];
+
+        assert!(replay(&values, Some(&labels), DetectorConfig::default(), 0.5).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    calc_merkle_root, node_to_hash, BlockFeedConfig, BlockHeader, BlockReporterAction, FeedActions,
+    HashType, GENESIS_HASH, MAX_FEED_ID_TO_DELETE_IN_BLOCK, MAX_NEW_FEEDS_IN_BLOCK,
+    MAX_REPORTER_ACTIONS_IN_BLOCK,
+};
+use anyhow::Result;
+use blocksense_utils::{time::current_unix_time, EncodedFeedId};
+use tracing::error;
+
+/// Storage of the sequencer's blockchain. Implementors only need to provide the primitive
+/// operations to persist and look up blocks, the chain validation logic is shared.
+pub trait BlockStore: Send + Sync {
+    fn get_latest_block_height(&self) -> u64;
+
+    fn get_block_header_hash_by_height(&self, block_height: u64) -> Option<HashType>;
+
+    fn get_block_header_by_hash(&self, header_hash: &HashType) -> Option<&BlockHeader>;
+
+    /// Returns the feed actions stored under the given Merkle root (as found in the block header).
+    fn get_feed_actions(&self, add_remove_feeds_merkle_root: &HashType) -> Option<&FeedActions>;
+
+    /// Stores the block without checking if it extends the chain. The latest block height is
+    /// updated if the block is higher than all stored ones.
+    fn add_block(&mut self, header: BlockHeader, add_remove_feeds: FeedActions) -> Result<()>;
+
+    fn get_block_header_by_height(&self, block_height: u64) -> Option<&BlockHeader> {
+        let header_hash = self.get_block_header_hash_by_height(block_height)?;
+        self.get_block_header_by_hash(&header_hash)
+    }
+
+    /// Hash of the latest block header or the genesis hash if the chain is empty.
+    fn get_latest_block_hash(&self) -> Result<HashType> {
+        let latest_height = self.get_latest_block_height();
+        if latest_height == 0 {
+            return Ok(GENESIS_HASH);
+        }
+        match self.get_block_header_hash_by_height(latest_height) {
+            Some(hash) => Ok(hash),
+            None => anyhow::bail!("Missing block header for latest block height {latest_height}!"),
+        }
+    }
+
+    fn create_new_block(
+        &self,
+        sequencer_id: u64,
+        new_block_height: u64,
+        new_feeds_in_block: Vec<BlockFeedConfig>,
+        feed_ids_to_delete_in_block: Vec<EncodedFeedId>,
+        reporter_actions_in_block: Vec<BlockReporterAction>,
+    ) -> Result<(BlockHeader, FeedActions)> {
+        // Populate new and to be removed feeds in block:
+        let mut add_remove_feeds = FeedActions::default();
+
+        if new_feeds_in_block.len() > MAX_NEW_FEEDS_IN_BLOCK {
+            error!("Trying to insert in block more newly registered feeds {} than supported {}. All above supported limit will be dropped!", new_feeds_in_block.len(), MAX_NEW_FEEDS_IN_BLOCK)
+        }
+
+        let mut iter = new_feeds_in_block.into_iter();
+        for i in 0..add_remove_feeds.new_feeds.len() {
+            if let Some(feed) = iter.next() {
+                add_remove_feeds.new_feeds[i] = Some(feed);
+            } else {
+                add_remove_feeds.new_feeds[i] = None;
+            }
+        }
+
+        if feed_ids_to_delete_in_block.len() > MAX_FEED_ID_TO_DELETE_IN_BLOCK {
+            error!("Trying to insert in block more to be deleted feeds {} than supported {}. All above supported limit will be dropped!", feed_ids_to_delete_in_block.len(), MAX_FEED_ID_TO_DELETE_IN_BLOCK)
+        }
+
+        let mut iter = feed_ids_to_delete_in_block.into_iter();
+        for i in 0..add_remove_feeds.feed_ids_to_rm.len() {
+            if let Some(feed_id) = iter.next() {
+                add_remove_feeds.feed_ids_to_rm[i] = Some(feed_id);
+            } else {
+                add_remove_feeds.feed_ids_to_rm[i] = None;
+            }
+        }
+
+        if reporter_actions_in_block.len() > MAX_REPORTER_ACTIONS_IN_BLOCK {
+            error!("Trying to insert in block more reporter actions {} than supported {}. All above supported limit will be dropped!", reporter_actions_in_block.len(), MAX_REPORTER_ACTIONS_IN_BLOCK)
+        }
+
+        let mut iter = reporter_actions_in_block.into_iter();
+        for i in 0..add_remove_feeds.reporter_actions.len() {
+            add_remove_feeds.reporter_actions[i] = iter.next();
+        }
+
+        add_remove_feeds.block_height = new_block_height;
+        let add_remove_feeds_merkle_root = calc_merkle_root(&mut add_remove_feeds)?;
+
+        let block_header = BlockHeader {
+            issuer_id: sequencer_id,
+            block_height: new_block_height,
+            timestamp: current_unix_time() as u64,
+            prev_block_hash: self.get_latest_block_hash()?,
+            add_remove_feeds_merkle_root: node_to_hash(add_remove_feeds_merkle_root)?,
+        };
+
+        Ok((block_header, add_remove_feeds))
+    }
+
+    fn add_next_block(&mut self, header: BlockHeader, add_remove_feeds: FeedActions) -> Result<()> {
+        // Check if block can be added as next in blockchain:
+        let latest_block_height = self.get_latest_block_height();
+        let block_height = header.block_height;
+        if block_height <= latest_block_height {
+            anyhow::bail!(
+                "Block height not as expected, got {}, expected higher than {}!",
+                block_height,
+                latest_block_height
+            );
+        }
+
+        if latest_block_height == 0 && header.prev_block_hash != GENESIS_HASH {
+            anyhow::bail!("First block does not refer genesis in blockchain!");
+        }
+
+        if latest_block_height != 0 && header.prev_block_hash != self.get_latest_block_hash()? {
+            anyhow::bail!("New block does not refer latest in blockchain!");
+        }
+
+        self.add_block(header, add_remove_feeds)
+    }
+}
@@ -0,0 +1,594 @@
+use crate::block_store::BlockStore;
+use crate::in_mem_db::InMemDb;
+use crate::{calc_merkle_root, node_to_hash, BlockHeader, FeedActions, HashType};
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+const SEGMENT_FILE_PREFIX: &str = "blocks_";
+const SEGMENT_FILE_EXTENSION: &str = "ssz";
+pub const DEFAULT_MAX_SEGMENT_SIZE: u64 = 64 * 1024 * 1024;
+
+const SEGMENT_MAGIC: &[u8; 4] = b"BSBK";
+/// Version of the record layout, including the SSZ layout of `BlockHeader` and `FeedActions`.
+/// It has to be bumped whenever any of them changes, so that a store written in an older layout
+/// is rejected on open instead of its records being decoded as garbage.
+pub const SEGMENT_FORMAT_VERSION: u32 = 1;
+// magic | format_version: u32 LE
+const SEGMENT_HEADER_SIZE: usize = 8;
+
+// header_len: u32 | feed_actions_len: u32
+const RECORD_PREFIX_SIZE: usize = 8;
+const RECORD_HASH_SIZE: usize = 32;
+// Guards against allocating huge buffers when reading a corrupted length prefix.
+const MAX_RECORD_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
+
+/// Append-only on-disk block store.
+///
+/// Every segment file in `dir` starts with `| magic | format version: u32 LE |`, followed by one
+/// record per block:
+/// `| header_len: u32 LE | feed_actions_len: u32 LE | header SSZ | feed actions SSZ | header hash |`
+/// A new segment is started once the current one grows above `max_segment_size`. The records
+/// are replayed into an `InMemDb` index on open. A record that was only partially written
+/// (e.g. the process was killed mid-append) can only be at the end of the last segment and is
+/// truncated away, so the store resumes from the last complete block. Complete records that do
+/// not decode and segments of another format version fail the open and are left untouched.
+pub struct DiskDb {
+    dir: PathBuf,
+    max_segment_size: u64,
+    segment: Option<(File, PathBuf, u64)>, // (file, path, size)
+    index: InMemDb,
+}
+
+impl DiskDb {
+    pub fn open(dir: impl AsRef<Path>) -> Result<DiskDb> {
+        Self::open_with_segment_size(dir, DEFAULT_MAX_SEGMENT_SIZE)
+    }
+
+    pub fn open_with_segment_size(dir: impl AsRef<Path>, max_segment_size: u64) -> Result<DiskDb> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Could not create block store dir {}", dir.display()))?;
+
+        let mut db = DiskDb {
+            dir,
+            max_segment_size,
+            segment: None,
+            index: InMemDb::new(),
+        };
+
+        let segments = db.list_segments()?;
+        let segments_count = segments.len();
+        for (i, segment_path) in segments.into_iter().enumerate() {
+            let is_last = i + 1 == segments_count;
+            let valid_len = db.replay_segment(&segment_path, is_last)?;
+            if is_last {
+                let file = OpenOptions::new()
+                    .append(true)
+                    .open(&segment_path)
+                    .with_context(|| format!("Could not open {}", segment_path.display()))?;
+                db.segment = Some((file, segment_path, valid_len));
+            }
+        }
+
+        info!(
+            "Opened block store in {} at block height {}",
+            db.dir.display(),
+            db.index.get_latest_block_height()
+        );
+
+        Ok(db)
+    }
+
+    fn list_segments(&self) -> Result<Vec<PathBuf>> {
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let is_segment = path
+                .extension()
+                .is_some_and(|e| e == SEGMENT_FILE_EXTENSION)
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(SEGMENT_FILE_PREFIX));
+            if is_segment {
+                segments.push(path);
+            }
+        }
+        // Segment names contain the zero padded height of their first block
+        segments.sort();
+        Ok(segments)
+    }
+
+    fn segment_path(&self, first_block_height: u64) -> PathBuf {
+        self.dir.join(format!(
+            "{SEGMENT_FILE_PREFIX}{first_block_height:020}.{SEGMENT_FILE_EXTENSION}"
+        ))
+    }
+
+    /// Loads all records from the segment into the index and returns the length of the valid
+    /// part of the file. An incomplete record at the end is truncated if this is the last
+    /// segment, otherwise it is reported as an error. Records that are complete but fail to
+    /// decode are always an error.
+    fn replay_segment(&mut self, path: &Path, is_last: bool) -> Result<u64> {
+        let data = fs::read(path).with_context(|| format!("Could not read {}", path.display()))?;
+        if is_last && data.len() < SEGMENT_HEADER_SIZE && segment_header().starts_with(&data) {
+            // The segment was created, but writing its header did not complete
+            warn!("Rewriting incomplete header of {}", path.display());
+            let mut file = OpenOptions::new().write(true).truncate(true).open(path)?;
+            file.write_all(&segment_header())?;
+            file.sync_all()?;
+            return Ok(SEGMENT_HEADER_SIZE as u64);
+        }
+        check_segment_header(&data)
+            .with_context(|| format!("Unsupported block store segment {}", path.display()))?;
+
+        let mut offset = SEGMENT_HEADER_SIZE;
+        while offset < data.len() {
+            let record = decode_record(&data[offset..]).with_context(|| {
+                format!(
+                    "Corrupted block record in {} at offset {offset}",
+                    path.display()
+                )
+            })?;
+            let Some((header, feed_actions, record_len)) = record else {
+                if !is_last {
+                    anyhow::bail!(
+                        "Incomplete block record in {} at offset {offset}",
+                        path.display()
+                    );
+                }
+                warn!(
+                    "Dropping incomplete block record in {} at offset {offset} ({} bytes)",
+                    path.display(),
+                    data.len() - offset
+                );
+                let file = OpenOptions::new().write(true).open(path)?;
+                file.set_len(offset as u64)?;
+                file.sync_all()?;
+                break;
+            };
+            self.index.add_block(header, feed_actions)?;
+            offset += record_len;
+        }
+        Ok(offset as u64)
+    }
+
+    fn append_record(&mut self, block_height: u64, record: &[u8]) -> Result<()> {
+        // A segment without records is reused, it would otherwise clash with the next one
+        let needs_new_segment = match &self.segment {
+            Some((_, _, size)) => {
+                *size >= self.max_segment_size && *size > SEGMENT_HEADER_SIZE as u64
+            }
+            None => true,
+        };
+        if needs_new_segment {
+            let path = self.segment_path(block_height);
+            let mut file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("Could not create {}", path.display()))?;
+            file.write_all(&segment_header())
+                .and_then(|_| file.sync_data())
+                .with_context(|| format!("Could not write header of {}", path.display()))?;
+            // Make the new segment file itself durable
+            File::open(&self.dir)?.sync_all()?;
+            self.segment = Some((file, path, SEGMENT_HEADER_SIZE as u64));
+        }
+
+        let Some((file, path, size)) = self.segment.as_mut() else {
+            anyhow::bail!("No block store segment open for writing!");
+        };
+        write_record(file, *size, record)
+            .with_context(|| format!("Could not append block to {}", path.display()))?;
+        *size += record.len() as u64;
+        Ok(())
+    }
+}
+
+/// The operations `write_record` needs from a segment file, so tests can fail writes midway.
+trait SegmentFile: Write {
+    fn set_len(&self, len: u64) -> io::Result<()>;
+    fn sync_data(&self) -> io::Result<()>;
+}
+
+impl SegmentFile for File {
+    fn set_len(&self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+/// Appends `record` to a segment of `size` bytes. A failed write is rolled back, otherwise the
+/// next record would be appended after the torn bytes and dropped by the recovery on open.
+fn write_record(file: &mut impl SegmentFile, size: u64, record: &[u8]) -> Result<()> {
+    let Err(e) = file.write_all(record).and_then(|_| file.sync_data()) else {
+        return Ok(());
+    };
+    file.set_len(size)
+        .with_context(|| format!("Could not roll back failed write ({e})"))?;
+    Err(e.into())
+}
+
+impl BlockStore for DiskDb {
+    fn get_latest_block_height(&self) -> u64 {
+        self.index.get_latest_block_height()
+    }
+
+    fn get_block_header_hash_by_height(&self, block_height: u64) -> Option<HashType> {
+        self.index.get_block_header_hash_by_height(block_height)
+    }
+
+    fn get_block_header_by_hash(&self, header_hash: &HashType) -> Option<&BlockHeader> {
+        self.index.get_block_header_by_hash(header_hash)
+    }
+
+    fn get_feed_actions(&self, add_remove_feeds_merkle_root: &HashType) -> Option<&FeedActions> {
+        self.index.get_feed_actions(add_remove_feeds_merkle_root)
+    }
+
+    fn add_block(&mut self, header: BlockHeader, add_remove_feeds: FeedActions) -> Result<()> {
+        let record = encode_record(&header, &add_remove_feeds)?;
+        // Only blocks that made it to disk become visible
+        self.append_record(header.block_height, &record)?;
+        self.index.add_block(header, add_remove_feeds)
+    }
+}
+
+fn segment_header() -> [u8; SEGMENT_HEADER_SIZE] {
+    let mut header = [0; SEGMENT_HEADER_SIZE];
+    header[..4].copy_from_slice(SEGMENT_MAGIC);
+    header[4..].copy_from_slice(&SEGMENT_FORMAT_VERSION.to_le_bytes());
+    header
+}
+
+fn check_segment_header(data: &[u8]) -> Result<()> {
+    if data.len() < SEGMENT_HEADER_SIZE || data[..4] != SEGMENT_MAGIC[..] {
+        anyhow::bail!("Missing segment header, the store was written by an older version");
+    }
+    let version = u32::from_le_bytes(data[4..SEGMENT_HEADER_SIZE].try_into()?);
+    if version != SEGMENT_FORMAT_VERSION {
+        anyhow::bail!(
+            "Segment format version {version} is not supported, expected {SEGMENT_FORMAT_VERSION}"
+        );
+    }
+    Ok(())
+}
+
+fn encode_record(header: &BlockHeader, feed_actions: &FeedActions) -> Result<Vec<u8>> {
+    let mut header = header.clone();
+    let serialized_header = header.serialize()?;
+    let serialized_feed_actions = feed_actions.clone().serialize()?;
+    let header_hash = node_to_hash(calc_merkle_root(&mut header)?)?;
+
+    let mut record = Vec::with_capacity(
+        RECORD_PREFIX_SIZE
+            + serialized_header.len()
+            + serialized_feed_actions.len()
+            + RECORD_HASH_SIZE,
+    );
+    record.extend_from_slice(&(serialized_header.len() as u32).to_le_bytes());
+    record.extend_from_slice(&(serialized_feed_actions.len() as u32).to_le_bytes());
+    record.extend_from_slice(&serialized_header);
+    record.extend_from_slice(&serialized_feed_actions);
+    record.extend_from_slice(&header_hash);
+    Ok(record)
+}
+
+/// Decodes the record at the start of `data`, returning it together with its length in bytes,
+/// or `None` if `data` ends before the record does.
+fn decode_record(data: &[u8]) -> Result<Option<(BlockHeader, FeedActions, usize)>> {
+    if data.len() < RECORD_PREFIX_SIZE {
+        return Ok(None);
+    }
+    let header_len = u32::from_le_bytes(data[0..4].try_into()?) as usize;
+    let feed_actions_len = u32::from_le_bytes(data[4..8].try_into()?) as usize;
+    if header_len + feed_actions_len > MAX_RECORD_PAYLOAD_SIZE {
+        anyhow::bail!(
+            "Record payload size {} too big",
+            header_len + feed_actions_len
+        );
+    }
+
+    let header_end = RECORD_PREFIX_SIZE + header_len;
+    let feed_actions_end = header_end + feed_actions_len;
+    let record_len = feed_actions_end + RECORD_HASH_SIZE;
+    if data.len() < record_len {
+        return Ok(None);
+    }
+
+    let mut header = BlockHeader::deserialize(&data[RECORD_PREFIX_SIZE..header_end])?;
+    let mut feed_actions = FeedActions::deserialize(&data[header_end..feed_actions_end])?;
+
+    let header_hash = node_to_hash(calc_merkle_root(&mut header)?)?;
+    if header_hash[..] != data[feed_actions_end..record_len] {
+        anyhow::bail!("Block header hash mismatch");
+    }
+    let feed_actions_hash = node_to_hash(calc_merkle_root(&mut feed_actions)?)?;
+    if feed_actions_hash != header.add_remove_feeds_merkle_root {
+        anyhow::bail!("Feed actions do not match the Merkle root in the block header");
+    }
+
+    Ok(Some((header, feed_actions, record_len)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BlockReporterAction, ReporterActionKind};
+    use blocksense_utils::EncodedFeedId;
+
+    fn append_blocks(db: &mut DiskDb, count: u64) {
+        for _ in 0..count {
+            let height = db.get_latest_block_height() + 1;
+            let (header, feed_actions) = db
+                .create_new_block(
+                    1,
+                    height,
+                    vec![],
+                    vec![EncodedFeedId::new(height as u128, 0)],
+                    vec![],
+                )
+                .unwrap();
+            db.add_next_block(header, feed_actions).unwrap();
+        }
+    }
+
+    fn next_record(db: &DiskDb) -> Vec<u8> {
+        let height = db.get_latest_block_height() + 1;
+        let (header, feed_actions) = db
+            .create_new_block(1, height, vec![], vec![], vec![])
+            .unwrap();
+        encode_record(&header, &feed_actions).unwrap()
+    }
+
+    fn current_segment_path(db: &DiskDb) -> PathBuf {
+        db.segment.as_ref().unwrap().1.clone()
+    }
+
+    /// Fails once `remaining` bytes were written, like a full disk.
+    struct FailingFile {
+        file: File,
+        remaining: usize,
+    }
+
+    impl Write for FailingFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                return Err(io::Error::other("No space left on device"));
+            }
+            let len = buf.len().min(self.remaining);
+            self.remaining -= len;
+            self.file.write(&buf[..len])
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.file.flush()
+        }
+    }
+
+    impl SegmentFile for FailingFile {
+        fn set_len(&self, len: u64) -> io::Result<()> {
+            self.file.set_len(len)
+        }
+
+        fn sync_data(&self) -> io::Result<()> {
+            self.file.sync_data()
+        }
+    }
+
+    #[test]
+    fn test_disk_db_resumes_after_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let latest_hash = {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            assert_eq!(db.get_latest_block_height(), 0);
+            append_blocks(&mut db, 3);
+            db.get_latest_block_hash().unwrap()
+        };
+
+        let mut db = DiskDb::open(dir.path()).unwrap();
+        assert_eq!(db.get_latest_block_height(), 3);
+        assert_eq!(db.get_latest_block_hash().unwrap(), latest_hash);
+        let header = db.get_block_header_by_height(3).unwrap();
+        assert!(db
+            .get_feed_actions(&header.add_remove_feeds_merkle_root)
+            .is_some());
+
+        // The chain continues from the persisted tip
+        let (header, feed_actions) = db.create_new_block(1, 4, vec![], vec![], vec![]).unwrap();
+        assert_eq!(header.prev_block_hash, latest_hash);
+        db.add_next_block(header, feed_actions).unwrap();
+        assert_eq!(db.get_latest_block_height(), 4);
+    }
+
+    #[test]
+    fn test_disk_db_drops_torn_write_on_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let (record, segment_path, valid_len) = {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            append_blocks(&mut db, 2);
+            let segment_path = current_segment_path(&db);
+            let valid_len = fs::metadata(&segment_path).unwrap().len();
+            (next_record(&db), segment_path, valid_len)
+        };
+
+        // Simulate the writer being killed at different points of appending the third block
+        let cuts = [
+            1,
+            RECORD_PREFIX_SIZE,
+            RECORD_PREFIX_SIZE + 5,
+            record.len() / 2,
+            record.len() - 1,
+        ];
+        for cut in cuts {
+            let mut file = OpenOptions::new().append(true).open(&segment_path).unwrap();
+            file.write_all(&record[..cut]).unwrap();
+            drop(file);
+
+            let db = DiskDb::open(dir.path()).unwrap();
+            assert_eq!(db.get_latest_block_height(), 2, "cut at {cut}");
+            assert_eq!(fs::metadata(&segment_path).unwrap().len(), valid_len);
+        }
+
+        // After recovery appending works and is persisted
+        {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            append_blocks(&mut db, 1);
+        }
+        let db = DiskDb::open(dir.path()).unwrap();
+        assert_eq!(db.get_latest_block_height(), 3);
+    }
+
+    #[test]
+    fn test_disk_db_keeps_blocks_appended_after_failed_write() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            append_blocks(&mut db, 2);
+            let segment_path = current_segment_path(&db);
+            let valid_len = fs::metadata(&segment_path).unwrap().len();
+
+            let record = next_record(&db);
+            let mut file = FailingFile {
+                file: OpenOptions::new().append(true).open(&segment_path).unwrap(),
+                remaining: record.len() / 2,
+            };
+            assert!(write_record(&mut file, valid_len, &record).is_err());
+            assert_eq!(fs::metadata(&segment_path).unwrap().len(), valid_len);
+
+            // The write is retried with the next block
+            append_blocks(&mut db, 1);
+        }
+
+        let db = DiskDb::open(dir.path()).unwrap();
+        assert_eq!(db.get_latest_block_height(), 3);
+    }
+
+    #[test]
+    fn test_disk_db_rejects_corrupted_tail_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let segment_path = {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            append_blocks(&mut db, 3);
+            current_segment_path(&db)
+        };
+
+        // Flip a bit in the header hash of the last record
+        let mut data = fs::read(&segment_path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0x01;
+        fs::write(&segment_path, &data).unwrap();
+
+        // The record is complete, so it is not mistaken for a torn write and kept as is
+        assert!(DiskDb::open(dir.path()).is_err());
+        assert_eq!(fs::read(&segment_path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_disk_db_rejects_other_format_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let segment_path = {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            append_blocks(&mut db, 2);
+            current_segment_path(&db)
+        };
+
+        let mut data = fs::read(&segment_path).unwrap();
+        data[4..SEGMENT_HEADER_SIZE].copy_from_slice(&(SEGMENT_FORMAT_VERSION + 1).to_le_bytes());
+        fs::write(&segment_path, &data).unwrap();
+        assert!(DiskDb::open(dir.path()).is_err());
+        assert_eq!(fs::read(&segment_path).unwrap(), data);
+
+        // Stores from before the segment header are rejected as well
+        fs::write(&segment_path, &data[SEGMENT_HEADER_SIZE..]).unwrap();
+        assert!(DiskDb::open(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_disk_db_recovers_torn_segment_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let torn_segment = {
+            let mut db = DiskDb::open_with_segment_size(dir.path(), 1).unwrap();
+            append_blocks(&mut db, 1);
+            db.segment_path(2)
+        };
+        fs::write(&torn_segment, &segment_header()[..3]).unwrap();
+
+        {
+            let mut db = DiskDb::open_with_segment_size(dir.path(), 1).unwrap();
+            assert_eq!(db.get_latest_block_height(), 1);
+            // The repaired segment is reused for the next block
+            append_blocks(&mut db, 2);
+            assert_eq!(db.list_segments().unwrap().len(), 3);
+        }
+
+        let db = DiskDb::open_with_segment_size(dir.path(), 1).unwrap();
+        assert_eq!(db.get_latest_block_height(), 3);
+    }
+
+    #[test]
+    fn test_disk_db_rotates_segments() {
+        let dir = tempfile::tempdir().unwrap();
+        {
+            // Every record exceeds the limit, so each block gets its own segment
+            let mut db = DiskDb::open_with_segment_size(dir.path(), 1).unwrap();
+            append_blocks(&mut db, 3);
+            assert_eq!(db.list_segments().unwrap().len(), 3);
+        }
+
+        let db = DiskDb::open_with_segment_size(dir.path(), 1).unwrap();
+        assert_eq!(db.get_latest_block_height(), 3);
+        for height in 1..=3 {
+            assert!(db.get_block_header_by_height(height).is_some());
+        }
+    }
+
+    #[test]
+    fn test_disk_db_fails_on_corruption_before_last_segment() {
+        let dir = tempfile::tempdir().unwrap();
+        let first_segment = {
+            let mut db = DiskDb::open_with_segment_size(dir.path(), 1).unwrap();
+            append_blocks(&mut db, 2);
+            db.list_segments().unwrap()[0].clone()
+        };
+
+        let data = fs::read(&first_segment).unwrap();
+        fs::write(&first_segment, &data[..data.len() - 1]).unwrap();
+
+        assert!(DiskDb::open_with_segment_size(dir.path(), 1).is_err());
+    }
+
+    #[test]
+    fn test_disk_db_persists_reporter_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let action = BlockReporterAction {
+            action: ReporterActionKind::Suspend as u8,
+            reporter_id: 7,
+            ..Default::default()
+        };
+        {
+            let mut db = DiskDb::open(dir.path()).unwrap();
+            let (header, feed_actions) = db
+                .create_new_block(1, 1, vec![], vec![], vec![action.clone()])
+                .unwrap();
+            db.add_next_block(header, feed_actions).unwrap();
+        }
+
+        let db = DiskDb::open(dir.path()).unwrap();
+        let header = db.get_block_header_by_height(1).unwrap();
+        let feed_actions = db
+            .get_feed_actions(&header.add_remove_feeds_merkle_root)
+            .unwrap();
+        assert_eq!(feed_actions.reporter_actions[0], Some(action));
+        assert!(feed_actions.reporter_actions[1..]
+            .iter()
+            .all(Option::is_none));
+    }
+}
@@ -1,12 +1,16 @@
+pub mod block_store;
+pub mod disk_db;
 pub mod in_mem_db;
 
 use anyhow::Result;
 use blocksense_utils::{EncodedFeedId, FeedId};
+use hex::FromHex;
+use hex_literal::hex;
 use ssz_rs::prelude::*;
 
 pub const DATA_CHUNK_SIZE: usize = 32;
 pub const KEY_CHUNK_SIZE: usize = 32;
-type HashType = [u8; DATA_CHUNK_SIZE];
+pub type HashType = [u8; DATA_CHUNK_SIZE];
 pub type FeedIdChunk = [u8; KEY_CHUNK_SIZE];
 pub type DataChunk = [u8; DATA_CHUNK_SIZE];
 pub type Resources = [Option<DataChunk>; DATA_CHUNK_SIZE];
@@ -36,6 +40,49 @@ pub struct BlockFeedConfig {
     pub value_type: DataChunk,
     pub aggregate_type: DataChunk,
     pub stride: u8,
+    pub trim_percentage: Option<[u8; 8]>,
+    pub twap_window_ms: Option<u64>,
+    pub anomaly_score_threshold: Option<[u8; 8]>,
+    pub history_depth: Option<u64>,
+}
+
+pub const BLS_PUBLIC_KEY_SIZE: usize = 48;
+pub const ADDRESS_SIZE: usize = 20;
+
+/// Kind of change to the reporter set, stored as `BlockReporterAction::action`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[repr(u8)]
+pub enum ReporterActionKind {
+    Add = 1,
+    Remove = 2,
+    Suspend = 3,
+    Resume = 4,
+    Rotate = 5,
+}
+
+impl TryFrom<u8> for ReporterActionKind {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u8) -> Result<Self> {
+        Ok(match value {
+            1 => ReporterActionKind::Add,
+            2 => ReporterActionKind::Remove,
+            3 => ReporterActionKind::Suspend,
+            4 => ReporterActionKind::Resume,
+            5 => ReporterActionKind::Rotate,
+            _ => anyhow::bail!("Unknown reporter action kind {value}"),
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, SimpleSerialize, Default, Clone)]
+pub struct BlockReporterAction {
+    pub action: u8,
+    pub reporter_id: u64,
+    // The key and address are only set for add and rotate, the stake only for add.
+    pub pub_key: [DataChunk; 2], // BLS public key of BLS_PUBLIC_KEY_SIZE bytes, zero padded.
+    pub address: [u8; ADDRESS_SIZE],
+    pub stake: [u8; 8],
 }
 
 #[derive(Debug, PartialEq, SimpleSerialize, Default, Clone)]
@@ -73,6 +120,7 @@ pub struct FeedActions {
     pub block_height: u64,
     pub new_feeds: [Option<BlockFeedConfig>; MAX_NEW_FEEDS_IN_BLOCK],
     pub feed_ids_to_rm: [Option<EncodedFeedId>; MAX_FEED_ID_TO_DELETE_IN_BLOCK],
+    pub reporter_actions: [Option<BlockReporterAction>; MAX_REPORTER_ACTIONS_IN_BLOCK],
 }
 
 impl FeedActions {
@@ -92,3 +140,18 @@ pub const MAX_ASSET_FEED_UPDATES_IN_BLOCK: usize =
 
 pub const MAX_NEW_FEEDS_IN_BLOCK: usize = 32;
 pub const MAX_FEED_ID_TO_DELETE_IN_BLOCK: usize = 32;
+pub const MAX_REPORTER_ACTIONS_IN_BLOCK: usize = 32;
+
+pub const GENESIS_HASH: HashType =
+    hex!("ec59d3d7860eadc9207b6ccf7c897b23b6b8e82d3d4b80212dfebc15a6b16b17");
+
+pub fn node_to_hash(node: Node) -> Result<HashType> {
+    let hex = node.to_string();
+    let result = <HashType>::from_hex(hex.strip_prefix("0x").unwrap_or(&hex))?;
+    Ok(result)
+}
+
+pub fn calc_merkle_root(obj: &mut impl SimpleSerialize) -> Result<Node> {
+    let result = obj.hash_tree_root()?;
+    Ok(result)
+}
@@ -1,6 +1,7 @@
 use blocksense_registry::config::{
     CompatibilityInfo, FeedConfig, FeedQuorum, FeedSchedule, PriceFeedInfo,
 };
+use blocksense_secrets_config::signer::SignerConfig;
 use blocksense_utils::constants::{
     FEEDS_CONFIG_DIR, FEEDS_CONFIG_FILE, SEQUENCER_CONFIG_DIR, SEQUENCER_CONFIG_FILE,
 };
@@ -69,6 +70,38 @@ impl Validated for FeedConfig {
         );
         }
 
+        if let Some(params) = &self.quorum.aggregation_params {
+            if let Err(e) = params.validate() {
+                anyhow::bail!(
+                    "{}: aggregation_params for feed {} with id {} are invalid: {}",
+                    context,
+                    self.full_name,
+                    self.id,
+                    e
+                );
+            }
+        }
+
+        if let Some(threshold) = self.schedule.anomaly_score_threshold {
+            if !(0.0..=1.0).contains(&threshold) {
+                anyhow::bail!(
+                    "{}: anomaly_score_threshold for feed {} with id {} must be between 0 and 1",
+                    context,
+                    self.full_name,
+                    self.id
+                );
+            }
+        }
+
+        if self.history_depth == Some(0) {
+            anyhow::bail!(
+                "{}: history_depth for feed {} with id {} cannot be set to 0",
+                context,
+                self.full_name,
+                self.id
+            );
+        }
+
         if self.schedule.deviation_percentage > 0.0f32 {
             info!(
                 "{}: Skipping updates in feed {} with id {} that deviate less then {} %",
@@ -165,6 +198,10 @@ pub struct PublishCriteria {
     pub peg_to_value: Option<f64>,
     #[serde(default)]
     pub peg_tolerance_percentage: f64,
+    /// Market of the feed, see `PriceFeedInfo::market_hours`. The heartbeat is not due while
+    /// the market is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub market_hours: Option<String>,
 }
 
 impl PublishCriteria {
@@ -199,8 +236,25 @@ pub struct ContractConfig {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 // #[serde(rename_all = "PascalCase")]
 pub struct Provider {
+    /// Hex encoded private key in plain text, only used if `signer` is not set
+    #[serde(default)]
     pub private_key_path: String,
+    /// Where the key that signs the transactions of the network is kept
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signer: Option<SignerConfig>,
     pub url: String,
+    /// RPC endpoints to fail over to when `url` stops answering, in the order of preference.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fallback_urls: Vec<String>,
+    /// Consecutive failed calls after which the next healthy endpoint is used
+    #[serde(default = "default_rpc_failover_after_failures")]
+    pub rpc_failover_after_failures: u32,
+    /// Number of endpoints that have to agree on the nonce and the chain id. With the default of
+    /// 1 they are read only from the active endpoint.
+    #[serde(default = "default_rpc_read_quorum")]
+    pub rpc_read_quorum: usize,
     pub transaction_retries_count_limit: u32,
     pub transaction_retry_timeout_secs: u32,
     pub retry_fee_increment_fraction: f64,
@@ -221,6 +275,11 @@ pub struct Provider {
     #[serde(default = "default_is_enabled")]
     pub should_load_rb_indices: bool,
 
+    /// Chain id the RPC of the network is expected to report, checked by the readiness probe.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(deserialize_with = "deserialize_optional_encoded_feed_id_vec")]
@@ -237,6 +296,14 @@ fn default_is_enabled() -> bool {
     true
 }
 
+fn default_rpc_failover_after_failures() -> u32 {
+    3
+}
+
+fn default_rpc_read_quorum() -> usize {
+    1
+}
+
 fn default_receipt_polling_back_off_period_ms() -> u64 {
     // Default 5 seconds (as milliseconds)
     5_000
@@ -300,8 +367,42 @@ where
     Ok(Some(out))
 }
 
+impl Provider {
+    /// All RPC endpoints of the network, starting with the preferred one.
+    pub fn urls(&self) -> Vec<&str> {
+        std::iter::once(self.url.as_str())
+            .chain(self.fallback_urls.iter().map(String::as_str))
+            .collect()
+    }
+
+    pub fn signer_config(&self) -> SignerConfig {
+        self.signer
+            .clone()
+            .unwrap_or_else(|| SignerConfig::PrivateKeyFile {
+                path: self.private_key_path.clone().into(),
+            })
+    }
+}
+
 impl Validated for Provider {
     fn validate(&self, context: &str) -> anyhow::Result<()> {
+        if self.signer.is_none() && self.private_key_path.is_empty() {
+            anyhow::bail!("{}: either signer or private_key_path must be set", context);
+        }
+        if self.rpc_failover_after_failures == 0 {
+            anyhow::bail!(
+                "{}: rpc_failover_after_failures cannot be set to 0",
+                context
+            );
+        }
+        let endpoints = self.urls().len();
+        if self.rpc_read_quorum == 0 || self.rpc_read_quorum > endpoints {
+            anyhow::bail!(
+                "{}: rpc_read_quorum must be between 1 and the {} RPC endpoints",
+                context,
+                endpoints
+            );
+        }
         if self.transaction_retry_timeout_secs == 0 {
             anyhow::bail!(
                 "{}: transaction_retry_timeout_secs cannot be set to 0",
@@ -342,6 +443,13 @@ pub struct Reporter {
     pub id: u32,
     pub pub_key: String,
     pub address: String,
+    /// Weight of the reporter's values in stake weighted aggregations
+    #[serde(default = "default_reporter_stake")]
+    pub stake: f64,
+}
+
+fn default_reporter_stake() -> f64 {
+    1.0
 }
 
 impl Validated for Reporter {
@@ -353,16 +461,50 @@ impl Validated for Reporter {
                 e
             );
         }
+        if !self.stake.is_finite() || self.stake < 0.0 {
+            anyhow::bail!(
+                "Stake of reporter id {} must be a non-negative number, got {}",
+                self.id,
+                self.stake
+            );
+        }
         Ok(())
     }
 }
 
+/// Request of a node operator to join the reporter set. `signature` is a BLS signature and
+/// `address_signature` an EIP-191 secp256k1 signature of `signing_message`, proving that the
+/// node operator holds the keys of `pub_key` and `address`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReporterRegistration {
+    pub reporter: Reporter,
+    pub signature: String,
+    pub address_signature: String,
+}
+
+impl ReporterRegistration {
+    pub fn signing_message(reporter: &Reporter) -> Vec<u8> {
+        format!(
+            "Blocksense reporter registration:{}:{}:{}:{}",
+            reporter.id,
+            reporter.pub_key,
+            reporter.address.to_lowercase(),
+            reporter.stake
+        )
+        .into_bytes()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct BlockConfig {
     pub max_feed_updates_to_batch: usize,
     pub block_generation_period: u64,
     pub genesis_block_timestamp_ms: Option<u128>,
     pub aggregation_consensus_discard_period_blocks: u64,
+    /// Directory of the on-disk block store. If not set blocks are kept only in memory and
+    /// the chain starts from genesis on every restart.
+    #[serde(default)]
+    pub block_store_path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -378,6 +520,61 @@ pub struct PyroscopeConfig {
     pub sample_rate: u32,
 }
 
+/// Roles of the admin API, each allowed everything the previous ones are.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    ReadOnly,
+    Operator,
+    Deployer,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdminToken {
+    /// Who uses the token, as recorded in the audit log
+    pub name: String,
+    /// File containing the bearer token
+    pub token_path: String,
+    pub role: AdminRole,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AdminAuthConfig {
+    /// Bearer tokens accepted by the admin API. The sequencer does not start without any, unless
+    /// `allow_unauthenticated` is set.
+    #[serde(default)]
+    pub tokens: Vec<AdminToken>,
+    /// Opens the admin API to everyone who can reach `admin_port` if no tokens are configured.
+    /// Only meant for local and test setups.
+    #[serde(default)]
+    pub allow_unauthenticated: bool,
+    /// File the state-changing admin calls are appended to as JSON lines. They are only logged
+    /// if not set.
+    #[serde(default)]
+    pub audit_log_path: Option<String>,
+}
+
+impl Validated for AdminAuthConfig {
+    fn validate(&self, context: &str) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+        for token in &self.tokens {
+            if token.name.is_empty() {
+                anyhow::bail!("{}: admin token name cannot be empty", context);
+            }
+            if !names.insert(token.name.as_str()) {
+                anyhow::bail!("{}: admin token {} is not unique", context, token.name);
+            }
+        }
+        if self.tokens.is_empty() && !self.allow_unauthenticated {
+            anyhow::bail!(
+                "{}: no admin tokens configured, set allow_unauthenticated to open the admin API to everyone",
+                context
+            );
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct SequencerConfig {
     pub sequencer_id: u64,
@@ -392,6 +589,17 @@ pub struct SequencerConfig {
     pub pyroscope_config: Option<PyroscopeConfig>,
     #[serde(default = "default_is_enabled")]
     pub send_aggregated_updates_to_publishers: bool,
+    /// Upper bound in MB for the memory of the histories of all feeds. Checked at startup and
+    /// when the history depth of a feed changes; not enforced if not set.
+    #[serde(default)]
+    pub history_memory_budget_mb: Option<u64>,
+    /// JSON file of the oracle script catalog managed through the admin API. Defaults to
+    /// `oracle_scripts.json` in `block_store_path`; without either the catalog is kept only in
+    /// memory. A missing catalog starts with the oracle scripts built into the sequencer.
+    #[serde(default)]
+    pub oracle_scripts_path: Option<String>,
+    #[serde(default)]
+    pub admin_auth: AdminAuthConfig,
 }
 
 impl Validated for SequencerConfig {
@@ -413,11 +621,26 @@ impl Validated for SequencerConfig {
             reporter.validate(format!("{}: Reporter id: {}", context, reporter.id).as_str())?
         }
 
+        self.admin_auth
+            .validate(format!("{}: admin_auth", context).as_str())?;
+
         Ok(())
     }
 }
 
 impl SequencerConfig {
+    /// Where the oracle script catalog is saved, see `oracle_scripts_path`.
+    pub fn oracle_scripts_catalog_path(&self) -> Option<String> {
+        self.oracle_scripts_path.clone().or_else(|| {
+            self.block_config.block_store_path.as_ref().map(|dir| {
+                Path::new(dir)
+                    .join("oracle_scripts.json")
+                    .display()
+                    .to_string()
+            })
+        })
+    }
+
     pub fn should_load_rb_indices(&self, network: &str) -> bool {
         if let Some(p) = self.providers.get(network) {
             p.should_load_rb_indices
@@ -486,6 +709,7 @@ pub fn test_feed_config(id: FeedId, stride: u8) -> FeedConfig {
         quorum: FeedQuorum {
             percentage: 100.0,
             aggregation: "median".to_owned(),
+            aggregation_params: None,
         },
         schedule: FeedSchedule {
             interval_ms: 90000,
@@ -495,7 +719,9 @@ pub fn test_feed_config(id: FeedId, stride: u8) -> FeedConfig {
                 .duration_since(UNIX_EPOCH)
                 .unwrap()
                 .as_millis() as u64,
+            anomaly_score_threshold: None,
         },
+        history_depth: None,
         additional_feed_info: PriceFeedInfo {
             pair: Some(blocksense_registry::config::AssetPair {
                 base: "FOXY".to_owned(),
@@ -556,6 +782,7 @@ pub fn get_test_config_with_no_providers() -> SequencerConfig {
             block_generation_period: 500,
             genesis_block_timestamp_ms: None,
             aggregation_consensus_discard_period_blocks: 100,
+            block_store_path: None,
         },
         providers: HashMap::new(),
         reporters: Vec::new(),
@@ -563,6 +790,12 @@ pub fn get_test_config_with_no_providers() -> SequencerConfig {
         http_input_buffer_size: None,
         pyroscope_config: None,
         send_aggregated_updates_to_publishers: false,
+        history_memory_budget_mb: None,
+        oracle_scripts_path: None,
+        admin_auth: AdminAuthConfig {
+            allow_unauthenticated: true,
+            ..Default::default()
+        },
     }
 }
 
@@ -578,7 +811,11 @@ pub fn get_test_config_with_multiple_providers(
                     .to_str()
                     .expect("Error in private_key_path: ")
                     .to_string(),
+                signer: None,
                 url: url.to_string(),
+                fallback_urls: vec![],
+                rpc_failover_after_failures: default_rpc_failover_after_failures(),
+                rpc_read_quorum: default_rpc_read_quorum(),
                 transaction_retries_count_limit: 10,
                 transaction_retry_timeout_secs: 24,
                 retry_fee_increment_fraction: 0.1,
@@ -587,6 +824,7 @@ pub fn get_test_config_with_multiple_providers(
                 transaction_retry_back_off_ms: default_transaction_retry_back_off_ms(),
                 is_enabled: true,
                 should_load_rb_indices: false,
+                chain_id: None,
                 allow_feeds: None,
                 publishing_criteria: vec![],
                 impersonated_anvil_account: None,
@@ -651,6 +889,38 @@ mod tests {
         assert!(invalid_config_3.validate("").is_err());
     }
 
+    #[test]
+    fn oracle_scripts_catalog_is_kept_next_to_the_block_store() {
+        let mut sequencer_config = get_test_config_with_no_providers();
+        assert_eq!(sequencer_config.oracle_scripts_catalog_path(), None);
+
+        sequencer_config.block_config.block_store_path = Some("/var/lib/sequencer".to_string());
+        assert_eq!(
+            sequencer_config.oracle_scripts_catalog_path().as_deref(),
+            Some("/var/lib/sequencer/oracle_scripts.json")
+        );
+
+        sequencer_config.oracle_scripts_path = Some("/etc/oracle_scripts.json".to_string());
+        assert_eq!(
+            sequencer_config.oracle_scripts_catalog_path().as_deref(),
+            Some("/etc/oracle_scripts.json")
+        );
+    }
+
+    #[test]
+    fn sequencer_config_without_admin_tokens_fails_validation() {
+        let mut sequencer_config = get_test_config_with_no_providers();
+        sequencer_config.admin_auth.allow_unauthenticated = false;
+        assert!(sequencer_config.validate("").is_err());
+
+        sequencer_config.admin_auth.tokens.push(AdminToken {
+            name: "ops".to_string(),
+            token_path: "/run/secrets/ops".to_string(),
+            role: AdminRole::Operator,
+        });
+        assert!(sequencer_config.validate("").is_ok());
+    }
+
     #[test]
     fn parsing_provider_config_missing_publish_criteria() {
         let provider_a: Provider = serde_json::from_str(
@@ -950,6 +1220,40 @@ mod tests {
         assert_eq!(p.allow_feeds, None);
     }
 
+    #[test]
+    fn provider_signer_falls_back_to_private_key_path() {
+        let json = r#"
+        {
+            "signer": {"type": "keystore", "path": "/run/keys/eth.json"},
+            "url": "http://127.0.0.1:8546",
+            "transaction_retries_count_limit": 42,
+            "transaction_retry_timeout_secs": 20,
+            "retry_fee_increment_fraction": 0.1,
+            "transaction_gas_limit": 7500000
+        }
+        "#;
+
+        let mut p: Provider = serde_json::from_str(json).unwrap();
+        assert!(p.validate("ETH1").is_ok());
+        assert_eq!(
+            p.signer_config(),
+            SignerConfig::Keystore {
+                path: "/run/keys/eth.json".into(),
+                password_env: None,
+            }
+        );
+
+        p.signer = None;
+        assert!(p.validate("ETH1").is_err());
+        p.private_key_path = "/tmp/priv_key_test".to_string();
+        assert_eq!(
+            p.signer_config(),
+            SignerConfig::PrivateKeyFile {
+                path: "/tmp/priv_key_test".into()
+            }
+        );
+    }
+
     #[test]
     fn provider_allow_feeds_missing_is_none() {
         // Field omitted entirely should default to None
@@ -1065,4 +1369,22 @@ mod tests {
         assert_eq!(config.feeds[0].id, 0_u128);
         assert_eq!(config.feeds[0].stride, 0);
     }
+
+    #[test]
+    fn feed_config_with_invalid_aggregation_params_fails_validation() {
+        let mut feed = test_feed_config(1, 0);
+        feed.quorum.aggregation = "trimmed_mean".to_owned();
+        feed.quorum.aggregation_params = Some(blocksense_registry::config::AggregationParams {
+            trim_percentage: Some(25.0),
+            twap_window_ms: None,
+        });
+        assert!(feed.validate("").is_ok());
+
+        feed.quorum.aggregation_params = Some(blocksense_registry::config::AggregationParams {
+            trim_percentage: Some(50.0),
+            twap_window_ms: None,
+        });
+        let config = AllFeedsConfig { feeds: vec![feed] };
+        assert!(config.validate("").is_err());
+    }
 }
@@ -4,6 +4,7 @@ use blocksense_feed_registry::{
     registry::FeedAggregateHistory,
     types::{DataFeedPayload, FeedType, Timestamp},
 };
+use blocksense_market_calendar::MarketCalendar;
 use blocksense_utils::{from_hex_string, EncodedFeedId, FeedId, Stride};
 use log::error;
 use serde::Deserialize;
@@ -69,6 +70,8 @@ pub enum DontSkipReason {
 pub enum DoSkipReason {
     TooSimilarTooSoon, // threshold not crossed and heartbeat not timed out
     NothingToPost,
+    AggregationFailed, // the reported values could not be aggregated, e.g. NaN or mixed types
+    AnomalyDetected,   // anomaly score above the feed's threshold
 }
 
 #[derive(Debug, PartialEq)]
@@ -155,20 +158,27 @@ impl VotedFeedUpdate {
         history: &FeedAggregateHistory,
         caller_context: &str,
     ) -> SkipDecision {
-        if let FeedType::Numerical(candidate_value) = self.value {
+        if let Some(candidate_value) = self.value.as_f64() {
             let feed_id = self.encoded_feed_id;
             let res = match history.last(feed_id) {
-                Some(last_published) => match last_published.value {
-                    FeedType::Numerical(last) => {
+                Some(last_published) => match last_published.value.as_f64() {
+                    Some(last) => {
                         // Note: a price can be negative,
                         // e.g. there have been cases for electricity and crude oil prices
                         // This is why we take absolute value
                         let a = f64::abs(last);
                         let diff = f64::abs(last - candidate_value);
+                        // A feed does not go stale while its market is closed
+                        let is_market_open = criteria
+                            .market_hours
+                            .as_deref()
+                            .and_then(MarketCalendar::from_market_hours)
+                            .is_none_or(|calendar| calendar.is_open_at_ms(self.end_slot_timestamp));
                         let has_heartbeat_timed_out = match criteria.always_publish_heartbeat_ms {
                             Some(heartbeat) => {
-                                self.end_slot_timestamp
-                                    >= heartbeat + last_published.end_slot_timestamp
+                                is_market_open
+                                    && self.end_slot_timestamp
+                                        >= heartbeat + last_published.end_slot_timestamp
                             }
                             None => false,
                         };
@@ -182,7 +192,7 @@ impl VotedFeedUpdate {
                             SkipDecision::DoSkip(DoSkipReason::TooSimilarTooSoon)
                         }
                     }
-                    _ => {
+                    None => {
                         error!("History for numerical feed with id {feed_id} contains a non-numerical update {:?}.", last_published.value);
                         SkipDecision::DontSkip(DontSkipReason::HistoryError)
                     }
@@ -271,6 +281,88 @@ mod tests {
         assert_eq!(value.to_string(), reversed.parse_to_string());
     }
 
+    #[test]
+    fn naive_packing_decimal_round_trip() {
+        use alloy_primitives::I256;
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..1000 {
+            let mantissa = I256::try_from(rng.gen::<i128>()).unwrap();
+            let scale = rng.gen_range(0..=18u8);
+            let timestamp = rng.gen::<u64>();
+            let value = FeedType::Decimal { mantissa, scale };
+
+            let bytes = naive_packing(&value, 18, timestamp).unwrap();
+
+            // On chain layout: int192 value with 18 decimals followed by the timestamp
+            let on_chain = mantissa * I256::try_from(10u64.pow(18 - scale as u32)).unwrap();
+            assert_eq!(bytes.len(), 32);
+            assert_eq!(bytes[..24], on_chain.to_be_bytes::<32>()[8..]);
+            assert_eq!(bytes[24..], timestamp.to_be_bytes());
+
+            let reversed = FeedType::from_bytes(bytes, value.clone(), 18).unwrap();
+            assert_eq!(
+                reversed,
+                FeedType::Decimal {
+                    mantissa: on_chain,
+                    scale: 18
+                }
+            );
+            assert_eq!(
+                FeedType::decimal_from_str(&value.parse_to_string()).unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn naive_packing_decimal_limits() {
+        let decimal = |value: &str| FeedType::decimal_from_str(value).unwrap();
+
+        let bytes = naive_packing(&decimal("-1e-18"), 18, 0).unwrap();
+        assert_eq!(bytes[..24], [0xff; 24]);
+
+        // Values that do not fit in f64 or in an unsigned number
+        for (value, expected) in [
+            ("1e21", "1000000000000000000000.000000000000000000"),
+            ("-0.5", "-0.500000000000000000"),
+            (
+                "-123456789012345678901234567.123456789",
+                "-123456789012345678901234567.123456789000000000",
+            ),
+        ] {
+            let bytes = naive_packing(&decimal(value), 18, 0).unwrap();
+            let reversed = FeedType::from_bytes(bytes, decimal("0"), 18).unwrap();
+            assert_eq!(reversed.parse_to_string(), expected);
+        }
+
+        // int192 is between -2^191 and 2^191 - 1
+        let max = "3138550867693340381917894711603833208051177722232017256447";
+        let min = "-3138550867693340381917894711603833208051177722232017256448";
+        for value in [max, min] {
+            let bytes = naive_packing(&decimal(value), 0, 0).unwrap();
+            assert_eq!(
+                FeedType::from_bytes(bytes, decimal("0"), 0).unwrap(),
+                decimal(value)
+            );
+        }
+        assert!(naive_packing(&decimal(&format!("{max}1")), 0, 0).is_err());
+        assert!(naive_packing(
+            &decimal("3138550867693340381917894711603833208051177722232017256448"),
+            0,
+            0
+        )
+        .is_err());
+        assert!(naive_packing(
+            &decimal("-3138550867693340381917894711603833208051177722232017256449"),
+            0,
+            0
+        )
+        .is_err());
+        assert!(naive_packing(&decimal(max), 1, 0).is_err());
+    }
+
     #[test]
     fn naive_packing_string_value() {
         let value = "blocksense"; // size is 10
@@ -340,6 +432,7 @@ mod tests {
             always_publish_heartbeat_ms: None,
             peg_to_value: None,
             peg_tolerance_percentage: 0.0f64,
+            market_hours: None,
         };
 
         let one_percent_threshold = PublishCriteria {
@@ -348,6 +441,7 @@ mod tests {
             always_publish_heartbeat_ms: None,
             peg_to_value: None,
             peg_tolerance_percentage: 0.0f64,
+            market_hours: None,
         };
 
         let always_publish_every_second = PublishCriteria {
@@ -356,6 +450,7 @@ mod tests {
             always_publish_heartbeat_ms: Some(1000_u128),
             peg_to_value: None,
             peg_tolerance_percentage: 0.0f64,
+            market_hours: None,
         };
 
         // No history
@@ -453,6 +548,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn heartbeat_is_not_due_while_market_is_closed() {
+        let saturday_noon_utc = 1_746_878_400_000_u128; // 10 May 2025
+        let monday_11_am_et = 1_747_062_000_000_u128; // 12 May 2025
+        let encoded_feed_id = EncodedFeedId::new(56, 0);
+        let mut history = FeedAggregateHistory::new();
+        history.register_feed(encoded_feed_id, 100);
+        history.push_next(
+            encoded_feed_id,
+            FeedType::Numerical(100.0f64),
+            saturday_noon_utc - 3_600_000,
+        );
+        let criteria = PublishCriteria {
+            encoded_feed_id,
+            skip_publish_if_less_then_percentage: 1.0f64,
+            always_publish_heartbeat_ms: Some(1000_u128),
+            peg_to_value: None,
+            peg_tolerance_percentage: 0.0f64,
+            market_hours: Some("US_Equities".to_string()),
+        };
+        let update_at = |end_slot_timestamp| VotedFeedUpdate {
+            encoded_feed_id,
+            value: FeedType::Numerical(100.0),
+            end_slot_timestamp,
+        };
+
+        assert_eq!(
+            update_at(saturday_noon_utc).should_skip(&criteria, &history, "test"),
+            SkipDecision::DoSkip(DoSkipReason::TooSimilarTooSoon)
+        );
+        assert_eq!(
+            update_at(monday_11_am_et).should_skip(&criteria, &history, "test"),
+            SkipDecision::DontSkip(DontSkipReason::HeartbeatTimedOut)
+        );
+
+        let crypto_criteria = PublishCriteria {
+            market_hours: Some("Crypto".to_string()),
+            ..criteria
+        };
+        assert_eq!(
+            update_at(saturday_noon_utc).should_skip(&crypto_criteria, &history, "test"),
+            SkipDecision::DontSkip(DontSkipReason::HeartbeatTimedOut)
+        );
+    }
+
     #[test]
     fn test_voted_feed_update() {
         let end_slot_timestamp = SystemTime::now()
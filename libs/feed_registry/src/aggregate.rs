@@ -3,37 +3,351 @@ use std::{
     fmt::{Debug, Display},
 };
 
+use alloy_primitives::{I256, U256};
+use blocksense_registry::config::FeedQuorum;
 use num::Integer;
+use thiserror::Error;
 use tracing::{error, info_span};
 
-use super::types::FeedType;
+use super::types::{rescale_decimal, FeedType, Timestamp, MAX_DECIMAL_SCALE};
+
+pub const DEFAULT_TRIM_PERCENTAGE: f64 = 10.0;
+pub const DEFAULT_TWAP_WINDOW_MS: u64 = 60 * 60 * 1000;
 
 #[derive(Clone, Copy)]
 pub enum FeedAggregate {
     AverageAggregator,
     MedianAggregator,
     MajorityVoteAggregator,
+    // Drops `trim_percentage` % of the lowest and of the highest values and averages the rest
+    TrimmedMeanAggregator { trim_percentage: f64 },
+    // Median where every value is weighted by the stake of the reporter that sent it
+    StakeWeightedMedianAggregator,
+    // Time weighted average of the published values in the last `window_ms` and the median of
+    // the current values
+    TwapAggregator { window_ms: u64 },
+}
+
+/// Data besides the reported values needed by some of the aggregators.
+#[derive(Debug, Default, Clone)]
+pub struct AggregationContext {
+    /// Stake of the reporter of each value, in the same order as the values.
+    pub weights: Option<Vec<f64>>,
+    /// Published numerical values of the feed as `(end_slot_timestamp, value)`, oldest first.
+    pub history: Vec<(Timestamp, f64)>,
+    /// End of the slot for which the values are aggregated.
+    pub end_slot_timestamp: Timestamp,
+}
+
+#[derive(PartialEq, Debug, Error, Clone)]
+pub enum AggregationError {
+    #[error("No values to aggregate")]
+    EmptyInput,
+    #[error("Value {0} is not finite")]
+    NonFiniteValue(f64),
+    #[error("Mixing {0} and {1} values is not allowed")]
+    MixedTypes(String, String),
+    #[error("{0} values cannot be aggregated with {1}")]
+    UnsupportedType(String, String),
+    #[error("Decimal values overflow")]
+    DecimalOverflow,
+}
+
+fn numerical_values(
+    values: &[FeedType],
+    aggregator: &FeedAggregate,
+) -> Result<Vec<f64>, AggregationError> {
+    if values.is_empty() {
+        return Err(AggregationError::EmptyInput);
+    }
+    let mut filtered = Vec::with_capacity(values.len());
+    for value in values {
+        match value {
+            FeedType::Numerical(x) if x.is_finite() => filtered.push(*x),
+            FeedType::Numerical(x) => return Err(AggregationError::NonFiniteValue(*x)),
+            FeedType::Decimal { .. } => {
+                return Err(AggregationError::MixedTypes(
+                    "FeedType::Numerical".to_owned(),
+                    "FeedType::Decimal".to_owned(),
+                ))
+            }
+            other => {
+                return Err(AggregationError::UnsupportedType(
+                    other.enum_type_to_string().to_owned(),
+                    aggregator.to_string(),
+                ))
+            }
+        };
+    }
+    Ok(filtered)
+}
+
+// Callers guarantee `values` is not empty and holds only finite numbers
+fn median(mut values: Vec<f64>) -> f64 {
+    let size = values.len();
+    values.sort_by(f64::total_cmp);
+
+    let middle = size / 2;
+    if size.is_odd() {
+        values[middle]
+    } else {
+        (values[middle] + values[middle - 1]) / 2.0
+    }
+}
+
+fn trimmed_mean(mut values: Vec<f64>, trim_percentage: f64) -> f64 {
+    let size = values.len();
+    values.sort_by(f64::total_cmp);
+
+    // Always keep at least the middle element(s)
+    let to_trim = ((size as f64 * trim_percentage / 100.0).floor() as usize).min((size - 1) / 2);
+    let kept = &values[to_trim..size - to_trim];
+    kept.iter().sum::<f64>() / kept.len() as f64
+}
+
+fn weighted_median(values: &[f64], weights: &[f64]) -> f64 {
+    let mut pairs: Vec<(f64, f64)> = values
+        .iter()
+        .copied()
+        .zip(weights.iter().copied())
+        .filter(|(_, w)| w.is_finite() && *w > 0.0)
+        .collect();
+    let total_weight: f64 = pairs.iter().map(|(_, w)| w).sum();
+    if pairs.is_empty() || !total_weight.is_finite() {
+        error!("No valid stake behind the values, falling back to median!");
+        return median(values.to_vec());
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let half = total_weight / 2.0;
+    let mut cumulative = 0.0;
+    for (i, (value, weight)) in pairs.iter().enumerate() {
+        cumulative += weight;
+        if cumulative > half {
+            return *value;
+        }
+        if cumulative == half {
+            // The stake is split exactly in half, same as an even number of values in a median
+            return pairs
+                .get(i + 1)
+                .map_or(*value, |(next, _)| (value + next) / 2.0);
+        }
+    }
+    pairs[pairs.len() - 1].0
+}
+
+// Every value is weighted by the time from the previous published value (or the start of the
+// window) until its own slot end. The current value covers the time since the last publication.
+fn time_weighted_average(
+    current: f64,
+    history: &[(Timestamp, f64)],
+    end_slot_timestamp: Timestamp,
+    window_ms: u64,
+) -> f64 {
+    let window_start = end_slot_timestamp.saturating_sub(window_ms as u128);
+    let mut previous_timestamp = window_start;
+    let mut weighted_sum = 0.0;
+    let mut total_time = 0.0;
+    for (timestamp, value) in history
+        .iter()
+        .filter(|(t, v)| *t > window_start && *t < end_slot_timestamp && v.is_finite())
+    {
+        let duration = timestamp.saturating_sub(previous_timestamp) as f64;
+        weighted_sum += value * duration;
+        total_time += duration;
+        previous_timestamp = *timestamp;
+    }
+    let duration = end_slot_timestamp.saturating_sub(previous_timestamp) as f64;
+    weighted_sum += current * duration;
+    total_time += duration;
+
+    if total_time > 0.0 {
+        weighted_sum / total_time
+    } else {
+        current
+    }
+}
+
+// Mantissas of decimal values, all rescaled to the largest scale among the values
+fn decimal_values(
+    values: &[FeedType],
+    aggregator: &FeedAggregate,
+) -> Result<(Vec<I256>, u8), AggregationError> {
+    let mut decimals = Vec::with_capacity(values.len());
+    for value in values {
+        match value {
+            FeedType::Decimal { mantissa, scale } => decimals.push((*mantissa, *scale)),
+            FeedType::Numerical(_) => {
+                return Err(AggregationError::MixedTypes(
+                    "FeedType::Decimal".to_owned(),
+                    "FeedType::Numerical".to_owned(),
+                ))
+            }
+            other => {
+                return Err(AggregationError::UnsupportedType(
+                    other.enum_type_to_string().to_owned(),
+                    aggregator.to_string(),
+                ))
+            }
+        }
+    }
+    let scale = decimals.iter().map(|(_, scale)| *scale).max().unwrap_or(0);
+    let mantissas = decimals
+        .into_iter()
+        .map(|(mantissa, s)| rescale_decimal(mantissa, s, scale))
+        .collect::<Option<Vec<I256>>>()
+        .ok_or(AggregationError::DecimalOverflow)?;
+    Ok((mantissas, scale))
+}
+
+fn decimal(value: u64) -> I256 {
+    I256::from_raw(U256::from(value))
+}
+
+// The midpoint of two decimals needs one more digit in the fraction, unless their sum is even
+fn decimal_midpoint(a: I256, b: I256, scale: u8) -> Result<(I256, u8), AggregationError> {
+    let sum = a.checked_add(b).ok_or(AggregationError::DecimalOverflow)?;
+    if sum % decimal(2) == I256::ZERO || scale == MAX_DECIMAL_SCALE {
+        return Ok((sum / decimal(2), scale));
+    }
+    match sum.checked_mul(decimal(5)) {
+        Some(midpoint) => Ok((midpoint, scale + 1)),
+        None => Ok((sum / decimal(2), scale)),
+    }
+}
+
+fn decimal_median(mut values: Vec<I256>, scale: u8) -> Result<(I256, u8), AggregationError> {
+    let size = values.len();
+    values.sort();
+
+    let middle = size / 2;
+    if size.is_odd() {
+        Ok((values[middle], scale))
+    } else {
+        decimal_midpoint(values[middle - 1], values[middle], scale)
+    }
+}
+
+// The mean is truncated towards zero at the scale of the values
+fn decimal_mean(values: &[I256]) -> Result<I256, AggregationError> {
+    let sum = values
+        .iter()
+        .try_fold(I256::ZERO, |sum, value| sum.checked_add(*value))
+        .ok_or(AggregationError::DecimalOverflow)?;
+    Ok(sum / decimal(values.len() as u64))
+}
+
+fn decimal_weighted_median(
+    values: &[I256],
+    weights: &[f64],
+    scale: u8,
+) -> Result<(I256, u8), AggregationError> {
+    let mut pairs: Vec<(I256, f64)> = values
+        .iter()
+        .copied()
+        .zip(weights.iter().copied())
+        .filter(|(_, w)| w.is_finite() && *w > 0.0)
+        .collect();
+    let total_weight: f64 = pairs.iter().map(|(_, w)| w).sum();
+    if pairs.is_empty() || !total_weight.is_finite() {
+        error!("No valid stake behind the values, falling back to median!");
+        return decimal_median(values.to_vec(), scale);
+    }
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let half = total_weight / 2.0;
+    let mut cumulative = 0.0;
+    for (i, (value, weight)) in pairs.iter().enumerate() {
+        cumulative += weight;
+        if cumulative > half {
+            return Ok((*value, scale));
+        }
+        if cumulative == half {
+            return match pairs.get(i + 1) {
+                Some((next, _)) => decimal_midpoint(*value, *next, scale),
+                None => Ok((*value, scale)),
+            };
+        }
+    }
+    Ok((pairs[pairs.len() - 1].0, scale))
 }
 
 impl FeedAggregate {
-    pub fn aggregate(&self, values: &[FeedType]) -> FeedType {
+    pub fn aggregate(&self, values: &[FeedType]) -> Result<FeedType, AggregationError> {
+        self.aggregate_with_context(values, &AggregationContext::default())
+    }
+
+    pub fn aggregate_with_context(
+        &self,
+        values: &[FeedType],
+        context: &AggregationContext,
+    ) -> Result<FeedType, AggregationError> {
+        if values.is_empty() {
+            return Err(AggregationError::EmptyInput);
+        }
+        let result = self.aggregate_values(values, context)?;
+        // Finite inputs can still overflow, e.g. when averaging values close to f64::MAX
+        match result {
+            FeedType::Numerical(x) if !x.is_finite() => Err(AggregationError::NonFiniteValue(x)),
+            _ => Ok(result),
+        }
+    }
+
+    // Decimal values are aggregated exactly, without converting them to f64
+    fn aggregate_decimals(
+        &self,
+        values: &[FeedType],
+        context: &AggregationContext,
+    ) -> Result<FeedType, AggregationError> {
+        let (mantissas, scale) = decimal_values(values, self)?;
+        let (mantissa, scale) = match self {
+            FeedAggregate::AverageAggregator => (decimal_mean(&mantissas)?, scale),
+            FeedAggregate::MedianAggregator => decimal_median(mantissas, scale)?,
+            FeedAggregate::TrimmedMeanAggregator { trim_percentage } => {
+                let mut mantissas = mantissas;
+                mantissas.sort();
+                let size = mantissas.len();
+                let to_trim =
+                    ((size as f64 * trim_percentage / 100.0).floor() as usize).min((size - 1) / 2);
+                (decimal_mean(&mantissas[to_trim..size - to_trim])?, scale)
+            }
+            FeedAggregate::StakeWeightedMedianAggregator => {
+                match &context.weights {
+                    Some(weights) if weights.len() == mantissas.len() => {
+                        decimal_weighted_median(&mantissas, weights, scale)?
+                    }
+                    _ => {
+                        error!("Reporter stakes do not match the reported values, falling back to median!");
+                        decimal_median(mantissas, scale)?
+                    }
+                }
+            }
+            FeedAggregate::MajorityVoteAggregator | FeedAggregate::TwapAggregator { .. } => {
+                return Err(AggregationError::UnsupportedType(
+                    "FeedType::Decimal".to_owned(),
+                    self.to_string(),
+                ))
+            }
+        };
+        Ok(FeedType::Decimal { mantissa, scale })
+    }
+
+    fn aggregate_values(
+        &self,
+        values: &[FeedType],
+        context: &AggregationContext,
+    ) -> Result<FeedType, AggregationError> {
+        if matches!(values.first(), Some(FeedType::Decimal { .. })) {
+            return self.aggregate_decimals(values, context);
+        }
         match self {
             FeedAggregate::AverageAggregator => {
                 let span = info_span!("AverageAggregator");
                 let _guard = span.enter();
-                assert!(!values.is_empty());
-                let num_elements = values.len() as f64;
-                let mut filtered = Vec::new();
-
-                for value in values {
-                    match value {
-                        FeedType::Numerical(x) => filtered.push(x),
-                        _ => error!("Attempting to perform arithmetic on non-numerical type!"), //TODO(snikolov): What level of error?
-                    };
-                }
-
+                let filtered = numerical_values(values, self)?;
+                let num_elements = filtered.len() as f64;
                 let sum: f64 = filtered.into_iter().sum();
-                FeedType::Numerical(sum / num_elements)
+                Ok(FeedType::Numerical(sum / num_elements))
             }
             FeedAggregate::MajorityVoteAggregator => {
                 let span = info_span!("MajorityVoteAggregator");
@@ -48,25 +362,31 @@ impl FeedAggregate {
                         FeedType::Text(t) => *frequency_map_text.entry(t).or_insert(0) += 1,
                         FeedType::Bytes(b) => *frequency_map_bytes.entry(b).or_insert(0) += 1,
                         _ => {
-                            error!("Attempting to perform frequency_map on {:?}", v);
+                            return Err(AggregationError::UnsupportedType(
+                                v.enum_type_to_string().to_owned(),
+                                self.to_string(),
+                            ));
                         }
                     }
                 }
 
+                if !frequency_map_text.is_empty() && !frequency_map_bytes.is_empty() {
+                    return Err(AggregationError::MixedTypes(
+                        "FeedType::Text".to_owned(),
+                        "FeedType::Bytes".to_owned(),
+                    ));
+                }
+
                 // Find the string with the maximum occurrences
                 if !frequency_map_text.is_empty() {
-                    assert!(
-                        frequency_map_bytes.is_empty(),
-                        "Mixing Text and Bytes in MajorityVoteAggregator is not allowed!"
-                    );
                     let result = frequency_map_text
                         .into_iter()
                         .max_by_key(|&(_, count)| count)
                         .map(|(s, _)| s)
                         .unwrap()
                         .clone();
-                    FeedType::Text(result)
-                } else if !frequency_map_bytes.is_empty() {
+                    Ok(FeedType::Text(result))
+                } else {
                     let most_frequent = frequency_map_bytes
                         .into_iter()
                         .max_by_key(|&(_, count)| count)
@@ -113,46 +433,92 @@ impl FeedAggregate {
 
                     result.resize(target_length, 0);
 
-                    FeedType::Bytes(result)
-                } else {
-                    panic!("No valid types to aggregate in MajorityVoteAggregator!");
+                    Ok(FeedType::Bytes(result))
                 }
             }
             FeedAggregate::MedianAggregator => {
                 let span = info_span!("MedianAggregator");
                 let _guard = span.enter();
-                let mut filtered = Vec::new();
-
-                for value in values {
-                    match value {
-                        FeedType::Numerical(x) => filtered.push(x),
-                        _ => error!("Attempting to perform arithmetic on non-numerical type!"), //TODO(snikolov): What level of error?
-                    };
-                }
-                let size = filtered.len();
-                assert!(size > 0);
-                filtered.sort_by(|a, b| {
-                    a.partial_cmp(b)
-                        .expect("Ordering between elements does not exists.")
-                });
-
-                let middle = size / 2;
-                if size.is_odd() {
-                    FeedType::Numerical(*filtered[middle])
-                } else {
-                    FeedType::Numerical((filtered[middle] + filtered[middle - 1]) / 2.0)
+                Ok(FeedType::Numerical(median(numerical_values(values, self)?)))
+            }
+            FeedAggregate::TrimmedMeanAggregator { trim_percentage } => {
+                let span = info_span!("TrimmedMeanAggregator");
+                let _guard = span.enter();
+                Ok(FeedType::Numerical(trimmed_mean(
+                    numerical_values(values, self)?,
+                    *trim_percentage,
+                )))
+            }
+            FeedAggregate::StakeWeightedMedianAggregator => {
+                let span = info_span!("StakeWeightedMedianAggregator");
+                let _guard = span.enter();
+                let filtered = numerical_values(values, self)?;
+                match &context.weights {
+                    Some(weights) if weights.len() == filtered.len() => {
+                        Ok(FeedType::Numerical(weighted_median(&filtered, weights)))
+                    }
+                    _ => {
+                        error!("Reporter stakes do not match the reported values, falling back to median!");
+                        Ok(FeedType::Numerical(median(filtered)))
+                    }
                 }
             }
+            FeedAggregate::TwapAggregator { window_ms } => {
+                let span = info_span!("TwapAggregator");
+                let _guard = span.enter();
+                let current = median(numerical_values(values, self)?);
+                Ok(FeedType::Numerical(time_weighted_average(
+                    current,
+                    &context.history,
+                    context.end_slot_timestamp,
+                    *window_ms,
+                )))
+            }
         }
     }
+
+    // Whether the aggregator needs the stakes of the reporters in the `AggregationContext`
+    pub fn needs_weights(&self) -> bool {
+        matches!(self, FeedAggregate::StakeWeightedMedianAggregator)
+    }
+
+    // Whether the aggregator needs the history of the feed in the `AggregationContext`
+    pub fn needs_history(&self) -> bool {
+        matches!(self, FeedAggregate::TwapAggregator { .. })
+    }
+
     pub fn create_from_str(aggregate_type: &str) -> anyhow::Result<Self> {
         match aggregate_type {
             "median" => Ok(Self::MedianAggregator),
             "majority" => Ok(Self::MajorityVoteAggregator),
             "average" => Ok(Self::AverageAggregator),
+            "trimmed_mean" => Ok(Self::TrimmedMeanAggregator {
+                trim_percentage: DEFAULT_TRIM_PERCENTAGE,
+            }),
+            "stake_weighted_median" => Ok(Self::StakeWeightedMedianAggregator),
+            "twap" => Ok(Self::TwapAggregator {
+                window_ms: DEFAULT_TWAP_WINDOW_MS,
+            }),
             _ => anyhow::bail!("Could not convert {aggregate_type} to a valid aggregator!"),
         }
     }
+
+    pub fn create_from_config(quorum: &FeedQuorum) -> anyhow::Result<Self> {
+        let aggregator = Self::create_from_str(quorum.aggregation.as_str())?;
+        let Some(params) = &quorum.aggregation_params else {
+            return Ok(aggregator);
+        };
+        params.validate()?;
+        match aggregator {
+            Self::TrimmedMeanAggregator { .. } => Ok(Self::TrimmedMeanAggregator {
+                trim_percentage: params.trim_percentage.unwrap_or(DEFAULT_TRIM_PERCENTAGE),
+            }),
+            Self::TwapAggregator { .. } => Ok(Self::TwapAggregator {
+                window_ms: params.twap_window_ms.unwrap_or(DEFAULT_TWAP_WINDOW_MS),
+            }),
+            _ => Ok(aggregator),
+        }
+    }
 }
 
 impl Display for FeedAggregate {
@@ -161,6 +527,15 @@ impl Display for FeedAggregate {
             FeedAggregate::AverageAggregator => write!(f, "AverageAggregator"),
             FeedAggregate::MajorityVoteAggregator => write!(f, "FeedAggregate"),
             FeedAggregate::MedianAggregator => write!(f, "MedianAggregator"),
+            FeedAggregate::TrimmedMeanAggregator { trim_percentage } => {
+                write!(f, "TrimmedMeanAggregator({trim_percentage}%)")
+            }
+            FeedAggregate::StakeWeightedMedianAggregator => {
+                write!(f, "StakeWeightedMedianAggregator")
+            }
+            FeedAggregate::TwapAggregator { window_ms } => {
+                write!(f, "TwapAggregator({window_ms}ms)")
+            }
         }
     }
 }
@@ -171,6 +546,15 @@ impl Debug for FeedAggregate {
             FeedAggregate::AverageAggregator => write!(f, "AverageAggregator"),
             FeedAggregate::MajorityVoteAggregator => write!(f, "FeedAggregate"),
             FeedAggregate::MedianAggregator => write!(f, "MedianAggregator"),
+            FeedAggregate::TrimmedMeanAggregator { trim_percentage } => {
+                write!(f, "TrimmedMeanAggregator({trim_percentage}%)")
+            }
+            FeedAggregate::StakeWeightedMedianAggregator => {
+                write!(f, "StakeWeightedMedianAggregator")
+            }
+            FeedAggregate::TwapAggregator { window_ms } => {
+                write!(f, "TwapAggregator({window_ms}ms)")
+            }
         }
     }
 }
@@ -188,7 +572,7 @@ mod tests {
             .map(FeedType::Numerical)
             .collect();
 
-        let result = aggregator.aggregate(&values[..]);
+        let result = aggregator.aggregate(&values[..]).unwrap();
         let expected_result = (2. + 2. + 3.) / 3.;
         assert_eq!(FeedType::Numerical(expected_result), result);
     }
@@ -202,7 +586,7 @@ mod tests {
             .map(FeedType::Numerical)
             .collect();
 
-        let result = aggregator.aggregate(&values[..]);
+        let result = aggregator.aggregate(&values[..]).unwrap();
 
         assert_eq!(result, FeedType::Numerical(0.));
     }
@@ -219,7 +603,7 @@ mod tests {
         .map(FeedType::Numerical)
         .collect();
 
-        let result = aggregator.aggregate(&values[..]);
+        let result = aggregator.aggregate(&values[..]).unwrap();
 
         assert_eq!(
             result,
@@ -233,8 +617,263 @@ mod tests {
 
         let values: Vec<FeedType> = vec![0., 0.].into_iter().map(FeedType::Numerical).collect();
 
-        let result = aggregator.aggregate(&values[..]);
+        let result = aggregator.aggregate(&values[..]).unwrap();
 
         assert_ne!(result, FeedType::Numerical(0.00000000001));
     }
+
+    fn numerical(values: &[f64]) -> Vec<FeedType> {
+        values.iter().copied().map(FeedType::Numerical).collect()
+    }
+
+    #[test]
+    fn test_trimmed_mean_aggregator_drops_outliers() {
+        let aggregator = FeedAggregate::TrimmedMeanAggregator {
+            trim_percentage: 20.0,
+        };
+
+        let values = numerical(&[1000., 10., 11., 12., 0.]);
+
+        let result = aggregator.aggregate(&values[..]).unwrap();
+
+        assert_eq!(result, FeedType::Numerical(11.));
+    }
+
+    #[test]
+    fn test_trimmed_mean_aggregator_keeps_middle_values() {
+        let aggregator = FeedAggregate::TrimmedMeanAggregator {
+            trim_percentage: 49.0,
+        };
+
+        assert_eq!(
+            aggregator.aggregate(&numerical(&[1., 2., 3., 4.])[..]),
+            Ok(FeedType::Numerical(2.5))
+        );
+        assert_eq!(
+            aggregator.aggregate(&numerical(&[7.])[..]),
+            Ok(FeedType::Numerical(7.))
+        );
+    }
+
+    #[test]
+    fn test_stake_weighted_median_aggregator() {
+        let aggregator = FeedAggregate::StakeWeightedMedianAggregator;
+        let values = numerical(&[1., 2., 3.]);
+
+        let context = AggregationContext {
+            weights: Some(vec![1., 1., 5.]),
+            ..Default::default()
+        };
+        assert_eq!(
+            aggregator.aggregate_with_context(&values[..], &context),
+            Ok(FeedType::Numerical(3.))
+        );
+
+        // Stake split exactly in half behaves like the median of an even number of values
+        let context = AggregationContext {
+            weights: Some(vec![1., 1., 2.]),
+            ..Default::default()
+        };
+        assert_eq!(
+            aggregator.aggregate_with_context(&values[..], &context),
+            Ok(FeedType::Numerical(2.5))
+        );
+
+        // Without stakes the plain median is used
+        assert_eq!(
+            aggregator.aggregate(&values[..]),
+            Ok(FeedType::Numerical(2.))
+        );
+    }
+
+    #[test]
+    fn test_twap_aggregator() {
+        let aggregator = FeedAggregate::TwapAggregator { window_ms: 100 };
+        let values = numerical(&[30.]);
+
+        // Every value covers the time since the previous one: 5 covers (0, 20], 10 covers
+        // (20, 50], 20 covers (50, 75] and the current value covers (75, 100]
+        let context = AggregationContext {
+            weights: None,
+            history: vec![(20, 5.), (50, 10.), (75, 20.)],
+            end_slot_timestamp: 100,
+        };
+        let expected = (5. * 20. + 10. * 30. + 20. * 25. + 30. * 25.) / 100.;
+        assert_eq!(
+            aggregator.aggregate_with_context(&values[..], &context),
+            Ok(FeedType::Numerical(expected))
+        );
+
+        // Without history only the current value is left
+        let context = AggregationContext {
+            end_slot_timestamp: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            aggregator.aggregate_with_context(&values[..], &context),
+            Ok(FeedType::Numerical(30.))
+        );
+    }
+
+    #[test]
+    fn test_create_aggregator_from_config() {
+        let mut quorum = FeedQuorum {
+            percentage: 100.0,
+            aggregation: "trimmed_mean".to_owned(),
+            aggregation_params: Some(blocksense_registry::config::AggregationParams {
+                trim_percentage: Some(25.0),
+                twap_window_ms: None,
+            }),
+        };
+        assert!(matches!(
+            FeedAggregate::create_from_config(&quorum),
+            Ok(FeedAggregate::TrimmedMeanAggregator { trim_percentage }) if trim_percentage == 25.0
+        ));
+
+        quorum.aggregation_params = Some(blocksense_registry::config::AggregationParams {
+            trim_percentage: Some(50.0),
+            twap_window_ms: None,
+        });
+        assert!(FeedAggregate::create_from_config(&quorum).is_err());
+
+        quorum.aggregation = "twap".to_owned();
+        quorum.aggregation_params = None;
+        assert!(matches!(
+            FeedAggregate::create_from_config(&quorum),
+            Ok(FeedAggregate::TwapAggregator { window_ms }) if window_ms == DEFAULT_TWAP_WINDOW_MS
+        ));
+    }
+
+    #[test]
+    fn test_aggregators_reject_empty_input() {
+        for aggregator in [
+            FeedAggregate::AverageAggregator,
+            FeedAggregate::MedianAggregator,
+            FeedAggregate::MajorityVoteAggregator,
+            FeedAggregate::StakeWeightedMedianAggregator,
+        ] {
+            assert_eq!(aggregator.aggregate(&[]), Err(AggregationError::EmptyInput));
+        }
+    }
+
+    #[test]
+    fn test_numerical_aggregators_reject_non_finite_values() {
+        let aggregator = FeedAggregate::MedianAggregator;
+
+        let values = numerical(&[1., f64::NAN, 3.]);
+        assert!(matches!(
+            aggregator.aggregate(&values[..]),
+            Err(AggregationError::NonFiniteValue(x)) if x.is_nan()
+        ));
+
+        let values = numerical(&[1., f64::INFINITY]);
+        assert_eq!(
+            aggregator.aggregate(&values[..]),
+            Err(AggregationError::NonFiniteValue(f64::INFINITY))
+        );
+
+        // The sum overflows even though every value is finite
+        let values = numerical(&[f64::MAX, f64::MAX]);
+        assert_eq!(
+            FeedAggregate::AverageAggregator.aggregate(&values[..]),
+            Err(AggregationError::NonFiniteValue(f64::INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_aggregators_reject_wrong_types() {
+        let values = vec![FeedType::Text("a".to_owned()), FeedType::Bytes(vec![1])];
+        assert!(matches!(
+            FeedAggregate::MajorityVoteAggregator.aggregate(&values[..]),
+            Err(AggregationError::MixedTypes(_, _))
+        ));
+
+        let values = vec![FeedType::Numerical(1.), FeedType::Text("a".to_owned())];
+        assert!(matches!(
+            FeedAggregate::AverageAggregator.aggregate(&values[..]),
+            Err(AggregationError::UnsupportedType(_, _))
+        ));
+        assert!(matches!(
+            FeedAggregate::MajorityVoteAggregator.aggregate(&values[..]),
+            Err(AggregationError::UnsupportedType(_, _))
+        ));
+    }
+
+    fn decimals(values: &[&str]) -> Vec<FeedType> {
+        values
+            .iter()
+            .map(|v| FeedType::decimal_from_str(v).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_decimal_aggregation_is_exact() {
+        let values = decimals(&["-10.5", "0.000000000000000000001", "-3"]);
+        assert_eq!(
+            FeedAggregate::MedianAggregator
+                .aggregate(&values[..])
+                .unwrap()
+                .parse_to_string(),
+            "-3.000000000000000000000"
+        );
+
+        // The midpoint of two values adds a digit to the fraction
+        let values = decimals(&["-1.5", "2", "-2", "1.2"]);
+        assert_eq!(
+            FeedAggregate::MedianAggregator
+                .aggregate(&values[..])
+                .unwrap()
+                .parse_to_string(),
+            "-0.15"
+        );
+
+        let values = decimals(&["123456789012345678901234567890.1", "1", "2"]);
+        assert_eq!(
+            FeedAggregate::AverageAggregator
+                .aggregate(&values[..])
+                .unwrap()
+                .parse_to_string(),
+            "41152263004115226300411522631.0"
+        );
+        assert_eq!(
+            FeedAggregate::TrimmedMeanAggregator {
+                trim_percentage: 34.0
+            }
+            .aggregate(&values[..])
+            .unwrap()
+            .parse_to_string(),
+            "2.0"
+        );
+
+        let context = AggregationContext {
+            weights: Some(vec![1.0, 1.0, 5.0]),
+            ..Default::default()
+        };
+        assert_eq!(
+            FeedAggregate::StakeWeightedMedianAggregator
+                .aggregate_with_context(&values[..], &context)
+                .unwrap()
+                .parse_to_string(),
+            "2.0"
+        );
+    }
+
+    #[test]
+    fn test_decimal_aggregation_rejects_mixed_types() {
+        let mut values = decimals(&["1.5"]);
+        values.push(FeedType::Numerical(1.5));
+        assert!(matches!(
+            FeedAggregate::MedianAggregator.aggregate(&values[..]),
+            Err(AggregationError::MixedTypes(_, _))
+        ));
+        values.reverse();
+        assert!(matches!(
+            FeedAggregate::MedianAggregator.aggregate(&values[..]),
+            Err(AggregationError::MixedTypes(_, _))
+        ));
+        assert!(matches!(
+            FeedAggregate::TwapAggregator { window_ms: 1000 }.aggregate(&decimals(&["1.5"])),
+            Err(AggregationError::UnsupportedType(_, _))
+        ));
+    }
 }
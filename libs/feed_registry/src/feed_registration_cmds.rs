@@ -1,3 +1,4 @@
+use blocksense_config::Reporter;
 use blocksense_registry::config::FeedConfig;
 use blocksense_utils::EncodedFeedId;
 use tokio::sync::mpsc;
@@ -12,10 +13,46 @@ pub struct DeleteAssetFeed {
     pub id: EncodedFeedId,
 }
 
+/// Change to the set of reporters accepted by the sequencer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReporterAction {
+    Add(Reporter),
+    Remove {
+        id: u64,
+    },
+    /// Suspended reporters keep their keys and metrics, but their votes are rejected and they do
+    /// not count towards the quorum.
+    Suspend {
+        id: u64,
+    },
+    Resume {
+        id: u64,
+    },
+    /// Replaces the keys of the reporter, keeping its id, stake and metrics.
+    Rotate {
+        id: u64,
+        pub_key: String,
+        address: String,
+    },
+}
+
+impl ReporterAction {
+    pub fn reporter_id(&self) -> u64 {
+        match self {
+            ReporterAction::Add(reporter) => reporter.id.into(),
+            ReporterAction::Remove { id }
+            | ReporterAction::Suspend { id }
+            | ReporterAction::Resume { id }
+            | ReporterAction::Rotate { id, .. } => *id,
+        }
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
 pub enum FeedsManagementCmds {
     RegisterNewAssetFeed(RegisterNewAssetFeed),
     DeleteAssetFeed(DeleteAssetFeed),
+    ReporterAction(ReporterAction),
 }
 
 pub enum ProcessorResultValue {
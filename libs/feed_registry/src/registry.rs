@@ -5,16 +5,16 @@ use std::{
 };
 
 use crate::types::{DataFeedPayload, FeedMetaData, FeedType, Repeatability, Timestamp};
+use anyhow::Context;
 use blocksense_config::AllFeedsConfig;
 use blocksense_utils::{time::current_unix_time, EncodedFeedId};
 use chrono::{DateTime, TimeZone, Utc};
 use ringbuf::{
     storage::Heap,
-    traits::{Consumer, RingBuffer},
+    traits::{Consumer, Observer, RingBuffer},
     HeapRb, SharedRb,
 };
 use serde::{ser::SerializeMap, Deserialize, Serialize, Serializer};
-use std::time::UNIX_EPOCH;
 use tokio::{sync::RwLock, time};
 use tracing::{debug, info};
 
@@ -97,27 +97,24 @@ pub fn new_feeds_meta_data_reg_with_test_data() -> FeedMetaDataRegistry {
     fmdr
 }
 
-pub fn new_feeds_meta_data_reg_from_config(conf: &AllFeedsConfig) -> FeedMetaDataRegistry {
+pub fn new_feeds_meta_data_reg_from_config(
+    conf: &AllFeedsConfig,
+) -> anyhow::Result<FeedMetaDataRegistry> {
     let mut fmdr = FeedMetaDataRegistry::new();
 
     for feed in &conf.feeds {
-        fmdr.push(
-            EncodedFeedId::new(feed.id, feed.stride),
-            FeedMetaData::new(
-                feed.full_name.clone(),
-                feed.schedule.interval_ms,
-                feed.quorum.percentage,
-                feed.schedule.deviation_percentage,
-                feed.schedule.heartbeat_ms,
-                UNIX_EPOCH + Duration::from_millis(feed.schedule.first_report_start_unix_time_ms),
-                feed.value_type.clone(),
-                feed.quorum.aggregation.clone(),
-                None, // Will be filled once FeedsSlotsManager is started and processors are up and running.
-            ),
-        );
+        // The processor command channel is filled once FeedsSlotsManager is started and
+        // processors are up and running.
+        let feed_metadata = FeedMetaData::try_from_config(feed).with_context(|| {
+            format!(
+                "Invalid config of feed {} with id {}",
+                feed.full_name, feed.id
+            )
+        })?;
+        fmdr.push(EncodedFeedId::new(feed.id, feed.stride), feed_metadata);
     }
 
-    fmdr
+    Ok(fmdr)
 }
 
 // For a given Feed this struct represents the received votes from different reporters.
@@ -146,10 +143,38 @@ impl HistoryEntry {
     }
 }
 
+/// What happened in a voting slot of a feed, whether or not a value was published.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SlotOutcome {
+    pub end_slot_timestamp: Timestamp,
+    /// Number of reporters that voted in the slot
+    pub votes: usize,
+    /// Number of reporters that were allowed to vote in the slot
+    pub valid_reporters: usize,
+    pub quorum_reached: bool,
+    /// Aggregate of the votes, if they could be aggregated
+    pub value: Option<FeedType>,
+    /// Update number of the published value, if the value was published
+    pub update_number: Option<u128>,
+    /// Why the value was published or skipped
+    pub reason: String,
+}
+
+/// Selects slot outcomes of a feed. The time range is applied before `last`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistoryQuery {
+    pub last: Option<usize>,
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    pub published_only: bool,
+}
+
 #[derive(Serialize)]
 pub struct FeedAggregateHistory {
     #[serde(serialize_with = "serialize_aggregate_history")]
     pub aggregate_history: HashMap<EncodedFeedId, HeapRb<HistoryEntry>>,
+    #[serde(skip)]
+    pub slot_outcomes: HashMap<EncodedFeedId, HeapRb<SlotOutcome>>,
 }
 
 fn serialize_aggregate_history<S>(
@@ -179,6 +204,12 @@ where
     serialize_map.end()
 }
 
+/// Approximate number of bytes the history of a feed with capacity `buf_size` takes. Heap data
+/// of bytes values is not accounted for.
+pub fn history_memory_bytes(buf_size: usize) -> usize {
+    buf_size * (std::mem::size_of::<HistoryEntry>() + std::mem::size_of::<SlotOutcome>())
+}
+
 impl Default for FeedAggregateHistory {
     fn default() -> Self {
         Self::new()
@@ -189,6 +220,7 @@ impl FeedAggregateHistory {
     pub fn new() -> Self {
         Self {
             aggregate_history: HashMap::new(),
+            slot_outcomes: HashMap::new(),
         }
     }
 
@@ -196,10 +228,45 @@ impl FeedAggregateHistory {
         let shared_rb = SharedRb::new(buf_size);
 
         self.aggregate_history.insert(encoded_feed_id, shared_rb);
+        self.slot_outcomes
+            .insert(encoded_feed_id, SharedRb::new(buf_size));
+    }
+
+    /// Changes the number of entries kept for the feed. Existing entries are kept, except for the
+    /// oldest ones when shrinking below their count. Returns false if the feed is not registered.
+    pub fn resize_feed(&mut self, encoded_feed_id: EncodedFeedId, buf_size: usize) -> bool {
+        let Some(old_history) = self.aggregate_history.get_mut(&encoded_feed_id) else {
+            return false;
+        };
+        let mut history = SharedRb::new(buf_size);
+        history.push_iter_overwrite(old_history.pop_iter());
+        *old_history = history;
+
+        let mut outcomes = SharedRb::new(buf_size);
+        if let Some(old_outcomes) = self.slot_outcomes.get_mut(&encoded_feed_id) {
+            outcomes.push_iter_overwrite(old_outcomes.pop_iter());
+        }
+        self.slot_outcomes.insert(encoded_feed_id, outcomes);
+        true
+    }
+
+    pub fn capacity(&self, encoded_feed_id: EncodedFeedId) -> Option<usize> {
+        self.aggregate_history
+            .get(&encoded_feed_id)
+            .map(|ring_buffer| ring_buffer.capacity().get())
+    }
+
+    /// Sum of the capacities of all registered feeds.
+    pub fn total_capacity(&self) -> usize {
+        self.aggregate_history
+            .values()
+            .map(|ring_buffer| ring_buffer.capacity().get())
+            .sum()
     }
 
     pub fn deregister_feed(&mut self, encoded_feed_id: EncodedFeedId) {
         self.aggregate_history.remove(&encoded_feed_id);
+        self.slot_outcomes.remove(&encoded_feed_id);
     }
 
     pub fn is_registered_feed(&self, encoded_feed_id: EncodedFeedId) -> bool {
@@ -218,6 +285,9 @@ impl FeedAggregateHistory {
     }
 
     pub fn clear(&mut self, encoded_feed_id: EncodedFeedId) -> usize {
+        if let Some(outcomes) = self.slot_outcomes.get_mut(&encoded_feed_id) {
+            outcomes.clear();
+        }
         match self.aggregate_history.get_mut(&encoded_feed_id) {
             Some(feed) => feed.clear(),
             _ => 0_usize,
@@ -261,6 +331,42 @@ impl FeedAggregateHistory {
     pub fn last_value(&self, encoded_feed_id: EncodedFeedId) -> Option<&FeedType> {
         self.last(encoded_feed_id).map(|h| &h.value)
     }
+
+    pub fn push_slot_outcome(&mut self, encoded_feed_id: EncodedFeedId, outcome: SlotOutcome) {
+        if let Some(ring_buffer) = self.slot_outcomes.get_mut(&encoded_feed_id) {
+            ring_buffer.push_overwrite(outcome);
+        } else {
+            info!(
+                "Feed Id: {}, not registered in FeedAggregateHistory!",
+                encoded_feed_id
+            );
+        }
+    }
+
+    /// Slot outcomes of the feed matching the query, oldest first. `None` if the feed is not
+    /// registered.
+    pub fn query_slot_outcomes(
+        &self,
+        encoded_feed_id: EncodedFeedId,
+        query: &HistoryQuery,
+    ) -> Option<Vec<SlotOutcome>> {
+        let ring_buffer = self.slot_outcomes.get(&encoded_feed_id)?;
+        let mut outcomes: Vec<SlotOutcome> = ring_buffer
+            .iter()
+            .filter(|outcome| {
+                query
+                    .from
+                    .is_none_or(|from| outcome.end_slot_timestamp >= from)
+                    && query.to.is_none_or(|to| outcome.end_slot_timestamp <= to)
+                    && (!query.published_only || outcome.update_number.is_some())
+            })
+            .cloned()
+            .collect();
+        if let Some(last) = query.last {
+            outcomes.drain(..outcomes.len().saturating_sub(last));
+        }
+        Some(outcomes)
+    }
 }
 
 // This struct holds all the Feeds by ID (the key in the map) and the received votes for them
@@ -413,6 +519,7 @@ mod tests {
     use crate::registry::new_feeds_meta_data_reg_with_test_data;
     use crate::registry::AllFeedsReports;
     use crate::registry::SlotTimeTracker;
+    use crate::registry::{FeedAggregateHistory, HistoryQuery, SlotOutcome};
     use crate::types::test_payload_from_result;
     use crate::types::FeedMetaData;
     use crate::types::FeedResult;
@@ -867,4 +974,93 @@ mod tests {
         // assert
         assert!(duration_ms < 0);
     }
+
+    #[test]
+    fn test_query_slot_outcomes() {
+        let feed_id = EncodedFeedId::new(1, 0);
+        let mut history = FeedAggregateHistory::new();
+        history.register_feed(feed_id, 3);
+        for i in 0..5_u128 {
+            history.push_slot_outcome(
+                feed_id,
+                SlotOutcome {
+                    end_slot_timestamp: 1000 * (i + 1),
+                    votes: 2,
+                    valid_reporters: 3,
+                    quorum_reached: true,
+                    value: Some(FeedType::Numerical(i as f64)),
+                    update_number: (i % 2 == 0).then_some(i),
+                    reason: "ThresholdCrossed".to_string(),
+                },
+            );
+        }
+        let timestamps = |query: &HistoryQuery| -> Vec<u128> {
+            history
+                .query_slot_outcomes(feed_id, query)
+                .unwrap()
+                .iter()
+                .map(|outcome| outcome.end_slot_timestamp)
+                .collect()
+        };
+
+        // Only the last 3 slots are kept
+        assert_eq!(timestamps(&HistoryQuery::default()), vec![3000, 4000, 5000]);
+        assert_eq!(
+            timestamps(&HistoryQuery {
+                last: Some(2),
+                ..Default::default()
+            }),
+            vec![4000, 5000]
+        );
+        assert_eq!(
+            timestamps(&HistoryQuery {
+                from: Some(3500),
+                to: Some(4000),
+                ..Default::default()
+            }),
+            vec![4000]
+        );
+        assert_eq!(
+            timestamps(&HistoryQuery {
+                published_only: true,
+                ..Default::default()
+            }),
+            vec![3000, 5000]
+        );
+        assert!(history
+            .query_slot_outcomes(EncodedFeedId::new(2, 0), &HistoryQuery::default())
+            .is_none());
+    }
+
+    #[test]
+    fn test_resize_feed_keeps_entries() {
+        let feed_id = EncodedFeedId::new(1, 0);
+        let mut history = FeedAggregateHistory::new();
+        history.register_feed(feed_id, 3);
+        for i in 0..3_u128 {
+            history.push_next(feed_id, FeedType::Numerical(i as f64), 1000 * (i + 1));
+        }
+        let entries = |history: &FeedAggregateHistory| -> Vec<u128> {
+            history
+                .get(feed_id)
+                .unwrap()
+                .iter()
+                .map(|entry| entry.update_number)
+                .collect()
+        };
+
+        assert!(history.resize_feed(feed_id, 5));
+        assert_eq!(history.capacity(feed_id), Some(5));
+        assert_eq!(entries(&history), vec![0, 1, 2]);
+        history.push_next(feed_id, FeedType::Numerical(3.0), 4000);
+        assert_eq!(entries(&history), vec![0, 1, 2, 3]);
+
+        // Shrinking drops the oldest entries
+        assert!(history.resize_feed(feed_id, 2));
+        assert_eq!(entries(&history), vec![2, 3]);
+        assert_eq!(history.total_capacity(), 2);
+        assert_eq!(history.last_value(feed_id), Some(&FeedType::Numerical(3.0)));
+
+        assert!(!history.resize_feed(EncodedFeedId::new(2, 0), 5));
+    }
 }
@@ -10,6 +10,7 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::debug;
 
 use crate::aggregate::FeedAggregate;
+use alloy_primitives::{I256, U256};
 use blocksense_crypto::{JsonSerializableSignature, Signature};
 use blocksense_registry::config::FeedConfig;
 use num::BigUint;
@@ -39,6 +40,8 @@ pub struct FeedMetaData {
     pub quorum_percentage: f32,
     pub skip_publish_if_less_then_percentage: f32,
     pub always_publish_heartbeat_ms: Option<u128>,
+    pub anomaly_score_threshold: Option<f64>,
+    pub market_hours: Option<String>,
     first_report_start_time: SystemTime,
     feed_aggregator: FeedAggregate,
     pub value_type: String,
@@ -62,6 +65,8 @@ impl FeedMetaData {
             quorum_percentage,
             skip_publish_if_less_then_percentage,
             always_publish_heartbeat_ms,
+            anomaly_score_threshold: None,
+            market_hours: None,
             first_report_start_time,
             feed_aggregator: FeedAggregate::MajorityVoteAggregator,
             value_type: "text".to_string(),
@@ -89,6 +94,8 @@ impl FeedMetaData {
             quorum_percentage,
             skip_publish_if_less_then_percentage,
             always_publish_heartbeat_ms,
+            anomaly_score_threshold: None,
+            market_hours: None,
             first_report_start_time,
             feed_aggregator: FeedAggregate::create_from_str(aggregate_type.as_str())
                 .expect("Could not convert {aggregate_type} to a valid aggregator!"), //TODO(snikolov): This should be resolved based upon the ConsensusMetric enum sent from the reporter or directly based on the feed_id
@@ -98,8 +105,11 @@ impl FeedMetaData {
         }
     }
 
-    pub fn from_config(cfg: &FeedConfig) -> Self {
-        Self::new(
+    pub fn try_from_config(cfg: &FeedConfig) -> anyhow::Result<Self> {
+        // Resolve the aggregator first, so that invalid aggregation parameters are reported
+        // instead of falling back to the defaults
+        let feed_aggregator = FeedAggregate::create_from_config(&cfg.quorum)?;
+        let mut feed = Self::new(
             cfg.full_name.clone(),
             cfg.schedule.interval_ms,
             cfg.quorum.percentage,
@@ -109,7 +119,11 @@ impl FeedMetaData {
             cfg.value_type.clone(),
             cfg.quorum.aggregation.clone(),
             None,
-        )
+        );
+        feed.feed_aggregator = feed_aggregator;
+        feed.anomaly_score_threshold = cfg.schedule.anomaly_score_threshold;
+        feed.market_hours = cfg.additional_feed_info.market_hours.clone();
+        Ok(feed)
     }
 
     pub fn set_processor_cmd_chan(&mut self, send_chan: UnboundedSender<FeedsSlotProcessorCmds>) {
@@ -131,6 +145,12 @@ impl FeedMetaData {
     pub fn get_always_publish_heartbeat_ms(&self) -> Option<u128> {
         self.always_publish_heartbeat_ms
     }
+    pub fn get_anomaly_score_threshold(&self) -> Option<f64> {
+        self.anomaly_score_threshold
+    }
+    pub fn get_market_hours(&self) -> Option<String> {
+        self.market_hours.clone()
+    }
 
     pub fn get_first_report_start_time_ms(&self) -> u128 {
         let since_the_epoch = self
@@ -192,6 +212,33 @@ pub enum FeedType {
     Numerical(f64),
     Text(String),
     Bytes(Vec<u8>),
+    /// Signed fixed-point number equal to `mantissa * 10^-scale`. Unlike `Numerical` it can be
+    /// negative and is not limited by the precision of f64. On chain it is stored as a two's
+    /// complement int192 with the decimals of the feed.
+    Decimal {
+        mantissa: I256,
+        scale: u8,
+    },
+}
+
+/// Largest scale of a `FeedType::Decimal`, `10^76` is the largest power of ten that fits in I256.
+pub const MAX_DECIMAL_SCALE: u8 = 76;
+
+fn pow10(exponent: u8) -> I256 {
+    I256::from_raw(U256::from(10u8).pow(U256::from(exponent)))
+}
+
+/// Changes the scale of a decimal mantissa. Digits are truncated towards zero when the scale
+/// decreases. `None` on overflow or if `new_scale` is above `MAX_DECIMAL_SCALE`.
+pub fn rescale_decimal(mantissa: I256, scale: u8, new_scale: u8) -> Option<I256> {
+    if new_scale > MAX_DECIMAL_SCALE {
+        return None;
+    }
+    if new_scale >= scale {
+        mantissa.checked_mul(pow10(new_scale - scale))
+    } else {
+        Some(mantissa / pow10(scale - new_scale))
+    }
 }
 
 impl FeedType {
@@ -200,6 +247,58 @@ impl FeedType {
             FeedType::Numerical(_) => std::mem::size_of::<f64>(),
             FeedType::Bytes(v) => v.len(),
             FeedType::Text(s) => s.len(),
+            FeedType::Decimal { .. } => std::mem::size_of::<I256>(),
+        }
+    }
+
+    /// Parses a decimal number like `-12.345` or `1.5e21` into a `FeedType::Decimal`, without
+    /// going through f64.
+    pub fn decimal_from_str(value: &str) -> anyhow::Result<FeedType> {
+        let value = value.trim();
+        let (number, exponent) = match value.split_once(['e', 'E']) {
+            Some((number, exponent)) => (number, exponent.parse::<i32>()?),
+            None => (value, 0),
+        };
+        let (negative, number) = match number.strip_prefix('-') {
+            Some(number) => (true, number),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+        let digits = format!("{integer}{fraction}");
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            bail!("{value:?} is not a decimal number");
+        }
+
+        let mut mantissa = I256::from_dec_str(&digits)?;
+        let mut scale = fraction.len() as i64 - exponent as i64;
+        if scale < 0 {
+            let zeros = u8::try_from(-scale)
+                .ok()
+                .filter(|zeros| *zeros <= MAX_DECIMAL_SCALE);
+            mantissa = match zeros.and_then(|zeros| mantissa.checked_mul(pow10(zeros))) {
+                Some(mantissa) => mantissa,
+                None => bail!("{value:?} does not fit in a decimal"),
+            };
+            scale = 0;
+        }
+        if scale > MAX_DECIMAL_SCALE as i64 {
+            bail!("{value:?} has more than {MAX_DECIMAL_SCALE} digits in the fraction");
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        Ok(FeedType::Decimal {
+            mantissa,
+            scale: scale as u8,
+        })
+    }
+
+    /// Numerical and decimal values as f64, for checks where its precision is enough.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FeedType::Numerical(val) => Some(*val),
+            FeedType::Decimal { .. } => self.parse_to_string().parse().ok(),
+            FeedType::Text(_) | FeedType::Bytes(_) => None,
         }
     }
 
@@ -252,6 +351,36 @@ impl FeedType {
             }
             FeedType::Text(s) => Ok(s.as_bytes().to_vec()),
             FeedType::Bytes(bytes) => Ok(bytes.clone()),
+            FeedType::Decimal { mantissa, scale } => {
+                let value = match u8::try_from(digits_in_fraction)
+                    .ok()
+                    .and_then(|digits| rescale_decimal(*mantissa, *scale, digits))
+                {
+                    Some(value) => value,
+                    None => bail!(
+                        "FeedType::as_bytes error: {} cannot be stored with {digits_in_fraction} decimals",
+                        self.parse_to_string()
+                    ),
+                };
+
+                // The value is stored as int192, so the 8 bytes dropped below may only hold the
+                // sign extension
+                let value_bytes = value.to_be_bytes::<32>();
+                let sign_extension = if value.is_negative() { 0xff } else { 0 };
+                if value_bytes[..8].iter().any(|b| *b != sign_extension)
+                    || (value_bytes[8] & 0x80 != 0) != value.is_negative()
+                {
+                    bail!(
+                        "FeedType::as_bytes error: {} does not fit in int192 with {digits_in_fraction} decimals",
+                        self.parse_to_string()
+                    );
+                }
+
+                let mut bytes_vec = value_bytes[8..].to_vec();
+                bytes_vec.extend(timestamp.to_be_bytes());
+
+                Ok(bytes_vec)
+            }
         }
     }
 
@@ -260,6 +389,21 @@ impl FeedType {
             FeedType::Numerical(val) => format!("{val}"),
             FeedType::Text(s) => s.clone(),
             FeedType::Bytes(bytes) => format!("{bytes:?}"),
+            FeedType::Decimal { mantissa, scale } => {
+                let scale = *scale as usize;
+                let digits = format!(
+                    "{:0>width$}",
+                    mantissa.unsigned_abs().to_string(),
+                    width = scale + 1
+                );
+                let (integer, fraction) = digits.split_at(digits.len() - scale);
+                let sign = if mantissa.is_negative() { "-" } else { "" };
+                if fraction.is_empty() {
+                    format!("{sign}{integer}")
+                } else {
+                    format!("{sign}{integer}.{fraction}")
+                }
+            }
         }
     }
 
@@ -300,6 +444,25 @@ impl FeedType {
                 Ok(FeedType::Text(s))
             }
             FeedType::Bytes(_) => Ok(FeedType::Bytes(bytes)),
+            FeedType::Decimal { .. } => {
+                if bytes.len() < 32 {
+                    return Err("Bytes len less than required!".to_string());
+                }
+                let scale = match u8::try_from(digits_in_fraction) {
+                    Ok(scale) if scale <= MAX_DECIMAL_SCALE => scale,
+                    _ => return Err(format!("Unsupported decimals {digits_in_fraction}")),
+                };
+
+                // Sign extend the int192 value to 256 bits
+                let sign_extension = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+                let mut value_bytes = [sign_extension; 32];
+                value_bytes[8..].copy_from_slice(&bytes[..24]);
+
+                Ok(FeedType::Decimal {
+                    mantissa: I256::from_be_bytes(value_bytes),
+                    scale,
+                })
+            }
         }
     }
 
@@ -308,6 +471,7 @@ impl FeedType {
             FeedType::Numerical(_) => "FeedType::Numerical",
             FeedType::Text(_) => "FeedType::Text",
             FeedType::Bytes(_) => "FeedType::Bytes",
+            FeedType::Decimal { .. } => "FeedType::Decimal",
         }
     }
 
@@ -316,6 +480,10 @@ impl FeedType {
             "numerical" => FeedType::Numerical(0.0f64),
             "text" => FeedType::Text("".to_string()),
             "bytes" => FeedType::Bytes(vec![]),
+            "decimal" => FeedType::Decimal {
+                mantissa: I256::ZERO,
+                scale: 0,
+            },
             _ => {
                 return Err(format!("Unsupported feed type {feed_type}"));
             }
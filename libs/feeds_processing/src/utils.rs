@@ -1,8 +1,8 @@
 use crate::adfs_gen_calldata::adfs_serialize_updates;
 use alloy_primitives::{Address, Bytes, Uint, U256};
 use anyhow::bail;
-use anyhow::{anyhow, Context, Result};
-use blocksense_anomaly_detection::ingest::anomaly_detector_aggregate;
+use anyhow::{Context, Result};
+use blocksense_anomaly_detection::detector::{AnomalyDetector, DetectorConfig};
 use blocksense_config::{FeedStrideAndDecimals, PublishCriteria};
 use blocksense_crypto::{verify_signature, PublicKey, Signature};
 use blocksense_data_feeds::feeds_processing::{
@@ -10,7 +10,7 @@ use blocksense_data_feeds::feeds_processing::{
     VotedFeedUpdateWithProof,
 };
 use blocksense_feed_registry::{
-    aggregate::FeedAggregate,
+    aggregate::{AggregationContext, FeedAggregate},
     registry::FeedAggregateHistory,
     types::{DataFeedPayload, FeedResult, FeedType, Timestamp},
 };
@@ -24,7 +24,7 @@ use ringbuf::traits::consumer::Consumer;
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tracing::{debug, info, warn};
 
 pub const AD_MIN_DATA_POINTS_THRESHOLD: usize = 100;
@@ -59,6 +59,11 @@ pub fn check_signature(
 #[derive(Debug)]
 pub struct ConsumedReports {
     pub is_quorum_reached: bool,
+    /// Number of votes for the slot, out of `num_valid_reporters`
+    pub votes: usize,
+    /// Votes dropped because their value was NaN or infinite, they are not part of `votes`
+    pub non_finite_votes: usize,
+    pub num_valid_reporters: usize,
     pub skip_decision: SkipDecision,
     pub ad_score: Option<f64>,
     pub result_post_to_contract: Option<VotedFeedUpdateWithProof>,
@@ -74,20 +79,40 @@ pub async fn consume_reports(
     quorum_percentage: f32,
     skip_publish_if_less_then_percentage: f64,
     always_publish_heartbeat_ms: Option<u128>,
+    market_hours: Option<String>,
     end_slot_timestamp: Timestamp,
     num_reporters: usize,
+    reporter_stakes: &HashMap<u64, f64>,
     is_oneshot: bool,
     aggregator: FeedAggregate,
     history: Option<Arc<RwLock<FeedAggregateHistory>>>,
+    anomaly_detector: Option<Arc<Mutex<AnomalyDetector>>>,
+    anomaly_score_threshold: Option<f64>,
     encoded_feed_id: EncodedFeedId,
     caller_context: &str,
 ) -> ConsumedReports {
-    let values = collect_reported_values(feed_type, encoded_feed_id, reports, slot);
+    // A single faulty reporter must not fail the aggregation of the whole slot
+    let mut non_finite_votes = 0;
+    let (reporter_ids, values): (Vec<u64>, Vec<FeedType>) =
+        collect_reported_values(feed_type, encoded_feed_id, reports, slot)
+            .into_iter()
+            .filter(|(reporter_id, value)| match value {
+                FeedType::Numerical(x) if !x.is_finite() => {
+                    warn!("Dropping non finite value {x} reported by reporter {reporter_id} for feed id {encoded_feed_id} slot {slot}");
+                    non_finite_votes += 1;
+                    false
+                }
+                _ => true,
+            })
+            .unzip();
 
     if values.is_empty() {
         info!("No reports found for feed: {} slot: {}!", name, &slot);
         ConsumedReports {
             is_quorum_reached: false,
+            votes: 0,
+            non_finite_votes,
+            num_valid_reporters: num_reporters,
             skip_decision: SkipDecision::DoSkip(DoSkipReason::NothingToPost),
             ad_score: None,
             result_post_to_contract: None,
@@ -104,10 +129,47 @@ pub async fn consume_reports(
             );
         }
 
+        let mut context = AggregationContext {
+            end_slot_timestamp,
+            ..Default::default()
+        };
+        if aggregator.needs_weights() {
+            context.weights = Some(
+                reporter_ids
+                    .iter()
+                    .map(|id| reporter_stakes.get(id).copied().unwrap_or(0.0))
+                    .collect(),
+            );
+        }
+        if aggregator.needs_history() {
+            if let Some(history) = &history {
+                context.history = numerical_history(encoded_feed_id, history).await;
+            }
+        }
+
         // Dispatch to concrete FeedAggregate implementation.
+        let value = match aggregator.aggregate_with_context(&values[..], &context) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!(
+                    "Could not aggregate reports for feed: {} slot: {} with {:?}: {}",
+                    name, &slot, aggregator, e
+                );
+                return ConsumedReports {
+                    is_quorum_reached,
+                    votes: values.len(),
+                    non_finite_votes,
+                    num_valid_reporters: num_reporters,
+                    skip_decision: SkipDecision::DoSkip(DoSkipReason::AggregationFailed),
+                    ad_score: None,
+                    result_post_to_contract: None,
+                    end_slot_timestamp,
+                };
+            }
+        };
         let result_post_to_contract = VotedFeedUpdate {
             encoded_feed_id,
-            value: aggregator.aggregate(&values[..]), // Perform the concrete aggregation
+            value,
             end_slot_timestamp,
         };
 
@@ -121,23 +183,30 @@ pub async fn consume_reports(
         // Oneshot feeds have no history, so we cannot perform anomaly detection on them.
         let skip_decision = if !is_oneshot {
             if let Some(history) = history {
-                if let FeedType::Numerical(candidate_value) = result_post_to_contract.value {
-                    let ad_score = perform_anomaly_detection(
-                        encoded_feed_id,
-                        history.clone(),
-                        candidate_value,
-                    )
-                    .await;
-                    match ad_score {
-                        Ok(ad_score) => {
-                            info!(
-                                "AD_score for {:?} is {}",
-                                result_post_to_contract.value, ad_score
-                            );
-                            ad_score_opt = Some(ad_score)
-                        }
-                        Err(e) => {
-                            warn!("Anomaly Detection failed with error - {e}");
+                if let Some(candidate_value) = result_post_to_contract.value.as_f64() {
+                    // Anomaly detection is only performed for feeds that act on its score
+                    if let (Some(anomaly_detector), Some(_)) =
+                        (anomaly_detector, anomaly_score_threshold)
+                    {
+                        let ad_score = perform_anomaly_detection(
+                            encoded_feed_id,
+                            history.clone(),
+                            anomaly_detector,
+                            candidate_value,
+                            anomaly_detection_seed(encoded_feed_id, slot),
+                        )
+                        .await;
+                        match ad_score {
+                            Ok(ad_score) => {
+                                info!(
+                                    "AD_score for {:?} is {}",
+                                    result_post_to_contract.value, ad_score
+                                );
+                                ad_score_opt = Some(ad_score)
+                            }
+                            Err(e) => {
+                                warn!("Anomaly Detection failed with error - {e}");
+                            }
                         }
                     }
                     {
@@ -147,6 +216,7 @@ pub async fn consume_reports(
                             always_publish_heartbeat_ms,
                             peg_to_value: None,
                             peg_tolerance_percentage: 0.0f64,
+                            market_hours,
                         };
                         debug!("Get a read lock on history [feed {encoded_feed_id}]");
                         let history_guard = history.read().await;
@@ -167,8 +237,28 @@ pub async fn consume_reports(
         } else {
             SkipDecision::DontSkip(DontSkipReason::OneShotFeed)
         };
+        // An anomalous value only holds back updates triggered by the deviation threshold. Once
+        // the heartbeat times out the value is published, so that a lasting move of the price is
+        // not blocked forever.
+        let skip_decision = match (ad_score_opt, anomaly_score_threshold) {
+            (Some(ad_score), Some(threshold))
+                if ad_score > threshold
+                    && skip_decision
+                        == SkipDecision::DontSkip(DontSkipReason::ThresholdCrossed) =>
+            {
+                warn!(
+                    "Anomalous value {:?} for feed: {} slot: {}! Score {} is above threshold {}",
+                    result_post_to_contract.value, name, &slot, ad_score, threshold
+                );
+                SkipDecision::DoSkip(DoSkipReason::AnomalyDetected)
+            }
+            _ => skip_decision,
+        };
         let res = ConsumedReports {
             is_quorum_reached,
+            votes: values.len(),
+            non_finite_votes,
+            num_valid_reporters: num_reporters,
             skip_decision,
             ad_score: ad_score_opt,
             result_post_to_contract: Some(VotedFeedUpdateWithProof {
@@ -190,13 +280,13 @@ pub fn collect_reported_values(
     encoded_feed_id: EncodedFeedId,
     reports: &HashMap<u64, DataFeedPayload>,
     slot: u64,
-) -> Vec<FeedType> {
-    let mut values: Vec<FeedType> = vec![];
+) -> Vec<(u64, FeedType)> {
+    let mut values: Vec<(u64, FeedType)> = vec![];
     for kv in reports {
         match &kv.1.result {
             Ok(value) => {
                 if value.same_enum_type_as(expected_feed_type) {
-                    values.push(value.clone());
+                    values.push((*kv.0, value.clone()));
                 } else {
                     warn!("Wrong value type reported by reporter {} for feed id {} slot {}! {} expected", kv.0, encoded_feed_id, slot, expected_feed_type.enum_type_to_string());
                 }
@@ -212,10 +302,46 @@ pub fn collect_reported_values(
     values
 }
 
+// Published numerical values of the feed as `(end_slot_timestamp, value)`, oldest first
+async fn numerical_history(
+    encoded_feed_id: EncodedFeedId,
+    history: &Arc<RwLock<FeedAggregateHistory>>,
+) -> Vec<(Timestamp, f64)> {
+    debug!("Get a read lock on history [feed {encoded_feed_id}]");
+    let history_lock = history.read().await;
+    let Some(heap) = history_lock.get(encoded_feed_id) else {
+        return Vec::new();
+    };
+    let (first, last) = heap.as_slices();
+    let res = first
+        .iter()
+        .chain(last.iter())
+        .filter_map(|h| h.value.as_f64().map(|value| (h.end_slot_timestamp, value)))
+        .collect();
+    debug!("Release the read lock on history [feed {encoded_feed_id}]");
+    res
+}
+
+pub fn new_anomaly_detector() -> Arc<Mutex<AnomalyDetector>> {
+    Arc::new(Mutex::new(AnomalyDetector::new(DetectorConfig {
+        min_training_values: AD_MIN_DATA_POINTS_THRESHOLD,
+        ..Default::default()
+    })))
+}
+
+/// The anomaly score decides whether an update is skipped, so every sequencer must compute the
+/// same score for the same slot of a feed.
+pub fn anomaly_detection_seed(encoded_feed_id: EncodedFeedId, slot: u64) -> u64 {
+    let feed_id = encoded_feed_id.0;
+    (feed_id as u64) ^ ((feed_id >> 64) as u64) ^ slot.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
 pub async fn perform_anomaly_detection(
     encoded_feed_id: EncodedFeedId,
     history: Arc<RwLock<FeedAggregateHistory>>,
+    anomaly_detector: Arc<Mutex<AnomalyDetector>>,
     candidate_value: f64,
+    seed: u64,
 ) -> Result<f64, anyhow::Error> {
     let anomaly_detection_future = async move {
         debug!("Get a read lock on history [feed {encoded_feed_id}]");
@@ -227,14 +353,13 @@ pub async fn perform_anomaly_detection(
             .get(encoded_feed_id)
             .context("Missing key from History!")?;
         let (first, last) = heap.as_slices();
-        let history_vec: Vec<&FeedType> =
-            first.iter().chain(last.iter()).map(|h| &h.value).collect();
-        let mut numerical_vec: Vec<f64> = history_vec
+        let numerical_history: Vec<(u128, f64)> = first
             .iter()
-            .filter_map(|feed| {
-                if let FeedType::Numerical(value) = feed {
-                    Some(*value)
-                } else if let FeedType::Text(_) = feed {
+            .chain(last.iter())
+            .filter_map(|h| {
+                if let Some(value) = h.value.as_f64() {
+                    Some((h.update_number, value))
+                } else if let FeedType::Text(_) = h.value {
                     warn!("Anomaly Detection not implemented for FeedType::Text, skipping...");
                     None
                 } else {
@@ -247,19 +372,12 @@ pub async fn perform_anomaly_detection(
         drop(history_lock);
         debug!("Release the read lock on history [feed {encoded_feed_id}]");
 
-        numerical_vec.push(candidate_value);
+        // The model is refreshed only with the values published since the last call
+        let mut detector = anomaly_detector.lock().await;
+        detector.ingest_history(numerical_history);
 
-        // Get AD prediction only if enough data is present
-        if numerical_vec.len() > AD_MIN_DATA_POINTS_THRESHOLD {
-            debug!("Starting anomaly detection for [feed {encoded_feed_id}]");
-            anomaly_detector_aggregate(numerical_vec).map_err(|e| anyhow!("{e}"))
-        } else {
-            Err(anyhow!(
-                "Skipping anomaly detection; numerical_vec.len() = {} threshold: {}",
-                numerical_vec.len(),
-                AD_MIN_DATA_POINTS_THRESHOLD
-            ))
-        }
+        debug!("Starting anomaly detection for [feed {encoded_feed_id}]");
+        detector.score(candidate_value, seed)
     };
 
     tokio::task::Builder::new()
@@ -284,17 +402,17 @@ fn check_aggregated_votes_deviation(
             anyhow::bail!("Failed to get latest vote for feed: {}", encoded_feed_id);
         };
 
-        let update_aggregate_value = match update.value {
-            FeedType::Numerical(v) => v,
-            _ => anyhow::bail!(
+        let update_aggregate_value = match update.value.as_f64() {
+            Some(v) => v,
+            None => anyhow::bail!(
                 "Non numeric value in update_aggregate_value for feed: {}",
                 encoded_feed_id
             ),
         };
 
-        let reporter_voted_value = match reporter_vote.value {
-            FeedType::Numerical(v) => v,
-            _ => anyhow::bail!(
+        let reporter_voted_value = match reporter_vote.value.as_f64() {
+            Some(v) => v,
+            None => anyhow::bail!(
                 "Non numeric value in reporter_vote for feed: {}",
                 encoded_feed_id
             ),
@@ -660,4 +778,88 @@ pub mod tests {
             "Unexpected error message: {error_message}"
         );
     }
+
+    async fn consume_test_reports(
+        values: &[f64],
+        num_reporters: usize,
+        aggregator: FeedAggregate,
+    ) -> ConsumedReports {
+        use blocksense_feed_registry::types::test_payload_from_result;
+
+        let reports: HashMap<u64, DataFeedPayload> = values
+            .iter()
+            .enumerate()
+            .map(|(id, value)| {
+                (
+                    id as u64,
+                    test_payload_from_result(Ok(FeedType::Numerical(*value))),
+                )
+            })
+            .collect();
+
+        consume_reports(
+            "test_feed",
+            &reports,
+            &FeedType::Numerical(0.0),
+            1,
+            50.0,
+            0.0,
+            None,
+            None,
+            0,
+            num_reporters,
+            &HashMap::new(),
+            false,
+            aggregator,
+            None,
+            None,
+            None,
+            EncodedFeedId::new(1, 0),
+            "test",
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_consume_reports_skips_when_aggregation_fails() {
+        // The average of the two values overflows
+        let consumed =
+            consume_test_reports(&[f64::MAX, f64::MAX], 2, FeedAggregate::AverageAggregator).await;
+
+        assert!(consumed.is_quorum_reached);
+        assert_eq!(
+            consumed.skip_decision,
+            SkipDecision::DoSkip(DoSkipReason::AggregationFailed)
+        );
+        assert!(consumed.result_post_to_contract.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_consume_reports_drops_non_finite_votes() {
+        let consumed = consume_test_reports(
+            &[100.0, f64::NAN, 102.0, f64::INFINITY],
+            4,
+            FeedAggregate::MedianAggregator,
+        )
+        .await;
+
+        assert!(consumed.is_quorum_reached);
+        assert_eq!(consumed.votes, 2);
+        assert_eq!(consumed.non_finite_votes, 2);
+        assert_eq!(
+            consumed.result_post_to_contract.unwrap().update.value,
+            FeedType::Numerical(101.0)
+        );
+
+        // Without the dropped votes the quorum of 50% is not reached
+        let consumed = consume_test_reports(
+            &[100.0, f64::NAN, f64::NEG_INFINITY],
+            3,
+            FeedAggregate::MedianAggregator,
+        )
+        .await;
+        assert!(!consumed.is_quorum_reached);
+        assert_eq!(consumed.votes, 1);
+        assert_eq!(consumed.non_finite_votes, 2);
+    }
 }
@@ -0,0 +1,37 @@
+use alloy::signers::local::PrivateKeySigner;
+use blocksense_gnosis_safe::stand_in;
+use blocksense_gnosis_safe::utils::create_private_key_signer;
+use blocksense_secrets::signer::DEFAULT_SIGNER_TOKEN_ENV;
+
+const USAGE: &str = "Usage: remote_signer_stand_in [<address>]";
+
+/// Signs with the hex encoded private keys in the `SIGNER_KEYS` environment variable, separated by
+/// commas, or with a random key. Clients need the token from `BLOCKSENSE_SIGNER_TOKEN`.
+#[actix_web::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() > 1 {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    }
+    let addr = args.first().map_or("127.0.0.1:8300", String::as_str);
+    let token = std::env::var(DEFAULT_SIGNER_TOKEN_ENV).unwrap_or_else(|_| "dev-token".to_string());
+
+    let (url, server, signer) = stand_in::start(addr, &token).await.unwrap();
+    let keys = match std::env::var("SIGNER_KEYS") {
+        Ok(keys) => keys
+            .split(',')
+            .map(|key| create_private_key_signer(key).expect("Invalid key in SIGNER_KEYS"))
+            .collect(),
+        Err(_) => vec![PrivateKeySigner::random()],
+    };
+    for key in keys {
+        println!("Signing for {}", signer.add_key(key));
+    }
+    println!("Signer stand-in listening on {url}");
+
+    actix_web::rt::signal::ctrl_c().await.unwrap();
+    server.stop(true).await;
+}
@@ -1,2 +1,5 @@
 pub mod data_types;
+pub mod signer;
+#[cfg(any(test, feature = "stand-in"))]
+pub mod stand_in;
 pub mod utils;
@@ -0,0 +1,352 @@
+//! Signers of the transactions the sequencer sends and of the Safe transaction hashes the
+//! reporters sign in the second consensus round. The key is read from a plain file or an
+//! encrypted keystore, or it is held by a signing service and never leaves it.
+//!
+//! A signing service is asked with a POST of `SignHashRequest` to `/sign` and answers with a
+//! `SignHashResponse`.
+
+use std::time::Duration;
+
+use alloy::consensus::SignableTransaction;
+use alloy::network::TxSigner;
+use alloy::signers::{local::PrivateKeySigner, Signer};
+use alloy_primitives::{Address, ChainId, Signature, B256};
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use blocksense_secrets::signer::{
+    SignerConfig, DEFAULT_SIGNER_TIMEOUT_MS, DEFAULT_SIGNER_TOKEN_ENV,
+};
+use blocksense_secrets::DEFAULT_KEYSTORE_PASSPHRASE_ENV;
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::create_private_key_signer;
+
+pub const SIGN_PATH: &str = "/sign";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SignHashRequest {
+    /// The service may hold several keys
+    pub address: Address,
+    pub hash: B256,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SignHashResponse {
+    /// Hex encoded `r`, `s` and `v`
+    pub signature: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    url: Url,
+    address: Address,
+    token_env: String,
+    chain_id: Option<ChainId>,
+    client: Client,
+}
+
+impl RemoteSigner {
+    /// The token is read from `token_env` on every request, so it can be rotated without a restart.
+    pub fn new(
+        base_url: &str,
+        address: Address,
+        token_env: String,
+        timeout: Duration,
+    ) -> Result<RemoteSigner> {
+        let url = format!("{}{SIGN_PATH}", base_url.trim_end_matches('/'));
+        Ok(RemoteSigner {
+            url: Url::parse(&url).with_context(|| format!("Invalid signer URL {base_url:?}"))?,
+            address,
+            token_env,
+            chain_id: None,
+            client: Client::builder().timeout(timeout).build()?,
+        })
+    }
+
+    async fn request_signature(&self, hash: &B256) -> Result<Signature> {
+        let token = std::env::var(&self.token_env).with_context(|| {
+            format!(
+                "Environment variable {} with the signer token is not set",
+                self.token_env
+            )
+        })?;
+        let response = self
+            .client
+            .post(self.url.clone())
+            .bearer_auth(token)
+            .json(&SignHashRequest {
+                address: self.address,
+                hash: *hash,
+            })
+            .send()
+            .await
+            .with_context(|| format!("Could not reach signer {}", self.url))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!(
+                "Signer {} did not sign for {}: {status} {body}",
+                self.url,
+                self.address
+            );
+        }
+        let response: SignHashResponse = response
+            .json()
+            .await
+            .with_context(|| format!("Invalid response from signer {}", self.url))?;
+        let signature: Signature = response
+            .signature
+            .parse()
+            .with_context(|| format!("Invalid signature from signer {}", self.url))?;
+        // A signature by any other key would only be rejected on chain
+        let recovered = signature.recover_address_from_prehash(hash)?;
+        if recovered != self.address {
+            bail!(
+                "Signer {} signed with {recovered} instead of {}",
+                self.url,
+                self.address
+            );
+        }
+        Ok(signature)
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn sign_hash(&self, hash: &B256) -> alloy::signers::Result<Signature> {
+        self.request_signature(hash)
+            .await
+            .map_err(alloy::signers::Error::other)
+    }
+
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        self.chain_id
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        self.chain_id = chain_id;
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> alloy::signers::Result<Signature> {
+        if let Some(chain_id) = self.chain_id {
+            if !tx.set_chain_id_checked(chain_id) {
+                return Err(alloy::signers::Error::TransactionChainIdMismatch {
+                    signer: chain_id,
+                    tx: tx.chain_id().unwrap_or_default(),
+                });
+            }
+        }
+        self.sign_hash(&tx.signature_hash()).await
+    }
+}
+
+/// Signer built from a `SignerConfig`. It can be used wherever a `PrivateKeySigner` was, also
+/// to build an `EthereumWallet`.
+#[derive(Clone, Debug)]
+pub enum AnySigner {
+    Local(PrivateKeySigner),
+    Remote(RemoteSigner),
+}
+
+impl AnySigner {
+    pub fn from_config(config: &SignerConfig) -> Result<AnySigner> {
+        match config {
+            SignerConfig::PrivateKeyFile { path } => {
+                let key = std::fs::read_to_string(path).with_context(|| {
+                    format!("Could not read private key from {}", path.display())
+                })?;
+                let signer = create_private_key_signer(&key)
+                    .with_context(|| format!("Invalid private key in {}", path.display()))?;
+                Ok(AnySigner::Local(signer))
+            }
+            SignerConfig::Keystore { path, password_env } => {
+                let password_env = password_env
+                    .as_deref()
+                    .unwrap_or(DEFAULT_KEYSTORE_PASSPHRASE_ENV);
+                let password = std::env::var(password_env).with_context(|| {
+                    format!(
+                        "Environment variable {password_env} with the password of keystore {} is not set",
+                        path.display()
+                    )
+                })?;
+                let signer =
+                    PrivateKeySigner::decrypt_keystore(path, password).with_context(|| {
+                        format!(
+                            "Could not decrypt keystore {}, is the password right?",
+                            path.display()
+                        )
+                    })?;
+                Ok(AnySigner::Local(signer))
+            }
+            SignerConfig::Remote {
+                url,
+                address,
+                token_env,
+                timeout_ms,
+            } => {
+                let address = address
+                    .parse()
+                    .with_context(|| format!("Invalid address {address} of signer {url}"))?;
+                let signer = RemoteSigner::new(
+                    url,
+                    address,
+                    token_env
+                        .clone()
+                        .unwrap_or_else(|| DEFAULT_SIGNER_TOKEN_ENV.to_string()),
+                    Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_SIGNER_TIMEOUT_MS)),
+                )?;
+                Ok(AnySigner::Remote(signer))
+            }
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        match self {
+            AnySigner::Local(signer) => signer.address(),
+            AnySigner::Remote(signer) => signer.address,
+        }
+    }
+}
+
+impl From<PrivateKeySigner> for AnySigner {
+    fn from(signer: PrivateKeySigner) -> Self {
+        AnySigner::Local(signer)
+    }
+}
+
+#[async_trait]
+impl Signer for AnySigner {
+    async fn sign_hash(&self, hash: &B256) -> alloy::signers::Result<Signature> {
+        match self {
+            AnySigner::Local(signer) => signer.sign_hash(hash).await,
+            AnySigner::Remote(signer) => signer.sign_hash(hash).await,
+        }
+    }
+
+    fn address(&self) -> Address {
+        AnySigner::address(self)
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        match self {
+            AnySigner::Local(signer) => Signer::chain_id(signer),
+            AnySigner::Remote(signer) => signer.chain_id,
+        }
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        match self {
+            AnySigner::Local(signer) => signer.set_chain_id(chain_id),
+            AnySigner::Remote(signer) => signer.set_chain_id(chain_id),
+        }
+    }
+}
+
+#[async_trait]
+impl TxSigner<Signature> for AnySigner {
+    fn address(&self) -> Address {
+        AnySigner::address(self)
+    }
+
+    async fn sign_transaction(
+        &self,
+        tx: &mut dyn SignableTransaction<Signature>,
+    ) -> alloy::signers::Result<Signature> {
+        match self {
+            AnySigner::Local(signer) => signer.sign_transaction(tx).await,
+            AnySigner::Remote(signer) => signer.sign_transaction(tx).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in;
+    use crate::utils::sign_hash;
+
+    // First Anvil account
+    const PRIVATE_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn local_signers_are_read_from_files_and_keystores() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_path = dir.path().join("key");
+        std::fs::write(&key_path, format!("{PRIVATE_KEY}\n")).unwrap();
+        let from_file = AnySigner::from_config(&SignerConfig::PrivateKeyFile {
+            path: key_path.clone(),
+        })
+        .unwrap();
+        let expected = create_private_key_signer(PRIVATE_KEY).unwrap().address();
+        assert_eq!(from_file.address(), expected);
+
+        std::fs::write(&key_path, "not a key").unwrap();
+        let err =
+            AnySigner::from_config(&SignerConfig::PrivateKeyFile { path: key_path }).unwrap_err();
+        assert!(!format!("{err:?}").contains("not a key"));
+
+        let mut rng = rand::thread_rng();
+        let (_, name) = PrivateKeySigner::encrypt_keystore(
+            dir.path(),
+            &mut rng,
+            hex::decode(PRIVATE_KEY).unwrap(),
+            "keystore-password",
+            None,
+        )
+        .unwrap();
+        std::env::set_var("BLOCKSENSE_SIGNER_TEST_PASSWORD", "keystore-password");
+        let config = SignerConfig::Keystore {
+            path: dir.path().join(name),
+            password_env: Some("BLOCKSENSE_SIGNER_TEST_PASSWORD".to_string()),
+        };
+        assert_eq!(AnySigner::from_config(&config).unwrap().address(), expected);
+
+        std::env::set_var("BLOCKSENSE_SIGNER_TEST_PASSWORD", "wrong-password");
+        assert!(AnySigner::from_config(&config).is_err());
+    }
+
+    #[actix_web::test]
+    async fn remote_signer_signs_with_the_key_of_the_service() {
+        let (url, server, service) = stand_in::start("127.0.0.1:0", "test-token").await.unwrap();
+        let key = create_private_key_signer(PRIVATE_KEY).unwrap();
+        let address = service.add_key(key.clone());
+        std::env::set_var("BLOCKSENSE_SIGNER_TEST_TOKEN", "test-token");
+        let config = |address: Address| SignerConfig::Remote {
+            url: url.to_string(),
+            address: address.to_string(),
+            token_env: Some("BLOCKSENSE_SIGNER_TEST_TOKEN".to_string()),
+            timeout_ms: None,
+        };
+
+        let signer = AnySigner::from_config(&config(address)).unwrap();
+        let hash = B256::repeat_byte(7);
+        let remote = sign_hash(&signer, &hash).await.unwrap();
+        let local = sign_hash(&key, &hash).await.unwrap();
+        assert_eq!(remote.signature, local.signature);
+        assert_eq!(remote.signer_address, address);
+
+        // The service does not hold this key
+        let unknown = AnySigner::from_config(&config(Address::repeat_byte(1))).unwrap();
+        assert!(unknown.sign_hash(&hash).await.is_err());
+
+        std::env::set_var("BLOCKSENSE_SIGNER_TEST_TOKEN", "wrong-token");
+        assert!(signer.sign_hash(&hash).await.is_err());
+
+        server.stop(true).await;
+    }
+}
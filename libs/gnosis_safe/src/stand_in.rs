@@ -0,0 +1,86 @@
+//! In-memory signing service answering like the services `signer::RemoteSigner` talks to. Used
+//! in tests and for local development, see `bin/remote_signer_stand_in.rs`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_web::dev::ServerHandle;
+use actix_web::http::header;
+use actix_web::{post, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use alloy::signers::{local::PrivateKeySigner, SignerSync};
+use alloy_primitives::Address;
+use anyhow::{Context, Result};
+use reqwest::Url;
+
+use crate::signer::{SignHashRequest, SignHashResponse};
+
+pub struct StandInSigner {
+    token: String,
+    keys: Mutex<HashMap<Address, PrivateKeySigner>>,
+}
+
+impl StandInSigner {
+    /// Adds a key and returns its address, which the clients sign with.
+    pub fn add_key(&self, key: PrivateKeySigner) -> Address {
+        let address = key.address();
+        self.keys.lock().unwrap().insert(address, key);
+        address
+    }
+
+    fn is_authorized(&self, request: &HttpRequest) -> bool {
+        request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            == Some(self.token.as_str())
+    }
+}
+
+#[post("/sign")]
+async fn sign(
+    request: HttpRequest,
+    body: web::Json<SignHashRequest>,
+    signer: web::Data<StandInSigner>,
+) -> impl Responder {
+    if !signer.is_authorized(&request) {
+        return HttpResponse::Unauthorized().body("Invalid signer token");
+    }
+    let keys = signer.keys.lock().unwrap();
+    let Some(key) = keys.get(&body.address) else {
+        return HttpResponse::NotFound().body(format!("Unknown key {}", body.address));
+    };
+    match key.sign_hash_sync(&body.hash) {
+        Ok(signature) => HttpResponse::Ok().json(SignHashResponse {
+            signature: hex::encode(signature.as_bytes()),
+        }),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Starts the stand-in in the background and returns its URL and its keys. `addr` may use port 0
+/// to pick any free port.
+pub async fn start(
+    addr: &str,
+    token: &str,
+) -> Result<(Url, ServerHandle, web::Data<StandInSigner>)> {
+    let signer = web::Data::new(StandInSigner {
+        token: token.to_string(),
+        keys: Mutex::new(HashMap::new()),
+    });
+    let app_signer = signer.clone();
+    let server = HttpServer::new(move || App::new().app_data(app_signer.clone()).service(sign))
+        .workers(1)
+        .bind(addr)
+        .with_context(|| format!("Could not bind signer stand-in to {addr}"))?;
+    let local_addr = *server
+        .addrs()
+        .first()
+        .context("Signer stand-in is not bound to any address")?;
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+    let url = Url::parse(&format!("http://{local_addr}"))?;
+    tracing::info!("Signer stand-in listening on {url}");
+    Ok((url, handle, signer))
+}
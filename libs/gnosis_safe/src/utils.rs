@@ -88,11 +88,15 @@ pub fn generate_transaction_hash(safe_address: Address, chain_id: U256, data: Sa
     keccak256(parts)
 }
 
-pub fn create_private_key_signer(private_key: &str) -> PrivateKeySigner {
-    PrivateKeySigner::from_bytes(&B256::new(
-        hex::decode(private_key).unwrap().try_into().unwrap(),
-    ))
-    .unwrap()
+/// The error does not mention the key, so it can be logged.
+pub fn create_private_key_signer(private_key: &str) -> anyhow::Result<PrivateKeySigner> {
+    let private_key = private_key.trim();
+    let bytes: [u8; 32] = hex::decode(private_key.strip_prefix("0x").unwrap_or(private_key))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Private key is not 32 hex encoded bytes"))?;
+    PrivateKeySigner::from_bytes(&B256::new(bytes))
+        .map_err(|_| anyhow::anyhow!("Private key is not a valid secp256k1 key"))
 }
 
 pub fn hex_str_to_bytes32(hex_string: &str) -> anyhow::Result<FixedBytes<32>> {
@@ -113,8 +117,8 @@ pub fn hex_str_to_bytes32(hex_string: &str) -> anyhow::Result<FixedBytes<32>> {
     Ok(FixedBytes::<32>::new(bytes))
 }
 
-pub async fn sign_hash(
-    owner: &PrivateKeySigner,
+pub async fn sign_hash<S: Signer + Sync>(
+    owner: &S,
     tx_hash: &FixedBytes<32>,
 ) -> anyhow::Result<SignatureWithAddress> {
     let signature = owner.sign_hash(tx_hash).await?;
@@ -59,6 +59,10 @@ pub static ref REPORTER_FAILED_WASM_EXECS: IntCounterVec =
     register_int_counter_vec!("FAILED_WASM_EXECS",
         "Count of failed wasm executions", &["oracle_id"]).unwrap();
 
+pub static ref REPORTER_WASM_ERRORS: IntCounterVec =
+    register_int_counter_vec!("WASM_ERRORS",
+        "Count of errors returned by oracle scripts by kind", &["oracle_id", "kind"]).unwrap();
+
 pub static ref REPORTER_FAILED_SEQ_REQUESTS: IntCounterVec =
     register_int_counter_vec!("FAILED_SEQ_REQUESTS",
         "Count of failed sequncer requests", &["code"]).unwrap();
@@ -172,6 +176,9 @@ pub struct ProviderMetrics {
     pub total_mismatched_gnosis_safe_nonce: IntCounterVec,
     pub num_transactions_in_queue: IntGaugeVec,
     pub is_enabled: IntGaugeVec,
+    pub success_rpc_endpoint_calls: IntCounterVec,
+    pub failed_rpc_endpoint_calls: IntCounterVec,
+    pub active_rpc_endpoint: IntGaugeVec,
 }
 
 impl ProviderMetrics {
@@ -278,6 +285,21 @@ impl ProviderMetrics {
                 "Whether the network is currently enabled or not",
                 &["Network"]
             )?,
+            success_rpc_endpoint_calls: register_int_counter_vec!(
+                format!("{}success_rpc_endpoint_calls", prefix),
+                "Total number of RPC calls served by the endpoint of the network",
+                &["Network", "Endpoint", "Call"]
+            )?,
+            failed_rpc_endpoint_calls: register_int_counter_vec!(
+                format!("{}failed_rpc_endpoint_calls", prefix),
+                "Total number of RPC calls that failed on the endpoint of the network",
+                &["Network", "Endpoint", "Call"]
+            )?,
+            active_rpc_endpoint: register_int_gauge_vec!(
+                format!("{}active_rpc_endpoint", prefix),
+                "Whether the endpoint is the one the network is currently sending to",
+                &["Network", "Endpoint"]
+            )?,
         })
     }
 }
@@ -346,10 +368,13 @@ pub struct FeedsMetrics {
     pub quorums_reached: IntCounterVec,
     pub failures_to_reach_quorum: IntCounterVec,
     pub updates_to_networks: IntCounterVec,
+    pub dropped_non_finite_votes: IntCounterVec,
 
     // skip-publishing related metrics
     pub skipped_too_similar_too_soon: IntCounterVec,
     pub skipped_nothing_to_post: IntCounterVec,
+    pub skipped_aggregation_failed: IntCounterVec,
+    pub skipped_anomaly_detected: IntCounterVec,
 
     pub updated_threshold_crossed: IntCounterVec,
     pub updated_heartbeat_timed_out: IntCounterVec,
@@ -377,6 +402,11 @@ impl FeedsMetrics {
                 "Number of updates for a given feed id per Network",
                 &["FeedId", "Network"]
             )?,
+            dropped_non_finite_votes: register_int_counter_vec!(
+                format!("{}dropped_non_finite_votes", prefix),
+                "Number of votes for a given feed id dropped before aggregation, because their value was NaN or infinite",
+                &["FeedId"]
+            )?,
 
             skipped_too_similar_too_soon: register_int_counter_vec!(
                 format!("{}skipped_too_similar_too_soon", prefix),
@@ -388,6 +418,16 @@ impl FeedsMetrics {
                 "Number of updates skipped for a given feed, because there was nothing to post",
                 &["FeedId"]
             )?,
+            skipped_aggregation_failed: register_int_counter_vec!(
+                format!("{}skipped_aggregation_failed", prefix),
+                "Number of updates skipped for a given feed, because the reported values could not be aggregated",
+                &["FeedId"]
+            )?,
+            skipped_anomaly_detected: register_int_counter_vec!(
+                format!("{}skipped_anomaly_detected", prefix),
+                "Number of updates skipped for a given feed, because the anomaly score was above the feed's threshold",
+                &["FeedId"]
+            )?,
 
             updated_threshold_crossed: register_int_counter_vec!(
                 format!("{}updated_threshold_crossed", prefix),
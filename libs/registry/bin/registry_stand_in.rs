@@ -0,0 +1,30 @@
+use blocksense_registry::{config::FeedsResponse, stand_in};
+
+const USAGE: &str = "Usage: registry_stand_in [<address>] [<feeds_config_json>]";
+
+#[actix_web::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() > 2 {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    }
+    let addr = args.first().map_or("127.0.0.1:8099", String::as_str);
+    let feeds = match args.get(1) {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path).expect("Could not read feeds config");
+            let feeds: FeedsResponse =
+                serde_json::from_str(&contents).expect("Invalid feeds config");
+            feeds.feeds
+        }
+        None => vec![],
+    };
+
+    let (url, server) = stand_in::start(addr, feeds).await.unwrap();
+    println!("Registry stand-in listening on {url}");
+
+    actix_web::rt::signal::ctrl_c().await.unwrap();
+    server.stop(true).await;
+}
@@ -1,6 +1,8 @@
+use blocksense_secrets_config::signer::SignerConfig;
+use blocksense_secrets_config::SecretsConfig;
 use blocksense_utils::FeedId;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -31,6 +33,33 @@ pub struct AssetPair {
 pub struct FeedQuorum {
     pub percentage: f32,
     pub aggregation: String,
+    /// Parameters of the aggregation, if not set the defaults of the aggregator are used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aggregation_params: Option<AggregationParams>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct AggregationParams {
+    /// Percentage of values dropped from each end by `trimmed_mean`
+    #[serde(default)]
+    pub trim_percentage: Option<f64>,
+    /// Length of the averaging window of `twap`
+    #[serde(default)]
+    pub twap_window_ms: Option<u64>,
+}
+
+impl AggregationParams {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(trim_percentage) = self.trim_percentage {
+            if !(0.0..50.0).contains(&trim_percentage) {
+                anyhow::bail!("trim_percentage must be in [0, 50), got {trim_percentage}");
+            }
+        }
+        if self.twap_window_ms == Some(0) {
+            anyhow::bail!("twap_window_ms cannot be set to 0");
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -39,6 +68,10 @@ pub struct FeedSchedule {
     pub heartbeat_ms: Option<u128>,
     pub deviation_percentage: f32,
     pub first_report_start_unix_time_ms: u64,
+    /// Anomaly score in [0, 1] above which an update is not published. Anomaly detection runs
+    /// only for feeds that set it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anomaly_score_threshold: Option<f64>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
@@ -69,14 +102,25 @@ pub struct FeedConfig {
     pub stride: u8,
     pub quorum: FeedQuorum,
     pub schedule: FeedSchedule,
+    /// Number of aggregated values kept in the history of the feed, `DEFAULT_HISTORY_DEPTH` if
+    /// not set. Slow feeds need a longer history for the deviation and anomaly checks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_depth: Option<usize>,
     pub additional_feed_info: PriceFeedInfo,
     pub compatibility_info: Option<CompatibilityInfo>,
 }
 
+/// The depth every feed had before it became configurable.
+pub const DEFAULT_HISTORY_DEPTH: usize = 10_000;
+
 impl FeedConfig {
     pub fn compare(left: &FeedConfig, right: &FeedConfig) -> std::cmp::Ordering {
         left.id.cmp(&right.id)
     }
+
+    pub fn get_history_depth(&self) -> usize {
+        self.history_depth.unwrap_or(DEFAULT_HISTORY_DEPTH)
+    }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -98,8 +142,36 @@ pub struct ReporterInfo {
     pub secret_key: String,
     /// Reporter secret key for second consensus
     pub second_consensus_secret_key: Option<String>,
+    /// Where the key for second consensus is kept, used instead of `second_consensus_secret_key`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub second_consensus_signer: Option<SignerConfig>,
     /// Reporter id
     pub reporter_id: u64,
+    /// Largest expected difference in ms between the clocks of the reporter and the sequencer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_skew_tolerance_ms: Option<u64>,
+    /// Limits and caching of the requests the reporter proxies to each upstream host
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub upstream_hosts: BTreeMap<String, UpstreamHostConfig>,
+    /// Where the secrets referred to by capabilities are kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SecretsConfig>,
+}
+
+/// Limits and caching for one upstream host, keyed by an allowed outbound host such as
+/// `https://api.example.com` or just by the host name.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct UpstreamHostConfig {
+    /// Most requests sent to the host at the same time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_requests: Option<usize>,
+    /// Least time between the start of two requests to the host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_request_interval_ms: Option<u64>,
+    /// How long a successful response is served again without asking the host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_ttl_ms: Option<u64>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -143,6 +215,7 @@ pub struct OracleScript {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Capability {
     pub id: String,
+    /// Path of a file with the value, or a reference to a secret such as `env:CMC_API_KEY`
     pub data: String,
 }
 
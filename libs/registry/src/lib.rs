@@ -1,3 +1,5 @@
 pub mod config;
 mod custom_serde;
 pub mod registry;
+#[cfg(any(test, feature = "stand-in"))]
+pub mod stand_in;
@@ -1,28 +1,256 @@
-use crate::config::BlocksenseConfig;
+use std::collections::BTreeSet;
 
-#[derive(Debug)]
-pub struct Registry;
+use alloy_primitives::keccak256;
+use anyhow::{Context, Result};
+use blocksense_utils::FeedId;
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::config::{
+    BlocksenseConfig, FeedConfig, FeedsResponse, OracleScript, OraclesResponse, ReporterInfo,
+};
+
+pub const GET_ORACLE_SCRIPTS: &str = "/get_oracle_scripts";
+pub const GET_FEEDS_CONFIG: &str = "/get_feeds_config";
+pub const GET_ORACLE_SCRIPT_WASM: &str = "/get_oracle_script_wasm";
+pub const GET_ORACLE_MANIFEST: &str = "/get_oracle_manifest";
+pub const PUBLISH_ORACLE_SCRIPT: &str = "/publish_oracle_script";
+pub const RETIRE_ORACLE_SCRIPT: &str = "/retire_oracle_script";
+
+/// Everything the registry needs to know about a published oracle script.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct OracleManifest {
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval_time_in_seconds: Option<u64>,
+    /// Data feeds the oracle script reports
+    #[serde(default)]
+    pub feeds: Vec<FeedId>,
+    #[serde(default)]
+    pub capabilities: BTreeSet<String>,
+    #[serde(default)]
+    pub allowed_outbound_hosts: Vec<String>,
+    /// Keccak-256 hash of the wasm, see `content_hash`
+    pub content_hash: String,
+}
+
+impl OracleManifest {
+    /// The oracle script as a node operator configures it, with the wasm saved as `<id>.wasm`.
+    pub fn to_oracle_script(&self) -> OracleScript {
+        OracleScript {
+            id: self.id.clone(),
+            interval_time_in_seconds: self.interval_time_in_seconds,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            oracle_script_wasm: format!("{}.wasm", self.id),
+            allowed_outbound_hosts: self.allowed_outbound_hosts.clone(),
+            capabilities: self.capabilities.iter().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PublishOracleRequest {
+    pub manifest: OracleManifest,
+    /// Hex encoded wasm
+    pub wasm: String,
+}
+
+pub fn content_hash(wasm: &[u8]) -> String {
+    keccak256(wasm).to_string()
+}
+
+pub fn check_content_hash(manifest: &OracleManifest, wasm: &[u8]) -> Result<()> {
+    if manifest.content_hash != content_hash(wasm) {
+        anyhow::bail!(
+            "Content hash of oracle script {} does not match its wasm",
+            manifest.id
+        );
+    }
+    Ok(())
+}
+
+/// Client of the Blocksense registry.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    base_url: Url,
+    client: Client,
+}
 
 impl Registry {
-    pub async fn get_config() -> anyhow::Result<BlocksenseConfig> {
-        // TODO(adikov): Get the configuration from the sequencer when the implementation is
-        let json = r#"
-        {
-          "oracles": [{
-            "id": "revolut-example",
-            "oracle_script_wasm": "revolut-example.wasm",
-            "data_feeds": [{
-                "id": "USD/BTC",
-                "interval": 10
-              }, {
-                "id": "USD/ETH",
-                "interval": 15
-            }]
-          }],
-          "capabilities": []
+    pub fn new(base_url: &str) -> Result<Registry> {
+        let base_url =
+            Url::parse(base_url).with_context(|| format!("Invalid registry URL {base_url:?}"))?;
+        Ok(Registry {
+            base_url,
+            client: Client::new(),
+        })
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.base_url
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url> {
+        Ok(self.base_url.join(path)?)
+    }
+
+    pub async fn get_oracle_scripts(&self) -> Result<Vec<OracleScript>> {
+        let url = self.endpoint(GET_ORACLE_SCRIPTS)?;
+        tracing::info!("Getting oracles config from {:?}", url);
+        let response = check_response(self.client.get(url).send().await?).await?;
+        let response: OraclesResponse = serde_json::from_str(&response.text().await?)?;
+        Ok(response.oracles)
+    }
+
+    pub async fn get_feeds(&self) -> Result<Vec<FeedConfig>> {
+        let url = self.endpoint(GET_FEEDS_CONFIG)?;
+        tracing::info!("Getting data feed config from {:?}", url);
+        let response = check_response(self.client.get(url).send().await?).await?;
+        let response: FeedsResponse = serde_json::from_str(&response.text().await?)?;
+        Ok(response.feeds)
+    }
+
+    /// Node operator configuration with the oracle scripts and data feeds currently in the
+    /// registry.
+    pub async fn get_config(&self) -> Result<BlocksenseConfig> {
+        Ok(BlocksenseConfig {
+            reporter_info: ReporterInfo {
+                registry: self.base_url.to_string(),
+                ..Default::default()
+            },
+            oracles: self.get_oracle_scripts().await?,
+            capabilities: vec![],
+            data_feeds: self.get_feeds().await?,
+        })
+    }
+
+    pub async fn get_oracle_manifest(&self, oracle_id: &str) -> Result<OracleManifest> {
+        let url = self.endpoint(&format!("{GET_ORACLE_MANIFEST}/{oracle_id}"))?;
+        let response = check_response(self.client.get(url).send().await?).await?;
+        Ok(serde_json::from_str(&response.text().await?)?)
+    }
+
+    /// Downloads the wasm of the oracle script and checks it against the content hash in its
+    /// manifest.
+    pub async fn download_oracle_wasm(&self, oracle_id: &str) -> Result<Vec<u8>> {
+        let manifest = self.get_oracle_manifest(oracle_id).await?;
+        let url = self.endpoint(&format!("{GET_ORACLE_SCRIPT_WASM}/{oracle_id}"))?;
+        let response = check_response(self.client.get(url).send().await?).await?;
+        let wasm = response.bytes().await?.to_vec();
+        check_content_hash(&manifest, &wasm)?;
+        Ok(wasm)
+    }
+
+    pub async fn publish_oracle(&self, manifest: &OracleManifest, wasm: &[u8]) -> Result<()> {
+        check_content_hash(manifest, wasm)?;
+        let url = self.endpoint(PUBLISH_ORACLE_SCRIPT)?;
+        tracing::info!("Publishing oracle script {} to {:?}", manifest.id, url);
+        let request = PublishOracleRequest {
+            manifest: manifest.clone(),
+            wasm: hex::encode(wasm),
+        };
+        check_response(self.client.post(url).json(&request).send().await?).await?;
+        Ok(())
+    }
+
+    /// Marks the oracle script as retired, so it is no longer handed out to node operators.
+    pub async fn retire_oracle(&self, oracle_id: &str) -> Result<()> {
+        let url = self.endpoint(&format!("{RETIRE_ORACLE_SCRIPT}/{oracle_id}"))?;
+        tracing::info!("Retiring oracle script {oracle_id} at {:?}", url);
+        check_response(self.client.post(url).send().await?).await?;
+        Ok(())
+    }
+}
+
+async fn check_response(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let url = response.url().clone();
+    let body = response.text().await.unwrap_or_default();
+    anyhow::bail!("Registry request to {url} failed with {status}: {body}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in;
+
+    fn test_manifest(wasm: &[u8]) -> OracleManifest {
+        OracleManifest {
+            id: "my-oracle".to_string(),
+            name: None,
+            description: Some("Test oracle".to_string()),
+            interval_time_in_seconds: Some(10),
+            feeds: vec![0, 1],
+            capabilities: BTreeSet::from(["API_KEY".to_string()]),
+            allowed_outbound_hosts: vec!["https://api.example.com".to_string()],
+            content_hash: content_hash(wasm),
         }
-            "#;
-        let config: BlocksenseConfig = serde_json::from_str(json).expect("Failed to parse json.");
-        Ok(config)
+    }
+
+    #[actix_web::test]
+    async fn publish_and_retire_oracle() {
+        let (url, server) = stand_in::start("127.0.0.1:0", vec![]).await.unwrap();
+        let registry = Registry::new(url.as_str()).unwrap();
+
+        let wasm = b"\0asm test oracle".to_vec();
+        let manifest = test_manifest(&wasm);
+        registry.publish_oracle(&manifest, &wasm).await.unwrap();
+
+        let config = registry.get_config().await.unwrap();
+        assert_eq!(config.oracles.len(), 1);
+        assert_eq!(config.oracles[0].id, "my-oracle");
+        assert_eq!(config.oracles[0].oracle_script_wasm, "my-oracle.wasm");
+        assert!(config.oracles[0].capabilities.contains("API_KEY"));
+        assert_eq!(
+            registry.download_oracle_wasm("my-oracle").await.unwrap(),
+            wasm
+        );
+        assert_eq!(
+            registry.get_oracle_manifest("my-oracle").await.unwrap(),
+            manifest
+        );
+
+        registry.retire_oracle("my-oracle").await.unwrap();
+        assert!(registry.get_oracle_scripts().await.unwrap().is_empty());
+        assert!(registry.download_oracle_wasm("my-oracle").await.is_err());
+        assert!(registry.retire_oracle("unknown").await.is_err());
+        assert!(registry.get_oracle_manifest("my-oracle").await.is_err());
+
+        server.stop(true).await;
+    }
+
+    #[actix_web::test]
+    async fn publish_rejects_mismatched_content_hash() {
+        let (url, server) = stand_in::start("127.0.0.1:0", vec![]).await.unwrap();
+        let registry = Registry::new(url.as_str()).unwrap();
+
+        let wasm = b"\0asm test oracle".to_vec();
+        let manifest = test_manifest(b"\0asm other oracle");
+        assert!(registry.publish_oracle(&manifest, &wasm).await.is_err());
+        assert!(check_content_hash(&test_manifest(&wasm), &wasm).is_ok());
+
+        // The stand-in checks the hash as well
+        let request = PublishOracleRequest {
+            manifest,
+            wasm: hex::encode(&wasm),
+        };
+        let response = Client::new()
+            .post(url.join(PUBLISH_ORACLE_SCRIPT).unwrap())
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        server.stop(true).await;
     }
 }
@@ -0,0 +1,142 @@
+//! In-memory registry serving the same endpoints as the real one. Used in tests and for local
+//! development of oracle scripts, see `bin/registry_stand_in.rs`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use actix_web::dev::ServerHandle;
+use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
+use anyhow::{Context, Result};
+use url::Url;
+
+use crate::config::{FeedConfig, FeedsResponse, OraclesResponse};
+use crate::registry::{content_hash, OracleManifest, PublishOracleRequest};
+
+struct PublishedOracle {
+    manifest: OracleManifest,
+    wasm: Vec<u8>,
+    retired: bool,
+}
+
+#[derive(Default)]
+pub struct StandInState {
+    oracles: Mutex<BTreeMap<String, PublishedOracle>>,
+    feeds: Vec<FeedConfig>,
+}
+
+#[get("/get_oracle_scripts")]
+async fn get_oracle_scripts(state: web::Data<StandInState>) -> impl Responder {
+    let oracles = state.oracles.lock().unwrap();
+    HttpResponse::Ok().json(OraclesResponse {
+        oracles: oracles
+            .values()
+            .filter(|oracle| !oracle.retired)
+            .map(|oracle| oracle.manifest.to_oracle_script())
+            .collect(),
+    })
+}
+
+#[get("/get_feeds_config")]
+async fn get_feeds_config(state: web::Data<StandInState>) -> impl Responder {
+    HttpResponse::Ok().json(FeedsResponse {
+        feeds: state.feeds.clone(),
+    })
+}
+
+#[get("/get_oracle_script_wasm/{id}")]
+async fn get_oracle_script_wasm(
+    id: web::Path<String>,
+    state: web::Data<StandInState>,
+) -> impl Responder {
+    let oracles = state.oracles.lock().unwrap();
+    match oracles.get(id.as_str()) {
+        Some(oracle) if !oracle.retired => HttpResponse::Ok()
+            .content_type("application/wasm")
+            .body(oracle.wasm.clone()),
+        _ => HttpResponse::NotFound().body(format!("Unknown oracle script {id}")),
+    }
+}
+
+#[get("/get_oracle_manifest/{id}")]
+async fn get_oracle_manifest(
+    id: web::Path<String>,
+    state: web::Data<StandInState>,
+) -> impl Responder {
+    let oracles = state.oracles.lock().unwrap();
+    match oracles.get(id.as_str()) {
+        Some(oracle) if !oracle.retired => HttpResponse::Ok().json(&oracle.manifest),
+        _ => HttpResponse::NotFound().body(format!("Unknown oracle script {id}")),
+    }
+}
+
+#[post("/publish_oracle_script")]
+async fn publish_oracle_script(
+    request: web::Json<PublishOracleRequest>,
+    state: web::Data<StandInState>,
+) -> impl Responder {
+    let PublishOracleRequest { manifest, wasm } = request.into_inner();
+    let wasm = match hex::decode(&wasm) {
+        Ok(wasm) => wasm,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid wasm encoding: {e}")),
+    };
+    if manifest.content_hash != content_hash(&wasm) {
+        return HttpResponse::BadRequest().body("Content hash does not match the wasm");
+    }
+    // Publishing an oracle script again replaces it, and brings back a retired one
+    state.oracles.lock().unwrap().insert(
+        manifest.id.clone(),
+        PublishedOracle {
+            manifest,
+            wasm,
+            retired: false,
+        },
+    );
+    HttpResponse::Ok().finish()
+}
+
+#[post("/retire_oracle_script/{id}")]
+async fn retire_oracle_script(
+    id: web::Path<String>,
+    state: web::Data<StandInState>,
+) -> impl Responder {
+    let mut oracles = state.oracles.lock().unwrap();
+    match oracles.get_mut(id.as_str()) {
+        Some(oracle) => {
+            oracle.retired = true;
+            HttpResponse::Ok().finish()
+        }
+        None => HttpResponse::NotFound().body(format!("Unknown oracle script {id}")),
+    }
+}
+
+/// Starts the stand-in in the background and returns its URL. `addr` may use port 0 to pick any
+/// free port.
+pub async fn start(addr: &str, feeds: Vec<FeedConfig>) -> Result<(Url, ServerHandle)> {
+    let state = web::Data::new(StandInState {
+        oracles: Default::default(),
+        feeds,
+    });
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .service(get_oracle_scripts)
+            .service(get_feeds_config)
+            .service(get_oracle_script_wasm)
+            .service(get_oracle_manifest)
+            .service(publish_oracle_script)
+            .service(retire_oracle_script)
+    })
+    .workers(1)
+    .bind(addr)
+    .with_context(|| format!("Could not bind registry stand-in to {addr}"))?;
+    let local_addr = *server
+        .addrs()
+        .first()
+        .context("Registry stand-in is not bound to any address")?;
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+    let url = Url::parse(&format!("http://{local_addr}"))?;
+    tracing::info!("Registry stand-in listening on {url}");
+    Ok((url, handle))
+}
@@ -1,2 +1,4 @@
 pub mod price_data;
 pub mod sports_data;
+
+pub use blocksense_market_calendar as market_calendar;
@@ -0,0 +1,288 @@
+//! Conversion of prices between quote currencies. The fetched pairs make up a graph of rates
+//! between currencies, and a price in one quote is converted to another along the path with the
+//! fewest hops, preferring the rates with the most price points behind them.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+
+use crate::price_data::traits::prices_fetcher::PricePoint;
+use crate::price_data::types::ProviderPriceData;
+
+/// Longest path of rates used for a conversion
+pub const MAX_HOPS: usize = 3;
+
+/// Quote currencies of symbols without a separator, longest first so that `BTCUSDT` is not
+/// taken for a USD pair
+const KNOWN_QUOTES: &[&str] = &[
+    "FDUSD", "PYUSD", "USDT", "USDC", "TUSD", "BUSD", "USDE", "DAI", "USD", "EUR", "GBP", "JPY",
+    "TRY", "BRL", "KRW", "AUD", "CAD", "CHF", "BTC", "XBT", "ETH", "BNB", "SOL",
+];
+
+/// Kraken prefixes its older assets with X and fiat currencies with Z
+const KRAKEN_QUOTES: &[&str] = &[
+    "ZUSD", "ZEUR", "ZGBP", "ZJPY", "ZCAD", "ZAUD", "XXBT", "XETH",
+];
+
+fn normalize_currency(provider: &str, currency: &str) -> String {
+    let currency = match currency.len() {
+        4 if provider == "Kraken" && currency.starts_with(['X', 'Z']) => &currency[1..],
+        _ => currency,
+    };
+    match currency {
+        "XBT" => "BTC".to_string(),
+        "XDG" => "DOGE".to_string(),
+        _ => currency.to_string(),
+    }
+}
+
+fn split_concatenated<'a>(
+    symbol: &'a str,
+    quotes: &[&'static str],
+) -> Option<(&'a str, &'static str)> {
+    quotes.iter().find_map(|quote| {
+        symbol
+            .strip_suffix(quote)
+            .filter(|base| !base.is_empty())
+            .map(|base| (base, *quote))
+    })
+}
+
+/// Base and quote currency of a symbol as the provider writes it, e.g. `BTCUSDT`, `BTC-USD`,
+/// `tBTCUSD` or `USDT-BTC`.
+pub fn split_symbol(provider: &str, symbol: &str) -> Option<(String, String)> {
+    let symbol = symbol.trim().to_uppercase();
+    let symbol = match provider {
+        "Bitfinex" => symbol.strip_prefix('T').unwrap_or(&symbol).to_string(),
+        _ => symbol,
+    };
+    let (base, quote) = match symbol.split_once(['-', '_', '/', ':']) {
+        // Upbit names its markets quote first
+        Some((quote, base)) if provider == "Upbit" => (base, quote),
+        Some((base, quote)) => (base, quote),
+        None if provider == "Kraken" => split_concatenated(&symbol, KRAKEN_QUOTES)
+            .or_else(|| split_concatenated(&symbol, KNOWN_QUOTES))?,
+        None => split_concatenated(&symbol, KNOWN_QUOTES)?,
+    };
+    if base.is_empty() || quote.is_empty() {
+        return None;
+    }
+    Some((
+        normalize_currency(provider, base),
+        normalize_currency(provider, quote),
+    ))
+}
+
+#[derive(Debug, Clone, Default)]
+struct Rate {
+    weighted_sum: f64,
+    volume: f64,
+    sum: f64,
+    sources: usize,
+}
+
+impl Rate {
+    fn add(&mut self, rate: f64, volume: f64) {
+        self.weighted_sum += rate * volume;
+        self.volume += volume;
+        self.sum += rate;
+        self.sources += 1;
+    }
+
+    fn value(&self) -> f64 {
+        if self.volume > 0.0 {
+            self.weighted_sum / self.volume
+        } else {
+            self.sum / self.sources as f64
+        }
+    }
+}
+
+/// Rates between currencies, `rates[a][b]` is the price of one `a` in `b`.
+#[derive(Debug, Clone, Default)]
+pub struct ConversionGraph {
+    rates: BTreeMap<String, BTreeMap<String, Rate>>,
+}
+
+impl ConversionGraph {
+    pub fn new() -> ConversionGraph {
+        ConversionGraph::default()
+    }
+
+    /// Graph of every pair the providers returned a price for.
+    pub fn from_provider_prices(provider_prices: &[ProviderPriceData]) -> ConversionGraph {
+        let mut graph = ConversionGraph::new();
+        for provider in provider_prices {
+            for (symbol, point) in &provider.data {
+                if let Some((base, quote)) = split_symbol(&provider.name, symbol) {
+                    graph.add_pair(&base, &quote, point);
+                }
+            }
+        }
+        graph
+    }
+
+    pub fn add_pair(&mut self, base: &str, quote: &str, point: &PricePoint) {
+        if !(point.price.is_finite() && point.price > 0.0) || base == quote {
+            return;
+        }
+        self.rates
+            .entry(base.to_string())
+            .or_default()
+            .entry(quote.to_string())
+            .or_default()
+            .add(point.price, point.volume);
+        // The volume of the inverse pair is in units of the quote
+        self.rates
+            .entry(quote.to_string())
+            .or_default()
+            .entry(base.to_string())
+            .or_default()
+            .add(1.0 / point.price, point.volume * point.price);
+    }
+
+    /// Currencies on the best path from `from` to `to`, not passing through `avoid`.
+    fn best_path(&self, from: &str, to: &str, avoid: &[&str]) -> Option<Vec<String>> {
+        // Breadth first, keeping for every currency the path whose weakest rate has the most
+        // sources among the paths with the fewest hops
+        let mut hops: HashMap<&str, usize> = HashMap::from([(from, 0)]);
+        let mut width: HashMap<&str, usize> = HashMap::from([(from, usize::MAX)]);
+        let mut previous: HashMap<&str, &str> = HashMap::new();
+        let mut frontier = vec![from];
+        for depth in 0..MAX_HOPS {
+            let mut next = Vec::new();
+            for node in frontier {
+                let Some(neighbours) = self.rates.get(node) else {
+                    continue;
+                };
+                for (neighbour, rate) in neighbours {
+                    let neighbour = neighbour.as_str();
+                    if neighbour != to && avoid.contains(&neighbour) {
+                        continue;
+                    }
+                    let path_width = width[node].min(rate.sources);
+                    match hops.get(neighbour) {
+                        Some(&neighbour_hops) if neighbour_hops <= depth => {}
+                        Some(_) => {
+                            if path_width > width[neighbour] {
+                                width.insert(neighbour, path_width);
+                                previous.insert(neighbour, node);
+                            }
+                        }
+                        None => {
+                            hops.insert(neighbour, depth + 1);
+                            width.insert(neighbour, path_width);
+                            previous.insert(neighbour, node);
+                            next.push(neighbour);
+                        }
+                    }
+                }
+            }
+            if hops.contains_key(to) {
+                break;
+            }
+            frontier = next;
+        }
+
+        hops.get(to)?;
+        let mut path = vec![to.to_string()];
+        let mut node = to;
+        while node != from {
+            node = previous[node];
+            path.push(node.to_string());
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Price of one `from` in `to`. The feed's own base currency can be passed in `avoid`, so
+    /// that it is not converted with its own prices.
+    pub fn rate(&self, from: &str, to: &str, avoid: &[&str]) -> Result<f64> {
+        if from == to {
+            return Ok(1.0);
+        }
+        let path = self.best_path(from, to, avoid).with_context(|| {
+            format!("No conversion path from {from} to {to} within {MAX_HOPS} hops")
+        })?;
+        Ok(path
+            .windows(2)
+            .map(|pair| self.rates[&pair[0]][&pair[1]].value())
+            .product())
+    }
+
+    /// Price point quoted in `from` converted to be quoted in `to`. The volume stays in units of
+    /// the base currency.
+    pub fn convert(
+        &self,
+        point: &PricePoint,
+        from: &str,
+        to: &str,
+        avoid: &[&str],
+    ) -> Result<PricePoint> {
+        let rate = self.rate(from, to, avoid)?;
+        Ok(PricePoint {
+            price: point.price * rate,
+            ..point.clone()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(price: f64, volume: f64) -> PricePoint {
+        PricePoint {
+            price,
+            volume,
+            timestamp_ms: None,
+        }
+    }
+
+    #[test]
+    fn symbols_are_split() {
+        let split = |provider, symbol| split_symbol(provider, symbol);
+        let pair = |base: &str, quote: &str| Some((base.to_string(), quote.to_string()));
+        assert_eq!(split("Binance", "BTCUSDT"), pair("BTC", "USDT"));
+        assert_eq!(split("Binance", "BTCUSDC"), pair("BTC", "USDC"));
+        assert_eq!(split("Coinbase", "BTC-USD"), pair("BTC", "USD"));
+        assert_eq!(split("CryptoCom", "BTC_USDT"), pair("BTC", "USDT"));
+        assert_eq!(split("Bitfinex", "tBTCUSD"), pair("BTC", "USD"));
+        assert_eq!(split("Upbit", "USDT-BTC"), pair("BTC", "USDT"));
+        assert_eq!(split("Gemini", "ethusd"), pair("ETH", "USD"));
+        assert_eq!(split("Kraken", "XXBTZUSD"), pair("BTC", "USD"));
+        assert_eq!(split("Kraken", "USDTZUSD"), pair("USDT", "USD"));
+        assert_eq!(split("Gemini", "BTCUSDCPERP"), None);
+    }
+
+    #[test]
+    fn prices_are_converted_along_the_best_path() {
+        let mut graph = ConversionGraph::new();
+        graph.add_pair("USDT", "USD", &point(0.999, 1000.0));
+        graph.add_pair("USDT", "USD", &point(1.001, 1000.0));
+        graph.add_pair("USDC", "USDT", &point(1.0, 500.0));
+        graph.add_pair("BTC", "USDT", &point(100_000.0, 10.0));
+        graph.add_pair("BTC", "USD", &point(50_000.0, 10.0));
+
+        assert!((graph.rate("USDT", "USD", &["BTC"]).unwrap() - 1.0).abs() < 1e-9);
+        assert!((graph.rate("USD", "USDT", &["BTC"]).unwrap() - 1.0).abs() < 1e-6);
+        assert!((graph.rate("USDC", "USD", &["BTC"]).unwrap() - 1.0).abs() < 1e-9);
+
+        let converted = graph
+            .convert(&point(100_100.0, 2.0), "USDT", "USD", &["BTC"])
+            .unwrap();
+        assert!((converted.price - 100_100.0).abs() < 1e-6);
+        assert_eq!(converted.volume, 2.0);
+
+        // A feed no venue quotes directly
+        assert!((graph.rate("USDC", "BTC", &[]).unwrap() - 1e-5).abs() < 1e-12);
+
+        let error = graph.rate("EUR", "USD", &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No conversion path from EUR to USD within 3 hops"
+        );
+        // Only through the avoided currency
+        assert!(graph.rate("USDC", "BTC", &["USDT"]).is_err());
+    }
+}
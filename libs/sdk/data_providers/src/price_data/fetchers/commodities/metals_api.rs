@@ -16,6 +16,9 @@ pub struct MetalsApiResponse {
     pub success: Option<bool>,
     pub base: Option<String>,
     pub rates: HashMap<String, f64>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
 }
 
 pub struct MetalsApiPriceFetcher<'a> {
@@ -78,7 +81,14 @@ impl<'a> PricesFetcher<'a> for MetalsApiPriceFetcher<'a> {
                 let pair_symbol = format!("{metal}{base}");
                 match maybe_price {
                     Some(price) => {
-                        results.insert(pair_symbol, PricePoint { price, volume: 1.0 });
+                        results.insert(
+                            pair_symbol,
+                            PricePoint {
+                                price,
+                                volume: 1.0,
+                                timestamp_ms: response.timestamp.map(|secs| secs * 1000),
+                            },
+                        );
                     }
                     None => {
                         print_missing_provider_price_data(
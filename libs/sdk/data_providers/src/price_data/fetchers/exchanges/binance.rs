@@ -19,6 +19,8 @@ pub struct BinancePriceData {
     pub last_price: f64,
     #[serde(deserialize_with = "as_f64")]
     pub volume: f64,
+    #[serde(default)]
+    pub close_time: Option<u64>,
 }
 
 type BinancePriceResponse = Vec<BinancePriceData>;
@@ -57,6 +59,7 @@ impl<'a> PricesFetcher<'a> for BinancePriceFetcher<'a> {
                         PricePoint {
                             price: value.last_price,
                             volume: value.volume,
+                            timestamp_ms: value.close_time,
                         },
                     )
                 })
@@ -55,6 +55,7 @@ impl<'a> PricesFetcher<'a> for BitfinexPriceFetcher<'a> {
                         PricePoint {
                             price: ticker.last_price,
                             volume: ticker.volume,
+                            timestamp_ms: None,
                         },
                     )
                 })
@@ -64,6 +64,7 @@ impl PricesFetcher<'_> for BybitPriceFetcher {
                         PricePoint {
                             price: value.last_price,
                             volume,
+                            timestamp_ms: None,
                         },
                     )
                 })
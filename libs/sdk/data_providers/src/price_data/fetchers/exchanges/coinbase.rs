@@ -8,6 +8,7 @@ use futures::{
 };
 use std::ops::Deref;
 
+use chrono::DateTime;
 use serde::Deserialize;
 use serde_this_or_that::as_f64;
 
@@ -29,6 +30,8 @@ pub struct CoinbasePriceResponse {
     pub price: f64,
     #[serde(deserialize_with = "as_f64")]
     pub volume: f64,
+    #[serde(default)]
+    pub time: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -130,6 +133,10 @@ pub async fn fetch_price_for_symbol(
             PricePoint {
                 price: price_data.price,
                 volume: price_data.volume,
+                timestamp_ms: price_data
+                    .time
+                    .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                    .map(|time| time.timestamp_millis() as u64),
             },
         )),
         CoinbaseApiResponse::Error(error) => Err(SymbolError {
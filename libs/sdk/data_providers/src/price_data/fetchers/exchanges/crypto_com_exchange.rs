@@ -17,6 +17,8 @@ pub struct CryptoComPriceData {
     pub a: f64,
     #[serde(deserialize_with = "as_f64")]
     pub v: f64,
+    #[serde(default)]
+    pub t: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize)]
@@ -59,6 +61,7 @@ impl PricesFetcher<'_> for CryptoComPriceFetcher {
                         PricePoint {
                             price: value.a,
                             volume: value.v,
+                            timestamp_ms: value.t,
                         },
                     )
                 })
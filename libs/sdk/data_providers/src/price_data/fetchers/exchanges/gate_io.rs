@@ -48,6 +48,7 @@ impl PricesFetcher<'_> for GateIoPriceFetcher {
                         PricePoint {
                             price: value.last,
                             volume: value.base_volume,
+                            timestamp_ms: None,
                         },
                     )
                 })
@@ -137,11 +137,14 @@ pub async fn fetch_price_for_symbol(
                 None => 0.0,
             };
 
+            // Gemini puts the time of the ticker among the volumes
+            let timestamp_ms = price_data.volume.get("timestamp").and_then(Value::as_u64);
             Ok((
                 symbol.to_string(),
                 PricePoint {
                     price: price_data.last,
                     volume,
+                    timestamp_ms,
                 },
             ))
         }
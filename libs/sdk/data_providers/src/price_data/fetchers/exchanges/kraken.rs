@@ -64,7 +64,14 @@ impl PricesFetcher<'_> for KrakenPriceFetcher {
                     let volume = *price_data.v.get(1).with_context(|| {
                         format!("Kraken has no second volume in response for symbol: {symbol}")
                     })?;
-                    Ok((symbol, PricePoint { price, volume }))
+                    Ok((
+                        symbol,
+                        PricePoint {
+                            price,
+                            volume,
+                            timestamp_ms: None,
+                        },
+                    ))
                 })
                 .collect::<Result<PairPriceData>>()
         }
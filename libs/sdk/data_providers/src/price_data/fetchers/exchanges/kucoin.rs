@@ -73,6 +73,7 @@ impl PricesFetcher<'_> for KuCoinPriceFetcher {
                         PricePoint {
                             price: last_price,
                             volume,
+                            timestamp_ms: None,
                         },
                     )),
                     _ => None,
@@ -18,6 +18,8 @@ pub struct MEXCPriceData {
     pub last_price: f64,
     #[serde(deserialize_with = "as_f64")]
     pub volume: f64,
+    #[serde(default, rename = "closeTime")]
+    pub close_time: Option<u64>,
 }
 
 type MEXCPriceResponse = Vec<MEXCPriceData>;
@@ -49,6 +51,7 @@ impl PricesFetcher<'_> for MEXCPriceFetcher {
                         PricePoint {
                             price: value.last_price,
                             volume: value.volume,
+                            timestamp_ms: value.close_time,
                         },
                     )
                 })
@@ -4,7 +4,7 @@ use anyhow::Result;
 use futures::{future::LocalBoxFuture, FutureExt};
 
 use serde::Deserialize;
-use serde_this_or_that::as_f64;
+use serde_this_or_that::{as_f64, as_opt_u64};
 
 use blocksense_sdk::http::http_get_json;
 
@@ -18,6 +18,8 @@ pub struct OKXTickerData {
     pub last: f64,
     #[serde(deserialize_with = "as_f64")]
     pub vol24h: f64,
+    #[serde(default, deserialize_with = "as_opt_u64")]
+    pub ts: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize)]
@@ -54,6 +56,7 @@ impl PricesFetcher<'_> for OKXPriceFetcher {
                         PricePoint {
                             price: value.last,
                             volume: value.vol24h,
+                            timestamp_ms: value.ts,
                         },
                     )
                 })
@@ -14,6 +14,8 @@ pub struct UpBitTickerResponseData {
     pub market: String,
     pub trade_price: f64,
     pub acc_trade_volume_24h: f64,
+    #[serde(default)]
+    pub trade_timestamp: Option<u64>,
 }
 
 type UpBitResponse = Vec<UpBitTickerResponseData>;
@@ -48,6 +50,7 @@ impl<'a> PricesFetcher<'a> for UpBitPriceFetcher<'a> {
                         PricePoint {
                             price: data.trade_price,
                             volume: data.acc_trade_volume_24h,
+                            timestamp_ms: data.trade_timestamp,
                         },
                     )
                 })
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, ops::Deref};
 
 use anyhow::{Error, Result};
+use chrono::NaiveDateTime;
 use futures::{future::LocalBoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
 use serde::Deserialize;
 use serde_this_or_that::as_f64;
@@ -46,6 +47,10 @@ pub struct RealtimeCurrencyExchangeRate {
     #[serde(rename = "5. Exchange Rate")]
     #[serde(deserialize_with = "as_f64")]
     pub exchange_rate: f64,
+
+    /// In UTC, e.g. `2025-01-02 15:04:05`
+    #[serde(rename = "6. Last Refreshed", default)]
+    pub last_refreshed: Option<String>,
 }
 
 pub struct AlphaVantagePriceFetcher<'a> {
@@ -152,13 +157,21 @@ pub async fn fetch_price_for_pair(
     };
 
     match response {
-        AlphaVantageApiResponse::Success(price_data) => Ok((
-            symbol,
-            PricePoint {
-                price: price_data.realtime_currency_exchange_rate.exchange_rate,
-                volume: 1.0,
-            },
-        )),
+        AlphaVantageApiResponse::Success(price_data) => {
+            let rate = price_data.realtime_currency_exchange_rate;
+            let timestamp_ms = rate
+                .last_refreshed
+                .and_then(|time| NaiveDateTime::parse_from_str(&time, "%Y-%m-%d %H:%M:%S").ok())
+                .map(|time| time.and_utc().timestamp_millis() as u64);
+            Ok((
+                symbol,
+                PricePoint {
+                    price: rate.exchange_rate,
+                    volume: 1.0,
+                    timestamp_ms,
+                },
+            ))
+        }
         AlphaVantageApiResponse::Error(error) => Err(SymbolError {
             symbol,
             error: error.information,
@@ -17,6 +17,9 @@ pub struct PriceData {
     pub symbol: String,
     pub price: Option<f64>,
     pub previous_close: Option<f64>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
 }
 
 type FMPResponse = Vec<PriceData>;
@@ -61,7 +64,14 @@ impl<'a> PricesFetcher<'a> for FMPPriceFetcher<'a> {
                 .filter_map(|value| {
                     let price = value.price.or(value.previous_close);
                     match price {
-                        Some(price) => Some((value.symbol, PricePoint { price, volume: 1.0 })),
+                        Some(price) => Some((
+                            value.symbol,
+                            PricePoint {
+                                price,
+                                volume: 1.0,
+                                timestamp_ms: value.timestamp.map(|secs| secs * 1000),
+                            },
+                        )),
                         _ => {
                             print_missing_provider_price_data(
                                 "FMP",
@@ -14,6 +14,9 @@ use crate::price_data::traits::prices_fetcher::{PairPriceData, PricePoint, Price
 pub struct RateData {
     pub symbol: String,
     pub rate: Option<f64>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
 }
 
 type TwelveDataResponse = HashMap<String, RateData>;
@@ -59,7 +62,14 @@ impl<'a> PricesFetcher<'a> for TwelveDataPriceFetcher<'a> {
                     let price = value.rate;
                     let pair = value.symbol.replace('/', "");
                     match price {
-                        Some(price) => Some((pair, PricePoint { price, volume: 1.0 })),
+                        Some(price) => Some((
+                            pair,
+                            PricePoint {
+                                price,
+                                volume: 1.0,
+                                timestamp_ms: value.timestamp.map(|secs| secs * 1000),
+                            },
+                        )),
                         _ => {
                             print_missing_provider_price_data(
                                 "TwelveData",
@@ -20,6 +20,9 @@ pub struct PriceData {
     pub symbol: String,
     pub regular_market_previous_close: Option<f64>,
     pub regular_market_price: Option<f64>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub regular_market_time: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize)]
@@ -91,7 +94,14 @@ impl<'a> PricesFetcher<'a> for YFPriceFetcher<'a> {
                     let symbol = value.symbol.replace("=X", "");
                     match price {
                         Some(price) => {
-                            aggregated.insert(symbol, PricePoint { price, volume });
+                            aggregated.insert(
+                                symbol,
+                                PricePoint {
+                                    price,
+                                    volume,
+                                    timestamp_ms: value.regular_market_time.map(|secs| secs * 1000),
+                                },
+                            );
                         }
                         _ => {
                             print_missing_provider_price_data(
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use anyhow::{Error, Result};
+use chrono::DateTime;
 use futures::{future::LocalBoxFuture, FutureExt};
 
 use serde::Deserialize;
@@ -25,6 +26,9 @@ pub struct DailyBar {
 pub struct LatestTrade {
     #[serde(deserialize_with = "as_f64")]
     pub p: f64,
+    /// RFC 3339 time of the trade
+    #[serde(default)]
+    pub t: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Deserialize)]
@@ -80,9 +84,21 @@ impl<'a> PricesFetcher<'a> for AlpacaMarketsPriceFetcher<'a> {
                 .filter_map(|(symbol, data)| {
                     let price = data.latest_trade.p;
                     let volume = data.daily_bar.v;
+                    let timestamp_ms = data
+                        .latest_trade
+                        .t
+                        .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                        .map(|time| time.timestamp_millis() as u64);
 
                     if price > 0.0 && volume > 0.0 {
-                        Some((symbol, PricePoint { price, volume }))
+                        Some((
+                            symbol,
+                            PricePoint {
+                                price,
+                                volume,
+                                timestamp_ms,
+                            },
+                        ))
                     } else {
                         print_missing_provider_price_data(
                             "AlpacaMarkets",
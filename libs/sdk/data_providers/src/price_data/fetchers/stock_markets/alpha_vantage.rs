@@ -63,7 +63,14 @@ impl<'a> PricesFetcher<'a> for AlphaVantagePriceFetcher<'a> {
                     let price = data.close;
                     let volume = data.volume;
 
-                    (data.symbol, PricePoint { price, volume })
+                    (
+                        data.symbol,
+                        PricePoint {
+                            price,
+                            volume,
+                            timestamp_ms: None,
+                        },
+                    )
                 })
                 .collect::<PairPriceData>();
             Ok(results)
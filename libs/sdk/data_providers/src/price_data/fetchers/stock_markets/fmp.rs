@@ -17,6 +17,9 @@ pub struct PriceData {
     pub price: Option<f64>,
     pub volume: Option<f64>,
     pub previous_close: Option<f64>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
 }
 
 type FMPResponse = Vec<PriceData>;
@@ -61,9 +64,14 @@ impl<'a> PricesFetcher<'a> for FMPPriceFetcher<'a> {
                     let volume = value.volume;
 
                     match (price, volume) {
-                        (Some(price), Some(volume)) => {
-                            Some((value.symbol, PricePoint { price, volume }))
-                        }
+                        (Some(price), Some(volume)) => Some((
+                            value.symbol,
+                            PricePoint {
+                                price,
+                                volume,
+                                timestamp_ms: value.timestamp.map(|secs| secs * 1000),
+                            },
+                        )),
                         _ => {
                             print_missing_provider_price_data("FMP", value.symbol, price, volume);
                             None
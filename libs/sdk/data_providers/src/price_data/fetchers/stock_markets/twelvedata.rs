@@ -16,6 +16,9 @@ pub struct PriceData {
     pub close: Option<String>,
     pub volume: Option<String>,
     pub previous_close: Option<String>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub timestamp: Option<u64>,
 }
 
 type TwelveDataResponse = HashMap<String, PriceData>;
@@ -60,7 +63,14 @@ impl<'a> PricesFetcher<'a> for TwelveDataPriceFetcher<'a> {
                         (Some(price), Some(volume)) => {
                             let price = price.parse::<f64>().unwrap_or(0.0);
                             let volume = volume.parse::<f64>().unwrap_or(0.0);
-                            Some((value.symbol.clone(), PricePoint { price, volume }))
+                            Some((
+                                value.symbol.clone(),
+                                PricePoint {
+                                    price,
+                                    volume,
+                                    timestamp_ms: value.timestamp.map(|secs| secs * 1000),
+                                },
+                            ))
                         }
                         _ => {
                             print_missing_provider_price_data(
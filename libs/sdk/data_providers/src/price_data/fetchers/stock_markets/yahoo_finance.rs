@@ -20,6 +20,9 @@ pub struct PriceData {
     pub symbol: String,
     pub regular_market_previous_close: Option<f64>,
     pub regular_market_price: Option<f64>,
+    /// In seconds since the UNIX epoch
+    #[serde(default)]
+    pub regular_market_time: Option<u64>,
     pub regular_market_volume: Option<f64>,
 }
 
@@ -85,7 +88,14 @@ impl<'a> PricesFetcher<'a> for YFPriceFetcher<'a> {
                     let volume = value.regular_market_volume;
 
                     if let (Some(price), Some(volume)) = (price, volume) {
-                        aggregated.insert(value.symbol, PricePoint { price, volume });
+                        aggregated.insert(
+                            value.symbol,
+                            PricePoint {
+                                price,
+                                volume,
+                                timestamp_ms: value.regular_market_time.map(|secs| secs * 1000),
+                            },
+                        );
                     } else {
                         print_missing_provider_price_data(
                             "YahooFinance",
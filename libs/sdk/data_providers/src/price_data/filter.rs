@@ -0,0 +1,355 @@
+//! Filtering of the price points of a feed before they are aggregated, so that a single provider
+//! with a broken ticker or a depegged quote cannot move the price.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::price_data::types::{ProviderName, ProvidersPricePoints};
+
+/// Scales the median absolute deviation to the standard deviation of normally distributed prices
+const MAD_SCALE: f64 = 1.4826;
+/// Smallest deviation used, relative to the median price, so that a few providers reporting the
+/// exact same price do not make every other price an outlier
+const MIN_RELATIVE_MAD: f64 = 1e-4;
+
+/// Settings of the filtering, in the `price_filter` argument of a feed. Nothing is filtered by
+/// default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PriceFilterConfig {
+    /// Prices further from the median than this many (scaled) median absolute deviations are
+    /// dropped. Needs at least three price points.
+    pub max_mad_deviations: Option<f64>,
+    /// Fewer providers than this after filtering is an error
+    pub min_providers: usize,
+    /// Share of the total volume a single provider is weighted with at most. The volumes of the
+    /// larger providers are scaled down until they are at this share of the capped total, and
+    /// if there are too few providers for that, every provider is weighted the same.
+    pub max_volume_share: Option<f64>,
+    /// Price points older than this are dropped, points without a timestamp are kept
+    pub max_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DropReason {
+    Stale { age_secs: u64 },
+    Outlier { median: f64, deviations: f64 },
+}
+
+impl fmt::Display for DropReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DropReason::Stale { age_secs } => write!(f, "stale by {age_secs}s"),
+            DropReason::Outlier { median, deviations } => {
+                write!(f, "{deviations:.1} deviations from median {median}")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DroppedPricePoint {
+    pub provider: ProviderName,
+    pub price: f64,
+    pub reason: DropReason,
+}
+
+#[derive(Debug, Default)]
+pub struct FilteredPricePoints {
+    pub kept: ProvidersPricePoints,
+    pub dropped: Vec<DroppedPricePoint>,
+}
+
+/// The provider of a price point, keys of the form `"<provider> <symbol> price"` count once per
+/// provider.
+fn provider_of(key: &str) -> &str {
+    key.split(' ').next().unwrap_or(key)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Scales down the volumes of the providers above `max_share` of the total volume, so that after
+/// the scaling no provider is above `max_share` of the new total. The price points of a provider
+/// keep their weights relative to each other.
+fn cap_volume_shares(price_points: &mut ProvidersPricePoints, max_share: f64) {
+    let mut provider_volumes: HashMap<String, f64> = HashMap::new();
+    for (key, point) in price_points.iter() {
+        *provider_volumes
+            .entry(provider_of(key).to_string())
+            .or_default() += point.volume.max(0.0);
+    }
+    let mut volumes: Vec<f64> = provider_volumes
+        .values()
+        .copied()
+        .filter(|volume| *volume > 0.0)
+        .collect();
+    volumes.sort_by(|a, b| b.total_cmp(a));
+
+    // With the `capped` largest providers at the cap, the cap is `max_share` of the total of
+    // the rest plus the capped ones, which the next provider has to be below.
+    let mut rest: f64 = volumes.iter().sum();
+    let mut cap = None;
+    for (capped, volume) in volumes.iter().enumerate() {
+        let uncapped_share = 1.0 - max_share * capped as f64;
+        if uncapped_share <= 0.0 {
+            break;
+        }
+        let level = max_share * rest / uncapped_share;
+        if *volume <= level {
+            cap = Some(level);
+            break;
+        }
+        rest -= volume;
+    }
+    let Some(cap) = cap.or_else(|| volumes.last().copied()) else {
+        return;
+    };
+
+    for (key, point) in price_points.iter_mut() {
+        let provider_volume = provider_volumes[provider_of(key)];
+        if provider_volume > cap {
+            point.volume *= cap / provider_volume;
+        }
+    }
+}
+
+/// Current time in milliseconds since the UNIX epoch, to compare with the timestamps of the price
+/// points.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+/// Drops stale and outlying price points of a feed and caps the volume of the rest. `now_ms` is
+/// the current time in milliseconds since the UNIX epoch.
+pub fn filter_price_points(
+    feed: &str,
+    price_points: &ProvidersPricePoints,
+    config: &PriceFilterConfig,
+    now_ms: u64,
+) -> Result<FilteredPricePoints> {
+    let mut filtered = FilteredPricePoints::default();
+
+    for (provider, point) in price_points {
+        match (point.age_secs(now_ms), config.max_age_secs) {
+            (Some(age_secs), Some(max_age_secs)) if age_secs > max_age_secs => {
+                filtered.dropped.push(DroppedPricePoint {
+                    provider: provider.clone(),
+                    price: point.price,
+                    reason: DropReason::Stale { age_secs },
+                });
+            }
+            _ => {
+                filtered.kept.insert(provider.clone(), point.clone());
+            }
+        }
+    }
+
+    if let Some(max_mad_deviations) = config.max_mad_deviations {
+        if filtered.kept.len() >= 3 {
+            let mut prices: Vec<f64> = filtered.kept.values().map(|point| point.price).collect();
+            let median_price = median(&mut prices);
+            let mut deviations: Vec<f64> = prices
+                .iter()
+                .map(|price| (price - median_price).abs())
+                .collect();
+            let mad =
+                (MAD_SCALE * median(&mut deviations)).max(median_price.abs() * MIN_RELATIVE_MAD);
+            let outliers: Vec<(ProviderName, f64, f64)> = filtered
+                .kept
+                .iter()
+                .map(|(provider, point)| {
+                    (
+                        provider.clone(),
+                        point.price,
+                        (point.price - median_price).abs() / mad,
+                    )
+                })
+                .filter(|(_, _, deviations)| *deviations > max_mad_deviations)
+                .collect();
+            for (provider, price, deviations) in outliers {
+                filtered.kept.remove(&provider);
+                filtered.dropped.push(DroppedPricePoint {
+                    provider,
+                    price,
+                    reason: DropReason::Outlier {
+                        median: median_price,
+                        deviations,
+                    },
+                });
+            }
+        }
+    }
+
+    if let Some(max_volume_share) = config.max_volume_share {
+        cap_volume_shares(&mut filtered.kept, max_volume_share);
+    }
+
+    filtered.dropped.sort_by(|a, b| a.provider.cmp(&b.provider));
+    for dropped in &filtered.dropped {
+        warn!(
+            "Dropped price {} of {} for {feed}: {}",
+            dropped.price, dropped.provider, dropped.reason
+        );
+    }
+
+    let providers: HashSet<&str> = filtered.kept.keys().map(|key| provider_of(key)).collect();
+    if providers.len() < config.min_providers {
+        anyhow::bail!(
+            "Only {} of the required {} providers left after filtering",
+            providers.len(),
+            config.min_providers
+        );
+    }
+
+    Ok(filtered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::price_data::traits::prices_fetcher::PricePoint;
+
+    fn points(prices: &[(&str, f64, f64, Option<u64>)]) -> ProvidersPricePoints {
+        prices
+            .iter()
+            .map(|(provider, price, volume, timestamp_ms)| {
+                (
+                    provider.to_string(),
+                    PricePoint {
+                        price: *price,
+                        volume: *volume,
+                        timestamp_ms: *timestamp_ms,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outliers_and_stale_points_are_dropped() {
+        let now_ms = 1_750_000_000_000;
+        let price_points = points(&[
+            (
+                "Binance BTCUSDT price",
+                100_000.0,
+                10.0,
+                Some(now_ms - 1_000),
+            ),
+            ("Coinbase BTC-USD price", 100_050.0, 5.0, None),
+            ("Kraken XBTUSD price", 99_980.0, 2.0, Some(now_ms)),
+            ("Gemini btcusd price", 80_000.0, 1.0, Some(now_ms)),
+            (
+                "Upbit KRW-BTC price",
+                100_010.0,
+                1.0,
+                Some(now_ms - 600_000),
+            ),
+        ]);
+        let config = PriceFilterConfig {
+            max_mad_deviations: Some(5.0),
+            max_age_secs: Some(60),
+            ..Default::default()
+        };
+
+        let filtered = filter_price_points("BTC / USD", &price_points, &config, now_ms).unwrap();
+        let mut kept: Vec<&str> = filtered.kept.keys().map(String::as_str).collect();
+        kept.sort();
+        assert_eq!(
+            kept,
+            vec![
+                "Binance BTCUSDT price",
+                "Coinbase BTC-USD price",
+                "Kraken XBTUSD price"
+            ]
+        );
+        assert_eq!(filtered.dropped.len(), 2);
+        assert_eq!(filtered.dropped[0].provider, "Gemini btcusd price");
+        assert!(matches!(
+            filtered.dropped[0].reason,
+            DropReason::Outlier { .. }
+        ));
+        assert_eq!(
+            filtered.dropped[1].reason,
+            DropReason::Stale { age_secs: 600 }
+        );
+    }
+
+    #[test]
+    fn volumes_are_capped_and_providers_counted() {
+        let price_points = points(&[
+            ("Binance BTCUSDT price", 100.0, 90.0, None),
+            ("Binance BTCUSDC price", 101.0, 5.0, None),
+            ("Coinbase BTC-USD price", 102.0, 5.0, None),
+        ]);
+        let config = PriceFilterConfig {
+            max_volume_share: Some(0.5),
+            min_providers: 2,
+            ..Default::default()
+        };
+        let filtered = filter_price_points("BTC / USD", &price_points, &config, 0).unwrap();
+        assert_eq!(filtered.kept.len(), 3);
+        let binance_volume = filtered.kept["Binance BTCUSDT price"].volume
+            + filtered.kept["Binance BTCUSDC price"].volume;
+        assert!((binance_volume - 5.0).abs() < 1e-9);
+        assert!((filtered.kept["Binance BTCUSDT price"].volume - 90.0 * 5.0 / 95.0).abs() < 1e-9);
+        assert_eq!(filtered.kept["Coinbase BTC-USD price"].volume, 5.0);
+
+        let config = PriceFilterConfig {
+            min_providers: 3,
+            ..Default::default()
+        };
+        assert!(filter_price_points("BTC / USD", &price_points, &config, 0).is_err());
+    }
+
+    #[test]
+    fn volume_shares_hold_after_capping() {
+        let mut price_points = points(&[
+            ("A", 1.0, 80.0, None),
+            ("B", 1.0, 15.0, None),
+            ("C", 1.0, 5.0, None),
+        ]);
+        cap_volume_shares(&mut price_points, 0.5);
+        assert_eq!(price_points["A"].volume, 20.0);
+        assert_eq!(price_points["B"].volume, 15.0);
+        assert_eq!(price_points["C"].volume, 5.0);
+
+        let mut price_points = points(&[("A", 1.0, 80.0, None), ("B", 1.0, 20.0, None)]);
+        cap_volume_shares(&mut price_points, 0.3);
+        assert_eq!(price_points["A"].volume, 20.0);
+        assert_eq!(price_points["B"].volume, 20.0);
+    }
+
+    #[test]
+    fn nothing_is_filtered_by_default() {
+        let price_points = points(&[
+            ("A", 1.0, 1.0, Some(0)),
+            ("B", 1.0, 1.0, Some(0)),
+            ("C", 1000.0, 1.0, Some(0)),
+        ]);
+        let filtered = filter_price_points(
+            "X / Y",
+            &price_points,
+            &PriceFilterConfig::default(),
+            u64::MAX,
+        )
+        .unwrap();
+        assert_eq!(filtered.kept.len(), 3);
+        assert!(filtered.dropped.is_empty());
+    }
+}
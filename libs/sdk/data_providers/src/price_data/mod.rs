@@ -1,4 +1,6 @@
+pub mod conversion;
 pub mod fetchers;
+pub mod filter;
 pub mod traits;
 pub mod types;
 pub mod wap;
@@ -12,6 +12,16 @@ pub type Volume = f64;
 pub struct PricePoint {
     pub price: Price,
     pub volume: Volume,
+    /// Time of the price at the venue in milliseconds since the UNIX epoch, when it is known
+    pub timestamp_ms: Option<u64>,
+}
+
+impl PricePoint {
+    /// Age of the price in whole seconds at `now_ms`, when its time is known
+    pub fn age_secs(&self, now_ms: u64) -> Option<u64> {
+        self.timestamp_ms
+            .map(|timestamp_ms| now_ms.saturating_sub(timestamp_ms) / 1000)
+    }
 }
 
 pub type PairPriceData = HashMap<TradingPairSymbol, PricePoint>;
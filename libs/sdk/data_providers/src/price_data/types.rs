@@ -4,6 +4,7 @@ use blocksense_sdk::oracle::logging::PriceResultsAccessor;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
+use crate::price_data::filter::now_ms;
 use crate::price_data::traits::prices_fetcher::{PairPriceData, PricePoint, TradingPairSymbol};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,4 +57,20 @@ impl<'a> PriceResultsAccessor for ResultsView<'a> {
             })
             .unwrap_or_default()
     }
+
+    fn provider_ages_secs(&self, id: &str) -> Vec<(String, u64)> {
+        let now_ms = now_ms();
+        self.0
+            .get(id)
+            .map(|res| {
+                res.providers_data
+                    .iter()
+                    .filter_map(|(key, point)| {
+                        let provider = key.split(' ').next().unwrap_or("").to_string();
+                        Some((provider, point.age_secs(now_ms)?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
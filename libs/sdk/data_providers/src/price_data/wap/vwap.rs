@@ -7,7 +7,7 @@ pub fn compute_vwap<'a>(price_points: impl IntoIterator<Item = &'a PricePoint>)
     price_points
         .into_iter()
         .filter(|pp| pp.volume > 0.0)
-        .map(|PricePoint { price, volume }| (price * volume, *volume))
+        .map(|PricePoint { price, volume, .. }| (price * volume, *volume))
         .reduce(|(num, denom), (weighted_price, volume)| (num + weighted_price, denom + volume))
         .context("No price points found")
         .map(|(weighted_prices_sum, total_volume)| weighted_prices_sum / total_volume)
@@ -23,14 +23,17 @@ mod tests {
                 PricePoint {
                     price: 100.0,
                     volume: 10.0,
+                    timestamp_ms: None,
                 },
                 PricePoint {
                     price: 200.0,
                     volume: 20.0,
+                    timestamp_ms: None,
                 },
                 PricePoint {
                     price: 300.0,
                     volume: 30.0,
+                    timestamp_ms: None,
                 },
             ])
             .unwrap(),
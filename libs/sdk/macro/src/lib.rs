@@ -22,12 +22,7 @@ pub fn oracle_component(_attr: TokenStream, item: TokenStream) -> TokenStream {
                     ::blocksense_sdk::spin::http::run(async move {
                         match super::#func_name(settings.try_into().expect("cannot convert from Blocksense Oracle settings"))#await_postfix {
                             Ok(payload) => Ok(payload.try_into().expect("cannot convert from Blocksense Oracle payload")),
-                            Err(e) => {
-                                //TODO(adikov): Potentially include more types of wit Errors and
-                                //implement a from trait for them. This way we can use the
-                                //different Errors in the oracle components implementation.
-                                Err(self::preamble::blocksense::oracle::oracle_types::Error::Other(e.to_string()))
-                            },
+                            Err(e) => Err(::blocksense_sdk::oracle::OracleError::from(e).into()),
                         }
                     })
                 }
@@ -76,6 +71,19 @@ pub fn oracle_component(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
             }
 
+            impl From<::blocksense_sdk::oracle::OracleError> for self::preamble::blocksense::oracle::oracle_types::Error {
+                fn from(error: ::blocksense_sdk::oracle::OracleError) -> Self {
+                    match error {
+                        ::blocksense_sdk::oracle::OracleError::Timeout => Self::Timeout,
+                        ::blocksense_sdk::oracle::OracleError::UpstreamHttp(status) => Self::UpstreamHttp(status),
+                        ::blocksense_sdk::oracle::OracleError::RateLimited(retry_after) => Self::RateLimited(retry_after),
+                        ::blocksense_sdk::oracle::OracleError::MissingCapability(id) => Self::MissingCapability(id),
+                        ::blocksense_sdk::oracle::OracleError::InvalidSettings(reason) => Self::InvalidSettings(reason),
+                        ::blocksense_sdk::oracle::OracleError::Other(message) => Self::Other(message),
+                    }
+                }
+            }
+
             impl From<::blocksense_sdk::oracle::DataFeedResultValue> for self::preamble::blocksense::oracle::oracle_types::DataFeedResultValue {
                 fn from(value: ::blocksense_sdk::oracle::DataFeedResultValue) -> Self {
                     match value {
@@ -83,6 +91,7 @@ pub fn oracle_component(_attr: TokenStream, item: TokenStream) -> TokenStream {
                         ::blocksense_sdk::oracle::DataFeedResultValue::Numerical(value) => Self::Numerical(value),
                         ::blocksense_sdk::oracle::DataFeedResultValue::Text(value) => Self::Text(value),
                         ::blocksense_sdk::oracle::DataFeedResultValue::Bytes(value) => Self::Bytes(value),
+                        ::blocksense_sdk::oracle::DataFeedResultValue::Decimal(value) => Self::Decimal(value),
                         ::blocksense_sdk::oracle::DataFeedResultValue::Error(error) => Self::Error(error),
                     }
                 }
@@ -0,0 +1,350 @@
+//! Trading sessions of the markets that price feeds follow, so that oracle scripts and the
+//! sequencer can tell a market which is closed from a feed which stopped updating.
+//!
+//! A feed names its market in `market_hours`, see [`MarketCalendar::from_market_hours`]. The
+//! holiday tables cover the years 2025 to 2027, outside of them only weekends are closed.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::{Europe::London, Tz, US::Eastern};
+
+type Date = (i32, u32, u32);
+
+/// Full day closures of the NYSE
+const NYSE_HOLIDAYS: &[Date] = &[
+    (2025, 1, 1),
+    (2025, 1, 9),
+    (2025, 1, 20),
+    (2025, 2, 17),
+    (2025, 4, 18),
+    (2025, 5, 26),
+    (2025, 6, 19),
+    (2025, 7, 4),
+    (2025, 9, 1),
+    (2025, 11, 27),
+    (2025, 12, 25),
+    (2026, 1, 1),
+    (2026, 1, 19),
+    (2026, 2, 16),
+    (2026, 4, 3),
+    (2026, 5, 25),
+    (2026, 6, 19),
+    (2026, 7, 3),
+    (2026, 9, 7),
+    (2026, 11, 26),
+    (2026, 12, 25),
+    (2027, 1, 1),
+    (2027, 1, 18),
+    (2027, 2, 15),
+    (2027, 3, 26),
+    (2027, 5, 31),
+    (2027, 6, 18),
+    (2027, 7, 5),
+    (2027, 9, 6),
+    (2027, 11, 25),
+    (2027, 12, 24),
+];
+
+/// The NYSE closes at 13:00 on these days
+const NYSE_EARLY_CLOSES: &[Date] = &[
+    (2025, 7, 3),
+    (2025, 11, 28),
+    (2025, 12, 24),
+    (2026, 11, 27),
+    (2026, 12, 24),
+    (2027, 11, 26),
+];
+
+/// Full day closures of the LSE
+const LSE_HOLIDAYS: &[Date] = &[
+    (2025, 1, 1),
+    (2025, 4, 18),
+    (2025, 4, 21),
+    (2025, 5, 5),
+    (2025, 5, 26),
+    (2025, 8, 25),
+    (2025, 12, 25),
+    (2025, 12, 26),
+    (2026, 1, 1),
+    (2026, 4, 3),
+    (2026, 4, 6),
+    (2026, 5, 4),
+    (2026, 5, 25),
+    (2026, 8, 31),
+    (2026, 12, 25),
+    (2026, 12, 28),
+    (2027, 1, 1),
+    (2027, 3, 26),
+    (2027, 3, 29),
+    (2027, 5, 3),
+    (2027, 5, 31),
+    (2027, 8, 30),
+    (2027, 12, 27),
+    (2027, 12, 28),
+];
+
+/// The LSE closes at 12:30 on these days
+const LSE_EARLY_CLOSES: &[Date] = &[
+    (2025, 12, 24),
+    (2025, 12, 31),
+    (2026, 12, 24),
+    (2026, 12, 31),
+    (2027, 12, 24),
+    (2027, 12, 31),
+];
+
+/// Trade dates on which CME Globex does not trade metals at all. On the other NYSE holidays it
+/// halts early instead.
+const CME_HOLIDAYS: &[Date] = &[
+    (2025, 1, 1),
+    (2025, 4, 18),
+    (2025, 12, 25),
+    (2026, 1, 1),
+    (2026, 4, 3),
+    (2026, 12, 25),
+    (2027, 1, 1),
+    (2027, 3, 26),
+    (2027, 12, 24),
+];
+
+/// Month and day on which the FX market is closed every year
+const FX_HOLIDAYS: &[(u32, u32)] = &[(1, 1), (12, 25)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketCalendar {
+    /// Crypto markets, which never close
+    AlwaysOpen,
+    /// New York Stock Exchange, 09:30 to 16:00 New York time
+    Nyse,
+    /// London Stock Exchange, 08:00 to 16:30 London time
+    Lse,
+    /// Spot FX, from Sunday 17:00 to Friday 17:00 New York time
+    Fx,
+    /// CME Globex metals and energy, 18:00 to 17:00 New York time from Sunday to Friday
+    CmeMetals,
+}
+
+impl MarketCalendar {
+    /// Calendar for the `market_hours` of a feed, `None` for markets without one.
+    pub fn from_market_hours(market_hours: &str) -> Option<MarketCalendar> {
+        match market_hours {
+            "Crypto" => Some(MarketCalendar::AlwaysOpen),
+            "NYSE" | "US_Equities" => Some(MarketCalendar::Nyse),
+            "LSE" | "UK_ETF" => Some(MarketCalendar::Lse),
+            "Forex" | "FX" => Some(MarketCalendar::Fx),
+            "Precious_Metals" | "Commodities" | "NYMEX" | "WTI" => Some(MarketCalendar::CmeMetals),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarketCalendar::AlwaysOpen => "24/7",
+            MarketCalendar::Nyse => "NYSE",
+            MarketCalendar::Lse => "LSE",
+            MarketCalendar::Fx => "FX 24/5",
+            MarketCalendar::CmeMetals => "CME metals",
+        }
+    }
+
+    pub fn is_open(&self, at: DateTime<Utc>) -> bool {
+        match self {
+            MarketCalendar::AlwaysOpen => true,
+            MarketCalendar::Nyse => exchange_is_open(
+                at.with_timezone(&Eastern),
+                (time(9, 30), time(16, 0), time(13, 0)),
+                NYSE_HOLIDAYS,
+                NYSE_EARLY_CLOSES,
+            ),
+            MarketCalendar::Lse => exchange_is_open(
+                at.with_timezone(&London),
+                (time(8, 0), time(16, 30), time(12, 30)),
+                LSE_HOLIDAYS,
+                LSE_EARLY_CLOSES,
+            ),
+            MarketCalendar::Fx => {
+                let trade_date = trade_date(at.with_timezone(&Eastern), time(17, 0));
+                is_weekday(trade_date)
+                    && !FX_HOLIDAYS.contains(&(trade_date.month(), trade_date.day()))
+            }
+            MarketCalendar::CmeMetals => {
+                let now = at.with_timezone(&Eastern);
+                let (date, now_time) = (now.date_naive(), now.time());
+                // Daily maintenance break
+                if now_time >= time(17, 0) && now_time < time(18, 0) {
+                    return false;
+                }
+                let trade_date = trade_date(now, time(18, 0));
+                if !is_weekday(trade_date) || in_table(CME_HOLIDAYS, trade_date) {
+                    return false;
+                }
+                let halts_early = in_table(NYSE_HOLIDAYS, date) && !in_table(CME_HOLIDAYS, date);
+                !(halts_early && now_time >= time(13, 30))
+            }
+        }
+    }
+
+    /// Same as `is_open` for a time in milliseconds since the UNIX epoch.
+    pub fn is_open_at_ms(&self, unix_time_ms: u128) -> bool {
+        match i64::try_from(unix_time_ms)
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+        {
+            Some(at) => self.is_open(at),
+            None => true,
+        }
+    }
+}
+
+fn time(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).unwrap()
+}
+
+fn is_weekday(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+fn in_table(table: &[Date], date: NaiveDate) -> bool {
+    table.contains(&(date.year(), date.month(), date.day()))
+}
+
+/// Sessions which start in the evening count towards the next day.
+fn trade_date(now: DateTime<Tz>, session_start: NaiveTime) -> NaiveDate {
+    let date = now.date_naive();
+    if now.time() >= session_start {
+        date.succ_opt().unwrap_or(date)
+    } else {
+        date
+    }
+}
+
+/// Exchanges with a single session a day, from open to close inclusive.
+fn exchange_is_open(
+    now: DateTime<Tz>,
+    (open, close, early_close): (NaiveTime, NaiveTime, NaiveTime),
+    holidays: &[Date],
+    early_closes: &[Date],
+) -> bool {
+    let date = now.date_naive();
+    if !is_weekday(date) || in_table(holidays, date) {
+        return false;
+    }
+    let close = if in_table(early_closes, date) {
+        early_close
+    } else {
+        close
+    };
+    (open..=close).contains(&now.time())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(tz: Tz, (year, month, day): Date, (hour, min, sec): (u32, u32, u32)) -> DateTime<Utc> {
+        tz.with_ymd_and_hms(year, month, day, hour, min, sec)
+            .single()
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn nyse_sessions() {
+        let test_cases = [
+            // Weekdays - markets open
+            ((2025, 5, 12), (9, 30, 0), true), // Monday right at market open
+            ((2025, 5, 13), (10, 0, 0), true), // Tuesday
+            ((2025, 5, 14), (12, 0, 0), true), // Wednesday
+            ((2025, 5, 15), (15, 40, 52), true), // Thursday
+            ((2025, 5, 16), (16, 0, 0), true), // Friday right at market close
+            // Weekdays - markets closed
+            ((2025, 5, 12), (9, 29, 59), false), // Monday right before market open
+            ((2025, 5, 13), (16, 0, 1), false),  // Tuesday right after market close
+            ((2025, 5, 14), (3, 0, 0), false),   // Wednesday before market open
+            ((2025, 5, 15), (17, 0, 0), false),  // Thursday after market close
+            ((2025, 5, 16), (23, 30, 0), false), // Friday middle of the night
+            // Weekend - markets closed
+            ((2025, 5, 10), (10, 0, 0), false), // Working hours but on Saturday
+            ((2025, 5, 11), (10, 0, 0), false), // Working hours but on Sunday
+            // Holidays and early closes
+            ((2025, 7, 4), (12, 0, 0), false),   // Independence Day
+            ((2026, 4, 3), (12, 0, 0), false),   // Good Friday
+            ((2025, 11, 28), (12, 59, 0), true), // Day after Thanksgiving
+            ((2025, 11, 28), (13, 1, 0), false), // Day after Thanksgiving
+        ];
+
+        for (date, hms, expected_open) in test_cases {
+            assert_eq!(
+                MarketCalendar::Nyse.is_open(at(Eastern, date, hms)),
+                expected_open,
+                "{date:?} {hms:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn lse_sessions() {
+        let lse = MarketCalendar::Lse;
+        assert!(lse.is_open(at(London, (2025, 6, 2), (8, 0, 0))));
+        assert!(lse.is_open(at(London, (2025, 6, 2), (16, 30, 0))));
+        assert!(!lse.is_open(at(London, (2025, 6, 2), (16, 31, 0))));
+        assert!(!lse.is_open(at(London, (2025, 8, 25), (12, 0, 0)))); // Summer bank holiday
+        assert!(!lse.is_open(at(London, (2025, 12, 24), (13, 0, 0)))); // Early close
+        assert!(lse.is_open(at(London, (2025, 7, 4), (12, 0, 0)))); // US holiday only
+    }
+
+    #[test]
+    fn fx_sessions() {
+        let fx = MarketCalendar::Fx;
+        // Sunday 2025-05-11 and Friday 2025-05-16
+        assert!(!fx.is_open(at(Eastern, (2025, 5, 11), (16, 59, 0))));
+        assert!(fx.is_open(at(Eastern, (2025, 5, 11), (17, 0, 0))));
+        assert!(fx.is_open(at(Eastern, (2025, 5, 14), (3, 0, 0))));
+        assert!(fx.is_open(at(Eastern, (2025, 5, 16), (16, 59, 0))));
+        assert!(!fx.is_open(at(Eastern, (2025, 5, 16), (17, 0, 0))));
+        assert!(!fx.is_open(at(Eastern, (2025, 5, 17), (12, 0, 0))));
+        // Christmas
+        assert!(!fx.is_open(at(Eastern, (2025, 12, 24), (18, 0, 0))));
+        assert!(!fx.is_open(at(Eastern, (2025, 12, 25), (12, 0, 0))));
+        assert!(fx.is_open(at(Eastern, (2025, 12, 25), (17, 0, 0))));
+    }
+
+    #[test]
+    fn cme_metals_sessions() {
+        let cme = MarketCalendar::CmeMetals;
+        assert!(!cme.is_open(at(Eastern, (2025, 5, 11), (17, 30, 0)))); // Sunday before open
+        assert!(cme.is_open(at(Eastern, (2025, 5, 11), (18, 0, 0))));
+        assert!(cme.is_open(at(Eastern, (2025, 5, 13), (16, 59, 0))));
+        assert!(!cme.is_open(at(Eastern, (2025, 5, 13), (17, 30, 0)))); // Daily break
+        assert!(cme.is_open(at(Eastern, (2025, 5, 13), (18, 0, 0))));
+        assert!(!cme.is_open(at(Eastern, (2025, 5, 16), (18, 0, 0)))); // Friday evening
+                                                                       // Good Friday, from the evening before
+        assert!(!cme.is_open(at(Eastern, (2025, 4, 17), (19, 0, 0))));
+        assert!(!cme.is_open(at(Eastern, (2025, 4, 18), (12, 0, 0))));
+        // Early halt on Independence Day
+        assert!(cme.is_open(at(Eastern, (2025, 7, 4), (13, 0, 0))));
+        assert!(!cme.is_open(at(Eastern, (2025, 7, 4), (14, 0, 0))));
+    }
+
+    #[test]
+    fn calendars_are_named_by_market_hours() {
+        assert_eq!(
+            MarketCalendar::from_market_hours("US_Equities"),
+            Some(MarketCalendar::Nyse)
+        );
+        assert_eq!(
+            MarketCalendar::from_market_hours("Forex"),
+            Some(MarketCalendar::Fx)
+        );
+        assert_eq!(
+            MarketCalendar::from_market_hours("Crypto"),
+            Some(MarketCalendar::AlwaysOpen)
+        );
+        assert_eq!(MarketCalendar::from_market_hours(""), None);
+
+        let saturday_ms = at(Eastern, (2025, 5, 10), (12, 0, 0)).timestamp_millis() as u128;
+        assert!(!MarketCalendar::Nyse.is_open_at_ms(saturday_ms));
+        assert!(MarketCalendar::AlwaysOpen.is_open_at_ms(saturday_ms));
+    }
+}
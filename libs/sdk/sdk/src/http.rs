@@ -5,6 +5,8 @@ use serde::{de::DeserializeOwned, Serialize};
 use spin_sdk::http::{send, Method, Request, Response};
 use url::Url;
 
+use crate::oracle::OracleError;
+
 pub type QueryParam<'a, 'b> = (&'a str, &'b str);
 pub type HeaderParam<'a, 'b> = (&'a str, &'b str);
 
@@ -62,6 +64,23 @@ pub fn prepare_get_request(
     Ok(req.build())
 }
 
+/// Fails with `OracleError::RateLimited` or `OracleError::UpstreamHttp` unless the status code
+/// of the response is 2xx.
+pub fn check_status(response: &Response) -> Result<()> {
+    let status_code: u16 = *response.status();
+    if (200..=299).contains(&status_code) {
+        return Ok(());
+    }
+    if status_code == 429 {
+        let retry_after = response
+            .header("retry-after")
+            .and_then(|value| value.as_str())
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        bail!(OracleError::RateLimited(retry_after));
+    }
+    bail!(OracleError::UpstreamHttp(status_code));
+}
+
 pub async fn http_get_json<T>(
     url: &str,
     params: Option<&[QueryParam<'_, '_>]>,
@@ -73,13 +92,7 @@ where
 {
     let request = prepare_get_request(url, params, headers, timeout_secs)?;
     let response: Response = send(request).await?;
-
-    let status_code: u16 = *response.status();
-    let request_successful = (200..=299).contains(&status_code);
-
-    if !request_successful {
-        bail!("HTTP get request error: returned status code {status_code}");
-    }
+    check_status(&response)?;
 
     let body = response.body();
     serde_json::from_slice(body).map_err(Into::into)
@@ -134,13 +147,7 @@ where
 {
     let request = prepare_post_request(url, request_json, timeout_secs)?;
     let response: Response = send(request).await?;
-
-    let status_code: u16 = *response.status();
-    let request_successful = (200..=299).contains(&status_code);
-
-    if !request_successful {
-        bail!("HTTP get request error: returned status code {status_code}");
-    }
+    check_status(&response)?;
 
     let body = response.body();
     serde_json::from_slice(body).map_err(Into::into)
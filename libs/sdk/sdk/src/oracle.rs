@@ -1,7 +1,11 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 pub mod capabilities;
-pub use capabilities::{get_api_keys, get_capabilities_from_settings, Capabilities};
+pub use capabilities::{
+    get_api_keys, get_capabilities_from_settings, require_capability, Capabilities,
+};
 pub mod logging;
 
 #[derive(Clone, Debug)]
@@ -43,6 +47,54 @@ pub enum DataFeedResultValue {
     Text(String),
     Bytes(Vec<u8>),
     Error(String),
+    /// Exact signed decimal number, e.g. "-12.345" or "1.5e21". Use it for values that can be
+    /// negative or need more precision than f64.
+    Decimal(String),
+}
+
+/// Error of an oracle script, which lets the reporter retry, back off or report it depending on
+/// its kind. Errors of any other type are reported as `OracleError::Other`.
+///
+/// Oracle scripts returning `anyhow::Result` can still return these errors, e.g. with
+/// `bail!(OracleError::Timeout)`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OracleError {
+    Timeout,
+    UpstreamHttp(u16),
+    /// Seconds after which the upstream API accepts requests again, if known
+    RateLimited(Option<u64>),
+    MissingCapability(String),
+    InvalidSettings(String),
+    Other(String),
+}
+
+impl fmt::Display for OracleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OracleError::Timeout => write!(f, "Timed out"),
+            OracleError::UpstreamHttp(status) => {
+                write!(f, "Upstream API responded with status code {status}")
+            }
+            OracleError::RateLimited(Some(retry_after)) => {
+                write!(f, "Rate limited, retry after {retry_after} s")
+            }
+            OracleError::RateLimited(None) => write!(f, "Rate limited"),
+            OracleError::MissingCapability(id) => write!(f, "Missing capability {id}"),
+            OracleError::InvalidSettings(reason) => write!(f, "Invalid settings: {reason}"),
+            OracleError::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
+impl From<anyhow::Error> for OracleError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<OracleError>() {
+            Ok(error) => error,
+            Err(error) => OracleError::Other(format!("{error:#}")),
+        }
+    }
 }
 
 //TODO(adikov): Start using FeedType from feed_registry
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::oracle::Settings;
+use crate::oracle::{OracleError, Settings};
 
 pub type Capabilities = HashMap<String, String>;
 
@@ -12,6 +12,17 @@ pub fn get_capabilities_from_settings(settings: &Settings) -> Capabilities {
         .collect()
 }
 
+/// The capability with the given id, or `OracleError::MissingCapability` if it is not configured.
+pub fn require_capability<'a>(
+    capabilities: &'a Capabilities,
+    id: &str,
+) -> Result<&'a str, OracleError> {
+    capabilities
+        .get(id)
+        .map(String::as_str)
+        .ok_or_else(|| OracleError::MissingCapability(id.to_string()))
+}
+
 pub fn get_api_keys(capabilities: &Capabilities, keys: &[&str]) -> Option<HashMap<String, String>> {
     keys.iter()
         .map(|&key| {
@@ -43,3 +54,15 @@ fn test_get_api_keys() {
     let result = get_api_keys(&empty_capabilities, &["API_KEY"]);
     assert_eq!(result, None);
 }
+
+#[test]
+fn test_require_capability() {
+    let mut capabilities = Capabilities::new();
+    capabilities.insert("API_KEY".to_string(), "test_key".to_string());
+
+    assert_eq!(require_capability(&capabilities, "API_KEY"), Ok("test_key"));
+    assert_eq!(
+        require_capability(&capabilities, "OTHER_KEY"),
+        Err(OracleError::MissingCapability("OTHER_KEY".to_string()))
+    );
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use alloy::hex;
 use prettytable::{format, Cell, Row, Table};
 use tracing::{info, warn};
@@ -12,6 +14,11 @@ pub trait ResourceLogEntry {
 pub trait PriceResultsAccessor {
     fn has(&self, id: &str) -> bool;
     fn provider_names(&self, id: &str) -> Vec<String>;
+
+    /// Age in seconds of the price of each provider, for the providers that report its time
+    fn provider_ages_secs(&self, _id: &str) -> Vec<(String, u64)> {
+        Vec::new()
+    }
 }
 
 #[derive(Default)]
@@ -68,6 +75,7 @@ where
     };
 
     let mut results_info: Vec<ResultInfo> = Vec::new();
+    let mut provider_ages: BTreeMap<String, Vec<u64>> = BTreeMap::new();
     let mut pairs_with_missing_provider_data = String::new();
     let mut pairs_with_missing_provider_data_count = 0;
 
@@ -75,6 +83,9 @@ where
         let id_str = resource.get_id_str();
         let providers = if let Some(provider_results) = config.provider_results {
             if provider_results.has(&id_str) {
+                for (provider, age_secs) in provider_results.provider_ages_secs(&id_str) {
+                    provider_ages.entry(provider).or_default().push(age_secs);
+                }
                 provider_results.provider_names(&id_str)
             } else {
                 // Track missing provider data
@@ -102,6 +113,7 @@ where
                     DataFeedResultValue::Numerical(num) => Some(format!("{num:.8}")),
                     DataFeedResultValue::Bytes(bytes) => Some(format!("0x{}", hex::encode(bytes))),
                     DataFeedResultValue::Text(text) => Some(text.clone()),
+                    DataFeedResultValue::Decimal(value) => Some(value.clone()),
                     DataFeedResultValue::Error(err) => Some(format!("Error: {}", err)),
                     DataFeedResultValue::None => None,
                 })
@@ -169,4 +181,31 @@ where
     }
 
     info!("\nResults:\n{}", table.to_string().replace("\r\n", "\n"));
+
+    if !provider_ages.is_empty() {
+        print_provider_staleness(provider_ages);
+    }
+}
+
+fn print_provider_staleness(provider_ages: BTreeMap<String, Vec<u64>>) {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(vec![
+        Cell::new("Provider").style_spec("bc"),
+        Cell::new("Prices").style_spec("bc"),
+        Cell::new("Median age[s]").style_spec("bc"),
+        Cell::new("Max age[s]").style_spec("bc"),
+    ]));
+
+    for (provider, mut ages) in provider_ages {
+        ages.sort_unstable();
+        table.add_row(Row::new(vec![
+            Cell::new(&provider).style_spec("r"),
+            Cell::new(&ages.len().to_string()).style_spec("r"),
+            Cell::new(&ages[ages.len() / 2].to_string()).style_spec("r"),
+            Cell::new(&ages[ages.len() - 1].to_string()).style_spec("r"),
+        ]));
+    }
+
+    info!("\nStaleness:\n{}", table.to_string().replace("\r\n", "\n"));
 }
@@ -0,0 +1,24 @@
+use blocksense_secrets::{stand_in, DEFAULT_VAULT_TOKEN_ENV};
+
+const USAGE: &str = "Usage: vault_stand_in [<address>]";
+
+/// Secrets can be added with a POST of `{"data": {"value": "..."}}` to
+/// `/v1/secret/data/<NAME>`, with the token from `VAULT_TOKEN` in the `X-Vault-Token` header.
+#[actix_web::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.len() > 1 {
+        eprintln!("{USAGE}");
+        std::process::exit(1);
+    }
+    let addr = args.first().map_or("127.0.0.1:8200", String::as_str);
+    let token = std::env::var(DEFAULT_VAULT_TOKEN_ENV).unwrap_or_else(|_| "dev-token".to_string());
+
+    let (url, server, _) = stand_in::start(addr, &token).await.unwrap();
+    println!("Vault stand-in listening on {url}");
+
+    actix_web::rt::signal::ctrl_c().await.unwrap();
+    server.stop(true).await;
+}
@@ -0,0 +1,299 @@
+//! Keystore file with secrets encrypted by a passphrase. The key is derived from the passphrase
+//! with scrypt, and every secret is encrypted with AES-256-GCM bound to its name.
+
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{Context, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::Secret;
+
+const KEYSTORE_VERSION: u32 = 1;
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct KdfParams {
+    /// Hex encoded
+    pub salt: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl KdfParams {
+    /// Parameters with a new random salt.
+    pub fn new(log_n: u8) -> KdfParams {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        KdfParams {
+            salt: hex::encode(salt),
+            log_n,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams::new(15)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct EncryptedSecret {
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+struct KeystoreFile {
+    version: u32,
+    kdf: KdfParams,
+    secrets: BTreeMap<String, EncryptedSecret>,
+}
+
+/// Keystore file which is read on every access, so changes to it are seen right away.
+pub struct Keystore {
+    path: PathBuf,
+    /// Deriving the key is slow on purpose, the last one is kept with its salt and passphrase
+    derived_key: Mutex<Option<(String, String, [u8; 32])>>,
+}
+
+impl Keystore {
+    pub fn new(path: PathBuf) -> Keystore {
+        Keystore {
+            path,
+            derived_key: Mutex::new(None),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Creates an empty keystore, failing if the file already exists.
+    pub fn create(&self, kdf: KdfParams) -> Result<()> {
+        if self.path.exists() {
+            anyhow::bail!("Keystore {} already exists", self.path.display());
+        }
+        self.write(&KeystoreFile {
+            version: KEYSTORE_VERSION,
+            kdf,
+            secrets: BTreeMap::new(),
+        })
+    }
+
+    pub fn get(&self, name: &str, passphrase: &str) -> Result<Secret> {
+        let file = self.read()?;
+        let encrypted = file
+            .secrets
+            .get(name)
+            .with_context(|| format!("No secret {name} in {}", self.path.display()))?;
+        let cipher = self.cipher(&file.kdf, passphrase)?;
+        let nonce = hex::decode(&encrypted.nonce)?;
+        let ciphertext = hex::decode(&encrypted.ciphertext)?;
+        if nonce.len() != NONCE_LEN {
+            anyhow::bail!("Invalid nonce of secret {name}");
+        }
+        let value = cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| {
+                anyhow::anyhow!("Could not decrypt secret {name}, is the passphrase right?")
+            })?;
+        Ok(Secret::new(String::from_utf8(value).with_context(
+            || format!("Secret {name} is not valid UTF-8"),
+        )?))
+    }
+
+    /// Adds or replaces a secret, creating the keystore with default parameters if needed.
+    pub fn set(&self, name: &str, value: &str, passphrase: &str) -> Result<()> {
+        if !self.path.exists() {
+            self.create(KdfParams::default())?;
+        }
+        let mut file = self.read()?;
+        let cipher = self.cipher(&file.kdf, passphrase)?;
+        // A keystore has one passphrase, check it against a secret already in it
+        if let Some(existing) = file.secrets.keys().next().cloned() {
+            self.get(&existing, passphrase)?;
+        }
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: value.as_bytes(),
+                    aad: name.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow::anyhow!("Could not encrypt secret {name}"))?;
+        file.secrets.insert(
+            name.to_string(),
+            EncryptedSecret {
+                nonce: hex::encode(nonce),
+                ciphertext: hex::encode(ciphertext),
+            },
+        );
+        self.write(&file)
+    }
+
+    pub fn names(&self) -> Result<Vec<String>> {
+        Ok(self.read()?.secrets.into_keys().collect())
+    }
+
+    fn read(&self) -> Result<KeystoreFile> {
+        let content = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Could not read keystore {}", self.path.display()))?;
+        let file: KeystoreFile = serde_json::from_str(&content)
+            .with_context(|| format!("Could not parse keystore {}", self.path.display()))?;
+        if file.version != KEYSTORE_VERSION {
+            anyhow::bail!(
+                "Unsupported version {} of keystore {}",
+                file.version,
+                self.path.display()
+            );
+        }
+        Ok(file)
+    }
+
+    /// Writes a file next to the keystore that only its owner can read and moves it over the
+    /// keystore, so the keystore is never left half written or readable by others.
+    fn write(&self, file: &KeystoreFile) -> Result<()> {
+        let content = serde_json::to_string_pretty(file)?;
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        // Left over by an earlier write that failed, it may have other permissions
+        let _ = std::fs::remove_file(&tmp_path);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut tmp_file = options
+            .open(&tmp_path)
+            .with_context(|| format!("Could not create {}", tmp_path.display()))?;
+        tmp_file
+            .write_all(content.as_bytes())
+            .and_then(|()| tmp_file.sync_all())
+            .with_context(|| format!("Could not write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, &self.path)
+            .with_context(|| format!("Could not write keystore {}", self.path.display()))
+    }
+
+    fn cipher(&self, kdf: &KdfParams, passphrase: &str) -> Result<Aes256Gcm> {
+        let mut derived_key = self.derived_key.lock().unwrap();
+        let key = match &*derived_key {
+            Some((salt, cached_passphrase, key))
+                if *salt == kdf.salt && cached_passphrase == passphrase =>
+            {
+                *key
+            }
+            _ => {
+                let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p, 32)
+                    .context("Invalid scrypt parameters in keystore")?;
+                let mut key = [0u8; 32];
+                scrypt::scrypt(
+                    passphrase.as_bytes(),
+                    &hex::decode(&kdf.salt)?,
+                    &params,
+                    &mut key,
+                )
+                .context("Could not derive the keystore key")?;
+                *derived_key = Some((kdf.salt.clone(), passphrase.to_string(), key));
+                key
+            }
+        };
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_stored_encrypted() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::new(dir.path().join("keystore.json"));
+        // Cheap key derivation to keep the test fast
+        keystore.create(KdfParams::new(4)).unwrap();
+
+        keystore
+            .set("CMC_API_KEY", "first-key", "passphrase")
+            .unwrap();
+        assert_eq!(
+            keystore.get("CMC_API_KEY", "passphrase").unwrap().expose(),
+            "first-key"
+        );
+        let content = std::fs::read_to_string(keystore.path()).unwrap();
+        assert!(!content.contains("first-key"));
+
+        // Rotation
+        keystore
+            .set("CMC_API_KEY", "second-key", "passphrase")
+            .unwrap();
+        assert_eq!(
+            keystore.get("CMC_API_KEY", "passphrase").unwrap().expose(),
+            "second-key"
+        );
+        assert_eq!(keystore.names().unwrap(), vec!["CMC_API_KEY"]);
+
+        assert!(keystore.get("CMC_API_KEY", "wrong passphrase").is_err());
+        assert!(keystore
+            .set("OTHER_KEY", "value", "wrong passphrase")
+            .is_err());
+        assert!(keystore.get("OTHER_KEY", "passphrase").is_err());
+        assert!(keystore.create(KdfParams::new(4)).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keystore_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::new(dir.path().join("keystore.json"));
+        keystore.create(KdfParams::new(4)).unwrap();
+        keystore.set("A", "value of a", "passphrase").unwrap();
+
+        let mode = std::fs::metadata(keystore.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn ciphertexts_are_bound_to_their_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = Keystore::new(dir.path().join("keystore.json"));
+        keystore.create(KdfParams::new(4)).unwrap();
+        keystore.set("A", "value of a", "passphrase").unwrap();
+        keystore.set("B", "value of b", "passphrase").unwrap();
+
+        // Swapping the entries in the file must not swap the secrets
+        let mut file = keystore.read().unwrap();
+        let a = file.secrets["A"].clone();
+        let b = file.secrets["B"].clone();
+        file.secrets.insert("A".to_string(), b);
+        file.secrets.insert("B".to_string(), a);
+        keystore.write(&file).unwrap();
+        assert!(keystore.get("A", "passphrase").is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! Secrets of the reporter, such as the API keys in the capabilities of oracle scripts.
+//!
+//! A capability refers to its secret as `env:<NAME>`, `keystore:<NAME>` or `vault:<NAME>`. The
+//! reporter resolves the reference every time it runs the oracle script, so a rotated secret is
+//! picked up without a restart. Any other capability data is used as it is.
+
+pub mod keystore;
+#[cfg(any(test, feature = "stand-in"))]
+pub mod stand_in;
+pub mod vault;
+
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::keystore::Keystore;
+use crate::vault::VaultClient;
+
+pub use blocksense_secrets_config::{signer, SecretsConfig};
+
+pub const DEFAULT_KEYSTORE_PASSPHRASE_ENV: &str = "BLOCKSENSE_KEYSTORE_PASSPHRASE";
+pub const DEFAULT_VAULT_TOKEN_ENV: &str = "VAULT_TOKEN";
+pub const DEFAULT_VAULT_CACHE_TTL_MS: u64 = 60_000;
+
+/// Value of a secret, which is left out when formatted so it does not end up in logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: String) -> Secret {
+        Secret(value)
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret([REDACTED])")
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SecretRef {
+    Env(String),
+    Keystore(String),
+    Vault(String),
+}
+
+impl SecretRef {
+    /// The reference in capability data, `None` for data that is not a reference.
+    pub fn parse(data: &str) -> Option<SecretRef> {
+        let (scheme, name) = data.trim().split_once(':')?;
+        if name.is_empty() {
+            return None;
+        }
+        match scheme {
+            "env" => Some(SecretRef::Env(name.to_string())),
+            "keystore" => Some(SecretRef::Keystore(name.to_string())),
+            "vault" => Some(SecretRef::Vault(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::Env(name) => write!(f, "env:{name}"),
+            SecretRef::Keystore(name) => write!(f, "keystore:{name}"),
+            SecretRef::Vault(name) => write!(f, "vault:{name}"),
+        }
+    }
+}
+
+pub struct SecretsProvider {
+    keystore: Option<Keystore>,
+    keystore_passphrase_env: String,
+    vault: Option<VaultClient>,
+}
+
+impl SecretsProvider {
+    pub fn new(config: &SecretsConfig) -> Result<SecretsProvider> {
+        let vault = match &config.vault_url {
+            Some(url) => Some(VaultClient::new(
+                url,
+                config
+                    .vault_token_env
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_VAULT_TOKEN_ENV.to_string()),
+                Duration::from_millis(
+                    config
+                        .vault_cache_ttl_ms
+                        .unwrap_or(DEFAULT_VAULT_CACHE_TTL_MS),
+                ),
+            )?),
+            None => None,
+        };
+        Ok(SecretsProvider {
+            keystore: config.keystore.clone().map(Keystore::new),
+            keystore_passphrase_env: config
+                .keystore_passphrase_env
+                .clone()
+                .unwrap_or_else(|| DEFAULT_KEYSTORE_PASSPHRASE_ENV.to_string()),
+            vault,
+        })
+    }
+
+    pub async fn resolve(&self, reference: &SecretRef) -> Result<Secret> {
+        match reference {
+            SecretRef::Env(name) => std::env::var(name)
+                .map(Secret::new)
+                .with_context(|| format!("Environment variable {name} is not set")),
+            SecretRef::Keystore(name) => {
+                let keystore = self
+                    .keystore
+                    .as_ref()
+                    .with_context(|| format!("No keystore is configured for {reference}"))?;
+                let passphrase =
+                    std::env::var(&self.keystore_passphrase_env).with_context(|| {
+                        format!(
+                            "Environment variable {} with the keystore passphrase is not set",
+                            self.keystore_passphrase_env
+                        )
+                    })?;
+                keystore.get(name, &passphrase)
+            }
+            SecretRef::Vault(name) => {
+                let vault = self
+                    .vault
+                    .as_ref()
+                    .with_context(|| format!("No vault is configured for {reference}"))?;
+                vault.get(name).await
+            }
+        }
+    }
+
+    /// Value of the capability, resolved if its data is a reference to a secret.
+    pub async fn resolve_capability(&self, data: &str) -> Result<Secret> {
+        match SecretRef::parse(data) {
+            Some(reference) => self.resolve(&reference).await,
+            None => Ok(Secret::new(data.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_refs_are_parsed() {
+        assert_eq!(
+            SecretRef::parse("env:CMC_API_KEY"),
+            Some(SecretRef::Env("CMC_API_KEY".to_string()))
+        );
+        assert_eq!(
+            SecretRef::parse("keystore:CMC_API_KEY"),
+            Some(SecretRef::Keystore("CMC_API_KEY".to_string()))
+        );
+        assert_eq!(
+            SecretRef::parse("vault:cmc/api-key"),
+            Some(SecretRef::Vault("cmc/api-key".to_string()))
+        );
+        assert_eq!(SecretRef::parse("env:"), None);
+        assert_eq!(SecretRef::parse("/run/secrets/cmc_api_key"), None);
+        assert_eq!(SecretRef::parse("c0ffee:1234"), None);
+    }
+
+    #[test]
+    fn secrets_are_redacted() {
+        let secret = Secret::new("hunter2".to_string());
+        assert_eq!(secret.to_string(), "[REDACTED]");
+        assert!(!format!("{secret:?}").contains("hunter2"));
+        assert_eq!(secret.expose(), "hunter2");
+    }
+
+    #[actix_web::test]
+    async fn literal_and_env_capabilities_are_resolved() {
+        let provider = SecretsProvider::new(&SecretsConfig::default()).unwrap();
+        assert_eq!(
+            provider
+                .resolve_capability("plain-api-key")
+                .await
+                .unwrap()
+                .expose(),
+            "plain-api-key"
+        );
+
+        std::env::set_var("BLOCKSENSE_SECRETS_TEST_KEY", "from-env");
+        assert_eq!(
+            provider
+                .resolve_capability("env:BLOCKSENSE_SECRETS_TEST_KEY")
+                .await
+                .unwrap()
+                .expose(),
+            "from-env"
+        );
+        assert!(provider
+            .resolve_capability("keystore:BLOCKSENSE_SECRETS_TEST_KEY")
+            .await
+            .is_err());
+        assert!(provider
+            .resolve_capability("vault:BLOCKSENSE_SECRETS_TEST_KEY")
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! In-memory vault serving the same endpoint as the vaults the reporter reads its secrets from.
+//! Used in tests and for local development, see `bin/vault_stand_in.rs`.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use actix_web::dev::ServerHandle;
+use actix_web::{get, post, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
+use anyhow::{Context, Result};
+use url::Url;
+
+use crate::vault::{VaultSecretData, VaultSecretResponse, VAULT_TOKEN_HEADER};
+
+pub struct StandInVault {
+    token: String,
+    secrets: Mutex<HashMap<String, String>>,
+}
+
+impl StandInVault {
+    /// Adds or rotates a secret.
+    pub fn set(&self, name: &str, value: &str) {
+        self.secrets
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+    }
+
+    fn is_authorized(&self, request: &HttpRequest) -> bool {
+        request
+            .headers()
+            .get(VAULT_TOKEN_HEADER)
+            .and_then(|token| token.to_str().ok())
+            == Some(self.token.as_str())
+    }
+}
+
+#[get("/v1/secret/data/{name:.*}")]
+async fn read_secret(
+    name: web::Path<String>,
+    request: HttpRequest,
+    vault: web::Data<StandInVault>,
+) -> impl Responder {
+    if !vault.is_authorized(&request) {
+        return HttpResponse::Forbidden().body("Invalid vault token");
+    }
+    match vault.secrets.lock().unwrap().get(name.as_str()) {
+        Some(value) => HttpResponse::Ok().json(VaultSecretResponse {
+            data: VaultSecretData {
+                data: HashMap::from([("value".to_string(), value.clone())]),
+            },
+        }),
+        None => HttpResponse::NotFound().body(format!("Unknown secret {name}")),
+    }
+}
+
+#[post("/v1/secret/data/{name:.*}")]
+async fn write_secret(
+    name: web::Path<String>,
+    request: HttpRequest,
+    secret: web::Json<VaultSecretData>,
+    vault: web::Data<StandInVault>,
+) -> impl Responder {
+    if !vault.is_authorized(&request) {
+        return HttpResponse::Forbidden().body("Invalid vault token");
+    }
+    match secret.data.get("value") {
+        Some(value) => {
+            vault.set(&name, value);
+            HttpResponse::Ok().finish()
+        }
+        None => HttpResponse::BadRequest().body("Secret has no value"),
+    }
+}
+
+/// Starts the stand-in in the background and returns its URL and its secrets. `addr` may use
+/// port 0 to pick any free port.
+pub async fn start(
+    addr: &str,
+    token: &str,
+) -> Result<(Url, ServerHandle, web::Data<StandInVault>)> {
+    let vault = web::Data::new(StandInVault {
+        token: token.to_string(),
+        secrets: Mutex::new(HashMap::new()),
+    });
+    let app_vault = vault.clone();
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_vault.clone())
+            .service(read_secret)
+            .service(write_secret)
+    })
+    .workers(1)
+    .bind(addr)
+    .with_context(|| format!("Could not bind vault stand-in to {addr}"))?;
+    let local_addr = *server
+        .addrs()
+        .first()
+        .context("Vault stand-in is not bound to any address")?;
+    let server = server.run();
+    let handle = server.handle();
+    actix_web::rt::spawn(server);
+    let url = Url::parse(&format!("http://{local_addr}"))?;
+    tracing::info!("Vault stand-in listening on {url}");
+    Ok((url, handle, vault))
+}
@@ -0,0 +1,138 @@
+//! Client of a vault with a key-value API like the one of HashiCorp Vault. A secret `<NAME>` is
+//! read from `/v1/secret/data/<NAME>` and is the `value` field of its data.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::Secret;
+
+pub const VAULT_TOKEN_HEADER: &str = "X-Vault-Token";
+pub const VAULT_SECRET_PATH: &str = "/v1/secret/data";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VaultSecretData {
+    pub data: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VaultSecretResponse {
+    pub data: VaultSecretData,
+}
+
+pub struct VaultClient {
+    base_url: Url,
+    token_env: String,
+    client: Client,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, Secret)>>,
+}
+
+impl VaultClient {
+    /// The token is read from `token_env` on every request, so it can be rotated as well.
+    pub fn new(base_url: &str, token_env: String, cache_ttl: Duration) -> Result<VaultClient> {
+        Ok(VaultClient {
+            base_url: Url::parse(base_url)
+                .with_context(|| format!("Invalid vault URL {base_url:?}"))?,
+            token_env,
+            client: Client::new(),
+            cache_ttl,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub async fn get(&self, name: &str) -> Result<Secret> {
+        if let Some((fetched_at, secret)) = self.cache.lock().unwrap().get(name) {
+            if fetched_at.elapsed() < self.cache_ttl {
+                return Ok(secret.clone());
+            }
+        }
+
+        let url = self.base_url.join(&format!("{VAULT_SECRET_PATH}/{name}"))?;
+        let token = std::env::var(&self.token_env).with_context(|| {
+            format!(
+                "Environment variable {} with the vault token is not set",
+                self.token_env
+            )
+        })?;
+        let response = self
+            .client
+            .get(url)
+            .header(VAULT_TOKEN_HEADER, token)
+            .send()
+            .await
+            .with_context(|| format!("Could not reach the vault for secret {name}"))?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Vault responded with {status} for secret {name}");
+        }
+        let response: VaultSecretResponse = response.json().await?;
+        let secret = Secret::new(
+            response
+                .data
+                .data
+                .get("value")
+                .cloned()
+                .with_context(|| format!("Secret {name} in the vault has no value"))?,
+        );
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), (Instant::now(), secret.clone()));
+        Ok(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stand_in;
+
+    #[actix_web::test]
+    async fn secrets_are_read_from_the_vault_and_rotated() {
+        let (url, server, vault) = stand_in::start("127.0.0.1:0", "test-token").await.unwrap();
+        vault.set("cmc/api-key", "first-key");
+
+        std::env::set_var("BLOCKSENSE_VAULT_TEST_TOKEN", "test-token");
+        let client = VaultClient::new(
+            url.as_str(),
+            "BLOCKSENSE_VAULT_TEST_TOKEN".to_string(),
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        assert_eq!(
+            client.get("cmc/api-key").await.unwrap().expose(),
+            "first-key"
+        );
+
+        vault.set("cmc/api-key", "second-key");
+        // Still cached
+        assert_eq!(
+            client.get("cmc/api-key").await.unwrap().expose(),
+            "first-key"
+        );
+        actix_web::rt::time::sleep(Duration::from_millis(250)).await;
+        assert_eq!(
+            client.get("cmc/api-key").await.unwrap().expose(),
+            "second-key"
+        );
+
+        assert!(client.get("unknown").await.is_err());
+
+        std::env::set_var("BLOCKSENSE_VAULT_TEST_BAD_TOKEN", "wrong-token");
+        let unauthorized = VaultClient::new(
+            url.as_str(),
+            "BLOCKSENSE_VAULT_TEST_BAD_TOKEN".to_string(),
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert!(unauthorized.get("cmc/api-key").await.is_err());
+
+        server.stop(true).await;
+    }
+}
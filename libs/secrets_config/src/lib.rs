@@ -0,0 +1,30 @@
+//! Configuration of the secrets of the reporter and of the signers of the sequencer and the
+//! reporter. It only needs serde, so that the configs can refer to it without pulling in the
+//! crypto of `blocksense_secrets`.
+
+pub mod signer;
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Where the reporter finds the secrets which are not in environment variables.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SecretsConfig {
+    /// Encrypted keystore file, see `blocksense_secrets::keystore::Keystore`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore: Option<PathBuf>,
+    /// Environment variable with the passphrase of the keystore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keystore_passphrase_env: Option<String>,
+    /// Base URL of a vault serving secrets at `/v1/secret/data/<NAME>`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_url: Option<String>,
+    /// Environment variable with the token for the vault
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_token_env: Option<String>,
+    /// How long a secret from the vault is used before it is requested again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vault_cache_ttl_ms: Option<u64>,
+}
@@ -0,0 +1,89 @@
+//! Where the keys that sign transactions and Safe signatures are kept. The signers are built from
+//! this configuration in `blocksense_gnosis_safe::signer`, it lives here so that the configs of
+//! the sequencer and the reporter can refer to it.
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SIGNER_TOKEN_ENV: &str = "BLOCKSENSE_SIGNER_TOKEN";
+pub const DEFAULT_SIGNER_TIMEOUT_MS: u64 = 5_000;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignerConfig {
+    /// Hex encoded private key in a plain file
+    PrivateKeyFile { path: PathBuf },
+    /// Encrypted JSON keystore in the Web3 Secret Storage format, as written by geth or
+    /// `cast wallet import`
+    Keystore {
+        path: PathBuf,
+        /// Environment variable with the password, `BLOCKSENSE_KEYSTORE_PASSPHRASE` if not set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password_env: Option<String>,
+    },
+    /// Service that signs hashes with the key of `address`. The key never leaves the service.
+    Remote {
+        url: String,
+        /// Every signature of the service is checked against this address
+        address: String,
+        /// Environment variable with the bearer token, `BLOCKSENSE_SIGNER_TOKEN` if not set
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_env: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+    },
+}
+
+impl fmt::Display for SignerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerConfig::PrivateKeyFile { path } => {
+                write!(f, "private key file {}", path.display())
+            }
+            SignerConfig::Keystore { path, .. } => write!(f, "keystore {}", path.display()),
+            SignerConfig::Remote { url, address, .. } => {
+                write!(f, "remote signer {url} for {address}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signer_configs_are_tagged_by_type() {
+        let keystore: SignerConfig =
+            serde_json::from_str(r#"{"type": "keystore", "path": "/run/keys/eth.json"}"#).unwrap();
+        assert_eq!(
+            keystore,
+            SignerConfig::Keystore {
+                path: PathBuf::from("/run/keys/eth.json"),
+                password_env: None,
+            }
+        );
+
+        let remote: SignerConfig = serde_json::from_str(
+            r#"{
+                "type": "remote",
+                "url": "https://signer.internal:8443",
+                "address": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+                "token_env": "SIGNER_TOKEN"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            remote.to_string(),
+            "remote signer https://signer.internal:8443 for 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(
+            serde_json::from_str::<SignerConfig>(&serde_json::to_string(&remote).unwrap()).unwrap(),
+            remote
+        );
+
+        assert!(serde_json::from_str::<SignerConfig>(r#"{"type": "ledger"}"#).is_err());
+    }
+}
@@ -186,6 +186,7 @@ export const NewFeedSchema = S.mutable(
       S.Literal('numerical'),
       S.Literal('text'),
       S.Literal('bytes'),
+      S.Literal('decimal'),
     ).annotations({
       identifier: 'ValueType',
     }),
@@ -199,9 +200,18 @@ export const NewFeedSchema = S.mutable(
         S.Literal('median'),
         S.Literal('average'),
         S.Literal('majority'),
+        S.Literal('trimmed_mean'),
+        S.Literal('stake_weighted_median'),
+        S.Literal('twap'),
       ).annotations({
         identifier: 'QuorumAggregation',
       }),
+      aggregation_params: S.optional(
+        S.Struct({
+          trim_percentage: S.optional(S.NullishOr(S.Number)),
+          twap_window_ms: S.optional(S.NullishOr(S.Number)),
+        }).annotations({ identifier: 'AggregationParams' }),
+      ),
     }).annotations({ identifier: 'FeedQuorum' }),
 
     schedule: S.Struct({
@@ -209,8 +219,11 @@ export const NewFeedSchema = S.mutable(
       heartbeat_ms: S.Number,
       deviation_percentage: S.Number,
       first_report_start_unix_time_ms: S.Number,
+      anomaly_score_threshold: S.optional(S.NullishOr(S.Number)),
     }).annotations({ identifier: 'FeedSchedule' }),
 
+    history_depth: S.optional(S.NullishOr(S.Number)),
+
     // TODO: This field should be optional / different depending on the `type`.
     additional_feed_info: S.mutable(
       S.Struct({
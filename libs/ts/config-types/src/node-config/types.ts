@@ -24,12 +24,14 @@ const BlockConfigSchema = S.Struct({
   block_generation_period: S.Number,
   genesis_block_timestamp_ms: S.NullishOr(S.Number),
   aggregation_consensus_discard_period_blocks: S.Number,
+  block_store_path: S.optional(S.NullishOr(S.String)),
 });
 
 const ReporterSchema = S.Struct({
   id: S.Number,
   pub_key: S.String,
   address: S.String,
+  stake: S.optional(S.Number),
 });
 
 const PublishingCriteriaSchema = S.Struct({
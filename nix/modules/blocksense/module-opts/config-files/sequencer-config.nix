@@ -8,6 +8,7 @@ let
 in
 dashToUnderscoreRecursive {
   inherit (cfg.sequencer)
+    admin-auth
     block-config
     providers
     http-input-buffer-size
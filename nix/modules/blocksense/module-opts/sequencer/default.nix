@@ -25,6 +25,12 @@ lib: with lib; {
     };
   };
 
+  admin-auth.allow-unauthenticated = mkOption {
+    type = types.bool;
+    default = false;
+    description = mdDoc "Serve the admin API without bearer tokens. Only meant for local test environments.";
+  };
+
   block-config = {
     max-feed-updates-to-batch = mkOption {
       type = types.int;
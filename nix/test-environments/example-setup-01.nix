@@ -55,6 +55,8 @@ in
         metrics = 5551;
       };
 
+      admin-auth.allow-unauthenticated = true;
+
       block-config = {
         max-feed-updates-to-batch = 300;
         block-generation-period = 500;